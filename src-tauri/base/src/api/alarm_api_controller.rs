@@ -0,0 +1,36 @@
+use shared::{
+    current::logged_in_user,
+    db::{model::alarm::Alarm, service::alarm_service::AsyncAlarmService},
+};
+use uuid::Uuid;
+
+/// Returns the alarms of an event or to-do of the current user.
+#[tauri::command]
+pub async fn get_alarms(component_uuid: Uuid) -> Result<Vec<Alarm>, &'static str> {
+    let user = logged_in_user()?;
+    AsyncAlarmService::get_alarms(&user, component_uuid).await
+}
+
+/// Replaces the alarms of an event or to-do of the current user.
+#[tauri::command]
+pub async fn set_alarms(
+    component_uuid: Uuid,
+    alarms: Vec<Alarm>,
+) -> Result<Vec<Alarm>, &'static str> {
+    let user = logged_in_user()?;
+    AsyncAlarmService::set_alarms(&user, component_uuid, alarms).await
+}
+
+/// Snoozes a due alarm for the given minutes.
+#[tauri::command]
+pub async fn snooze_alarm(alarm_uuid: Uuid, minutes: i64) -> Result<Alarm, &'static str> {
+    let user = logged_in_user()?;
+    AsyncAlarmService::snooze(&user, alarm_uuid, minutes).await
+}
+
+/// Dismisses a due alarm until its next trigger.
+#[tauri::command]
+pub async fn dismiss_alarm(alarm_uuid: Uuid) -> Result<Alarm, &'static str> {
+    let user = logged_in_user()?;
+    AsyncAlarmService::dismiss(&user, alarm_uuid).await
+}
@@ -1,41 +1,48 @@
 use shared::{
-    auth_util::AuthUtil, current::driver, db::repository::user_repository::UserRepository,
+    auth_util::AsyncAuthUtil, current::async_driver,
+    db::repository::user_repository::AsyncUserRepository,
 };
-use std::ops::{Deref, DerefMut};
+use std::ops::DerefMut;
 use tauri::AppHandle;
 
 #[tauri::command]
-pub(crate) fn attempt_login(
+pub(crate) async fn attempt_login(
     app_handle: AppHandle,
     email: String,
     password: String,
     remember: bool,
 ) -> Result<(), &'static str> {
-    AuthUtil::attempt_login(Some(app_handle), email, password, remember)
+    AsyncAuthUtil::attempt_login(Some(app_handle), email, password, remember).await
 }
 
 #[tauri::command]
-pub(crate) fn attempt_signup(
+pub(crate) async fn attempt_signup(
     app_handle: AppHandle,
     username: String,
     email: String,
     password: String,
     remember: bool,
 ) -> Result<(), &'static str> {
-    AuthUtil::attempt_signup(Some(app_handle), username, email, password, remember)
+    AsyncAuthUtil::attempt_signup(Some(app_handle), username, email, password, remember).await
 }
 
 #[tauri::command]
-pub(crate) fn user_exists(email: &str) -> bool {
-    UserRepository::is_existing(driver().lock().unwrap().deref_mut(), email)
+pub(crate) async fn user_exists(email: String) -> bool {
+    let Ok(mut driver) = async_driver().await else {
+        return false;
+    };
+    AsyncUserRepository::is_existing(driver.deref_mut(), &email).await
 }
 
 #[tauri::command]
-pub(crate) fn logout() -> Result<(), &'static str> {
-    AuthUtil::logout(driver().lock().unwrap().deref_mut())
+pub(crate) async fn logout() -> Result<(), &'static str> {
+    AsyncAuthUtil::logout(async_driver().await?.deref_mut()).await
 }
 
 #[tauri::command]
-pub(crate) fn is_valid_session() -> bool {
-    AuthUtil::is_valid_session(driver().lock().unwrap().deref_mut())
+pub(crate) async fn is_valid_session() -> bool {
+    let Ok(mut driver) = async_driver().await else {
+        return false;
+    };
+    AsyncAuthUtil::is_valid_session(driver.deref_mut()).await
 }
@@ -1,41 +1,115 @@
+use chrono::{DateTime, Utc};
 use shared::{
-    current::{driver, get_current_user},
+    current::logged_in_user,
     db::{
-        db_actions::DbActions,
         model::{
-            calendar::Calendar, simple::simple_calendar::SimpleCalendar,
-            user_calendar_combination::UserCalendarCombination,
+            conflict::ConflictMode,
+            simple::{
+                simple_calendar::SimpleCalendar,
+                simple_component::{ComponentPage, SimpleComponent},
+            },
         },
-        repository::{
-            calendar_repository::CalendarRepository,
-            user_calendar_combination_repository::UserCalendarCombinationRepository,
-            user_repository::UserRepository,
-        },
-        service::calendar_service::CalendarService,
+        service::calendar_service::AsyncCalendarService,
     },
+    errors::error_messages::INVALID_TIME_RANGE_ERR,
+    ical::import::ImportReport,
 };
+use uuid::Uuid;
+
+#[tauri::command]
+pub async fn get_calendar_of_current_user() -> Result<Vec<SimpleCalendar>, &'static str> {
+    let user = logged_in_user()?;
+    Ok(AsyncCalendarService::get_user_calendars_or_stale(&user).await)
+}
 
-use crate::api::auth_api_controller::user_exists;
-use std::ops::DerefMut;
+/// Returns a page of the components of a calendar of the current user. Pass the `next` uuid of
+/// a page as `after` to get the following one.
+#[tauri::command]
+pub async fn get_calendar_components(
+    calendar_uuid: Uuid,
+    after: Option<Uuid>,
+    limit: u64,
+) -> Result<ComponentPage, &'static str> {
+    let user = logged_in_user()?;
+    AsyncCalendarService::get_components_page(&user, calendar_uuid, after, limit).await
+}
+
+/// Returns the components of calendars of the current user overlapping `from..to`, for the
+/// month and week views. All calendars of the user are read if `calendar_uuids` is empty.
+#[tauri::command]
+pub async fn get_components_in_range(
+    calendar_uuids: Vec<Uuid>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<SimpleComponent>, &'static str> {
+    let user = logged_in_user()?;
+    AsyncCalendarService::get_components_in_range(&user, calendar_uuids, from, to).await
+}
+
+/// Imports the calendars of an .ics file for the current user. `name` names the calendars
+/// without a name of their own, the report lists what was imported and what was skipped.
+#[tauri::command]
+pub async fn import_ics(
+    contents: String,
+    name: Option<String>,
+) -> Result<ImportReport, &'static str> {
+    let user = logged_in_user()?;
+    let name = name.unwrap_or_else(|| String::from("Imported calendar"));
+    AsyncCalendarService::import_ics(&user, &contents, &name).await
+}
+
+/// Imports the components of an .ics file into one of the calendars of the current user. A
+/// calendar rejecting overlapping events rejects the whole file if an event overlaps another.
+#[tauri::command]
+pub async fn import_ics_into(
+    calendar_uuid: Uuid,
+    contents: String,
+) -> Result<ImportReport, &'static str> {
+    let user = logged_in_user()?;
+    AsyncCalendarService::import_ics_into(&user, calendar_uuid, &contents).await
+}
+
+/// Exports calendars of the current user as the content of an .ics file. All calendars are
+/// exported if `calendar_uuids` is empty, only the components overlapping `from..to` if both are
+/// given.
+#[tauri::command]
+pub async fn export_ics(
+    calendar_uuids: Vec<Uuid>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<String, &'static str> {
+    let range = match (from, to) {
+        (Some(from), Some(to)) => Some((from, to)),
+        (None, None) => None,
+        _ => return Err(INVALID_TIME_RANGE_ERR),
+    };
+    let user = logged_in_user()?;
+    AsyncCalendarService::export_ics(&user, calendar_uuids, range).await
+}
+
+#[tauri::command]
+pub async fn store_new_calendar(
+    calendar_name: String,
+    users_emails: Vec<String>,
+) -> Result<(), &'static str> {
+    AsyncCalendarService::create_calendar(calendar_name, users_emails).await?;
+    Ok(())
+}
 
+/// Returns how a calendar of the current user treats events overlapping other events.
 #[tauri::command]
-pub fn get_calendar_of_current_user() -> Vec<SimpleCalendar> {
-    CalendarService::get_user_calendars(get_current_user().lock().unwrap().as_ref().unwrap())
+pub async fn get_conflict_mode(calendar_uuid: Uuid) -> Result<ConflictMode, &'static str> {
+    let user = logged_in_user()?;
+    AsyncCalendarService::get_conflict_mode(&user, calendar_uuid).await
 }
 
+/// Sets how a calendar of the current user treats events overlapping other events. `HARD`
+/// calendars, like the ones of rooms, reject them, `SOFT` ones only report them.
 #[tauri::command]
-pub fn store_new_calendar(calendar_name: String, users_emails: Vec<String>) -> bool {
-    let calendar = Calendar::new(calendar_name);
-    let result = CalendarRepository::store(driver().lock().unwrap().deref_mut(), &calendar);
-    // retrieve user uuids via emails
-    for email in users_emails {
-        let user = UserRepository::get_by_email(driver().lock().unwrap().deref_mut(), email)
-            .expect("Error occured while retrieving the user");
-        UserCalendarCombinationRepository::store(
-            driver().lock().unwrap().deref_mut(),
-            &UserCalendarCombination::new(user.get_uuid(), calendar.uuid),
-        )
-        .expect("Error occured while retrieving the user");
-    }
-    true
+pub async fn set_conflict_mode(
+    calendar_uuid: Uuid,
+    conflict_mode: ConflictMode,
+) -> Result<(), &'static str> {
+    let user = logged_in_user()?;
+    AsyncCalendarService::set_conflict_mode(&user, calendar_uuid, conflict_mode).await
 }
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use shared::{
+    current::logged_in_user,
+    db::{
+        model::{
+            conflict::{Conflict, Stored},
+            series::{ComponentChanges, EditScope},
+            typed::TypedComponent,
+        },
+        service::component_service::AsyncComponentService,
+    },
+};
+use uuid::Uuid;
+
+/// Edits occurrences of a component of the current user. `recurrence_id` is the one of the
+/// edited occurrence, `scope` tells whether to edit only it, the following ones or all of them.
+/// Returns the events the edited occurrences now overlap.
+#[tauri::command]
+pub async fn edit_occurrence(
+    component_uuid: Uuid,
+    recurrence_id: DateTime<Utc>,
+    scope: EditScope,
+    changes: ComponentChanges,
+) -> Result<Vec<Conflict>, &'static str> {
+    let user = logged_in_user()?;
+    AsyncComponentService::edit_occurrence(&user, component_uuid, recurrence_id, scope, &changes)
+        .await
+}
+
+/// Returns the events an edit of occurrences would overlap, without storing it, to warn about
+/// them beforehand.
+#[tauri::command]
+pub async fn check_occurrence(
+    component_uuid: Uuid,
+    recurrence_id: DateTime<Utc>,
+    scope: EditScope,
+    changes: ComponentChanges,
+) -> Result<Vec<Conflict>, &'static str> {
+    let user = logged_in_user()?;
+    AsyncComponentService::check_occurrence(&user, component_uuid, recurrence_id, scope, &changes)
+        .await
+}
+
+/// Cancels occurrences of a component of the current user, or deletes it if it doesn't recur.
+#[tauri::command]
+pub async fn cancel_occurrence(
+    component_uuid: Uuid,
+    recurrence_id: DateTime<Utc>,
+    scope: EditScope,
+) -> Result<(), &'static str> {
+    let user = logged_in_user()?;
+    AsyncComponentService::cancel_occurrence(&user, component_uuid, recurrence_id, scope).await
+}
+
+/// Returns a component of the current user as an event, to-do or venue with typed fields.
+#[tauri::command]
+pub async fn get_typed_component(component_uuid: Uuid) -> Result<TypedComponent, &'static str> {
+    let user = logged_in_user()?;
+    AsyncComponentService::get_typed(&user, component_uuid).await
+}
+
+/// Stores an event, to-do or venue of the current user, a new one if its uuid is nil. Returns
+/// the uuid of the stored component along with the events it overlaps.
+#[tauri::command]
+pub async fn store_typed_component(component: TypedComponent) -> Result<Stored, &'static str> {
+    let user = logged_in_user()?;
+    AsyncComponentService::store_typed(&user, &component).await
+}
+
+/// Returns the events an event, to-do or venue would overlap, without storing it.
+#[tauri::command]
+pub async fn check_typed_component(
+    component: TypedComponent,
+) -> Result<Vec<Conflict>, &'static str> {
+    let user = logged_in_user()?;
+    AsyncComponentService::check_typed(&user, &component).await
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use shared::{
+    current::logged_in_user,
+    db::{model::free_busy::FreeBusy, service::free_busy_service::AsyncFreeBusyService},
+};
+use uuid::Uuid;
+
+/// Returns when users sharing a calendar with the current user are busy within `from..to`,
+/// merged across all of their calendars. Only the current user is read if `user_uuids` is
+/// empty.
+#[tauri::command]
+pub async fn get_free_busy(
+    user_uuids: Vec<Uuid>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<FreeBusy, &'static str> {
+    let user = logged_in_user()?;
+    AsyncFreeBusyService::get_free_busy(&user, user_uuids, from, to).await
+}
+
+/// Exports the free/busy times of `get_free_busy` as the content of an .ics file.
+#[tauri::command]
+pub async fn export_free_busy(
+    user_uuids: Vec<Uuid>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<String, &'static str> {
+    let user = logged_in_user()?;
+    AsyncFreeBusyService::export_free_busy(&user, user_uuids, from, to).await
+}
@@ -1,2 +1,7 @@
+pub(crate) mod alarm_api_controller;
 pub(crate) mod auth_api_controller;
-pub(crate) mod calendar_api_controller;
\ No newline at end of file
+pub(crate) mod calendar_api_controller;
+pub(crate) mod component_api_controller;
+pub(crate) mod free_busy_api_controller;
+pub(crate) mod scheduling_api_controller;
+pub(crate) mod user_api_controller;
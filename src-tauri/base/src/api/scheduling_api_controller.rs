@@ -0,0 +1,16 @@
+use shared::{
+    current::logged_in_user,
+    db::{
+        model::scheduling::{Slot, SlotRequest},
+        service::scheduling_service::AsyncSchedulingService,
+    },
+};
+
+/// Proposes the earliest slots a meeting with the current user and the requested attendees can
+/// take place in, honouring their working hours and meeting buffers. Optional attendees rank
+/// slots first but never rule one out.
+#[tauri::command]
+pub async fn find_meeting_slots(request: SlotRequest) -> Result<Vec<Slot>, &'static str> {
+    let user = logged_in_user()?;
+    AsyncSchedulingService::find_slots(&user, &request).await
+}
@@ -0,0 +1,48 @@
+use shared::{
+    current::logged_in_user,
+    db::{model::scheduling::WorkingHours, service::user_service::AsyncUserService},
+};
+
+/// The zone the current user wants times shown in, like `Europe/Berlin`. None for the zone of
+/// the device.
+#[tauri::command]
+pub(crate) async fn get_time_zone() -> Result<Option<String>, &'static str> {
+    let user = logged_in_user()?;
+    Ok(user.get_time_zone().cloned())
+}
+
+/// Sets the zone the current user wants times shown in, None for the zone of the device.
+#[tauri::command]
+pub(crate) async fn set_time_zone(time_zone: Option<String>) -> Result<(), &'static str> {
+    let user = logged_in_user()?;
+    AsyncUserService::set_time_zone(&user, time_zone.as_deref())
+        .await
+        .map(|_| ())
+}
+
+/// The hours the current user works, in their zone. None if they can meet at any time.
+#[tauri::command]
+pub(crate) async fn get_working_hours() -> Result<Option<WorkingHours>, &'static str> {
+    let user = logged_in_user()?;
+    Ok(user.working_hours())
+}
+
+/// How many minutes the current user wants kept free between proposed meetings and their other
+/// events.
+#[tauri::command]
+pub(crate) async fn get_meeting_buffer() -> Result<i32, &'static str> {
+    let user = logged_in_user()?;
+    Ok(user.get_meeting_buffer())
+}
+
+/// Sets when the current user can be proposed meetings at, see `find_meeting_slots`.
+#[tauri::command]
+pub(crate) async fn set_scheduling_preferences(
+    working_hours: Option<WorkingHours>,
+    meeting_buffer: i32,
+) -> Result<(), &'static str> {
+    let user = logged_in_user()?;
+    AsyncUserService::set_scheduling_preferences(&user, working_hours.as_ref(), meeting_buffer)
+        .await
+        .map(|_| ())
+}
@@ -4,22 +4,40 @@
 
 use std::env;
 use std::ops::DerefMut;
-use std::thread;
+use std::sync::Arc;
 
 use api::{
+    alarm_api_controller::{dismiss_alarm, get_alarms, set_alarms, snooze_alarm},
     auth_api_controller::{attempt_login, attempt_signup, is_valid_session, logout, user_exists},
-    calendar_api_controller::{get_calendar_of_current_user, store_new_calendar},
+    calendar_api_controller::{
+        export_ics, get_calendar_components, get_calendar_of_current_user, get_components_in_range,
+        get_conflict_mode, import_ics, import_ics_into, set_conflict_mode, store_new_calendar,
+    },
+    component_api_controller::{
+        cancel_occurrence, check_occurrence, check_typed_component, edit_occurrence,
+        get_typed_component, store_typed_component,
+    },
+    free_busy_api_controller::{export_free_busy, get_free_busy},
+    scheduling_api_controller::find_meeting_slots,
+    user_api_controller::{
+        get_meeting_buffer, get_time_zone, get_working_hours, set_scheduling_preferences,
+        set_time_zone,
+    },
 };
 use shared::{
+    alarms::{self, TauriNotifier},
     auth_util,
     current::{
         driver, get_session_type, set_app_handle as set_shared_app_handle, set_error_queue,
         SessionType,
     },
+    db::migrations::Migrations,
     errors::error_queue::ErrorQueue,
+    notifications,
 };
 
 use dotenv::dotenv;
+use pg_driver::OfflineError;
 use tauri::{AppHandle, Manager, Runtime};
 
 mod api;
@@ -36,6 +54,31 @@ fn main() {
             is_valid_session,
             set_app_handle,
             get_calendar_of_current_user,
+            get_calendar_components,
+            get_components_in_range,
+            import_ics,
+            import_ics_into,
+            export_ics,
+            get_conflict_mode,
+            set_conflict_mode,
+            edit_occurrence,
+            check_occurrence,
+            cancel_occurrence,
+            get_typed_component,
+            store_typed_component,
+            check_typed_component,
+            get_free_busy,
+            export_free_busy,
+            find_meeting_slots,
+            get_time_zone,
+            set_time_zone,
+            get_working_hours,
+            get_meeting_buffer,
+            set_scheduling_preferences,
+            get_alarms,
+            set_alarms,
+            snooze_alarm,
+            dismiss_alarm,
             user_exists,
             store_new_calendar,
         ])
@@ -49,19 +92,51 @@ fn main() {
 
 pub fn init() {
     dotenv().ok();
-    env::set_var("RUST_LOG", "info");
-    env_logger::init();
+    let log_level = config::load()
+        .and_then(|settings| settings.get_or("app.log_level", String::from("info")))
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        });
+    env_logger::Builder::new().parse_filters(&log_level).init();
     let error_queue = ErrorQueue::new();
     set_error_queue(error_queue);
 
-    thread::spawn(move || {
-        let driver = driver();
-    });
+    // Bring the schema up to date before anything else uses the database
+    if let Err(e) = migrate() {
+        log::error!("Could not migrate the database: {:#}", e);
+        std::process::exit(1);
+    }
+
+    // Keep the calendars in sync with the changes of other clients
+    if let Err(e) = notifications::listen() {
+        log::error!("Could not listen for database changes: {:#}", e);
+    }
+
+    // Remind the user of their events and to-dos
+    if let Err(e) = alarms::start(Arc::new(TauriNotifier)) {
+        log::error!("Could not start the alarm scheduler: {:#}", e);
+    }
+}
+
+/// Applies the pending migrations. If the database is offline, it is migrated on the next start
+/// and the app works from its cache until then.
+fn migrate() -> anyhow::Result<()> {
+    let mut driver = driver().map_err(anyhow::Error::msg)?;
+    match Migrations::run(driver.deref_mut()) {
+        Err(e) if e.is::<OfflineError>() => {
+            log::warn!("The database is offline, it is migrated on the next start");
+            Ok(())
+        }
+        res => res.map(|_| ()),
+    }
 }
 
 fn elim_session_if_temp() {
     if let SessionType::TEMPORARY = get_session_type() {
-        auth_util::AuthUtil::logout(driver().lock().unwrap().deref_mut());
+        if let Ok(mut driver) = driver() {
+            auth_util::AuthUtil::logout(driver.deref_mut());
+        }
     }
 }
 
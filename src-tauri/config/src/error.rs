@@ -0,0 +1,37 @@
+use std::fmt;
+
+/// A setting that is missing or has an invalid value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// The setting that is wrong, like `database.port`. None if the whole file is unreadable.
+    key: Option<String>,
+    /// Where the wrong value is set, like the config file or an environment variable.
+    source: String,
+    message: String,
+}
+
+impl ConfigError {
+    pub fn new(key: Option<&str>, source: impl Into<String>, message: impl fmt::Display) -> Self {
+        Self {
+            key: key.map(String::from),
+            source: source.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// The setting that is wrong.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.key {
+            Some(key) => write!(f, "{}: `{}` {}", self.source, key, self.message),
+            None => write!(f, "{}: {}", self.source, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
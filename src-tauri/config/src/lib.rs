@@ -0,0 +1,30 @@
+use std::sync::OnceLock;
+
+pub mod error;
+pub mod settings;
+
+pub use error::ConfigError;
+pub use settings::{data_dir, default_path, Settings};
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Loads the settings on the first call, see `Settings::load`. Later calls return the same
+/// settings.
+///
+/// # Errors
+/// If the settings are invalid. They are read again on the next call then.
+pub fn load() -> Result<&'static Settings, ConfigError> {
+    if let Some(settings) = SETTINGS.get() {
+        return Ok(settings);
+    }
+    let settings = Settings::load()?;
+    Ok(SETTINGS.get_or_init(|| settings))
+}
+
+/// The loaded settings.
+///
+/// # Panics
+/// If the settings are invalid. Call `load` on startup to report that properly.
+pub fn get() -> &'static Settings {
+    load().unwrap_or_else(|e| panic!("Invalid configuration: {}", e))
+}
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use toml::{Table, Value};
+
+use crate::error::ConfigError;
+
+/// Every known setting and the environment variable overriding it.
+const KEYS: &[(&str, &str)] = &[
+    ("app.log_level", "SCHEDULEFLOW_LOG_LEVEL"),
+    ("cache.ttl", "SCHEDULEFLOW_CACHE_TTL"),
+    ("database.backend", "DB_BACKEND"),
+    ("database.host", "PSQL_IP"),
+    ("database.port", "PSQL_PORT"),
+    ("database.name", "PSQL_NAME"),
+    ("database.user", "PSQL_USER"),
+    ("database.password", "PSQL_PASS"),
+    ("database.path", "SQLITE_PATH"),
+    ("database.tls.mode", "PSQL_SSL_MODE"),
+    ("database.tls.root_cert", "PSQL_SSL_ROOT_CERT"),
+    ("database.tls.cert", "PSQL_SSL_CERT"),
+    ("database.tls.key", "PSQL_SSL_KEY"),
+    ("database.pool.size", "PSQL_POOL_SIZE"),
+    ("database.pool.timeout", "PSQL_POOL_TIMEOUT"),
+    ("database.pool.health_check", "PSQL_POOL_HEALTH_CHECK"),
+];
+
+/// Where a setting got its value from.
+#[derive(Debug, Clone)]
+enum Origin {
+    File,
+    Env(&'static str),
+}
+
+#[derive(Debug, Clone)]
+struct Setting {
+    value: String,
+    origin: Origin,
+}
+
+/// The settings of the app, the tui and the database driver.
+///
+/// They are read from a TOML file, see `default_path`. Its top level sections apply to every
+/// profile, the sections of the selected `[profiles.<name>]` table override them. Environment
+/// variables, like the ones in a `.env` file, override both.
+///
+/// ```toml
+/// profile = "dev"
+///
+/// [database]
+/// name = "scheduleflow"
+/// user = "scheduleflow"
+///
+/// [profiles.dev.database]
+/// host = "localhost"
+///
+/// [profiles.prod.database]
+/// host = "db.example.com"
+/// tls.mode = "verify-full"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The file the settings were read from.
+    path: PathBuf,
+    profile: Option<String>,
+    values: HashMap<&'static str, Setting>,
+}
+
+impl Settings {
+    /// Reads the settings from the config file and the environment.
+    ///
+    /// * `SCHEDULEFLOW_CONFIG` - The config file, defaults to `default_path`. It is fine if it
+    ///   does not exist.
+    /// * `SCHEDULEFLOW_PROFILE` - The profile to use, overriding the `profile` of the file.
+    ///
+    /// # Errors
+    /// If the file can't be parsed, contains unknown settings or names an unknown profile.
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = std::env::var_os("SCHEDULEFLOW_CONFIG")
+            .map(PathBuf::from)
+            .unwrap_or_else(default_path);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(ConfigError::new(None, path.display().to_string(), e)),
+        };
+        let profile = std::env::var("SCHEDULEFLOW_PROFILE").ok();
+
+        Self::parse(&text, &path, profile.as_deref(), |var| {
+            std::env::var(var).ok()
+        })
+    }
+
+    /// Reads the settings from the given file content.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The TOML config.
+    /// * `path` - The file the config was read from, for error messages.
+    /// * `profile` - The profile to use, overriding the `profile` of the config.
+    /// * `env` - Looks up the environment variables overriding the config.
+    pub fn parse(
+        text: &str,
+        path: &Path,
+        profile: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let source = path.display().to_string();
+        let mut root: Table =
+            toml::from_str(text).map_err(|e| ConfigError::new(None, source.clone(), e))?;
+
+        let file_profile = match root.remove("profile") {
+            Some(Value::String(profile)) => Some(profile),
+            Some(_) => {
+                return Err(ConfigError::new(
+                    Some("profile"),
+                    source,
+                    "must be a string",
+                ))
+            }
+            None => None,
+        };
+        let mut profiles = match root.remove("profiles") {
+            Some(Value::Table(profiles)) => profiles,
+            Some(_) => {
+                return Err(ConfigError::new(
+                    Some("profiles"),
+                    source,
+                    "must be a table",
+                ))
+            }
+            None => Table::new(),
+        };
+
+        let mut settings = Self {
+            path: path.to_path_buf(),
+            profile: profile.map(String::from).or(file_profile),
+            values: HashMap::new(),
+        };
+        settings.read_table(root, "", "", &source)?;
+
+        if let Some(name) = settings.profile.clone() {
+            match profiles.remove(&name) {
+                Some(Value::Table(table)) => {
+                    settings.read_table(table, "", &format!("profiles.{}.", name), &source)?
+                }
+                Some(_) => {
+                    let key = format!("profiles.{}", name);
+                    return Err(ConfigError::new(Some(&key), source, "must be a table"));
+                }
+                None => {
+                    let known = profiles.keys().cloned().collect::<Vec<_>>().join(", ");
+                    let msg = format!("names the unknown profile '{}' (known: {})", name, known);
+                    return Err(ConfigError::new(Some("profile"), source, msg));
+                }
+            }
+        }
+
+        for (key, var) in KEYS {
+            if let Some(value) = env(var).filter(|value| !value.is_empty()) {
+                let origin = Origin::Env(var);
+                settings.values.insert(key, Setting { value, origin });
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// The selected profile, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Reads a setting.
+    ///
+    /// # Errors
+    /// If the value of the setting can't be parsed. The error names the setting and where its
+    /// value comes from.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>, ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        debug_assert!(
+            KEYS.iter().any(|(k, _)| *k == key),
+            "Unknown setting {}",
+            key
+        );
+
+        let setting = match self.values.get(key) {
+            Some(setting) => setting,
+            None => return Ok(None),
+        };
+        setting
+            .value
+            .parse()
+            .map(Some)
+            .map_err(|e| match setting.origin {
+                Origin::File => ConfigError::new(
+                    Some(key),
+                    self.path.display().to_string(),
+                    format!("is invalid: {}", e),
+                ),
+                Origin::Env(var) => {
+                    ConfigError::new(Some(var), "environment", format!("is invalid: {}", e))
+                }
+            })
+    }
+
+    /// Reads a setting, falling back to the given default if it is not set.
+    pub fn get_or<T>(&self, key: &str, default: T) -> Result<T, ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+
+    /// Reads a setting that has to be set.
+    ///
+    /// # Errors
+    /// If the setting is not set or can't be parsed.
+    pub fn require<T>(&self, key: &str) -> Result<T, ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get(key)?.ok_or_else(|| {
+            let var = KEYS
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map_or("", |(_, var)| var);
+            let msg = format!("is not set, set it in the config file or with {}", var);
+            ConfigError::new(Some(key), self.path.display().to_string(), msg)
+        })
+    }
+
+    /// Stores the values of the given table, rejecting settings that don't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The key of the table, relative to the profile.
+    /// * `location` - Where the table is in the file, prepended to keys in error messages.
+    fn read_table(
+        &mut self,
+        table: Table,
+        prefix: &str,
+        location: &str,
+        source: &str,
+    ) -> Result<(), ConfigError> {
+        for (name, value) in table {
+            let key = format!("{}{}", prefix, name);
+            let err = |msg| ConfigError::new(Some(&format!("{}{}", location, key)), source, msg);
+
+            match value {
+                Value::Table(table) => {
+                    let nested = format!("{}.", key);
+                    if !KEYS.iter().any(|(k, _)| k.starts_with(&nested)) {
+                        return Err(err("is not a known setting"));
+                    }
+                    self.read_table(table, &nested, location, source)?;
+                }
+                Value::Array(_) => return Err(err("must be a single value")),
+                value => {
+                    let key = match KEYS.iter().find(|(k, _)| *k == key) {
+                        Some((key, _)) => key,
+                        None => return Err(err("is not a known setting")),
+                    };
+                    let value = match value {
+                        Value::String(value) => value,
+                        value => value.to_string(),
+                    };
+                    let origin = Origin::File;
+                    self.values.insert(key, Setting { value, origin });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The default config file, `scheduleflow/config.toml` inside `$XDG_CONFIG_HOME`, `%APPDATA%`
+/// or `~/.config`.
+pub fn default_path() -> PathBuf {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default();
+    config_dir.join("scheduleflow").join("config.toml")
+}
+
+/// The directory the app keeps its local data in, `scheduleflow` inside `$XDG_DATA_HOME`,
+/// `%APPDATA%` or `~/.local/share`.
+pub fn data_dir() -> PathBuf {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_default();
+    data_dir.join("scheduleflow")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        profile = "dev"
+
+        [database]
+        name = "scheduleflow"
+        port = 5432
+
+        [profiles.dev.database]
+        host = "localhost"
+
+        [profiles.prod.database]
+        host = "db.example.com"
+        tls.mode = "verify-full"
+    "#;
+
+    fn parse(text: &str, profile: Option<&str>, env: &[(&str, &str)]) -> Settings {
+        try_parse(text, profile, env).unwrap()
+    }
+
+    fn try_parse(
+        text: &str,
+        profile: Option<&str>,
+        env: &[(&str, &str)],
+    ) -> Result<Settings, ConfigError> {
+        Settings::parse(text, Path::new("config.toml"), profile, |var| {
+            env.iter()
+                .find(|(name, _)| *name == var)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_profiles() {
+        let dev = parse(CONFIG, None, &[]);
+        assert_eq!(dev.profile(), Some("dev"));
+        assert_eq!(dev.require::<String>("database.host").unwrap(), "localhost");
+        assert_eq!(dev.require::<u16>("database.port").unwrap(), 5432);
+        assert_eq!(dev.get::<String>("database.tls.mode").unwrap(), None);
+
+        let prod = parse(CONFIG, Some("prod"), &[]);
+        assert_eq!(
+            prod.require::<String>("database.host").unwrap(),
+            "db.example.com"
+        );
+        assert_eq!(
+            prod.require::<String>("database.name").unwrap(),
+            "scheduleflow"
+        );
+        assert_eq!(
+            prod.get::<String>("database.tls.mode").unwrap().as_deref(),
+            Some("verify-full")
+        );
+
+        let err = try_parse(CONFIG, Some("staging"), &[]).unwrap_err();
+        assert_eq!(err.key(), Some("profile"));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let settings = parse(CONFIG, None, &[("PSQL_IP", "10.0.0.2"), ("PSQL_PORT", "x")]);
+        assert_eq!(
+            settings.require::<String>("database.host").unwrap(),
+            "10.0.0.2"
+        );
+
+        let err = settings.get::<u16>("database.port").unwrap_err();
+        assert_eq!(err.key(), Some("PSQL_PORT"));
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        let err = try_parse("[database]\nprot = 5432", None, &[]).unwrap_err();
+        assert_eq!(err.key(), Some("database.prot"));
+
+        let err = try_parse(
+            CONFIG.replace("tls.mode", "tls.mdoe").as_str(),
+            Some("prod"),
+            &[],
+        )
+        .unwrap_err();
+        assert_eq!(err.key(), Some("profiles.prod.database.tls.mdoe"));
+
+        let settings = parse("[database]\nport = 99999", None, &[]);
+        let err = settings.get::<u16>("database.port").unwrap_err();
+        assert_eq!(err.key(), Some("database.port"));
+
+        let err = settings.require::<String>("database.user").unwrap_err();
+        assert_eq!(err.key(), Some("database.user"));
+    }
+}
@@ -2,7 +2,9 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ItemFn, LitStr};
+use syn::{parse_macro_input, DeriveInput, ItemFn, LitStr};
+
+mod table;
 
 #[proc_macro_attribute]
 pub fn log_message(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -37,16 +39,71 @@ pub fn bench_message(attr: TokenStream, item: TokenStream) -> TokenStream {
     let message_str = message.value();
     let msg = message_str.as_str();
 
-    let expanded = quote! {
-         #fn_vis fn #fn_name(#fn_inputs) #fn_output {
-            use crate::bencher::Bencher;
-            let mut bencher = Bencher::new_msg(#msg);
-            bencher.start();
-            let result = (|| #fn_block)();
-            bencher.stop();
-            result
+    // An async body can't run inside a closure, so it is awaited as an async block instead
+    let expanded = if input_fn.sig.asyncness.is_some() {
+        quote! {
+             #fn_vis async fn #fn_name(#fn_inputs) #fn_output {
+                use crate::bencher::Bencher;
+                let mut bencher = Bencher::new_msg(#msg);
+                bencher.start();
+                let result = async move #fn_block.await;
+                bencher.stop();
+                result
+            }
+        }
+    } else {
+        quote! {
+             #fn_vis fn #fn_name(#fn_inputs) #fn_output {
+                use crate::bencher::Bencher;
+                let mut bencher = Bencher::new_msg(#msg);
+                bencher.start();
+                let result = (|| #fn_block)();
+                bencher.stop();
+                result
+            }
         }
     };
 
     TokenStream::from(expanded)
 }
+
+/// Implements `Table` for the repository of a model, mapping the model's fields to columns.
+///
+/// # Attributes
+///
+/// On the struct:
+/// * `name` - The name of the table.
+/// * `repository` - The repository the `Table` impl is generated for.
+/// * `fk_name` - The column other tables reference this table by, if any. `ForeignKey` is only
+///   implemented with it, so tables referencing a table without one don't compile.
+///
+/// On the fields:
+/// * `primary_key` - The column is left out when updating an entry.
+/// * `column` - The column name, if it differs from the field name.
+/// * `references` - The repository of the table this column is a foreign key of. The column is
+///   named like that table's foreign key.
+/// * `with` - A module with `to_sql` and `from_sql` functions, for fields the driver can't
+///   store as they are.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(Table)]
+/// #[table(name = "clients", repository = "ClientRepository", fk_name = "client_uuid")]
+/// pub struct Client {
+///     #[table(primary_key)]
+///     uuid: Uuid,
+///     #[table(references = "UserRepository")]
+///     user_uuid: Uuid,
+///     #[table(column = "public_key", with = "pub_key_column")]
+///     pub_key: VerifyingKey,
+/// }
+/// ```
+#[proc_macro_derive(Table, attributes(table))]
+pub fn derive_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match table::expand(input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => TokenStream::from(e.to_compile_error()),
+    }
+}
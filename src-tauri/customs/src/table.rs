@@ -0,0 +1,344 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Ident, Lit, Meta, NestedMeta, Path};
+
+/// The settings of the `#[table(...)]` attribute on the model struct.
+struct TableAttrs {
+    name: String,
+    repository: Path,
+    fk_name: Option<String>,
+}
+
+/// A field of the model struct and the settings of its `#[table(...)]` attribute.
+struct Column {
+    field: Ident,
+    /// The column name, defaults to the field name.
+    name: String,
+    primary_key: bool,
+    /// The repository of the table this column references. The column is named after its
+    /// foreign key then.
+    references: Option<Path>,
+    /// A module with `to_sql` and `from_sql` functions converting the field from and to a type
+    /// the driver supports.
+    with: Option<Path>,
+}
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let model = &input.ident;
+    let attrs = parse_table_attrs(&input)?;
+    let columns = parse_columns(&input)?;
+
+    let repository = &attrs.repository;
+    let name = &attrs.name;
+    // Only tables with an fk_name can be referenced, others fail to compile then
+    let foreign_key = attrs.fk_name.as_ref().map(|fk_name| {
+        quote! {
+            impl crate::db::db_actions::ForeignKey for #repository {
+                fn get_fk_uuid_name() -> String {
+                    String::from(#fk_name)
+                }
+            }
+        }
+    });
+
+    let col_names = columns.iter().map(column_name).collect::<Vec<_>>();
+    let vals = columns.iter().map(column_val).collect::<Vec<_>>();
+    let (col_names_no_id, vals_no_id): (Vec<_>, Vec<_>) = columns
+        .iter()
+        .zip(col_names.iter().zip(vals.iter()))
+        .filter(|(col, _)| !col.primary_key)
+        .map(|(_, pair)| pair)
+        .unzip();
+    let reads = columns.iter().map(column_read);
+
+    Ok(quote! {
+        impl crate::db::db_actions::Table<#model> for #repository {
+            fn get_name() -> String {
+                String::from(#name)
+            }
+
+            fn get_fmt_cols() -> String {
+                vec![#(#col_names),*].join(", ")
+            }
+
+            fn get_fmt_cols_no_id() -> String {
+                vec![#(#col_names_no_id),*].join(", ")
+            }
+
+            fn get_vals(model: &#model) -> Vec<pg_driver::SqlParam> {
+                vec![#(Box::new(#vals)),*]
+            }
+
+            fn get_vals_no_id(model: &#model) -> Vec<pg_driver::SqlParam> {
+                vec![#(Box::new(#vals_no_id)),*]
+            }
+
+            fn from_row(row: &pg_driver::Row) -> #model {
+                #model {
+                    #(#reads),*
+                }
+            }
+        }
+
+        #foreign_key
+    })
+}
+
+fn column_name(col: &Column) -> TokenStream {
+    match &col.references {
+        Some(repository) => quote! {
+            <#repository as crate::db::db_actions::ForeignKey>::get_fk_uuid_name()
+        },
+        None => {
+            let name = &col.name;
+            quote! { String::from(#name) }
+        }
+    }
+}
+
+fn column_val(col: &Column) -> TokenStream {
+    let field = &col.field;
+    match &col.with {
+        Some(with) => quote! { #with::to_sql(&model.#field) },
+        None => quote! { model.#field.clone() },
+    }
+}
+
+fn column_read(col: &Column) -> TokenStream {
+    let field = &col.field;
+    let name = column_name(col);
+    match &col.with {
+        Some(with) => quote! { #field: #with::from_sql(row.get(#name)) },
+        None => quote! { #field: row.get(#name) },
+    }
+}
+
+fn parse_table_attrs(input: &DeriveInput) -> syn::Result<TableAttrs> {
+    let mut name = None;
+    let mut repository = None;
+    let mut fk_name = None;
+
+    for meta in table_metas(&input.attrs)? {
+        match &meta {
+            Meta::NameValue(nv) if nv.path.is_ident("name") => name = Some(lit_str(&nv.lit)?),
+            Meta::NameValue(nv) if nv.path.is_ident("repository") => {
+                repository = Some(lit_path(&nv.lit)?)
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("fk_name") => fk_name = Some(lit_str(&nv.lit)?),
+            _ => return Err(syn::Error::new_spanned(meta, "Unknown table attribute")),
+        }
+    }
+
+    let missing =
+        |key| syn::Error::new_spanned(&input.ident, format!("Missing #[table({} = \"...\")]", key));
+    Ok(TableAttrs {
+        name: name.ok_or_else(|| missing("name"))?,
+        repository: repository.ok_or_else(|| missing("repository"))?,
+        fk_name,
+    })
+}
+
+fn parse_columns(input: &DeriveInput) -> syn::Result<Vec<Column>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Table can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Table can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut columns = vec![];
+    for field in fields {
+        let ident = field.ident.clone().unwrap();
+        let mut col = Column {
+            name: ident.to_string(),
+            field: ident,
+            primary_key: false,
+            references: None,
+            with: None,
+        };
+
+        for meta in table_metas(&field.attrs)? {
+            match &meta {
+                Meta::Path(path) if path.is_ident("primary_key") => col.primary_key = true,
+                Meta::NameValue(nv) if nv.path.is_ident("column") => col.name = lit_str(&nv.lit)?,
+                Meta::NameValue(nv) if nv.path.is_ident("references") => {
+                    col.references = Some(lit_path(&nv.lit)?)
+                }
+                Meta::NameValue(nv) if nv.path.is_ident("with") => {
+                    col.with = Some(lit_path(&nv.lit)?)
+                }
+                _ => return Err(syn::Error::new_spanned(meta, "Unknown column attribute")),
+            }
+        }
+        columns.push(col);
+    }
+
+    Ok(columns)
+}
+
+/// The settings of all `#[table(...)]` attributes in the given list.
+fn table_metas(attrs: &[syn::Attribute]) -> syn::Result<Vec<Meta>> {
+    let mut metas = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("table")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => metas.push(meta),
+                        NestedMeta::Lit(lit) => {
+                            return Err(syn::Error::new_spanned(lit, "Expected a table setting"))
+                        }
+                    }
+                }
+            }
+            meta => return Err(syn::Error::new_spanned(meta, "Expected #[table(...)]")),
+        }
+    }
+    Ok(metas)
+}
+
+fn lit_str(lit: &Lit) -> syn::Result<String> {
+    match lit {
+        Lit::Str(s) => Ok(s.value()),
+        _ => Err(syn::Error::new_spanned(lit, "Expected a string")),
+    }
+}
+
+fn lit_path(lit: &Lit) -> syn::Result<Path> {
+    match lit {
+        Lit::Str(s) => s.parse(),
+        _ => Err(syn::Error::new_spanned(lit, "Expected a path as string")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn expanded(input: DeriveInput) -> String {
+        expand(input).unwrap().to_string()
+    }
+
+    fn contains(expanded: &str, tokens: TokenStream) {
+        let tokens = tokens.to_string();
+        assert!(expanded.contains(&tokens), "{} not in {}", tokens, expanded);
+    }
+
+    #[test]
+    fn test_columns() {
+        let expanded = expanded(parse_quote! {
+            #[table(name = "clients", repository = "ClientRepository")]
+            struct Client {
+                #[table(primary_key)]
+                uuid: Uuid,
+                #[table(column = "device")]
+                device_name: String,
+                #[table(references = "UserRepository")]
+                user_uuid: Uuid,
+                #[table(column = "public_key", with = "pub_key_column")]
+                pub_key: VerifyingKey,
+            }
+        });
+
+        let user_uuid = quote! {
+            <UserRepository as crate::db::db_actions::ForeignKey>::get_fk_uuid_name()
+        };
+        contains(
+            &expanded,
+            quote! {
+                fn get_fmt_cols() -> String {
+                    vec![
+                        String::from("uuid"),
+                        String::from("device"),
+                        #user_uuid,
+                        String::from("public_key")
+                    ].join(", ")
+                }
+            },
+        );
+        contains(
+            &expanded,
+            quote! {
+                fn get_vals_no_id(model: &Client) -> Vec<pg_driver::SqlParam> {
+                    vec![
+                        Box::new(model.device_name.clone()),
+                        Box::new(model.user_uuid.clone()),
+                        Box::new(pub_key_column::to_sql(&model.pub_key))
+                    ]
+                }
+            },
+        );
+        contains(
+            &expanded,
+            quote! {
+                Client {
+                    uuid: row.get(String::from("uuid")),
+                    device_name: row.get(String::from("device")),
+                    user_uuid: row.get(#user_uuid),
+                    pub_key: pub_key_column::from_sql(row.get(String::from("public_key")))
+                }
+            },
+        );
+        assert!(!expanded.contains("impl crate :: db :: db_actions :: ForeignKey"));
+    }
+
+    #[test]
+    fn test_fk_name() {
+        let expanded = expanded(parse_quote! {
+            #[table(name = "users", repository = "UserRepository", fk_name = "user_uuid")]
+            struct User {
+                #[table(primary_key)]
+                uuid: Uuid,
+            }
+        });
+
+        contains(
+            &expanded,
+            quote! {
+                impl crate::db::db_actions::ForeignKey for UserRepository {
+                    fn get_fk_uuid_name() -> String {
+                        String::from("user_uuid")
+                    }
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn test_invalid_attributes() {
+        let missing = expand(parse_quote! {
+            #[table(repository = "UserRepository")]
+            struct User {
+                uuid: Uuid,
+            }
+        });
+        assert_eq!(
+            missing.err().unwrap().to_string(),
+            "Missing #[table(name = \"...\")]"
+        );
+
+        let unknown = expand(parse_quote! {
+            #[table(name = "users", repository = "UserRepository")]
+            struct User {
+                #[table(foreign)]
+                uuid: Uuid,
+            }
+        });
+        assert_eq!(
+            unknown.err().unwrap().to_string(),
+            "Unknown column attribute"
+        );
+    }
+}
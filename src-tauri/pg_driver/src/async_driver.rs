@@ -0,0 +1,284 @@
+use crate::backend::{AsyncStorageBackend, BackendKind, DriverConfig};
+use crate::health::{
+    AbortedTransactionError, ConnectionHealth, ConnectionState, NotSentError, OfflineError,
+    ReconnectPolicy,
+};
+use crate::value::{Row, ToSql};
+
+/// The async counterpart of `PgDriver`, for callers that must not block their thread, like the
+/// Tauri commands. It reconnects and reports its state the same way.
+pub struct AsyncPgDriver {
+    /// The database the statements are executed on.
+    backend: Box<dyn AsyncStorageBackend>,
+    /// The amount of nested transactions currently open, 0 if none.
+    tx_depth: usize,
+    /// Whether the open transaction was lost with the connection, see
+    /// `AbortedTransactionError`.
+    aborted: bool,
+    health: ConnectionHealth,
+}
+
+impl AsyncPgDriver {
+    /// Sets up the database driver for the given backend configuration.
+    pub fn from_config(config: &DriverConfig) -> Self {
+        Self::new(config.async_backend())
+    }
+
+    /// Sets up the database driver for the given backend.
+    pub fn new(backend: Box<dyn AsyncStorageBackend>) -> Self {
+        Self {
+            backend,
+            tx_depth: 0,
+            aborted: false,
+            health: ConnectionHealth::new(ReconnectPolicy::default()),
+        }
+    }
+
+    /// Replaces the default reconnect policy of this driver.
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.health = ConnectionHealth::new(policy);
+        self
+    }
+
+    /// The kind of database this driver talks to.
+    pub fn kind(&self) -> BackendKind {
+        self.backend.kind()
+    }
+
+    /// Whether this driver could reach the database the last time it tried.
+    pub fn state(&self) -> ConnectionState {
+        self.health.state()
+    }
+
+    /// Initializes the database connection client.
+    ///
+    /// # Errors
+    /// If the database cannot be reached, the driver goes offline, which is reported to the
+    /// `health` listeners. Queries reconnect later on.
+    pub async fn connect(&mut self) -> &mut Self {
+        match self.backend.connect().await {
+            Ok(()) => self.health.connected(),
+            Err(e) => {
+                log::warn!("Could not connect to the database: {:#}", e);
+                self.health.offline();
+            }
+        }
+
+        self
+    }
+
+    /// Whether this driver has an open connection.
+    pub fn is_connected(&self) -> bool {
+        self.backend.is_connected()
+    }
+
+    /// Checks whether the connection of this driver is still usable.
+    pub async fn is_healthy(&mut self) -> bool {
+        self.backend.is_healthy().await
+    }
+
+    /// Executes a query on the database, see `PgDriver::exec`.
+    pub async fn exec(
+        &mut self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> anyhow::Result<Vec<Row>> {
+        let params = params.iter().map(|p| p.to_value()).collect::<Vec<_>>();
+
+        self.check_transaction()?;
+        self.ensure_connected().await?;
+        match self.backend.query(query, &params).await {
+            Err(e) if self.tx_depth == 0 && e.is::<NotSentError>() => {
+                self.ensure_connected().await?;
+                self.backend.query(query, &params).await
+            }
+            res => {
+                self.abort_lost_transaction();
+                res
+            }
+        }
+    }
+
+    /// Opens a transaction, see `PgDriver::begin`.
+    pub async fn begin(&mut self) -> anyhow::Result<()> {
+        let stmt = match self.tx_depth {
+            0 => String::from("BEGIN"),
+            depth => format!("SAVEPOINT sp_{}", depth),
+        };
+        self.batch(&stmt).await?;
+        self.tx_depth += 1;
+        Ok(())
+    }
+
+    /// Commits the innermost open transaction, see `PgDriver::commit`.
+    pub async fn commit(&mut self) -> anyhow::Result<()> {
+        let stmt = match self.tx_depth {
+            0 => anyhow::bail!("There is no open transaction to commit"),
+            1 => String::from("COMMIT"),
+            depth => format!("RELEASE SAVEPOINT sp_{}", depth - 1),
+        };
+        let aborted = self.aborted;
+        self.end_transaction(&stmt).await?;
+        anyhow::ensure!(!aborted, AbortedTransactionError);
+        Ok(())
+    }
+
+    /// Rolls back the innermost open transaction, see `PgDriver::rollback`.
+    pub async fn rollback(&mut self) -> anyhow::Result<()> {
+        let stmt = match self.tx_depth {
+            0 => anyhow::bail!("There is no open transaction to roll back"),
+            1 => String::from("ROLLBACK"),
+            depth => format!("ROLLBACK TO SAVEPOINT sp_{}", depth - 1),
+        };
+        self.end_transaction(&stmt).await
+    }
+
+    /// Closes the innermost open transaction, see `PgDriver::end_transaction`.
+    async fn end_transaction(&mut self, stmt: &str) -> anyhow::Result<()> {
+        let res = match self.aborted {
+            true => Ok(()),
+            false => self.batch(stmt).await,
+        };
+        self.tx_depth = self.tx_depth.saturating_sub(1);
+        if self.tx_depth == 0 {
+            self.aborted = false;
+        }
+        res
+    }
+
+    /// Whether a transaction is currently open on this driver.
+    pub fn in_transaction(&self) -> bool {
+        self.tx_depth > 0
+    }
+
+    /// Runs the given operations inside a transaction, see `PgDriver::transaction`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// driver
+    ///     .transaction(async |driver| {
+    ///         AsyncCalendarRepository::store(driver, &calendar).await?;
+    ///         AsyncUserCalendarCombinationRepository::store(driver, &combination).await
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn transaction<T, E>(
+        &mut self,
+        f: impl AsyncFnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        E: From<anyhow::Error>,
+    {
+        self.begin().await?;
+        match f(self).await {
+            Ok(res) => {
+                self.commit().await?;
+                Ok(res)
+            }
+            Err(e) => {
+                // The original error tells more than a failing rollback would
+                let _ = self.rollback().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Rolls back every open transaction, e.g. before handing the driver to someone else.
+    pub(crate) async fn abort_transactions(&mut self) {
+        if self.tx_depth > 0 {
+            self.tx_depth = 0;
+            if !std::mem::take(&mut self.aborted) {
+                let _ = self.batch("ROLLBACK").await;
+            }
+        }
+    }
+
+    /// Executes statements without parameters, see `PgDriver::batch`.
+    async fn batch(&mut self, stmt: &str) -> anyhow::Result<()> {
+        self.check_transaction()?;
+        self.ensure_connected().await?;
+        let res = self.backend.batch(stmt).await;
+        self.abort_lost_transaction();
+        res
+    }
+
+    /// Fails if the open transaction was lost with the connection, see
+    /// `PgDriver::check_transaction`.
+    fn check_transaction(&mut self) -> anyhow::Result<()> {
+        self.abort_lost_transaction();
+        anyhow::ensure!(!self.aborted, AbortedTransactionError);
+        Ok(())
+    }
+
+    /// The transaction is lost with the connection, so no statement may run until it is rolled
+    /// back.
+    fn abort_lost_transaction(&mut self) {
+        if self.tx_depth > 0 && !self.backend.is_connected() {
+            self.aborted = true;
+        }
+    }
+
+    /// Reopens the connection if it is closed, see `PgDriver::ensure_connected`.
+    async fn ensure_connected(&mut self) -> anyhow::Result<()> {
+        if self.backend.is_connected() {
+            return Ok(());
+        }
+        if !self.health.is_retry_due() {
+            return Err(OfflineError.into());
+        }
+
+        for attempt in 0..self.health.start_reconnect() {
+            if attempt > 0 {
+                tokio::time::sleep(self.health.next_backoff()).await;
+            }
+            match self.backend.connect().await {
+                Ok(()) => {
+                    self.health.connected();
+                    return Ok(());
+                }
+                Err(e) => log::warn!("Reconnecting to the database failed: {:#}", e),
+            }
+        }
+
+        self.health.offline();
+        Err(OfflineError.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::AsyncSqliteBackend;
+
+    #[tokio::test]
+    async fn test_sqlite_transaction() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", uuid::Uuid::new_v4()));
+        let mut driver = AsyncPgDriver::new(Box::new(AsyncSqliteBackend::new(path.clone())));
+        driver.connect().await;
+        driver.batch("CREATE TABLE t (id INTEGER)").await.unwrap();
+
+        let res: anyhow::Result<()> = driver
+            .transaction(async |driver| {
+                driver.exec("INSERT INTO t VALUES ($1)", &[&1]).await?;
+                anyhow::bail!("abort")
+            })
+            .await;
+        assert!(res.is_err());
+
+        driver
+            .transaction(async |driver| {
+                driver.exec("INSERT INTO t VALUES ($1)", &[&2]).await?;
+                anyhow::Ok(())
+            })
+            .await
+            .unwrap();
+
+        let rows = driver.exec("SELECT id FROM t", &[]).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get::<_, i32>("id"), 2);
+
+        drop(driver);
+        let _ = std::fs::remove_file(path);
+    }
+}
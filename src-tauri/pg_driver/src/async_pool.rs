@@ -0,0 +1,129 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use config::{ConfigError, Settings};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{AsyncPgDriver, DriverConfig, PoolConfig};
+
+/// A connection waiting in the pool.
+struct Idle {
+    driver: AsyncPgDriver,
+    since: Instant,
+}
+
+/// The async counterpart of `PgPool`. Checkouts wait without blocking the runtime.
+pub struct AsyncPgPool {
+    driver_config: DriverConfig,
+    config: PoolConfig,
+    idle: Mutex<Vec<Idle>>,
+    /// One permit per connection the pool may hand out.
+    permits: Semaphore,
+}
+
+impl AsyncPgPool {
+    /// Creates a new pool for the given settings, see `PgPool::from_settings`.
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        Ok(Self::with_driver_config(
+            DriverConfig::from_settings(settings)?,
+            PoolConfig::from_settings(settings)?,
+        ))
+    }
+
+    /// Creates a new pool connecting to the given database.
+    pub fn with_driver_config(driver_config: DriverConfig, config: PoolConfig) -> Self {
+        Self {
+            driver_config,
+            permits: Semaphore::new(config.max_size),
+            config,
+            idle: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// Checks out a driver, see `PgPool::get`.
+    ///
+    /// # Errors
+    /// If no driver became available within the configured checkout timeout.
+    pub async fn get(&self) -> anyhow::Result<AsyncPooledDriver<'_>> {
+        let permit = match tokio::time::timeout(
+            self.config.checkout_timeout,
+            self.permits.acquire(),
+        )
+        .await
+        {
+            Ok(permit) => permit?,
+            Err(_) => anyhow::bail!(
+                "Timed out after {:?} waiting for a database connection",
+                self.config.checkout_timeout
+            ),
+        };
+
+        let idle = self.idle.lock().unwrap().pop();
+        let driver = match idle {
+            Some(idle) => {
+                let mut driver = idle.driver;
+                // Drivers can't roll back while being dropped, so it is done before reuse
+                driver.abort_transactions().await;
+                let stale = idle.since.elapsed() >= self.config.health_check_after;
+                if stale && driver.is_connected() && !driver.is_healthy().await {
+                    driver.connect().await;
+                }
+                driver
+            }
+            None => {
+                let mut driver = AsyncPgDriver::from_config(&self.driver_config)
+                    .with_reconnect_policy(self.config.reconnect.clone());
+                driver.connect().await;
+                driver
+            }
+        };
+
+        Ok(AsyncPooledDriver {
+            pool: self,
+            driver: Some(driver),
+            _permit: permit,
+        })
+    }
+
+    fn put_back(&self, driver: AsyncPgDriver) {
+        self.idle.lock().unwrap().push(Idle {
+            driver,
+            since: Instant::now(),
+        });
+    }
+}
+
+/// A driver checked out of an `AsyncPgPool`. Dereferences to the underlying `AsyncPgDriver`.
+pub struct AsyncPooledDriver<'a> {
+    pool: &'a AsyncPgPool,
+    driver: Option<AsyncPgDriver>,
+    /// Released after the driver went back to the pool.
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Deref for AsyncPooledDriver<'_> {
+    type Target = AsyncPgDriver;
+
+    fn deref(&self) -> &AsyncPgDriver {
+        self.driver.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for AsyncPooledDriver<'_> {
+    fn deref_mut(&mut self) -> &mut AsyncPgDriver {
+        self.driver.as_mut().unwrap()
+    }
+}
+
+impl Drop for AsyncPooledDriver<'_> {
+    fn drop(&mut self) {
+        if let Some(driver) = self.driver.take() {
+            self.pool.put_back(driver);
+        }
+    }
+}
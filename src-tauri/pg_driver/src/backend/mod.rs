@@ -0,0 +1,200 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use config::{ConfigError, Settings};
+
+use crate::tls::TlsConfig;
+use crate::value::{Row, Value};
+
+pub mod postgres;
+pub mod sqlite;
+
+pub use self::postgres::{AsyncPostgresBackend, PostgresBackend};
+pub use self::sqlite::{AsyncSqliteBackend, SqliteBackend};
+
+/// The kinds of databases a `PgDriver` can talk to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    Postgres,
+    Sqlite,
+}
+
+impl FromStr for BackendKind {
+    type Err = anyhow::Error;
+
+    fn from_str(kind: &str) -> anyhow::Result<Self> {
+        match kind.to_lowercase().as_str() {
+            "postgres" => Ok(Self::Postgres),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => anyhow::bail!("Unknown backend '{}', expected postgres or sqlite", kind),
+        }
+    }
+}
+
+/// A database the driver executes its statements on. Statements reference their parameters as
+/// `$1`, `$2`, ... regardless of the backend.
+pub trait StorageBackend: Send {
+    fn kind(&self) -> BackendKind;
+
+    /// Opens the connection, replacing the current one.
+    fn connect(&mut self) -> anyhow::Result<()>;
+
+    /// Whether a connection is open. False once the database closed it.
+    fn is_connected(&self) -> bool;
+
+    /// Checks whether the connection is still usable.
+    fn is_healthy(&mut self) -> bool;
+
+    /// Executes a single statement with the given parameters bound.
+    ///
+    /// # Errors
+    /// A `NotSentError`, if the connection turned out to be closed before the statement was
+    /// sent.
+    fn query(&mut self, stmt: &str, params: &[Value]) -> anyhow::Result<Vec<Row>>;
+
+    /// Executes one or multiple statements without parameters.
+    fn batch(&mut self, stmts: &str) -> anyhow::Result<()>;
+}
+
+/// The async counterpart of `StorageBackend`, used by the `AsyncPgDriver`.
+#[async_trait]
+pub trait AsyncStorageBackend: Send {
+    fn kind(&self) -> BackendKind;
+
+    /// Opens the connection, replacing the current one.
+    async fn connect(&mut self) -> anyhow::Result<()>;
+
+    /// Whether a connection is open. False once the database closed it.
+    fn is_connected(&self) -> bool;
+
+    /// Checks whether the connection is still usable.
+    async fn is_healthy(&mut self) -> bool;
+
+    /// Executes a single statement with the given parameters bound.
+    async fn query(&mut self, stmt: &str, params: &[Value]) -> anyhow::Result<Vec<Row>>;
+
+    /// Executes one or multiple statements without parameters.
+    async fn batch(&mut self, stmts: &str) -> anyhow::Result<()>;
+}
+
+/// Where the driver stores its data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriverConfig {
+    /// A PostgreSQL server reached by the given connection string, either a url or
+    /// `key=value` pairs like libpq's.
+    Postgres { url: String, tls: TlsConfig },
+    /// A local SQLite database file, for single-user installs without a database server.
+    Sqlite { path: PathBuf },
+}
+
+impl DriverConfig {
+    /// Reads the database settings.
+    ///
+    /// * `database.backend` - `postgres` (default) or `sqlite`.
+    /// * `database.host`, `database.port`, `database.name`, `database.user`,
+    ///   `database.password` - The postgres connection, the port defaults to 5432.
+    /// * `database.tls.*` - How the postgres connection is encrypted, see
+    ///   `TlsConfig::from_settings`.
+    /// * `database.path` - The SQLite database file, defaults to `scheduleflow.db` in the user's
+    ///   data directory.
+    ///
+    /// # Errors
+    /// If the postgres backend is used and its connection is not configured, or if a setting is
+    /// invalid.
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        let backend = settings.get_or("database.backend", BackendKind::Postgres)?;
+        if backend == BackendKind::Sqlite {
+            let path = settings
+                .get::<PathBuf>("database.path")?
+                .unwrap_or_else(sqlite::default_path);
+            return Ok(Self::Sqlite { path });
+        }
+
+        let host = settings.require::<String>("database.host")?;
+        let port = settings.get_or::<u16>("database.port", 5432)?;
+        let name = settings.require::<String>("database.name")?;
+        let user = settings.require::<String>("database.user")?;
+        let pass = settings.require::<String>("database.password")?;
+        let url = format!(
+            "host={} port={} dbname={} user={} password={}",
+            quote(&host),
+            port,
+            quote(&name),
+            quote(&user),
+            quote(&pass)
+        );
+        Ok(Self::Postgres {
+            url,
+            tls: TlsConfig::from_settings(settings)?,
+        })
+    }
+
+    /// Creates an unconnected backend for this configuration.
+    pub fn backend(&self) -> Box<dyn StorageBackend> {
+        match self {
+            Self::Postgres { url, tls } => Box::new(PostgresBackend::new(url.clone(), tls.clone())),
+            Self::Sqlite { path } => Box::new(SqliteBackend::new(path.clone())),
+        }
+    }
+
+    /// Creates an unconnected async backend for this configuration.
+    pub fn async_backend(&self) -> Box<dyn AsyncStorageBackend> {
+        match self {
+            Self::Postgres { url, tls } => {
+                Box::new(AsyncPostgresBackend::new(url.clone(), tls.clone()))
+            }
+            Self::Sqlite { path } => Box::new(AsyncSqliteBackend::new(path.clone())),
+        }
+    }
+}
+
+/// Quotes a value of a `key=value` connection string, so it may contain spaces and quotes.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn settings(text: &str) -> Settings {
+        Settings::parse(text, Path::new("config.toml"), None, |_| None).unwrap()
+    }
+
+    #[test]
+    fn test_from_settings() {
+        let config = DriverConfig::from_settings(&settings(
+            "[database]\nhost = \"db\"\nname = \"sf\"\nuser = \"sf\"\npassword = \"it's a secret\"",
+        ))
+        .unwrap();
+        let url = match &config {
+            DriverConfig::Postgres { url, .. } => url,
+            _ => panic!("Expected a postgres config"),
+        };
+        let parsed = ::postgres::Config::from_str(url).unwrap();
+        assert_eq!(parsed.get_ports(), &[5432]);
+        assert_eq!(parsed.get_password(), Some("it's a secret".as_bytes()));
+
+        let err = DriverConfig::from_settings(&settings("[database]\nhost = \"db\"")).unwrap_err();
+        assert_eq!(err.key(), Some("database.name"));
+
+        let err =
+            DriverConfig::from_settings(&settings("[database]\nbackend = \"mysql\"")).unwrap_err();
+        assert_eq!(err.key(), Some("database.backend"));
+
+        let config = DriverConfig::from_settings(&settings(
+            "[database]\nbackend = \"sqlite\"\npath = \"a.db\"",
+        ))
+        .unwrap();
+        assert_eq!(
+            config,
+            DriverConfig::Sqlite {
+                path: PathBuf::from("a.db")
+            }
+        );
+    }
+}
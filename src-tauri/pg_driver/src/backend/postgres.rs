@@ -0,0 +1,328 @@
+use std::error::Error;
+use std::future::{poll_fn, Future};
+use std::str::FromStr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::BytesMut;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use postgres::types::{IsNull, Type};
+use postgres::{Client, Config, NoTls};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio_postgres::AsyncMessage;
+use uuid::Uuid;
+
+use super::{AsyncStorageBackend, BackendKind, StorageBackend};
+use crate::health::{NotSentError, OfflineError};
+use crate::notify::Notification;
+use crate::tls::{TlsConfig, TlsMode};
+use crate::value::{Row, Value};
+
+/// How long opening a connection may take before the server is considered unreachable.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A PostgreSQL server.
+pub struct PostgresBackend {
+    url: String,
+    tls: TlsConfig,
+    client: Option<Client>,
+}
+
+impl PostgresBackend {
+    pub fn new(url: String, tls: TlsConfig) -> Self {
+        Self {
+            url,
+            tls,
+            client: None,
+        }
+    }
+}
+
+impl StorageBackend for PostgresBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Postgres
+    }
+
+    fn connect(&mut self) -> anyhow::Result<()> {
+        self.client = None;
+        let mut config = Config::from_str(&self.url)?;
+        config
+            .connect_timeout(CONNECT_TIMEOUT)
+            .ssl_mode(self.tls.ssl_mode());
+        self.client = Some(match self.tls.mode {
+            TlsMode::Disable => config.connect(NoTls)?,
+            _ => config.connect(self.tls.connector()?)?,
+        });
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client
+            .as_ref()
+            .is_some_and(|client| !client.is_closed())
+    }
+
+    fn is_healthy(&mut self) -> bool {
+        match self.client.as_mut() {
+            Some(client) => client.is_valid(Duration::from_secs(5)).is_ok(),
+            None => false,
+        }
+    }
+
+    fn query(&mut self, stmt: &str, params: &[Value]) -> anyhow::Result<Vec<Row>> {
+        let client = match self.client.as_mut() {
+            Some(client) if client.is_closed() => anyhow::bail!(NotSentError),
+            Some(client) => client,
+            None => anyhow::bail!(OfflineError),
+        };
+        let params = params
+            .iter()
+            .map(|val| val as &(dyn postgres::types::ToSql + Sync))
+            .collect::<Vec<_>>();
+
+        client
+            .query(stmt, &params)?
+            .iter()
+            .map(convert_row)
+            .collect()
+    }
+
+    fn batch(&mut self, stmts: &str) -> anyhow::Result<()> {
+        match self.client.as_mut() {
+            Some(client) if client.is_closed() => anyhow::bail!(NotSentError),
+            Some(client) => Ok(client.batch_execute(stmts)?),
+            None => anyhow::bail!(OfflineError),
+        }
+    }
+}
+
+/// A PostgreSQL server, talked to without blocking the async runtime.
+pub struct AsyncPostgresBackend {
+    url: String,
+    tls: TlsConfig,
+    client: Option<tokio_postgres::Client>,
+}
+
+impl AsyncPostgresBackend {
+    pub fn new(url: String, tls: TlsConfig) -> Self {
+        Self {
+            url,
+            tls,
+            client: None,
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncStorageBackend for AsyncPostgresBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Postgres
+    }
+
+    async fn connect(&mut self) -> anyhow::Result<()> {
+        self.client = None;
+        let mut config = tokio_postgres::Config::from_str(&self.url)?;
+        config
+            .connect_timeout(CONNECT_TIMEOUT)
+            .ssl_mode(self.tls.ssl_mode());
+
+        // The connection performs the actual communication with the server
+        let client = match self.tls.mode {
+            TlsMode::Disable => {
+                let (client, connection) = config.connect(NoTls).await?;
+                tokio::spawn(watch(connection));
+                client
+            }
+            _ => {
+                let (client, connection) = config.connect(self.tls.connector()?).await?;
+                tokio::spawn(watch(connection));
+                client
+            }
+        };
+
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client
+            .as_ref()
+            .is_some_and(|client| !client.is_closed())
+    }
+
+    async fn is_healthy(&mut self) -> bool {
+        match self.client.as_ref() {
+            Some(client) => tokio::time::timeout(Duration::from_secs(5), client.simple_query(""))
+                .await
+                .is_ok_and(|res| res.is_ok()),
+            None => false,
+        }
+    }
+
+    async fn query(&mut self, stmt: &str, params: &[Value]) -> anyhow::Result<Vec<Row>> {
+        let client = match self.client.as_ref() {
+            Some(client) if client.is_closed() => anyhow::bail!(NotSentError),
+            Some(client) => client,
+            None => anyhow::bail!(OfflineError),
+        };
+        let params = params
+            .iter()
+            .map(|val| val as &(dyn postgres::types::ToSql + Sync))
+            .collect::<Vec<_>>();
+
+        client
+            .query(stmt, &params)
+            .await?
+            .iter()
+            .map(convert_row)
+            .collect()
+    }
+
+    async fn batch(&mut self, stmts: &str) -> anyhow::Result<()> {
+        match self.client.as_ref() {
+            Some(client) if client.is_closed() => anyhow::bail!(NotSentError),
+            Some(client) => Ok(client.batch_execute(stmts).await?),
+            None => anyhow::bail!(OfflineError),
+        }
+    }
+}
+
+/// Opens a connection listening on the given channels.
+///
+/// # Returns
+/// The client of the connection and the notifications received on it. The connection closes
+/// once the client is dropped, the receiver ends once the connection closed.
+pub(crate) async fn listen(
+    url: &str,
+    tls: &TlsConfig,
+    channels: &[String],
+) -> anyhow::Result<(tokio_postgres::Client, UnboundedReceiver<Notification>)> {
+    let mut config = tokio_postgres::Config::from_str(url)?;
+    config
+        .connect_timeout(CONNECT_TIMEOUT)
+        .ssl_mode(tls.ssl_mode());
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let client = match tls.mode {
+        TlsMode::Disable => {
+            let (client, connection) = config.connect(NoTls).await?;
+            tokio::spawn(forward(connection, tx));
+            client
+        }
+        _ => {
+            let (client, connection) = config.connect(tls.connector()?).await?;
+            tokio::spawn(forward(connection, tx));
+            client
+        }
+    };
+
+    let stmts = channels
+        .iter()
+        .map(|channel| format!("LISTEN \"{}\";", channel.replace('"', "\"\"")))
+        .collect::<String>();
+    client.batch_execute(&stmts).await?;
+    Ok((client, rx))
+}
+
+/// Drives the given connection until it closes, passing on the notifications it receives.
+async fn forward<S, T>(
+    mut connection: tokio_postgres::Connection<S, T>,
+    tx: UnboundedSender<Notification>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    while let Some(message) = poll_fn(|cx| connection.poll_message(cx)).await {
+        match message {
+            Ok(AsyncMessage::Notification(notification)) => {
+                let _ = tx.send(Notification {
+                    channel: notification.channel().to_string(),
+                    payload: notification.payload().to_string(),
+                });
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::warn!("Listener connection closed: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+/// Drives the given connection until it closes.
+async fn watch(connection: impl Future<Output = Result<(), tokio_postgres::Error>>) {
+    if let Err(e) = connection.await {
+        log::warn!("Database connection closed: {}", e);
+    }
+}
+
+fn convert_row(row: &postgres::Row) -> anyhow::Result<Row> {
+    let mut columns = Vec::with_capacity(row.len());
+    let mut values = Vec::with_capacity(row.len());
+
+    for (idx, col) in row.columns().iter().enumerate() {
+        let val = match *col.type_() {
+            Type::BOOL => row.try_get::<_, Option<bool>>(idx)?.map(Value::Bool),
+            Type::INT2 => row
+                .try_get::<_, Option<i16>>(idx)?
+                .map(|i| Value::Int(i.into())),
+            Type::INT4 => row
+                .try_get::<_, Option<i32>>(idx)?
+                .map(|i| Value::Int(i.into())),
+            Type::INT8 => row.try_get::<_, Option<i64>>(idx)?.map(Value::Int),
+            Type::FLOAT4 => row
+                .try_get::<_, Option<f32>>(idx)?
+                .map(|f| Value::Float(f.into())),
+            Type::FLOAT8 => row.try_get::<_, Option<f64>>(idx)?.map(Value::Float),
+            Type::UUID => row.try_get::<_, Option<Uuid>>(idx)?.map(Value::Uuid),
+            Type::TIMESTAMP => row
+                .try_get::<_, Option<NaiveDateTime>>(idx)?
+                .map(Value::Timestamp),
+            Type::TIMESTAMPTZ => row
+                .try_get::<_, Option<DateTime<Utc>>>(idx)?
+                .map(|ts| Value::Timestamp(ts.naive_utc())),
+            // Text, varchar, name and the like
+            _ => row.try_get::<_, Option<String>>(idx)?.map(Value::Text),
+        };
+        columns.push(col.name().to_string());
+        values.push(val.unwrap_or(Value::Null));
+    }
+
+    Ok(Row::new(columns, values))
+}
+
+impl postgres::types::ToSql for Value {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match self {
+            Value::Null => Ok(IsNull::Yes),
+            Value::Bool(b) => b.to_sql(ty, out),
+            Value::Int(i) => match *ty {
+                Type::INT2 => i16::try_from(*i)?.to_sql(ty, out),
+                Type::INT4 => i32::try_from(*i)?.to_sql(ty, out),
+                _ => i.to_sql(ty, out),
+            },
+            Value::Float(f) => match *ty {
+                Type::FLOAT4 => (*f as f32).to_sql(ty, out),
+                _ => f.to_sql(ty, out),
+            },
+            Value::Text(s) => s.to_sql(ty, out),
+            Value::Uuid(uuid) => uuid.to_sql(ty, out),
+            Value::Timestamp(ts) => match *ty {
+                Type::TIMESTAMPTZ => ts.and_utc().to_sql(ty, out),
+                _ => ts.to_sql(ty, out),
+            },
+        }
+    }
+
+    // The server rejects mismatching types itself
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    postgres::types::to_sql_checked!();
+}
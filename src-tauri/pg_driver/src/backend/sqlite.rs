@@ -0,0 +1,237 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rusqlite::types::{Value as SqliteValue, ValueRef};
+use rusqlite::Connection;
+
+use super::{AsyncStorageBackend, BackendKind, StorageBackend};
+use crate::health::OfflineError;
+use crate::value::{Row, Value, TIMESTAMP_FORMAT};
+
+/// A local SQLite database file. Uuids and timestamps are stored as text.
+pub struct SqliteBackend {
+    path: PathBuf,
+    conn: Option<Connection>,
+}
+
+impl SqliteBackend {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, conn: None }
+    }
+}
+
+impl StorageBackend for SqliteBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Sqlite
+    }
+
+    fn connect(&mut self) -> anyhow::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(&self.path)?;
+        // Other pooled connections may hold the write lock for a moment
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+        self.conn = Some(conn);
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.conn.is_some()
+    }
+
+    fn is_healthy(&mut self) -> bool {
+        match self.conn.as_ref() {
+            Some(conn) => conn.query_row("SELECT 1", [], |_| Ok(())).is_ok(),
+            None => false,
+        }
+    }
+
+    fn query(&mut self, stmt: &str, params: &[Value]) -> anyhow::Result<Vec<Row>> {
+        let conn = match self.conn.as_ref() {
+            Some(conn) => conn,
+            None => anyhow::bail!(OfflineError),
+        };
+        let mut stmt = conn.prepare(&numbered_params(stmt))?;
+        let columns = stmt
+            .column_names()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter().map(convert_param)))?;
+        let mut res = vec![];
+        while let Some(row) = rows.next()? {
+            let values = (0..columns.len())
+                .map(|idx| Ok(convert_value(row.get_ref(idx)?)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            res.push(Row::new(columns.clone(), values));
+        }
+        Ok(res)
+    }
+
+    fn batch(&mut self, stmts: &str) -> anyhow::Result<()> {
+        match self.conn.as_ref() {
+            Some(conn) => Ok(conn.execute_batch(stmts)?),
+            None => anyhow::bail!(OfflineError),
+        }
+    }
+}
+
+/// A local SQLite database file, accessed on the blocking thread pool of the async runtime.
+pub struct AsyncSqliteBackend {
+    inner: Arc<Mutex<SqliteBackend>>,
+    connected: bool,
+}
+
+impl AsyncSqliteBackend {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SqliteBackend::new(path))),
+            connected: false,
+        }
+    }
+
+    /// Runs a blocking operation on the wrapped backend.
+    async fn run<T: Send + 'static>(
+        &self,
+        op: impl FnOnce(&mut SqliteBackend) -> anyhow::Result<T> + Send + 'static,
+    ) -> anyhow::Result<T> {
+        let inner = Arc::clone(&self.inner);
+        tokio::task::spawn_blocking(move || op(&mut inner.lock().unwrap())).await?
+    }
+}
+
+#[async_trait]
+impl AsyncStorageBackend for AsyncSqliteBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Sqlite
+    }
+
+    async fn connect(&mut self) -> anyhow::Result<()> {
+        self.connected = false;
+        self.run(|backend| backend.connect()).await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn is_healthy(&mut self) -> bool {
+        self.run(|backend| Ok(backend.is_healthy()))
+            .await
+            .unwrap_or(false)
+    }
+
+    async fn query(&mut self, stmt: &str, params: &[Value]) -> anyhow::Result<Vec<Row>> {
+        let stmt = stmt.to_string();
+        let params = params.to_vec();
+        self.run(move |backend| backend.query(&stmt, &params)).await
+    }
+
+    async fn batch(&mut self, stmts: &str) -> anyhow::Result<()> {
+        let stmts = stmts.to_string();
+        self.run(move |backend| backend.batch(&stmts)).await
+    }
+}
+
+/// The database file used if none is configured, inside the user's data directory.
+pub(crate) fn default_path() -> PathBuf {
+    config::data_dir().join("scheduleflow.db")
+}
+
+/// Rewrites the `$1` placeholders into SQLite's `?1`, leaving quoted strings and identifiers
+/// untouched.
+fn numbered_params(stmt: &str) -> String {
+    let mut res = String::with_capacity(stmt.len());
+    let mut quote = None;
+    let mut chars = stmt.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('$', None) if chars.peek().is_some_and(|n| n.is_ascii_digit()) => {
+                res.push('?');
+                continue;
+            }
+            _ => {}
+        }
+        res.push(c);
+    }
+    res
+}
+
+fn convert_param(val: &Value) -> SqliteValue {
+    match val {
+        Value::Null => SqliteValue::Null,
+        Value::Bool(b) => SqliteValue::Integer(*b as i64),
+        Value::Int(i) => SqliteValue::Integer(*i),
+        Value::Float(f) => SqliteValue::Real(*f),
+        Value::Text(s) => SqliteValue::Text(s.clone()),
+        Value::Uuid(uuid) => SqliteValue::Text(uuid.to_string()),
+        Value::Timestamp(ts) => SqliteValue::Text(ts.format(TIMESTAMP_FORMAT).to_string()),
+    }
+}
+
+fn convert_value(val: ValueRef) -> Value {
+    match val {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::Int(i),
+        ValueRef::Real(f) => Value::Float(f),
+        ValueRef::Text(s) | ValueRef::Blob(s) => {
+            Value::Text(String::from_utf8_lossy(s).into_owned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PgDriver;
+
+    #[test]
+    fn test_numbered_params() {
+        assert_eq!(
+            numbered_params("SELECT '$1', \"$2\" FROM t WHERE a = $1 AND b IN ($2, $10)"),
+            "SELECT '$1', \"$2\" FROM t WHERE a = ?1 AND b IN (?2, ?10)"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", uuid::Uuid::new_v4()));
+        let mut driver = PgDriver::new(Box::new(SqliteBackend::new(path.clone())));
+        driver.connect();
+
+        let uuid = uuid::Uuid::new_v4();
+        let now = chrono::DateTime::from_timestamp(1_700_000_000, 250_000_000)
+            .unwrap()
+            .naive_utc();
+        driver
+            .batch("CREATE TABLE t (uuid TEXT PRIMARY KEY, at TEXT, flag BOOLEAN)")
+            .unwrap();
+        driver
+            .exec("INSERT INTO t VALUES ($1, $2, $3)", &[&uuid, &now, &true])
+            .unwrap();
+        let rows = driver
+            .exec(
+                "SELECT * FROM t WHERE uuid = $1 AND at <= $2",
+                &[&uuid, &now],
+            )
+            .unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get::<_, uuid::Uuid>("uuid"), uuid);
+        assert_eq!(rows[0].get::<_, chrono::NaiveDateTime>("at"), now);
+        assert!(rows[0].get::<_, bool>("flag"));
+
+        drop(driver);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Whether the database can currently be reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    /// The connection dropped and is being reopened.
+    Reconnecting,
+    /// The database could not be reached. Queries fail until a later reconnect succeeds.
+    Offline,
+}
+
+impl ConnectionState {
+    fn from_u8(state: u8) -> Self {
+        match state {
+            0 => Self::Connected,
+            1 => Self::Reconnecting,
+            _ => Self::Offline,
+        }
+    }
+}
+
+/// How often and how fast a driver tries to reopen its connection.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// The delay before the second attempt. It doubles with every failed attempt.
+    pub initial_delay: Duration,
+    /// The upper bound of the delay between two attempts.
+    pub max_delay: Duration,
+    /// How often a connection that just dropped is retried right away, before the database is
+    /// considered offline. Afterwards, one attempt is made per delay.
+    pub attempts: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(30),
+            attempts: 3,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay following the given one.
+    pub(crate) fn next_delay(&self, delay: Duration) -> Duration {
+        (delay * 2).min(self.max_delay)
+    }
+}
+
+/// Tracks the connection state of a single driver and when it may try to reconnect.
+pub(crate) struct ConnectionHealth {
+    state: ConnectionState,
+    policy: ReconnectPolicy,
+    /// The delay before the next reconnect attempt.
+    backoff: Duration,
+    /// While offline, no reconnect is attempted before this point in time.
+    retry_at: Option<Instant>,
+}
+
+impl ConnectionHealth {
+    pub(crate) fn new(policy: ReconnectPolicy) -> Self {
+        Self {
+            state: ConnectionState::Connected,
+            backoff: policy.initial_delay,
+            policy,
+            retry_at: None,
+        }
+    }
+
+    pub(crate) fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Whether the backoff delay after the last failed attempt passed.
+    pub(crate) fn is_retry_due(&self) -> bool {
+        self.retry_at.is_none_or(|at| Instant::now() >= at)
+    }
+
+    /// Starts reconnecting. A connection that just dropped is retried a few times right away,
+    /// an offline database only once per backoff delay.
+    ///
+    /// # Returns
+    /// The amount of connect attempts to make.
+    pub(crate) fn start_reconnect(&mut self) -> u32 {
+        match self.state {
+            ConnectionState::Offline => 1,
+            _ => {
+                self.set_state(ConnectionState::Reconnecting);
+                self.policy.attempts.max(1)
+            }
+        }
+    }
+
+    /// The delay to wait before the next attempt. Every call doubles it, up to the maximum.
+    pub(crate) fn next_backoff(&mut self) -> Duration {
+        let delay = self.backoff;
+        self.backoff = self.policy.next_delay(delay);
+        delay
+    }
+
+    pub(crate) fn connected(&mut self) {
+        self.backoff = self.policy.initial_delay;
+        self.retry_at = None;
+        self.set_state(ConnectionState::Connected);
+    }
+
+    pub(crate) fn offline(&mut self) {
+        self.retry_at = Some(Instant::now() + self.next_backoff());
+        self.set_state(ConnectionState::Offline);
+    }
+
+    fn set_state(&mut self, state: ConnectionState) {
+        self.state = state;
+        report(state);
+    }
+}
+
+/// Returned by queries while the database is offline.
+#[derive(Debug)]
+pub struct OfflineError;
+
+impl fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No database connection")
+    }
+}
+
+impl std::error::Error for OfflineError {}
+
+/// Returned by backends when a statement failed before it was sent, because the connection
+/// closed since it was last checked. Only these statements are safe to retry, any other failure
+/// may have happened after the database executed them.
+#[derive(Debug)]
+pub struct NotSentError;
+
+impl fmt::Display for NotSentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The connection closed before the statement was sent")
+    }
+}
+
+impl std::error::Error for NotSentError {}
+
+/// Returned by statements of a transaction whose connection was lost. The database rolled it
+/// back already, so nothing else may run on the driver until it is rolled back there as well,
+/// or the statements would be stored outside of the transaction.
+#[derive(Debug)]
+pub struct AbortedTransactionError;
+
+impl fmt::Display for AbortedTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The transaction was lost with the connection and must be rolled back"
+        )
+    }
+}
+
+impl std::error::Error for AbortedTransactionError {}
+
+type Listener = Arc<dyn Fn(ConnectionState) + Send + Sync>;
+
+static STATE: AtomicU8 = AtomicU8::new(ConnectionState::Connected as u8);
+static LISTENERS: Mutex<Vec<Listener>> = Mutex::new(Vec::new());
+
+/// The connection state most recently reported by any driver.
+pub fn connection_state() -> ConnectionState {
+    ConnectionState::from_u8(STATE.load(Ordering::Relaxed))
+}
+
+/// Registers a function called whenever the connection state changes.
+///
+/// # Examples
+///
+/// ```
+/// use pg_driver::health::{on_state_change, ConnectionState};
+///
+/// on_state_change(|state| {
+///     if state == ConnectionState::Offline {
+///         eprintln!("The database is unreachable");
+///     }
+/// });
+/// ```
+pub fn on_state_change(listener: impl Fn(ConnectionState) + Send + Sync + 'static) {
+    LISTENERS.lock().unwrap().push(Arc::new(listener));
+}
+
+/// Records the state a driver observed and notifies the listeners if it changed.
+pub(crate) fn report(state: ConnectionState) {
+    if STATE.swap(state as u8, Ordering::Relaxed) == state as u8 {
+        return;
+    }
+    // Don't hold the lock while calling out, listeners may query the database themselves
+    let listeners = LISTENERS.lock().unwrap().clone();
+    for listener in listeners {
+        listener(state);
+    }
+}
@@ -1,63 +1,659 @@
-use postgres::{Client, NoTls, Row};
+use std::thread;
 
-// use crate::errors::error_impl::no_database_connection_error::NoDatabaseConnectionError;
-// use crate::runtime_objects::get_error_queue;
+pub mod async_driver;
+pub mod async_pool;
+pub mod backend;
+pub mod health;
+pub mod migrator;
+pub mod notify;
+pub mod pool;
+pub mod query;
+pub mod tls;
+pub mod value;
 
-/// The database driver for PostgreSQL.
-#[derive(Default)]
+pub use async_driver::AsyncPgDriver;
+pub use async_pool::{AsyncPgPool, AsyncPooledDriver};
+pub use backend::{AsyncStorageBackend, BackendKind, DriverConfig, StorageBackend};
+pub use health::{
+    AbortedTransactionError, ConnectionState, NotSentError, OfflineError, ReconnectPolicy,
+};
+pub use migrator::{Migration, MigrationStatus, Migrator};
+pub use notify::{ListenerEvent, Notification, NotificationListener};
+pub use pool::{PgPool, PoolConfig, PooledDriver};
+pub use query::{Column, Cursor, Filter, Order, Query};
+pub use tls::{TlsConfig, TlsMode};
+pub use value::{FromSql, Row, ToSql, Value};
+
+use config::ConfigError;
+use health::ConnectionHealth;
+
+/// An owned value that can be bound to a query parameter.
+pub type SqlParam = Box<dyn ToSql + Sync + Send>;
+
+/// The database driver. Despite its name it talks to any `StorageBackend`, PostgreSQL being the
+/// default one.
+///
+/// Broken connections are reopened with an exponential backoff. State changes are reported to
+/// the listeners registered with `health::on_state_change`.
 pub struct PgDriver {
-    url: String,
-    /// The postgres client.
-    client: Option<Client>,
+    /// The database the statements are executed on.
+    backend: Box<dyn StorageBackend>,
+    /// The amount of nested transactions currently open, 0 if none.
+    tx_depth: usize,
+    /// Whether the open transaction was lost with the connection, see
+    /// `AbortedTransactionError`.
+    aborted: bool,
+    health: ConnectionHealth,
 }
 
 impl PgDriver {
-    /// Sets up the database driver.
-    pub fn setup() -> Self {
-        let name = std::env::var("PSQL_NAME").expect("PSQL NAME NOT SET");
-        let user = std::env::var("PSQL_USER").expect("PSQL USER NOT SET");
-        let pass = std::env::var("PSQL_PASS").expect("PSQL PASS NOT SET");
-        let address = std::env::var("PSQL_IP").expect("PSQL ADDRESS NOT SET");
-        let url = format!("postgres://{}:{}@{}/{}", user, pass, address, name);
-        Self { url, client: None }
+    /// Sets up the database driver for the backend of the loaded settings, see
+    /// `DriverConfig::from_settings`.
+    ///
+    /// # Errors
+    /// If the settings can't be loaded or the database is not configured correctly.
+    pub fn setup() -> Result<Self, ConfigError> {
+        Ok(Self::from_config(&DriverConfig::from_settings(
+            config::load()?,
+        )?))
+    }
+
+    /// Sets up the database driver for the given backend configuration.
+    pub fn from_config(config: &DriverConfig) -> Self {
+        Self::new(config.backend())
+    }
+
+    /// Sets up the database driver for the given backend.
+    pub fn new(backend: Box<dyn StorageBackend>) -> Self {
+        Self {
+            backend,
+            tx_depth: 0,
+            aborted: false,
+            health: ConnectionHealth::new(ReconnectPolicy::default()),
+        }
+    }
+
+    /// Replaces the default reconnect policy of this driver.
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.health = ConnectionHealth::new(policy);
+        self
+    }
+
+    /// The kind of database this driver talks to.
+    pub fn kind(&self) -> BackendKind {
+        self.backend.kind()
+    }
+
+    /// Whether this driver could reach the database the last time it tried.
+    pub fn state(&self) -> ConnectionState {
+        self.health.state()
     }
 
     /// Initializes the database connection client.
     ///
     /// # Errors
-    /// If an error occurs during executing this function, like if there's no db connection, it
-    /// gets automatically handled by the error queue.
+    /// If the database cannot be reached, the driver goes offline, which is reported to the
+    /// `health` listeners. Queries reconnect later on.
     pub fn connect(&mut self) -> &mut Self {
-        let conn = Client::connect(&self.url, NoTls);
-
-        if let Ok(client) = conn {
-            self.client = Some(client);
-        } else {
-            // Handle that...
-            // let err = NoDatabaseConnectionError::new();
-            // get_error_queue().enqueue(err);
+        match self.backend.connect() {
+            Ok(()) => self.health.connected(),
+            Err(e) => {
+                log::warn!("Could not connect to the database: {:#}", e);
+                self.health.offline();
+            }
         }
 
         self
     }
 
-    /// Executes a query on the database.
+    /// Whether this driver has an open connection.
+    pub fn is_connected(&self) -> bool {
+        self.backend.is_connected()
+    }
+
+    /// Checks whether the connection of this driver is still usable.
     ///
     /// # Returns
-    /// The affected Rows, if there are any. If not, an Error.
-    pub fn exec(&mut self, query: &str) -> anyhow::Result<Vec<Row>> {
-        let mut rows: Vec<Row> = vec![];
-        match self.client.as_mut() {
-            Some(client) => {
-                rows = client.query(query, &[])?;
+    /// False, if the driver never connected or the database stopped answering.
+    pub fn is_healthy(&mut self) -> bool {
+        self.backend.is_healthy()
+    }
+
+    /// Executes a query on the database. If the connection turned out to be closed before the
+    /// query was sent, it is reopened and the query retried once, unless a transaction was open.
+    /// A query that failed after it was sent is never retried, it may have taken effect.
+    ///
+    /// # Arguments
+    /// * `query` - The statement to execute, referencing its parameters as `$1`, `$2`, ...
+    /// * `params` - The values bound to the placeholders of the statement, in order.
+    ///
+    /// # Returns
+    /// The affected Rows, if there are any. If not, an Error. While the database is offline, the
+    /// error is an `OfflineError`.
+    pub fn exec(
+        &mut self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> anyhow::Result<Vec<Row>> {
+        let params = params.iter().map(|p| p.to_value()).collect::<Vec<_>>();
+        self.run(true, |backend| backend.query(query, &params))
+    }
+
+    /// Opens a transaction. If one is open already, a savepoint is created instead, so
+    /// transactions can be nested.
+    ///
+    /// # Errors
+    /// If there is no database connection or the statement fails.
+    pub fn begin(&mut self) -> anyhow::Result<()> {
+        let stmt = match self.tx_depth {
+            0 => String::from("BEGIN"),
+            depth => format!("SAVEPOINT sp_{}", depth),
+        };
+        self.batch(&stmt)?;
+        self.tx_depth += 1;
+        Ok(())
+    }
+
+    /// Commits the innermost open transaction, or releases its savepoint if it is nested.
+    ///
+    /// # Errors
+    /// If no transaction is open or the statement fails. An `AbortedTransactionError`, if the
+    /// transaction was lost with the connection, it is closed nonetheless.
+    pub fn commit(&mut self) -> anyhow::Result<()> {
+        let stmt = match self.tx_depth {
+            0 => anyhow::bail!("There is no open transaction to commit"),
+            1 => String::from("COMMIT"),
+            depth => format!("RELEASE SAVEPOINT sp_{}", depth - 1),
+        };
+        let aborted = self.aborted;
+        self.end_transaction(&stmt)?;
+        anyhow::ensure!(!aborted, AbortedTransactionError);
+        Ok(())
+    }
+
+    /// Rolls back the innermost open transaction, or everything since its savepoint if it is
+    /// nested.
+    ///
+    /// # Errors
+    /// If no transaction is open or the statement fails.
+    pub fn rollback(&mut self) -> anyhow::Result<()> {
+        let stmt = match self.tx_depth {
+            0 => anyhow::bail!("There is no open transaction to roll back"),
+            1 => String::from("ROLLBACK"),
+            depth => format!("ROLLBACK TO SAVEPOINT sp_{}", depth - 1),
+        };
+        self.end_transaction(&stmt)
+    }
+
+    /// Closes the innermost open transaction with the given statement. The transaction counts
+    /// as open until the statement ran, so it is never mistaken for one outside a transaction.
+    /// It is closed even if the statement fails: a failing COMMIT ends the transaction as well,
+    /// and an enclosing transaction is rolled back by its caller.
+    ///
+    /// A transaction lost with the connection was rolled back by the database already, so
+    /// nothing is sent for it. The driver accepts statements again once the outermost
+    /// transaction is closed.
+    fn end_transaction(&mut self, stmt: &str) -> anyhow::Result<()> {
+        let res = match self.aborted {
+            true => Ok(()),
+            false => self.batch(stmt),
+        };
+        self.tx_depth = self.tx_depth.saturating_sub(1);
+        if self.tx_depth == 0 {
+            self.aborted = false;
+        }
+        res
+    }
+
+    /// Whether a transaction is currently open on this driver.
+    pub fn in_transaction(&self) -> bool {
+        self.tx_depth > 0
+    }
+
+    /// Runs the given operations inside a transaction. It is committed if they succeed and
+    /// rolled back if they return an error, so either all of them take effect or none. If the
+    /// connection is lost in between, every further statement fails until the outermost
+    /// transaction is rolled back, even if the operations carry on after the error.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// driver.transaction(|driver| {
+    ///     CalendarRepository::store(driver, &calendar)?;
+    ///     UserCalendarCombinationRepository::store(driver, &combination)
+    /// })?;
+    /// ```
+    pub fn transaction<T, E>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, E>) -> Result<T, E>
+    where
+        E: From<anyhow::Error>,
+    {
+        self.begin()?;
+        match f(self) {
+            Ok(res) => {
+                self.commit()?;
+                Ok(res)
             }
-            None => {
-                // Handle that...
-                // let err = NoDatabaseConnectionError::new();
-                // get_error_queue().enqueue(err);
+            Err(e) => {
+                // The original error tells more than a failing rollback would
+                let _ = self.rollback();
+                Err(e)
             }
         }
-        Ok(rows)
+    }
+
+    /// Rolls back every open transaction, e.g. before handing the driver to someone else.
+    pub(crate) fn abort_transactions(&mut self) {
+        if self.tx_depth > 0 {
+            self.tx_depth = 0;
+            if !std::mem::take(&mut self.aborted) {
+                let _ = self.batch("ROLLBACK");
+            }
+        }
+    }
+
+    /// Executes statements without parameters, like transaction control or migrations. They are
+    /// never retried, see `run`.
+    fn batch(&mut self, stmt: &str) -> anyhow::Result<()> {
+        self.run(false, |backend| backend.batch(stmt))
+    }
+
+    /// Runs an operation on the backend, reconnecting before. If `retry` is set and no
+    /// transaction is open, an operation that failed with a `NotSentError` is retried once on a
+    /// new connection.
+    ///
+    /// # Errors
+    /// An `AbortedTransactionError`, if the open transaction was lost with the connection.
+    fn run<T>(
+        &mut self,
+        retry: bool,
+        mut op: impl FnMut(&mut dyn StorageBackend) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        self.check_transaction()?;
+        self.ensure_connected()?;
+        match op(self.backend.as_mut()) {
+            Err(e) if retry && self.tx_depth == 0 && e.is::<NotSentError>() => {
+                self.ensure_connected()?;
+                op(self.backend.as_mut())
+            }
+            Err(e) => {
+                self.aborted = self.tx_depth > 0 && !self.backend.is_connected();
+                Err(e)
+            }
+            res => res,
+        }
+    }
+
+    /// Fails if the open transaction was lost with the connection. A new connection would run
+    /// the statements outside of it, so they must not be sent until it is rolled back.
+    fn check_transaction(&mut self) -> anyhow::Result<()> {
+        if self.tx_depth > 0 && !self.backend.is_connected() {
+            self.aborted = true;
+        }
+        anyhow::ensure!(!self.aborted, AbortedTransactionError);
+        Ok(())
+    }
+
+    /// Reopens the connection if it is closed, see `ConnectionHealth::start_reconnect`.
+    ///
+    /// # Errors
+    /// An `OfflineError`, if the database could not be reached.
+    fn ensure_connected(&mut self) -> anyhow::Result<()> {
+        if self.backend.is_connected() {
+            return Ok(());
+        }
+        if !self.health.is_retry_due() {
+            return Err(OfflineError.into());
+        }
+
+        for attempt in 0..self.health.start_reconnect() {
+            if attempt > 0 {
+                thread::sleep(self.health.next_backoff());
+            }
+            match self.backend.connect() {
+                Ok(()) => {
+                    self.health.connected();
+                    return Ok(());
+                }
+                Err(e) => log::warn!("Reconnecting to the database failed: {:#}", e),
+            }
+        }
+
+        self.health.offline();
+        Err(OfflineError.into())
     }
 }
 
+/// Borrows a list of owned parameters in the form `PgDriver::exec` expects them.
+pub fn params(vals: &[SqlParam]) -> Vec<&(dyn ToSql + Sync)> {
+    vals.iter()
+        .map(|val| val.as_ref() as &(dyn ToSql + Sync))
+        .collect()
+}
+
+/// Builds a comma separated list of `count` placeholders, starting at `$from`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(pg_driver::placeholders(1, 3), "$1, $2, $3");
+/// ```
+pub fn placeholders(from: usize, count: usize) -> String {
+    (from..from + count)
+        .map(|idx| format!("${}", idx))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+
+    /// A backend whose connection can be dropped and refused on demand.
+    struct FlakyBackend {
+        connected: bool,
+        /// How many connect attempts fail.
+        refuse: usize,
+        /// The statement that finds the connection closed before it is sent, once.
+        unsent: Option<&'static str>,
+        /// The statement that drops the connection after it was sent, once.
+        drops: Option<&'static str>,
+        /// The statements that reached the database, in order.
+        sent: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FlakyBackend {
+        fn send(&mut self, stmt: &str) -> anyhow::Result<()> {
+            if self.unsent == Some(stmt) {
+                self.unsent = None;
+                self.connected = false;
+                anyhow::bail!(NotSentError);
+            }
+            anyhow::ensure!(self.connected, "connection closed");
+            self.sent.lock().unwrap().push(stmt.to_string());
+            if self.drops == Some(stmt) {
+                self.drops = None;
+                self.connected = false;
+                anyhow::bail!("connection closed");
+            }
+            Ok(())
+        }
+    }
+
+    impl StorageBackend for FlakyBackend {
+        fn kind(&self) -> BackendKind {
+            BackendKind::Sqlite
+        }
+
+        fn connect(&mut self) -> anyhow::Result<()> {
+            if self.refuse > 0 {
+                self.refuse -= 1;
+                anyhow::bail!("refused");
+            }
+            self.connected = true;
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            self.connected
+        }
+
+        fn is_healthy(&mut self) -> bool {
+            self.connected
+        }
+
+        fn query(&mut self, stmt: &str, _params: &[Value]) -> anyhow::Result<Vec<Row>> {
+            self.send(stmt)?;
+            Ok(vec![])
+        }
+
+        fn batch(&mut self, stmts: &str) -> anyhow::Result<()> {
+            self.send(stmts)
+        }
+    }
+
+    /// A driver on a `FlakyBackend` and the statements that reached it.
+    fn flaky_driver(
+        refuse: usize,
+        unsent: Option<&'static str>,
+        drops: Option<&'static str>,
+    ) -> (PgDriver, Arc<Mutex<Vec<String>>>) {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(100),
+            attempts: 3,
+        };
+        let sent = Arc::new(Mutex::new(vec![]));
+        let backend = FlakyBackend {
+            connected: false,
+            refuse,
+            unsent,
+            drops,
+            sent: sent.clone(),
+        };
+        let driver = PgDriver::new(Box::new(backend)).with_reconnect_policy(policy);
+        (driver, sent)
+    }
+
+    /// The ids stored in the table `t`, in order.
+    fn ids(driver: &mut PgDriver) -> Vec<i32> {
+        driver
+            .exec("SELECT id FROM t ORDER BY id", &[])
+            .unwrap()
+            .iter()
+            .map(|row| row.get::<_, i32>("id"))
+            .collect()
+    }
+
+    #[test]
+    fn test_savepoints() {
+        let (mut driver, sent) = flaky_driver(0, None, None);
+        driver.begin().unwrap();
+        driver.begin().unwrap();
+        driver.begin().unwrap();
+        driver.commit().unwrap();
+        driver.rollback().unwrap();
+        assert!(driver.in_transaction());
+        driver.commit().unwrap();
+        assert!(!driver.in_transaction());
+
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![
+                "BEGIN",
+                "SAVEPOINT sp_1",
+                "SAVEPOINT sp_2",
+                "RELEASE SAVEPOINT sp_2",
+                "ROLLBACK TO SAVEPOINT sp_1",
+                "COMMIT",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_savepoints_after_lost_connection() {
+        let (mut driver, sent) = flaky_driver(0, None, Some("INSERT 2"));
+        driver.connect();
+
+        let res = driver.transaction(|driver| {
+            driver.exec("INSERT 1", &[])?;
+            let nested = driver.transaction(|driver| driver.exec("INSERT 2", &[]));
+            assert!(nested.is_err());
+            // Rolling back the savepoint leaves the lost outer transaction open
+            assert!(driver.in_transaction());
+            driver.exec("INSERT 3", &[])
+        });
+        assert!(res.unwrap_err().is::<AbortedTransactionError>());
+        assert!(!driver.in_transaction());
+        assert_eq!(
+            *sent.lock().unwrap(),
+            ["BEGIN", "INSERT 1", "SAVEPOINT sp_1", "INSERT 2"]
+        );
+
+        // The next transaction starts over on the new connection
+        sent.lock().unwrap().clear();
+        driver
+            .transaction(|driver| driver.transaction(|driver| driver.exec("INSERT 4", &[])))
+            .unwrap();
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [
+                "BEGIN",
+                "SAVEPOINT sp_1",
+                "INSERT 4",
+                "RELEASE SAVEPOINT sp_1",
+                "COMMIT"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_open_transaction() {
+        let (mut driver, sent) = flaky_driver(0, None, None);
+        assert_eq!(
+            driver.commit().unwrap_err().to_string(),
+            "There is no open transaction to commit"
+        );
+        assert_eq!(
+            driver.rollback().unwrap_err().to_string(),
+            "There is no open transaction to roll back"
+        );
+        assert!(sent.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_transactions() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", uuid::Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+        driver.exec("CREATE TABLE t (id INTEGER)", &[]).unwrap();
+
+        // A failing nested transaction only rolls back its own writes
+        driver
+            .transaction(|driver| {
+                driver.exec("INSERT INTO t VALUES ($1)", &[&1])?;
+                let nested: anyhow::Result<()> = driver.transaction(|driver| {
+                    driver.exec("INSERT INTO t VALUES ($1)", &[&2])?;
+                    anyhow::bail!("abort")
+                });
+                assert!(nested.is_err());
+                driver.exec("INSERT INTO t VALUES ($1)", &[&3])?;
+                anyhow::Ok(())
+            })
+            .unwrap();
+        assert!(!driver.in_transaction());
+        assert_eq!(ids(&mut driver), vec![1, 3]);
+
+        // A failing transaction rolls back its committed nested ones as well
+        let res: anyhow::Result<()> = driver.transaction(|driver| {
+            driver.exec("INSERT INTO t VALUES ($1)", &[&4])?;
+            driver.transaction(|driver| {
+                driver.exec("INSERT INTO t VALUES ($1)", &[&5])?;
+                anyhow::Ok(())
+            })?;
+            anyhow::bail!("abort")
+        });
+        assert_eq!(res.unwrap_err().to_string(), "abort");
+        assert!(!driver.in_transaction());
+        assert_eq!(ids(&mut driver), vec![1, 3]);
+
+        drop(driver);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_reconnects_within_attempts() {
+        let (mut driver, _) = flaky_driver(2, None, None);
+        assert!(driver.exec("SELECT 1", &[]).is_ok());
+        assert_eq!(driver.state(), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn test_offline_backoff() {
+        let (mut driver, _) = flaky_driver(3, None, None);
+        let err = driver.exec("SELECT 1", &[]).unwrap_err();
+        assert!(err.is::<OfflineError>());
+        assert_eq!(driver.state(), ConnectionState::Offline);
+
+        // Fails fast until the backoff delay passed, without using up a connect attempt
+        assert!(driver.exec("SELECT 1", &[]).is_err());
+        thread::sleep(Duration::from_millis(20));
+        assert!(driver.exec("SELECT 1", &[]).is_ok());
+        assert_eq!(driver.state(), ConnectionState::Connected);
+    }
+
+    #[test]
+    fn test_unsent_query_is_retried() {
+        let (mut driver, sent) = flaky_driver(0, Some("SELECT 1"), None);
+        driver.connect();
+        assert!(driver.exec("SELECT 1", &[]).is_ok());
+        assert_eq!(*sent.lock().unwrap(), ["SELECT 1"]);
+    }
+
+    #[test]
+    fn test_dropped_query_is_not_retried() {
+        let (mut driver, sent) = flaky_driver(0, None, Some("INSERT"));
+        driver.connect();
+        assert!(driver.exec("INSERT", &[]).is_err());
+        assert!(driver.exec("SELECT 1", &[]).is_ok());
+        assert_eq!(*sent.lock().unwrap(), ["INSERT", "SELECT 1"]);
+    }
+
+    #[test]
+    fn test_lost_transaction_is_not_retried() {
+        let (mut driver, _) = flaky_driver(0, Some("SELECT 1"), None);
+        driver.connect();
+        driver.begin().unwrap();
+
+        assert!(driver.exec("SELECT 1", &[]).is_err());
+        assert!(driver.in_transaction());
+        let err = driver.exec("SELECT 1", &[]).unwrap_err();
+        assert!(err.is::<AbortedTransactionError>());
+
+        driver.rollback().unwrap();
+        assert!(!driver.in_transaction());
+        assert!(driver.exec("SELECT 1", &[]).is_ok());
+    }
+
+    #[test]
+    fn test_nothing_stored_after_lost_transaction() {
+        let (mut driver, sent) = flaky_driver(0, None, Some("INSERT 1"));
+        driver.connect();
+
+        let res = driver.transaction(|driver| {
+            assert!(driver.exec("INSERT 1", &[]).is_err());
+            // Carrying on after the error must not store anything outside the transaction
+            let err = driver.exec("INSERT 2", &[]).unwrap_err();
+            assert!(err.is::<AbortedTransactionError>());
+            anyhow::Ok(())
+        });
+        assert!(res.unwrap_err().is::<AbortedTransactionError>());
+        assert!(!driver.in_transaction());
+        assert_eq!(*sent.lock().unwrap(), ["BEGIN", "INSERT 1"]);
+
+        // The driver accepts statements again once the transaction is closed
+        assert!(driver.exec("INSERT 3", &[]).is_ok());
+        assert_eq!(*sent.lock().unwrap(), ["BEGIN", "INSERT 1", "INSERT 3"]);
+    }
+
+    #[test]
+    fn test_dropped_commit_fails() {
+        let (mut driver, sent) = flaky_driver(0, None, Some("COMMIT"));
+        driver.connect();
+
+        let res = driver.transaction(|driver| driver.exec("INSERT", &[]));
+        assert!(res.is_err());
+        assert!(!driver.in_transaction());
+        assert_eq!(*sent.lock().unwrap(), ["BEGIN", "INSERT", "COMMIT"]);
+
+        // Neither is a COMMIT retried that was never sent
+        let (mut driver, sent) = flaky_driver(0, Some("COMMIT"), None);
+        driver.connect();
+        let res = driver.transaction(|driver| driver.exec("INSERT", &[]));
+        assert!(res.is_err());
+        assert!(!driver.in_transaction());
+        assert_eq!(*sent.lock().unwrap(), ["BEGIN", "INSERT"]);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{BackendKind, PgDriver};
+
+/// The table the applied migration versions are recorded in.
+const MIGRATIONS_TABLE: &str = "schema_migrations";
+
+/// Arbitrary key of the advisory lock that keeps two app instances from migrating at once.
+const MIGRATION_LOCK_KEY: i64 = 0x0053_4348_464c_4f57;
+
+/// A single versioned change of the database schema.
+pub struct Migration {
+    /// The version of the migration. Migrations are applied in ascending order.
+    pub version: i64,
+    /// A short description of the migration.
+    pub name: &'static str,
+    /// The statements applying the migration.
+    pub up: &'static str,
+    /// The statements reverting the migration.
+    pub down: &'static str,
+}
+
+/// Whether a known migration has been applied to the database.
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: &'static str,
+    pub applied: bool,
+}
+
+/// Applies and reverts a list of migrations, keeping track of them in the `schema_migrations`
+/// table.
+pub struct Migrator<'a> {
+    migrations: &'a [Migration],
+}
+
+impl<'a> Migrator<'a> {
+    /// Creates a new migrator for the given migrations.
+    ///
+    /// # Panics
+    /// If the migrations are not ordered by strictly ascending versions.
+    pub fn new(migrations: &'a [Migration]) -> Self {
+        assert!(
+            migrations.windows(2).all(|m| m[0].version < m[1].version),
+            "Migrations must be ordered by strictly ascending versions"
+        );
+        Self { migrations }
+    }
+
+    /// Applies all migrations that have not been applied yet. Either all of them are applied or
+    /// none.
+    ///
+    /// # Returns
+    /// The versions of the migrations applied.
+    pub fn run(&self, driver: &mut PgDriver) -> anyhow::Result<Vec<i64>> {
+        driver.transaction(|driver| {
+            let applied = self.lock_and_get_applied(driver)?;
+            let mut res = vec![];
+
+            for migration in self.migrations {
+                if applied.contains(&migration.version) {
+                    continue;
+                }
+
+                driver.batch(migration.up).map_err(|e| {
+                    e.context(format!(
+                        "Migration {} ({}) failed",
+                        migration.version, migration.name
+                    ))
+                })?;
+                driver.exec(
+                    &format!(
+                        "INSERT INTO {} (version, name) VALUES ($1, $2)",
+                        MIGRATIONS_TABLE
+                    ),
+                    &[&migration.version, &migration.name],
+                )?;
+                res.push(migration.version);
+            }
+
+            Ok(res)
+        })
+    }
+
+    /// Reverts the given amount of the most recently applied migrations.
+    ///
+    /// # Returns
+    /// The versions of the migrations reverted, most recent first.
+    pub fn rollback(&self, driver: &mut PgDriver, steps: usize) -> anyhow::Result<Vec<i64>> {
+        driver.transaction(|driver| {
+            let applied = self.lock_and_get_applied(driver)?;
+            let mut res = vec![];
+
+            for migration in self
+                .migrations
+                .iter()
+                .rev()
+                .filter(|m| applied.contains(&m.version))
+                .take(steps)
+            {
+                driver.batch(migration.down).map_err(|e| {
+                    e.context(format!(
+                        "Reverting migration {} ({}) failed",
+                        migration.version, migration.name
+                    ))
+                })?;
+                driver.exec(
+                    &format!("DELETE FROM {} WHERE version = $1", MIGRATIONS_TABLE),
+                    &[&migration.version],
+                )?;
+                res.push(migration.version);
+            }
+
+            Ok(res)
+        })
+    }
+
+    /// Lists all known migrations along with whether they are applied.
+    pub fn status(&self, driver: &mut PgDriver) -> anyhow::Result<Vec<MigrationStatus>> {
+        let applied = driver.transaction(|driver| self.lock_and_get_applied(driver))?;
+
+        Ok(self
+            .migrations
+            .iter()
+            .map(|m| MigrationStatus {
+                version: m.version,
+                name: m.name,
+                applied: applied.contains(&m.version),
+            })
+            .collect())
+    }
+
+    /// Creates the migrations table if needed and waits for other instances migrating the
+    /// database. Must run inside a transaction, which holds the lock until it ends.
+    fn lock_and_get_applied(&self, driver: &mut PgDriver) -> anyhow::Result<Vec<i64>> {
+        // SQLite locks the whole database on the first write of the transaction anyway
+        if driver.kind() == BackendKind::Postgres {
+            driver.exec("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK_KEY])?;
+        }
+        driver.batch(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {} (
+                version BIGINT NOT NULL,
+                name VARCHAR NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                CONSTRAINT schema_migrations_pkey PRIMARY KEY (version)
+            )
+            "#,
+            MIGRATIONS_TABLE
+        ))?;
+
+        let rows = driver.exec(&format!("SELECT version FROM {}", MIGRATIONS_TABLE), &[])?;
+        Ok(rows.iter().map(|row| row.get("version")).collect())
+    }
+}
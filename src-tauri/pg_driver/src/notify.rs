@@ -0,0 +1,161 @@
+use std::thread::{self, JoinHandle};
+
+use tokio::sync::oneshot;
+
+use crate::backend::postgres;
+use crate::tls::TlsConfig;
+use crate::{DriverConfig, ReconnectPolicy};
+
+/// A message sent with `NOTIFY` on a channel a `NotificationListener` listens on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub channel: String,
+    pub payload: String,
+}
+
+/// What a `NotificationListener` passes to its handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenerEvent {
+    Notification(Notification),
+    /// The connection of the listener dropped and was reopened. Notifications sent in between
+    /// are lost, so everything derived from them should be reloaded.
+    Reconnected,
+}
+
+/// Listens for `NOTIFY`s of a PostgreSQL database on a dedicated connection and thread. Broken
+/// connections are reopened with the backoff of the given `ReconnectPolicy`.
+///
+/// The listener stops once it is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pg_driver::{DriverConfig, ListenerEvent, NotificationListener, ReconnectPolicy};
+///
+/// let config = DriverConfig::from_settings(config::get()).unwrap();
+/// let listener = NotificationListener::spawn(
+///     &config,
+///     &["changes"],
+///     ReconnectPolicy::default(),
+///     |event| {
+///         if let ListenerEvent::Notification(notification) = event {
+///             println!("{}", notification.payload);
+///         }
+///     },
+/// )
+/// .unwrap();
+/// ```
+pub struct NotificationListener {
+    stop: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl NotificationListener {
+    /// Starts listening on the given channels.
+    ///
+    /// # Errors
+    /// If the configured backend has no notifications, like SQLite.
+    pub fn spawn(
+        config: &DriverConfig,
+        channels: &[&str],
+        policy: ReconnectPolicy,
+        handler: impl Fn(ListenerEvent) + Send + 'static,
+    ) -> anyhow::Result<Self> {
+        let (url, tls) = match config {
+            DriverConfig::Postgres { url, tls } => (url.clone(), tls.clone()),
+            DriverConfig::Sqlite { .. } => anyhow::bail!("SQLite databases have no notifications"),
+        };
+        let channels = channels.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (stop, stopped) = oneshot::channel();
+
+        let thread = thread::Builder::new()
+            .name(String::from("pg-listener"))
+            .spawn(move || {
+                runtime.block_on(run(url, tls, channels, policy, handler, stopped));
+            })?;
+
+        Ok(Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for NotificationListener {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Keeps a listening connection open until the listener is stopped.
+async fn run(
+    url: String,
+    tls: TlsConfig,
+    channels: Vec<String>,
+    policy: ReconnectPolicy,
+    handler: impl Fn(ListenerEvent),
+    mut stopped: oneshot::Receiver<()>,
+) {
+    let mut delay = policy.initial_delay;
+    let mut connected_before = false;
+
+    loop {
+        let (client, mut notifications) = tokio::select! {
+            res = postgres::listen(&url, &tls, &channels) => match res {
+                Ok(conn) => conn,
+                Err(e) => {
+                    log::warn!("Could not listen for notifications: {}", e);
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = &mut stopped => return,
+                    }
+                    delay = policy.next_delay(delay);
+                    continue;
+                }
+            },
+            _ = &mut stopped => return,
+        };
+
+        delay = policy.initial_delay;
+        if connected_before {
+            handler(ListenerEvent::Reconnected);
+        }
+        connected_before = true;
+
+        loop {
+            tokio::select! {
+                notification = notifications.recv() => match notification {
+                    Some(notification) => handler(ListenerEvent::Notification(notification)),
+                    // The connection closed, open a new one
+                    None => break,
+                },
+                _ = &mut stopped => return,
+            }
+        }
+        drop(client);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_sqlite_has_no_notifications() {
+        let config = DriverConfig::Sqlite {
+            path: PathBuf::from("unused.db"),
+        };
+        let res = NotificationListener::spawn(&config, &["changes"], Default::default(), |_| {});
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use config::{ConfigError, Settings};
+
+use crate::{DriverConfig, PgDriver, ReconnectPolicy};
+
+/// The settings a `PgPool` is created with.
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// The maximum amount of connections the pool opens.
+    pub max_size: usize,
+    /// How long a checkout waits for a free connection before it fails.
+    pub checkout_timeout: Duration,
+    /// Connections that were idle for longer than this are validated before being handed out.
+    pub health_check_after: Duration,
+    /// How the drivers of the pool reopen broken connections.
+    pub reconnect: ReconnectPolicy,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 8,
+            checkout_timeout: Duration::from_secs(10),
+            health_check_after: Duration::from_secs(30),
+            reconnect: ReconnectPolicy::default(),
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Reads the pool settings, falling back to the defaults for every setting that is not set.
+    ///
+    /// * `database.pool.size` - The maximum amount of connections.
+    /// * `database.pool.timeout` - The checkout timeout in seconds.
+    /// * `database.pool.health_check` - The idle time in seconds after which a connection is
+    ///   validated.
+    ///
+    /// # Errors
+    /// If one of the settings is not a number.
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        let default = Self::default();
+        let secs = |key| settings.get::<u64>(key).map(|v| v.map(Duration::from_secs));
+
+        Ok(Self {
+            max_size: settings
+                .get::<usize>("database.pool.size")?
+                .map_or(default.max_size, |v| v.max(1)),
+            checkout_timeout: secs("database.pool.timeout")?.unwrap_or(default.checkout_timeout),
+            health_check_after: secs("database.pool.health_check")?
+                .unwrap_or(default.health_check_after),
+            reconnect: default.reconnect,
+        })
+    }
+}
+
+/// A connection waiting in the pool.
+struct Idle {
+    driver: PgDriver,
+    since: Instant,
+}
+
+struct PoolState {
+    idle: Vec<Idle>,
+    /// The amount of connections currently opened, both idle and checked out.
+    open: usize,
+}
+
+/// A pool of database drivers, so concurrent database operations don't have to wait for each
+/// other.
+pub struct PgPool {
+    driver_config: DriverConfig,
+    config: PoolConfig,
+    state: Mutex<PoolState>,
+    returned: Condvar,
+}
+
+impl PgPool {
+    /// Creates a new pool for the database and with the pool settings of the given settings.
+    /// Connections are opened lazily on checkout.
+    ///
+    /// # Errors
+    /// If the settings are invalid, see `DriverConfig::from_settings`.
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        Ok(Self::with_driver_config(
+            DriverConfig::from_settings(settings)?,
+            PoolConfig::from_settings(settings)?,
+        ))
+    }
+
+    /// Creates a new pool connecting to the given database.
+    pub fn with_driver_config(driver_config: DriverConfig, config: PoolConfig) -> Self {
+        Self {
+            driver_config,
+            config,
+            state: Mutex::new(PoolState {
+                idle: vec![],
+                open: 0,
+            }),
+            returned: Condvar::new(),
+        }
+    }
+
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    pub fn driver_config(&self) -> &DriverConfig {
+        &self.driver_config
+    }
+
+    /// Checks out a driver, opening a new connection if none is idle and the pool is not full.
+    /// The driver is returned to the pool once the returned guard is dropped.
+    ///
+    /// # Errors
+    /// If no driver became available within the configured checkout timeout.
+    pub fn get(&self) -> anyhow::Result<PooledDriver<'_>> {
+        let deadline = Instant::now() + self.config.checkout_timeout;
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if let Some(idle) = state.idle.pop() {
+                drop(state);
+                let mut driver = idle.driver;
+                let stale = idle.since.elapsed() >= self.config.health_check_after;
+                // Closed connections are reopened by the driver itself, respecting its backoff
+                if stale && driver.is_connected() && !driver.is_healthy() {
+                    driver.connect();
+                }
+                return Ok(PooledDriver::new(self, driver));
+            }
+
+            if state.open < self.config.max_size {
+                state.open += 1;
+                drop(state);
+                let mut driver = PgDriver::from_config(&self.driver_config)
+                    .with_reconnect_policy(self.config.reconnect.clone());
+                driver.connect();
+                return Ok(PooledDriver::new(self, driver));
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                anyhow::bail!(
+                    "Timed out after {:?} waiting for a database connection",
+                    self.config.checkout_timeout
+                );
+            }
+            state = self.returned.wait_timeout(state, deadline - now).unwrap().0;
+        }
+    }
+
+    fn put_back(&self, mut driver: PgDriver) {
+        // Don't leak a transaction someone forgot to finish into the next checkout
+        driver.abort_transactions();
+        let mut state = self.state.lock().unwrap();
+        state.idle.push(Idle {
+            driver,
+            since: Instant::now(),
+        });
+        drop(state);
+        self.returned.notify_one();
+    }
+}
+
+/// A driver checked out of a `PgPool`. Dereferences to the underlying `PgDriver`.
+pub struct PooledDriver<'a> {
+    pool: &'a PgPool,
+    driver: Option<PgDriver>,
+}
+
+impl<'a> PooledDriver<'a> {
+    fn new(pool: &'a PgPool, driver: PgDriver) -> Self {
+        Self {
+            pool,
+            driver: Some(driver),
+        }
+    }
+}
+
+impl Deref for PooledDriver<'_> {
+    type Target = PgDriver;
+
+    fn deref(&self) -> &PgDriver {
+        self.driver.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledDriver<'_> {
+    fn deref_mut(&mut self) -> &mut PgDriver {
+        self.driver.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledDriver<'_> {
+    fn drop(&mut self) {
+        if let Some(driver) = self.driver.take() {
+            self.pool.put_back(driver);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+    use crate::backend::StorageBackend;
+    use crate::{BackendKind, Row, Value};
+
+    /// A connection that answers nothing, counting how often it was opened.
+    struct Probe {
+        healthy: bool,
+        connects: Arc<AtomicUsize>,
+    }
+
+    impl StorageBackend for Probe {
+        fn kind(&self) -> BackendKind {
+            BackendKind::Sqlite
+        }
+
+        fn connect(&mut self) -> anyhow::Result<()> {
+            self.connects.fetch_add(1, Ordering::SeqCst);
+            self.healthy = true;
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn is_healthy(&mut self) -> bool {
+            self.healthy
+        }
+
+        fn query(&mut self, _stmt: &str, _params: &[Value]) -> anyhow::Result<Vec<Row>> {
+            Ok(vec![])
+        }
+
+        fn batch(&mut self, _stmts: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn sqlite_pool(max_size: usize, checkout_timeout: Duration) -> (PgPool, PathBuf) {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", uuid::Uuid::new_v4()));
+        let config = PoolConfig {
+            max_size,
+            checkout_timeout,
+            ..PoolConfig::default()
+        };
+        let pool = PgPool::with_driver_config(DriverConfig::Sqlite { path: path.clone() }, config);
+        (pool, path)
+    }
+
+    #[test]
+    fn test_checkout_timeout() {
+        let (pool, path) = sqlite_pool(1, Duration::from_millis(200));
+        let driver = pool.get().unwrap();
+
+        let started = Instant::now();
+        assert!(pool.get().is_err());
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        // A checkout waiting for a full pool gets the next driver handed back
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(20));
+                drop(driver);
+            });
+            assert!(pool.get().is_ok());
+        });
+
+        drop(pool);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_put_back() {
+        let (pool, path) = sqlite_pool(2, Duration::from_millis(50));
+        drop(pool.get().unwrap());
+        assert_eq!(pool.state.lock().unwrap().idle.len(), 1);
+
+        let driver = pool.get().unwrap();
+        let state = pool.state.lock().unwrap();
+        assert!(state.idle.is_empty());
+        assert_eq!(state.open, 1);
+        drop(state);
+
+        drop(driver);
+        drop(pool);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_put_back_aborts_transactions() {
+        let (pool, path) = sqlite_pool(1, Duration::from_millis(50));
+        let mut driver = pool.get().unwrap();
+        driver.exec("CREATE TABLE t (id INTEGER)", &[]).unwrap();
+        driver.begin().unwrap();
+        driver.exec("INSERT INTO t VALUES ($1)", &[&1]).unwrap();
+        drop(driver);
+
+        let mut driver = pool.get().unwrap();
+        assert!(!driver.in_transaction());
+        assert!(driver.exec("SELECT id FROM t", &[]).unwrap().is_empty());
+        driver.begin().unwrap();
+        driver.commit().unwrap();
+
+        drop(driver);
+        drop(pool);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_stale_health_check() {
+        let config = PoolConfig {
+            health_check_after: Duration::from_millis(50),
+            ..PoolConfig::default()
+        };
+        let pool = PgPool::with_driver_config(
+            DriverConfig::Sqlite {
+                path: PathBuf::new(),
+            },
+            config,
+        );
+        let connects = Arc::new(AtomicUsize::new(0));
+        let idle = |age| Idle {
+            driver: PgDriver::new(Box::new(Probe {
+                healthy: false,
+                connects: connects.clone(),
+            })),
+            since: Instant::now() - age,
+        };
+
+        // Recently used connections are handed out unchecked
+        pool.state.lock().unwrap().idle.push(idle(Duration::ZERO));
+        drop(pool.get().unwrap());
+        assert_eq!(connects.load(Ordering::SeqCst), 0);
+
+        let mut state = pool.state.lock().unwrap();
+        state.idle.clear();
+        state.idle.push(idle(Duration::from_secs(1)));
+        drop(state);
+        drop(pool.get().unwrap());
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+    }
+}
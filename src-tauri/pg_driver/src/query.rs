@@ -0,0 +1,509 @@
+use std::marker::PhantomData;
+use std::ops::Not;
+
+use crate::{Row, SqlParam, ToSql, Value};
+
+/// A column of a table holding values of type `T`. Filters built from it only accept values of
+/// that type, so mismatches are caught at compile time.
+///
+/// # Examples
+///
+/// ```
+/// use pg_driver::query::Column;
+///
+/// const EMAIL: Column<String> = Column::new("email");
+/// let filter = EMAIL.eq("someone@example.com");
+/// ```
+pub struct Column<T> {
+    name: &'static str,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Column<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Column<T> {}
+
+impl<T> Column<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _type: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Matches rows where this column is NULL.
+    pub fn is_null(&self) -> Filter {
+        Filter::IsNull(self.name)
+    }
+}
+
+impl<T: ToSql + Sync + Send + 'static> Column<T> {
+    /// Matches rows where this column equals the given value.
+    pub fn eq(&self, val: impl Into<T>) -> Filter {
+        self.compare("=", val.into())
+    }
+
+    /// Matches rows where this column differs from the given value.
+    pub fn ne(&self, val: impl Into<T>) -> Filter {
+        self.compare("<>", val.into())
+    }
+
+    /// Matches rows where this column is less than the given value.
+    pub fn lt(&self, val: impl Into<T>) -> Filter {
+        self.compare("<", val.into())
+    }
+
+    /// Matches rows where this column is less than or equal to the given value.
+    pub fn le(&self, val: impl Into<T>) -> Filter {
+        self.compare("<=", val.into())
+    }
+
+    /// Matches rows where this column is greater than the given value.
+    pub fn gt(&self, val: impl Into<T>) -> Filter {
+        self.compare(">", val.into())
+    }
+
+    /// Matches rows where this column is greater than or equal to the given value.
+    pub fn ge(&self, val: impl Into<T>) -> Filter {
+        self.compare(">=", val.into())
+    }
+
+    /// Matches rows where this column equals any of the given values.
+    pub fn is_in(&self, vals: impl IntoIterator<Item = T>) -> Filter {
+        Filter::In(
+            self.name,
+            vals.into_iter()
+                .map(|val| Box::new(val) as SqlParam)
+                .collect(),
+        )
+    }
+
+    /// Matches rows where this column lies between both given values, inclusively.
+    pub fn between(&self, low: impl Into<T>, high: impl Into<T>) -> Filter {
+        Filter::Between(self.name, Box::new(low.into()), Box::new(high.into()))
+    }
+
+    fn compare(&self, op: &'static str, val: T) -> Filter {
+        Filter::Compare(self.name, op, Box::new(val))
+    }
+}
+
+/// A condition rows have to fulfill, rendered with bound parameters.
+pub enum Filter {
+    Compare(&'static str, &'static str, SqlParam),
+    In(&'static str, Vec<SqlParam>),
+    Between(&'static str, SqlParam, SqlParam),
+    IsNull(&'static str),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Matches rows fulfilling both this and the other filter.
+    pub fn and(self, other: Filter) -> Filter {
+        match self {
+            Filter::And(mut filters) => {
+                filters.push(other);
+                Filter::And(filters)
+            }
+            filter => Filter::And(vec![filter, other]),
+        }
+    }
+
+    /// Matches rows fulfilling this or the other filter.
+    pub fn or(self, other: Filter) -> Filter {
+        match self {
+            Filter::Or(mut filters) => {
+                filters.push(other);
+                Filter::Or(filters)
+            }
+            filter => Filter::Or(vec![filter, other]),
+        }
+    }
+
+    /// Appends this filter to `sql`, numbering its placeholders after the ones in `params`.
+    fn render<'a>(&'a self, sql: &mut String, params: &mut Vec<&'a (dyn ToSql + Sync)>) {
+        match self {
+            Filter::Compare(col, op, val) => {
+                params.push(val.as_ref());
+                sql.push_str(&format!("\"{}\" {} ${}", col, op, params.len()));
+            }
+            Filter::In(_, vals) if vals.is_empty() => sql.push_str("FALSE"),
+            Filter::In(col, vals) => {
+                let placeholders = vals
+                    .iter()
+                    .map(|val| {
+                        params.push(val.as_ref());
+                        format!("${}", params.len())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                sql.push_str(&format!("\"{}\" IN ({})", col, placeholders));
+            }
+            Filter::Between(col, low, high) => {
+                params.push(low.as_ref());
+                params.push(high.as_ref());
+                sql.push_str(&format!(
+                    "\"{}\" BETWEEN ${} AND ${}",
+                    col,
+                    params.len() - 1,
+                    params.len()
+                ));
+            }
+            Filter::IsNull(col) => sql.push_str(&format!("\"{}\" IS NULL", col)),
+            Filter::And(filters) => Self::render_all(filters, " AND ", "TRUE", sql, params),
+            Filter::Or(filters) => Self::render_all(filters, " OR ", "FALSE", sql, params),
+            Filter::Not(filter) => {
+                sql.push_str("NOT ");
+                filter.render_grouped(sql, params);
+            }
+        }
+    }
+
+    fn render_all<'a>(
+        filters: &'a [Filter],
+        separator: &str,
+        empty: &str,
+        sql: &mut String,
+        params: &mut Vec<&'a (dyn ToSql + Sync)>,
+    ) {
+        if filters.is_empty() {
+            sql.push_str(empty);
+        }
+        for (idx, filter) in filters.iter().enumerate() {
+            if idx > 0 {
+                sql.push_str(separator);
+            }
+            filter.render_grouped(sql, params);
+        }
+    }
+
+    /// Renders the filter wrapped in parentheses if it combines other filters.
+    fn render_grouped<'a>(&'a self, sql: &mut String, params: &mut Vec<&'a (dyn ToSql + Sync)>) {
+        match self {
+            Filter::And(_) | Filter::Or(_) | Filter::Not(_) => {
+                sql.push('(');
+                self.render(sql, params);
+                sql.push(')');
+            }
+            _ => self.render(sql, params),
+        }
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    /// Matches rows not fulfilling this filter.
+    fn not(self) -> Filter {
+        Filter::Not(Box::new(self))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// Where a page of rows ends, the values of the order columns of its last row. Passed to
+/// `Query::after` to read the rows following it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor(Vec<Value>);
+
+impl Cursor {
+    pub fn new(values: Vec<Value>) -> Self {
+        Self(values)
+    }
+
+    pub fn values(&self) -> &[Value] {
+        &self.0
+    }
+}
+
+/// Selects which rows of a table are read and in which order.
+///
+/// # Examples
+///
+/// ```
+/// use pg_driver::query::{Column, Order, Query};
+///
+/// const NAME: Column<String> = Column::new("name");
+/// let query = Query::new()
+///     .filter(NAME.ne("Holidays"))
+///     .order_by(NAME, Order::Asc)
+///     .limit(10);
+/// ```
+#[derive(Default)]
+pub struct Query {
+    filter: Option<Filter>,
+    order: Vec<(&'static str, Order)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    after: Option<Cursor>,
+}
+
+impl Query {
+    /// Creates a query matching every row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the rows matched. Multiple filters are combined with AND.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => existing.and(filter),
+            None => filter,
+        });
+        self
+    }
+
+    /// Sorts the rows by the given column. Called multiple times, later columns break ties.
+    pub fn order_by<T>(mut self, col: Column<T>, order: Order) -> Self {
+        self.order.push((col.name, order));
+        self
+    }
+
+    /// Returns at most the given amount of rows.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skips the given amount of rows.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Adds the given column to the order, unless it is ordered by already. Ordered by a unique
+    /// column last, no two rows share a position, which keyset pagination relies on.
+    pub fn unique_by<T>(self, col: Column<T>) -> Self {
+        match self.order.iter().any(|(name, _)| *name == col.name) {
+            true => self,
+            false => self.order_by(col, Order::Asc),
+        }
+    }
+
+    /// Only matches the rows following the one the given cursor was taken from, in the order of
+    /// this query. Unlike an offset, the rows skipped are not read at all, so later pages are as
+    /// fast as the first one.
+    ///
+    /// The order has to be set before and be unique, see `unique_by`. Its columns must not be
+    /// NULL.
+    ///
+    /// # Panics
+    /// If the cursor has not one value per order column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pg_driver::query::{Column, Cursor, Query};
+    /// use pg_driver::Value;
+    ///
+    /// const ID: Column<i64> = Column::new("id");
+    /// let query = Query::new()
+    ///     .unique_by(ID)
+    ///     .after(Cursor::new(vec![Value::Int(41)]))
+    ///     .limit(10);
+    /// ```
+    pub fn after(mut self, cursor: Cursor) -> Self {
+        assert_eq!(
+            cursor.0.len(),
+            self.order.len(),
+            "A cursor needs one value per order column"
+        );
+        self.after = Some(cursor);
+        self
+    }
+
+    /// The cursor pointing after the given row, which has to be read by this query.
+    ///
+    /// # Errors
+    /// If the row lacks an order column.
+    pub fn cursor(&self, row: &Row) -> anyhow::Result<Cursor> {
+        self.order
+            .iter()
+            .map(|(col, _)| row.try_get::<_, Value>(*col))
+            .collect::<anyhow::Result<_>>()
+            .map(Cursor)
+    }
+
+    /// Renders the clauses following `SELECT ... FROM <table>`, with its placeholders starting
+    /// at `$1`.
+    ///
+    /// # Returns
+    /// The clauses and the parameters bound to their placeholders.
+    pub fn to_sql(&self) -> (String, Vec<&(dyn ToSql + Sync)>) {
+        let mut sql = String::new();
+        let mut params = vec![];
+
+        if let Some(filter) = &self.filter {
+            sql.push_str(" WHERE ");
+            match self.after {
+                // Keep an OR of the filter from swallowing the cursor condition
+                Some(_) => filter.render_grouped(&mut sql, &mut params),
+                None => filter.render(&mut sql, &mut params),
+            }
+        }
+        if let Some(cursor) = &self.after {
+            sql.push_str(match self.filter {
+                Some(_) => " AND ",
+                None => " WHERE ",
+            });
+            self.render_after(cursor, &mut sql, &mut params);
+        }
+
+        if !self.order.is_empty() {
+            let order = self
+                .order
+                .iter()
+                .map(|(col, order)| match order {
+                    Order::Asc => format!("\"{}\" ASC", col),
+                    Order::Desc => format!("\"{}\" DESC", col),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            sql.push_str(&format!(" ORDER BY {}", order));
+        }
+
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        if let Some(offset) = self.offset {
+            sql.push_str(&format!(" OFFSET {}", offset));
+        }
+
+        (sql, params)
+    }
+}
+
+impl Query {
+    /// Renders the condition of rows following the cursor: greater in the first order column,
+    /// or equal in it and greater in the next one, and so on. Descending columns compare the
+    /// other way round.
+    fn render_after<'a>(
+        &self,
+        cursor: &'a Cursor,
+        sql: &mut String,
+        params: &mut Vec<&'a (dyn ToSql + Sync)>,
+    ) {
+        let mut alternatives = vec![];
+        for (idx, (col, order)) in self.order.iter().enumerate() {
+            let mut conds = vec![];
+            for (eq_col, _) in &self.order[..idx] {
+                params.push(&cursor.0[conds.len()]);
+                conds.push(format!("\"{}\" = ${}", eq_col, params.len()));
+            }
+            let op = match order {
+                Order::Asc => ">",
+                Order::Desc => "<",
+            };
+            params.push(&cursor.0[idx]);
+            conds.push(format!("\"{}\" {} ${}", col, op, params.len()));
+            alternatives.push(format!("({})", conds.join(" AND ")));
+        }
+        sql.push('(');
+        sql.push_str(&alternatives.join(" OR "));
+        sql.push(')');
+    }
+}
+
+impl From<Filter> for Query {
+    fn from(filter: Filter) -> Self {
+        Query::new().filter(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAME: Column<String> = Column::new("name");
+    const AGE: Column<i32> = Column::new("age");
+
+    #[test]
+    fn test_empty_query() {
+        let query = Query::new();
+        let (sql, params) = query.to_sql();
+        assert_eq!(sql, "");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_combined_filters() {
+        let query = Query::new()
+            .filter(NAME.eq("a").or(NAME.eq("b")))
+            .filter(AGE.between(18, 30))
+            .filter(!AGE.is_in(vec![1, 2]));
+        let (sql, params) = query.to_sql();
+
+        assert_eq!(
+            sql,
+            " WHERE (\"name\" = $1 OR \"name\" = $2) AND \"age\" BETWEEN $3 AND $4 \
+             AND (NOT \"age\" IN ($5, $6))"
+        );
+        assert_eq!(params.len(), 6);
+    }
+
+    #[test]
+    fn test_order_limit_offset() {
+        let query = Query::from(NAME.is_null())
+            .order_by(AGE, Order::Desc)
+            .order_by(NAME, Order::Asc)
+            .limit(10)
+            .offset(20);
+
+        assert_eq!(
+            query.to_sql().0,
+            " WHERE \"name\" IS NULL ORDER BY \"age\" DESC, \"name\" ASC LIMIT 10 OFFSET 20"
+        );
+    }
+
+    #[test]
+    fn test_after() {
+        let query = Query::from(NAME.ne("a"))
+            .order_by(AGE, Order::Desc)
+            .unique_by(NAME)
+            .unique_by(AGE)
+            .after(Cursor::new(vec![
+                Value::Int(30),
+                Value::Text(String::from("b")),
+            ]))
+            .limit(2);
+        let (sql, params) = query.to_sql();
+
+        assert_eq!(
+            sql,
+            " WHERE \"name\" <> $1 AND ((\"age\" < $2) OR (\"age\" = $3 AND \"name\" > $4)) \
+             ORDER BY \"age\" DESC, \"name\" ASC LIMIT 2"
+        );
+        assert_eq!(params[3].to_value(), Value::Text(String::from("b")));
+
+        let row = Row::new(
+            vec![String::from("name"), String::from("age")],
+            vec![Value::Text(String::from("c")), Value::Int(20)],
+        );
+        assert_eq!(
+            query.cursor(&row).unwrap(),
+            Cursor::new(vec![Value::Int(20), Value::Text(String::from("c"))])
+        );
+    }
+
+    #[test]
+    fn test_empty_in() {
+        let query = Query::from(AGE.is_in(vec![]));
+        let (sql, params) = query.to_sql();
+        assert_eq!(sql, " WHERE FALSE");
+        assert!(params.is_empty());
+    }
+}
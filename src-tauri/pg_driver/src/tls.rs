@@ -0,0 +1,162 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use config::{ConfigError, Settings};
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use tokio_postgres::config::SslMode;
+
+/// Whether and how the connection to a PostgreSQL server is encrypted. The modes are named like
+/// the `sslmode` settings of libpq.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TlsMode {
+    /// Never encrypt the connection.
+    Disable,
+    /// Encrypt the connection if the server supports it, without verifying its certificate.
+    #[default]
+    Prefer,
+    /// Always encrypt the connection, without verifying the server's certificate.
+    Require,
+    /// Always encrypt the connection, and verify that the server's certificate is signed by a
+    /// trusted CA and issued for the host connected to.
+    VerifyFull,
+}
+
+impl FromStr for TlsMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> anyhow::Result<Self> {
+        match mode.to_lowercase().as_str() {
+            "disable" => Ok(Self::Disable),
+            "prefer" => Ok(Self::Prefer),
+            "require" => Ok(Self::Require),
+            "verify-full" => Ok(Self::VerifyFull),
+            _ => anyhow::bail!(
+                "Unknown TLS mode '{}', expected disable, prefer, require or verify-full",
+                mode
+            ),
+        }
+    }
+}
+
+impl fmt::Display for TlsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            Self::Disable => "disable",
+            Self::Prefer => "prefer",
+            Self::Require => "require",
+            Self::VerifyFull => "verify-full",
+        };
+        write!(f, "{}", mode)
+    }
+}
+
+/// The TLS settings of a PostgreSQL connection.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TlsConfig {
+    pub mode: TlsMode,
+    /// A PEM file with the certificates of the CAs to trust, in addition to the system ones.
+    pub ca_file: Option<PathBuf>,
+    /// A PEM file with the certificate to authenticate this client with.
+    pub client_cert: Option<PathBuf>,
+    /// A PEM file with the PKCS#8 private key of the client certificate.
+    pub client_key: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    /// Reads the TLS settings.
+    ///
+    /// * `database.tls.mode` - `disable`, `prefer` (default), `require` or `verify-full`.
+    /// * `database.tls.root_cert` - A CA bundle to trust in addition to the system CAs.
+    /// * `database.tls.cert`, `database.tls.key` - The client certificate and its key, if the
+    ///   server requires one.
+    ///
+    /// # Errors
+    /// If `database.tls.mode` is not one of the modes above.
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        Ok(Self {
+            mode: settings.get_or("database.tls.mode", TlsMode::default())?,
+            ca_file: settings.get("database.tls.root_cert")?,
+            client_cert: settings.get("database.tls.cert")?,
+            client_key: settings.get("database.tls.key")?,
+        })
+    }
+
+    /// Whether the connection may or must be encrypted.
+    pub(crate) fn ssl_mode(&self) -> SslMode {
+        match self.mode {
+            TlsMode::Disable => SslMode::Disable,
+            TlsMode::Prefer => SslMode::Prefer,
+            TlsMode::Require | TlsMode::VerifyFull => SslMode::Require,
+        }
+    }
+
+    /// Builds the connector encrypting the connection.
+    ///
+    /// # Errors
+    /// If the CA bundle or the client certificate can't be read.
+    pub(crate) fn connector(&self) -> anyhow::Result<MakeTlsConnector> {
+        let mut builder = TlsConnector::builder();
+
+        if let Some(ca_file) = &self.ca_file {
+            for cert in Certificate::stack_from_pem(&read(ca_file)?)? {
+                builder.add_root_certificate(cert);
+            }
+        }
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                builder.identity(Identity::from_pkcs8(&read(cert)?, &read(key)?)?);
+            }
+            (None, None) => {}
+            _ => anyhow::bail!("A client certificate needs both a certificate and a key file"),
+        }
+
+        if self.mode != TlsMode::VerifyFull {
+            builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+
+        Ok(MakeTlsConnector::new(builder.build()?))
+    }
+}
+
+fn read(path: &Path) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| anyhow::anyhow!("Could not read {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(
+            "verify-full".parse::<TlsMode>().unwrap(),
+            TlsMode::VerifyFull
+        );
+        assert_eq!("Require".parse::<TlsMode>().unwrap(), TlsMode::Require);
+        assert!("verify-ca".parse::<TlsMode>().is_err());
+
+        for mode in [
+            TlsMode::Disable,
+            TlsMode::Prefer,
+            TlsMode::Require,
+            TlsMode::VerifyFull,
+        ] {
+            assert_eq!(mode.to_string().parse::<TlsMode>().unwrap(), mode);
+        }
+    }
+
+    #[test]
+    fn test_incomplete_client_cert() {
+        let config = TlsConfig {
+            mode: TlsMode::Require,
+            client_cert: Some(PathBuf::from("client.crt")),
+            ..Default::default()
+        };
+        assert!(config.connector().is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use uuid::Uuid;
+
+/// The format timestamps are stored in by backends without a native timestamp type. Its fixed
+/// width keeps the textual order equal to the chronological one.
+pub(crate) const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.6f";
+
+/// A value passed to or read from the database, independent of the backend storing it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Uuid(Uuid),
+    /// A timestamp in UTC.
+    Timestamp(NaiveDateTime),
+}
+
+/// A type that can be bound to a query parameter.
+pub trait ToSql {
+    fn to_value(&self) -> Value;
+}
+
+/// A type that can be read from a column of a `Row`, possibly borrowing from it.
+pub trait FromSql<'a>: Sized {
+    fn from_value(val: &'a Value) -> anyhow::Result<Self>;
+}
+
+impl ToSql for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl<T: ToSql + ?Sized> ToSql for &T {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+impl<T: ToSql> ToSql for Option<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(val) => val.to_value(),
+            None => Value::Null,
+        }
+    }
+}
+
+impl ToSql for bool {
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+macro_rules! int_to_sql {
+    ($($t:ty),*) => {
+        $(impl ToSql for $t {
+            fn to_value(&self) -> Value {
+                Value::Int(*self as i64)
+            }
+        })*
+    };
+}
+
+int_to_sql!(i16, i32, i64);
+
+impl ToSql for f64 {
+    fn to_value(&self) -> Value {
+        Value::Float(*self)
+    }
+}
+
+impl ToSql for str {
+    fn to_value(&self) -> Value {
+        Value::Text(self.to_string())
+    }
+}
+
+impl ToSql for String {
+    fn to_value(&self) -> Value {
+        Value::Text(self.clone())
+    }
+}
+
+impl ToSql for Uuid {
+    fn to_value(&self) -> Value {
+        Value::Uuid(*self)
+    }
+}
+
+impl ToSql for NaiveDateTime {
+    fn to_value(&self) -> Value {
+        Value::Timestamp(*self)
+    }
+}
+
+impl ToSql for DateTime<Utc> {
+    fn to_value(&self) -> Value {
+        Value::Timestamp(self.naive_utc())
+    }
+}
+
+impl<'a, T: FromSql<'a>> FromSql<'a> for Option<T> {
+    fn from_value(val: &'a Value) -> anyhow::Result<Self> {
+        match val {
+            Value::Null => Ok(None),
+            val => T::from_value(val).map(Some),
+        }
+    }
+}
+
+impl FromSql<'_> for Value {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        Ok(val.clone())
+    }
+}
+
+impl FromSql<'_> for bool {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        match val {
+            Value::Bool(b) => Ok(*b),
+            // Backends without a boolean type store them as 0 and 1
+            Value::Int(i) => Ok(*i != 0),
+            val => Err(mismatch("bool", val)),
+        }
+    }
+}
+
+macro_rules! int_from_sql {
+    ($($t:ty),*) => {
+        $(impl FromSql<'_> for $t {
+            fn from_value(val: &Value) -> anyhow::Result<Self> {
+                match val {
+                    Value::Int(i) => Ok(<$t>::try_from(*i)?),
+                    val => Err(mismatch(stringify!($t), val)),
+                }
+            }
+        })*
+    };
+}
+
+int_from_sql!(i16, i32, i64);
+
+impl FromSql<'_> for f64 {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        match val {
+            Value::Float(f) => Ok(*f),
+            Value::Int(i) => Ok(*i as f64),
+            val => Err(mismatch("f64", val)),
+        }
+    }
+}
+
+impl<'a> FromSql<'a> for &'a str {
+    fn from_value(val: &'a Value) -> anyhow::Result<Self> {
+        match val {
+            Value::Text(s) => Ok(s),
+            val => Err(mismatch("&str", val)),
+        }
+    }
+}
+
+impl FromSql<'_> for String {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        match val {
+            Value::Text(s) => Ok(s.clone()),
+            val => Err(mismatch("String", val)),
+        }
+    }
+}
+
+impl FromSql<'_> for Uuid {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        match val {
+            Value::Uuid(uuid) => Ok(*uuid),
+            Value::Text(s) => Ok(Uuid::parse_str(s)?),
+            val => Err(mismatch("Uuid", val)),
+        }
+    }
+}
+
+impl FromSql<'_> for NaiveDateTime {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        match val {
+            Value::Timestamp(ts) => Ok(*ts),
+            Value::Text(s) => parse_timestamp(s),
+            val => Err(mismatch("NaiveDateTime", val)),
+        }
+    }
+}
+
+impl FromSql<'_> for DateTime<Utc> {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        NaiveDateTime::from_value(val).map(|ts| ts.and_utc())
+    }
+}
+
+/// Parses a timestamp stored as text, accepting both the format written by this crate and the
+/// one of SQLite's `CURRENT_TIMESTAMP`.
+pub(crate) fn parse_timestamp(s: &str) -> anyhow::Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, TIMESTAMP_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f"))
+        .map_err(|e| anyhow!("Invalid timestamp '{}': {}", s, e))
+}
+
+fn mismatch(expected: &str, val: &Value) -> anyhow::Error {
+    anyhow!("Cannot read {:?} as {}", val, expected)
+}
+
+/// A row returned by a query, holding its values by column.
+#[derive(Debug, Clone)]
+pub struct Row {
+    columns: Vec<String>,
+    values: Vec<Value>,
+}
+
+/// Identifies a column of a `Row`, either by its position or its name.
+pub trait RowIndex: Display {
+    fn index(&self, row: &Row) -> Option<usize>;
+}
+
+impl RowIndex for usize {
+    fn index(&self, row: &Row) -> Option<usize> {
+        (*self < row.values.len()).then_some(*self)
+    }
+}
+
+impl RowIndex for str {
+    fn index(&self, row: &Row) -> Option<usize> {
+        row.columns.iter().position(|col| col == self)
+    }
+}
+
+impl RowIndex for String {
+    fn index(&self, row: &Row) -> Option<usize> {
+        self.as_str().index(row)
+    }
+}
+
+impl<T: RowIndex + ?Sized> RowIndex for &T {
+    fn index(&self, row: &Row) -> Option<usize> {
+        (**self).index(row)
+    }
+}
+
+impl Row {
+    pub(crate) fn new(columns: Vec<String>, values: Vec<Value>) -> Self {
+        Self { columns, values }
+    }
+
+    /// The names of the columns of this row.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Reads the value of a column.
+    ///
+    /// # Panics
+    /// If the column does not exist or its value cannot be read as `T`.
+    pub fn get<'a, I: RowIndex, T: FromSql<'a>>(&'a self, idx: I) -> T {
+        match self.try_get(&idx) {
+            Ok(val) => val,
+            Err(e) => panic!("error retrieving column {}: {}", idx, e),
+        }
+    }
+
+    /// Reads the value of a column.
+    ///
+    /// # Errors
+    /// If the column does not exist or its value cannot be read as `T`.
+    pub fn try_get<'a, I: RowIndex, T: FromSql<'a>>(&'a self, idx: I) -> anyhow::Result<T> {
+        let idx = idx
+            .index(self)
+            .ok_or_else(|| anyhow!("Unknown column {}", idx))?;
+        T::from_value(&self.values[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_get() {
+        let uuid = Uuid::new_v4();
+        let row = Row::new(
+            vec![String::from("uuid"), String::from("flag")],
+            vec![Value::Text(uuid.to_string()), Value::Int(1)],
+        );
+
+        assert_eq!(row.get::<_, Uuid>("uuid"), uuid);
+        assert!(row.get::<_, bool>(1));
+        assert!(row.try_get::<_, String>("missing").is_err());
+        assert!(row.try_get::<_, Option<i32>>("uuid").is_err());
+    }
+
+    #[test]
+    fn test_timestamp_text_roundtrip() {
+        let ts = DateTime::from_timestamp(1_700_000_000, 123_000)
+            .unwrap()
+            .naive_utc();
+        let stored = Value::Text(ts.format(TIMESTAMP_FORMAT).to_string());
+
+        assert_eq!(NaiveDateTime::from_value(&stored).unwrap(), ts);
+        assert!(
+            NaiveDateTime::from_value(&Value::Text(String::from("2024-01-02 03:04:05"))).is_ok()
+        );
+    }
+}
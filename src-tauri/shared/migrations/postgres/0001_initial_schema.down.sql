@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS public.clients;
+DROP TABLE IF EXISTS public.users_calendars;
+DROP TABLE IF EXISTS public.properties;
+DROP TABLE IF EXISTS public.components;
+DROP TABLE IF EXISTS public.calendars;
+DROP TABLE IF EXISTS public.users;
@@ -0,0 +1,49 @@
+CREATE TABLE IF NOT EXISTS public.users (
+    "uuid" uuid NOT NULL,
+    email varchar(255) NULL,
+    "password" varchar(255) NULL,
+    username varchar(255) NULL,
+    CONSTRAINT users_pkey PRIMARY KEY (uuid)
+);
+
+CREATE TABLE IF NOT EXISTS public.calendars (
+    "uuid" uuid NOT NULL,
+    "name" varchar NULL,
+    CONSTRAINT calendars_pkey PRIMARY KEY (uuid)
+);
+
+CREATE TABLE IF NOT EXISTS public.components (
+    "uuid" uuid NOT NULL,
+    c_type varchar(255) NULL,
+    calendar_uuid uuid NULL,
+    CONSTRAINT components_pkey PRIMARY KEY (uuid),
+    CONSTRAINT components_calendar_uuid_fkey FOREIGN KEY (calendar_uuid) REFERENCES public.calendars("uuid")
+);
+
+CREATE TABLE IF NOT EXISTS public.properties (
+    "uuid" uuid NOT NULL,
+    "key" varchar NOT NULL,
+    value varchar NOT NULL,
+    owner_uuid uuid NOT NULL,
+    owner_type varchar NOT NULL,
+    CONSTRAINT properties_pkey PRIMARY KEY (uuid)
+);
+
+CREATE TABLE IF NOT EXISTS public.users_calendars (
+    calendar_uuid uuid NOT NULL,
+    user_uuid uuid NOT NULL,
+    CONSTRAINT calendar_users_pkey PRIMARY KEY (calendar_uuid, user_uuid),
+    CONSTRAINT calendar_users_calendar_uuid_fkey FOREIGN KEY (calendar_uuid) REFERENCES public.calendars("uuid"),
+    CONSTRAINT calendar_users_user_uuid_fkey FOREIGN KEY (user_uuid) REFERENCES public.users("uuid")
+);
+
+CREATE TABLE IF NOT EXISTS public.clients (
+    "uuid" uuid NOT NULL,
+    user_uuid uuid NOT NULL,
+    public_key varchar NOT NULL,
+    device_name varchar NOT NULL,
+    last_used timestamp NOT NULL,
+    registered_at timestamp NOT NULL,
+    CONSTRAINT clients_pkey PRIMARY KEY (uuid),
+    CONSTRAINT clients_user_uuid_fkey FOREIGN KEY (user_uuid) REFERENCES public.users("uuid")
+);
@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS properties_notify_change ON public.properties;
+DROP TRIGGER IF EXISTS components_notify_change ON public.components;
+DROP TRIGGER IF EXISTS calendars_notify_change ON public.calendars;
+DROP FUNCTION IF EXISTS public.notify_change();
@@ -0,0 +1,41 @@
+-- Announces every change of a calendar, its components or its properties on the
+-- scheduleflow_changes channel, so other clients can refresh what they show.
+CREATE OR REPLACE FUNCTION public.notify_change() RETURNS trigger AS $$
+DECLARE
+    changed record;
+    calendar uuid;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    IF TG_TABLE_NAME = 'calendars' THEN
+        calendar := changed.uuid;
+    ELSIF TG_TABLE_NAME = 'components' THEN
+        calendar := changed.calendar_uuid;
+    ELSIF changed.owner_type = 'Calendar' THEN
+        calendar := changed.owner_uuid;
+    ELSE
+        SELECT c.calendar_uuid INTO calendar FROM public.components c WHERE c.uuid = changed.owner_uuid;
+    END IF;
+
+    PERFORM pg_notify('scheduleflow_changes', json_build_object(
+        'table', TG_TABLE_NAME,
+        'op', lower(TG_OP),
+        'uuid', changed.uuid,
+        'calendar_uuid', calendar
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER calendars_notify_change AFTER INSERT OR UPDATE OR DELETE ON public.calendars
+    FOR EACH ROW EXECUTE FUNCTION public.notify_change();
+
+CREATE TRIGGER components_notify_change AFTER INSERT OR UPDATE OR DELETE ON public.components
+    FOR EACH ROW EXECUTE FUNCTION public.notify_change();
+
+CREATE TRIGGER properties_notify_change AFTER INSERT OR UPDATE OR DELETE ON public.properties
+    FOR EACH ROW EXECUTE FUNCTION public.notify_change();
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS public.components_calendar_time_idx;
+
+ALTER TABLE public.components DROP COLUMN IF EXISTS ends_at;
+ALTER TABLE public.components DROP COLUMN IF EXISTS starts_at;
@@ -0,0 +1,8 @@
+-- The times of a component, copied from its DTSTART and DTEND properties, so time ranges can be
+-- queried without loading and parsing every property. Components without times, like venues,
+-- leave them NULL. A component without an end ends when it starts.
+ALTER TABLE public.components ADD COLUMN IF NOT EXISTS starts_at timestamp NULL;
+ALTER TABLE public.components ADD COLUMN IF NOT EXISTS ends_at timestamp NULL;
+
+CREATE INDEX IF NOT EXISTS components_calendar_time_idx
+    ON public.components (calendar_uuid, starts_at, ends_at);
@@ -0,0 +1 @@
+ALTER TABLE public.properties DROP COLUMN IF EXISTS params;
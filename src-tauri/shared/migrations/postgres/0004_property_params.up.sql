@@ -0,0 +1,3 @@
+-- The iCalendar parameters of a property, like TZID=Europe/Berlin, in the form they take in a
+-- content line. Empty if it has none.
+ALTER TABLE public.properties ADD COLUMN IF NOT EXISTS params varchar NOT NULL DEFAULT '';
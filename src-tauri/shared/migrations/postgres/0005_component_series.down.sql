@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS public.components_calendar_series_idx;
+CREATE INDEX IF NOT EXISTS components_calendar_time_idx
+    ON public.components (calendar_uuid, starts_at, ends_at);
+
+ALTER TABLE public.components DROP COLUMN IF EXISTS series_ends_at;
@@ -0,0 +1,17 @@
+-- When the last occurrence of a component ends, so recurring components are found by time range
+-- queries beyond their first occurrence. Equals ends_at for components that don't recur, NULL for
+-- series without an end.
+ALTER TABLE public.components ADD COLUMN IF NOT EXISTS series_ends_at timestamp NULL;
+
+UPDATE public.components SET series_ends_at = ends_at;
+-- The recurrences of components stored before can't be expanded here, they are treated as series
+-- without an end instead
+UPDATE public.components c SET series_ends_at = NULL
+    WHERE EXISTS (
+        SELECT 1 FROM public.properties p
+        WHERE p.owner_uuid = c.uuid AND p.owner_type = 'Component' AND p.key IN ('RRULE', 'RDATE')
+    );
+
+DROP INDEX IF EXISTS public.components_calendar_time_idx;
+CREATE INDEX IF NOT EXISTS components_calendar_series_idx
+    ON public.components (calendar_uuid, starts_at, series_ends_at);
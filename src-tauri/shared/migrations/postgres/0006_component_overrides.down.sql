@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS public.components_recurrence_of_idx;
+ALTER TABLE public.components DROP CONSTRAINT IF EXISTS components_recurrence_of_fkey;
+
+ALTER TABLE public.components DROP COLUMN IF EXISTS recurrence_id;
+ALTER TABLE public.components DROP COLUMN IF EXISTS recurrence_of;
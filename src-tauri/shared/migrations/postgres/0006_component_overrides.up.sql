@@ -0,0 +1,10 @@
+-- Overrides of single occurrences of a recurring component, like a moved meeting, point to the
+-- component they override and to the start of the occurrence they replace. Both are copied from
+-- their UID and RECURRENCE-ID properties. Components that don't override an occurrence leave
+-- them NULL.
+ALTER TABLE public.components ADD COLUMN IF NOT EXISTS recurrence_of uuid NULL;
+ALTER TABLE public.components ADD COLUMN IF NOT EXISTS recurrence_id timestamp NULL;
+
+ALTER TABLE public.components ADD CONSTRAINT components_recurrence_of_fkey
+    FOREIGN KEY (recurrence_of) REFERENCES public.components("uuid");
+CREATE INDEX IF NOT EXISTS components_recurrence_of_idx ON public.components (recurrence_of);
@@ -0,0 +1,2 @@
+ALTER TABLE public.users DROP COLUMN IF EXISTS time_zone;
+ALTER TABLE public.components DROP COLUMN IF EXISTS time_zone;
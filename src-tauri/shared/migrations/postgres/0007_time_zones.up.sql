@@ -0,0 +1,7 @@
+-- The IANA time zone the times of a component were given in, like Europe/Berlin, so recurrences
+-- are expanded in local time across DST changes. NULL for UTC, floating and whole day times.
+-- Components imported before keep the times they were read with.
+ALTER TABLE public.components ADD COLUMN IF NOT EXISTS time_zone varchar NULL;
+
+-- The time zone a user wants times to be shown in, NULL for the zone of the device.
+ALTER TABLE public.users ADD COLUMN IF NOT EXISTS time_zone varchar NULL;
@@ -0,0 +1,4 @@
+ALTER TABLE public.users DROP COLUMN IF EXISTS meeting_buffer;
+ALTER TABLE public.users DROP COLUMN IF EXISTS work_days;
+ALTER TABLE public.users DROP COLUMN IF EXISTS work_ends;
+ALTER TABLE public.users DROP COLUMN IF EXISTS work_starts;
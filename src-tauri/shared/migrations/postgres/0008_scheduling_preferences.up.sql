@@ -0,0 +1,9 @@
+-- The working hours of a user, as minutes after midnight in their time zone, and the days of the
+-- week they work on, like MO,TU,WE,TH,FR. Meetings are only proposed within them. NULL for users
+-- who can meet at any time, or for Monday to Friday.
+ALTER TABLE public.users ADD COLUMN IF NOT EXISTS work_starts integer NULL;
+ALTER TABLE public.users ADD COLUMN IF NOT EXISTS work_ends integer NULL;
+ALTER TABLE public.users ADD COLUMN IF NOT EXISTS work_days varchar NULL;
+
+-- How many minutes a user wants kept free between a proposed meeting and their other events.
+ALTER TABLE public.users ADD COLUMN IF NOT EXISTS meeting_buffer integer NOT NULL DEFAULT 0;
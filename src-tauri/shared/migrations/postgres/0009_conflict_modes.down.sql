@@ -0,0 +1 @@
+ALTER TABLE public.calendars DROP COLUMN IF EXISTS conflict_mode;
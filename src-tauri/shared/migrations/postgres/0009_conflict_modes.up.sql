@@ -0,0 +1,3 @@
+-- How a calendar treats events overlapping other events of its users. SOFT calendars store them
+-- and report the conflicts, HARD calendars, like the ones of rooms, reject them.
+ALTER TABLE public.calendars ADD COLUMN IF NOT EXISTS conflict_mode varchar NOT NULL DEFAULT 'SOFT';
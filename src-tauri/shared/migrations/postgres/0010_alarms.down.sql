@@ -0,0 +1,33 @@
+DROP TRIGGER IF EXISTS alarms_notify_change ON public.alarms;
+DROP TABLE IF EXISTS public.alarms;
+
+CREATE OR REPLACE FUNCTION public.notify_change() RETURNS trigger AS $$
+DECLARE
+    changed record;
+    calendar uuid;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    IF TG_TABLE_NAME = 'calendars' THEN
+        calendar := changed.uuid;
+    ELSIF TG_TABLE_NAME = 'components' THEN
+        calendar := changed.calendar_uuid;
+    ELSIF changed.owner_type = 'Calendar' THEN
+        calendar := changed.owner_uuid;
+    ELSE
+        SELECT c.calendar_uuid INTO calendar FROM public.components c WHERE c.uuid = changed.owner_uuid;
+    END IF;
+
+    PERFORM pg_notify('scheduleflow_changes', json_build_object(
+        'table', TG_TABLE_NAME,
+        'op', lower(TG_OP),
+        'uuid', changed.uuid,
+        'calendar_uuid', calendar
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
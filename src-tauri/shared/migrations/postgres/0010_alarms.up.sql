@@ -0,0 +1,56 @@
+-- Alarms of events and to-dos, their VALARMs. Relative triggers fire at every occurrence,
+-- `trigger_offset` seconds from its start or end. Absolute triggers fire once, at `trigger_at`.
+CREATE TABLE IF NOT EXISTS public.alarms (
+    "uuid" uuid NOT NULL,
+    component_uuid uuid NOT NULL,
+    "action" varchar NOT NULL DEFAULT 'DISPLAY',
+    trigger_offset bigint NULL,
+    trigger_related varchar NOT NULL DEFAULT 'START',
+    trigger_at timestamp NULL,
+    repeat_count integer NOT NULL DEFAULT 0,
+    repeat_interval bigint NULL,
+    description varchar NULL,
+    acknowledged_at timestamp NULL,
+    snoozed_until timestamp NULL,
+    CONSTRAINT alarms_pkey PRIMARY KEY (uuid),
+    CONSTRAINT alarms_component_uuid_fkey FOREIGN KEY (component_uuid) REFERENCES public.components("uuid") ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS alarms_component_uuid_idx ON public.alarms (component_uuid);
+
+-- Alarms are announced like the components they belong to, so a snooze on one client silences
+-- the others as well.
+CREATE OR REPLACE FUNCTION public.notify_change() RETURNS trigger AS $$
+DECLARE
+    changed record;
+    calendar uuid;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    IF TG_TABLE_NAME = 'calendars' THEN
+        calendar := changed.uuid;
+    ELSIF TG_TABLE_NAME = 'components' THEN
+        calendar := changed.calendar_uuid;
+    ELSIF TG_TABLE_NAME = 'alarms' THEN
+        SELECT c.calendar_uuid INTO calendar FROM public.components c WHERE c.uuid = changed.component_uuid;
+    ELSIF changed.owner_type = 'Calendar' THEN
+        calendar := changed.owner_uuid;
+    ELSE
+        SELECT c.calendar_uuid INTO calendar FROM public.components c WHERE c.uuid = changed.owner_uuid;
+    END IF;
+
+    PERFORM pg_notify('scheduleflow_changes', json_build_object(
+        'table', TG_TABLE_NAME,
+        'op', lower(TG_OP),
+        'uuid', changed.uuid,
+        'calendar_uuid', calendar
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER alarms_notify_change AFTER INSERT OR UPDATE OR DELETE ON public.alarms
+    FOR EACH ROW EXECUTE FUNCTION public.notify_change();
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS clients;
+DROP TABLE IF EXISTS users_calendars;
+DROP TABLE IF EXISTS properties;
+DROP TABLE IF EXISTS components;
+DROP TABLE IF EXISTS calendars;
+DROP TABLE IF EXISTS users;
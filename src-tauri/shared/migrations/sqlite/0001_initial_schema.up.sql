@@ -0,0 +1,49 @@
+CREATE TABLE IF NOT EXISTS users (
+    "uuid" TEXT NOT NULL,
+    email TEXT NULL,
+    "password" TEXT NULL,
+    username TEXT NULL,
+    CONSTRAINT users_pkey PRIMARY KEY (uuid)
+);
+
+CREATE TABLE IF NOT EXISTS calendars (
+    "uuid" TEXT NOT NULL,
+    "name" TEXT NULL,
+    CONSTRAINT calendars_pkey PRIMARY KEY (uuid)
+);
+
+CREATE TABLE IF NOT EXISTS components (
+    "uuid" TEXT NOT NULL,
+    c_type TEXT NULL,
+    calendar_uuid TEXT NULL,
+    CONSTRAINT components_pkey PRIMARY KEY (uuid),
+    CONSTRAINT components_calendar_uuid_fkey FOREIGN KEY (calendar_uuid) REFERENCES calendars("uuid")
+);
+
+CREATE TABLE IF NOT EXISTS properties (
+    "uuid" TEXT NOT NULL,
+    "key" TEXT NOT NULL,
+    value TEXT NOT NULL,
+    owner_uuid TEXT NOT NULL,
+    owner_type TEXT NOT NULL,
+    CONSTRAINT properties_pkey PRIMARY KEY (uuid)
+);
+
+CREATE TABLE IF NOT EXISTS users_calendars (
+    calendar_uuid TEXT NOT NULL,
+    user_uuid TEXT NOT NULL,
+    CONSTRAINT calendar_users_pkey PRIMARY KEY (calendar_uuid, user_uuid),
+    CONSTRAINT calendar_users_calendar_uuid_fkey FOREIGN KEY (calendar_uuid) REFERENCES calendars("uuid"),
+    CONSTRAINT calendar_users_user_uuid_fkey FOREIGN KEY (user_uuid) REFERENCES users("uuid")
+);
+
+CREATE TABLE IF NOT EXISTS clients (
+    "uuid" TEXT NOT NULL,
+    user_uuid TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    device_name TEXT NOT NULL,
+    last_used TEXT NOT NULL,
+    registered_at TEXT NOT NULL,
+    CONSTRAINT clients_pkey PRIMARY KEY (uuid),
+    CONSTRAINT clients_user_uuid_fkey FOREIGN KEY (user_uuid) REFERENCES users("uuid")
+);
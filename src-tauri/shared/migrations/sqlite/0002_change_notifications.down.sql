@@ -0,0 +1,2 @@
+-- SQLite has no notifications, a local database has no other clients to notify anyway.
+-- This migration only keeps the versions in sync with the postgres schema.
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS components_calendar_time_idx;
+
+ALTER TABLE components DROP COLUMN ends_at;
+ALTER TABLE components DROP COLUMN starts_at;
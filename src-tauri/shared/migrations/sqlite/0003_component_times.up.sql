@@ -0,0 +1,7 @@
+-- See the postgres migration. Timestamps are stored as text of a fixed width, so they compare
+-- chronologically.
+ALTER TABLE components ADD COLUMN starts_at TEXT NULL;
+ALTER TABLE components ADD COLUMN ends_at TEXT NULL;
+
+CREATE INDEX IF NOT EXISTS components_calendar_time_idx
+    ON components (calendar_uuid, starts_at, ends_at);
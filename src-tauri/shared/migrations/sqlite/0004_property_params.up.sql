@@ -0,0 +1,2 @@
+-- See the postgres migration.
+ALTER TABLE properties ADD COLUMN params TEXT NOT NULL DEFAULT '';
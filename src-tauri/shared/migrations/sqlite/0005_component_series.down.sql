@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS components_calendar_series_idx;
+CREATE INDEX IF NOT EXISTS components_calendar_time_idx
+    ON components (calendar_uuid, starts_at, ends_at);
+
+ALTER TABLE components DROP COLUMN series_ends_at;
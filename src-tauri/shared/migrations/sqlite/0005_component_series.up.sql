@@ -0,0 +1,14 @@
+-- See the postgres migration.
+ALTER TABLE components ADD COLUMN series_ends_at TEXT NULL;
+
+UPDATE components SET series_ends_at = ends_at;
+UPDATE components SET series_ends_at = NULL
+    WHERE EXISTS (
+        SELECT 1 FROM properties p
+        WHERE p.owner_uuid = components.uuid AND p.owner_type = 'Component'
+            AND p.key IN ('RRULE', 'RDATE')
+    );
+
+DROP INDEX IF EXISTS components_calendar_time_idx;
+CREATE INDEX IF NOT EXISTS components_calendar_series_idx
+    ON components (calendar_uuid, starts_at, series_ends_at);
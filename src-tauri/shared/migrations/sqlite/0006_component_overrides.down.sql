@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS components_recurrence_of_idx;
+
+ALTER TABLE components DROP COLUMN recurrence_id;
+ALTER TABLE components DROP COLUMN recurrence_of;
@@ -0,0 +1,5 @@
+-- See the postgres migration.
+ALTER TABLE components ADD COLUMN recurrence_of TEXT NULL REFERENCES components("uuid");
+ALTER TABLE components ADD COLUMN recurrence_id TEXT NULL;
+
+CREATE INDEX IF NOT EXISTS components_recurrence_of_idx ON components (recurrence_of);
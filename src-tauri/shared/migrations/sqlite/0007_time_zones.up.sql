@@ -0,0 +1,4 @@
+-- See the postgres migration.
+ALTER TABLE components ADD COLUMN time_zone TEXT NULL;
+
+ALTER TABLE users ADD COLUMN time_zone TEXT NULL;
@@ -0,0 +1,4 @@
+ALTER TABLE users DROP COLUMN meeting_buffer;
+ALTER TABLE users DROP COLUMN work_days;
+ALTER TABLE users DROP COLUMN work_ends;
+ALTER TABLE users DROP COLUMN work_starts;
@@ -0,0 +1,6 @@
+-- See the postgres migration.
+ALTER TABLE users ADD COLUMN work_starts INTEGER NULL;
+ALTER TABLE users ADD COLUMN work_ends INTEGER NULL;
+ALTER TABLE users ADD COLUMN work_days TEXT NULL;
+
+ALTER TABLE users ADD COLUMN meeting_buffer INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- See the postgres migration.
+ALTER TABLE calendars ADD COLUMN conflict_mode TEXT NOT NULL DEFAULT 'SOFT';
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS alarms_component_uuid_idx;
+DROP TABLE IF EXISTS alarms;
@@ -0,0 +1,17 @@
+-- See the postgres migration.
+CREATE TABLE IF NOT EXISTS alarms (
+    "uuid" TEXT NOT NULL,
+    component_uuid TEXT NOT NULL,
+    "action" TEXT NOT NULL DEFAULT 'DISPLAY',
+    trigger_offset INTEGER NULL,
+    trigger_related TEXT NOT NULL DEFAULT 'START',
+    trigger_at TEXT NULL,
+    repeat_count INTEGER NOT NULL DEFAULT 0,
+    repeat_interval INTEGER NULL,
+    description TEXT NULL,
+    acknowledged_at TEXT NULL,
+    snoozed_until TEXT NULL,
+    CONSTRAINT alarms_pkey PRIMARY KEY (uuid),
+    CONSTRAINT alarms_component_uuid_fkey FOREIGN KEY (component_uuid) REFERENCES components("uuid") ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS alarms_component_uuid_idx ON alarms (component_uuid);
@@ -0,0 +1,333 @@
+use std::ops::DerefMut;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tauri::Emitter;
+use uuid::Uuid;
+
+use crate::current::{self, get_app_handle, pool};
+use crate::db::model::alarm::{self, Alarm, DueAlarm, MAX_ALARM_REACH_DAYS};
+use crate::db::model::simple::simple_component::SimpleComponent;
+use crate::db::model::user::User;
+use crate::db::repository::alarm_repository::AlarmRepository;
+use crate::notifications;
+
+/// The name of the Tauri event every `DueAlarm` is emitted as by the `TauriNotifier`.
+pub const ALARM_EVENT: &str = "alarmDue";
+
+/// How long ago alarms may have fired while nobody was logged in to still fire after the next
+/// login. Alarms acknowledged since don't.
+pub const MISSED_ALARMS_HOURS: i64 = 12;
+
+/// How long the scheduler waits at most before looking for due alarms again. Changes announced
+/// by the database wake it up earlier, this catches the ones nothing announces, like the ones of
+/// a local SQLite database.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Tells the user about due alarms, like with a popup.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, alarm: &DueAlarm);
+}
+
+/// Emits due alarms to the frontend as `ALARM_EVENT`.
+pub struct TauriNotifier;
+
+impl Notifier for TauriNotifier {
+    fn notify(&self, alarm: &DueAlarm) {
+        if let Some(app_handle) = get_app_handle() {
+            if let Err(e) = app_handle.emit(ALARM_EVENT, alarm) {
+                log::warn!("Could not emit {}: {}", ALARM_EVENT, e);
+            }
+        }
+    }
+}
+
+/// Logs due alarms and keeps them, like to check which alarms a test fired.
+#[derive(Default)]
+pub struct LogNotifier {
+    fired: Mutex<Vec<DueAlarm>>,
+}
+
+impl LogNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The alarms fired so far, in the order they fired.
+    pub fn fired(&self) -> Vec<DueAlarm> {
+        self.fired.lock().unwrap().clone()
+    }
+}
+
+impl Notifier for LogNotifier {
+    fn notify(&self, alarm: &DueAlarm) {
+        log::info!(
+            "Alarm {} of {} is due at {}",
+            alarm.alarm_uuid,
+            alarm.component_uuid,
+            alarm.fires_at
+        );
+        self.fired.lock().unwrap().push(alarm.clone());
+    }
+}
+
+/// Whether the scheduler was asked to look for due alarms right away, see `wake`.
+static WAKE: (Mutex<bool>, Condvar) = (Mutex::new(false), Condvar::new());
+
+/// Starts the alarm scheduler in the background, if not done yet. It fires the alarms of the
+/// calendars of the current user through the given notifier when they are due, see
+/// `alarm::due_between`, and looks for them again on every change of the calendars.
+///
+/// # Errors
+/// If the scheduler thread could not be started.
+///
+/// # Examples
+///
+/// ```
+/// alarms::start(Arc::new(TauriNotifier))?;
+/// ```
+pub fn start(notifier: Arc<dyn Notifier>) -> anyhow::Result<()> {
+    static STARTED: Mutex<bool> = Mutex::new(false);
+
+    let mut started = STARTED.lock().unwrap();
+    if *started {
+        return Ok(());
+    }
+    thread::Builder::new()
+        .name(String::from("alarm-scheduler"))
+        .spawn(move || run(notifier))?;
+    notifications::on_change(|_| wake());
+    *started = true;
+    Ok(())
+}
+
+/// Makes the scheduler look for due alarms right away, like after alarms were changed.
+pub fn wake() {
+    let (requested, condvar) = &WAKE;
+    *requested.lock().unwrap() = true;
+    condvar.notify_all();
+}
+
+fn run(notifier: Arc<dyn Notifier>) {
+    let mut scheduler = Scheduler::new(notifier, Utc::now());
+    loop {
+        let now = Utc::now();
+        let timeout = match scheduler.tick(now) {
+            Some(next) => (next - now).to_std().unwrap_or_default(),
+            None => RESCAN_INTERVAL,
+        };
+        wait(timeout.min(RESCAN_INTERVAL));
+    }
+}
+
+/// Waits for the given time, or until the scheduler is woken up.
+fn wait(timeout: Duration) {
+    let (requested, condvar) = &WAKE;
+    let guard = requested.lock().unwrap();
+    let (mut guard, _) = condvar
+        .wait_timeout_while(guard, timeout, |requested| !*requested)
+        .unwrap();
+    *guard = false;
+}
+
+/// Fires the due alarms of the current user, remembering up to when they were fired.
+struct Scheduler {
+    notifier: Arc<dyn Notifier>,
+    /// The user whose alarms are fired, None while nobody is logged in.
+    user_uuid: Option<Uuid>,
+    /// Up to when the alarms were fired.
+    fired_until: DateTime<Utc>,
+}
+
+impl Scheduler {
+    fn new(notifier: Arc<dyn Notifier>, now: DateTime<Utc>) -> Self {
+        Self {
+            notifier,
+            user_uuid: None,
+            fired_until: now,
+        }
+    }
+
+    /// Fires the alarms of the current user due by `now` that weren't fired yet, see `advance`.
+    ///
+    /// # Returns
+    /// When the next alarm is due, None if no alarm is known to be due soon.
+    fn tick(&mut self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let user = current::CURRENT_USER
+            .get()
+            .and_then(|user| user.lock().unwrap().clone());
+        let Some(user) = user else {
+            self.user_uuid = None;
+            return None;
+        };
+        self.advance(&user, now, load)
+    }
+
+    /// Fires the alarms of the given user due by `now` that weren't fired yet. After a user
+    /// logged in, their alarms of the last `MISSED_ALARMS_HOURS` fire first. If the alarms can't
+    /// be loaded, e.g. while the database is offline, none are fired, so the ones due meanwhile
+    /// fire once they can be loaded again.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user whose alarms are fired.
+    /// * `now` - Up to when the alarms are fired.
+    /// * `load` - Loads the alarms of a user and the occurrences overlapping a time range, see
+    ///   `load`.
+    ///
+    /// # Returns
+    /// When the next alarm is due, None if no alarm is known to be due soon.
+    fn advance(
+        &mut self,
+        user: &User,
+        now: DateTime<Utc>,
+        load: impl FnOnce(&User, DateTime<Utc>, DateTime<Utc>) -> anyhow::Result<Loaded>,
+    ) -> Option<DateTime<Utc>> {
+        if self.user_uuid != Some(user.get_uuid()) {
+            self.user_uuid = Some(user.get_uuid());
+            self.fired_until = now - TimeDelta::hours(MISSED_ALARMS_HOURS);
+        }
+
+        let reach = TimeDelta::days(MAX_ALARM_REACH_DAYS);
+        let rescan = TimeDelta::from_std(RESCAN_INTERVAL).unwrap_or_default();
+        match load(user, self.fired_until - reach, now + rescan + reach) {
+            Ok((alarms, occurrences)) => self.fire(&alarms, &occurrences, now),
+            Err(e) => {
+                log::warn!("Could not load the alarms, retrying later: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Fires the given alarms due after the ones fired last, up to `now`.
+    ///
+    /// # Returns
+    /// When the next of the alarms is due.
+    fn fire(
+        &mut self,
+        alarms: &[Alarm],
+        occurrences: &[SimpleComponent],
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        for due in alarm::due_between(alarms, occurrences, self.fired_until, now) {
+            self.notifier.notify(&due);
+        }
+        self.fired_until = self.fired_until.max(now);
+        alarm::next_due(alarms, occurrences, now)
+    }
+}
+
+/// The alarms of a user and the occurrences of the components they remind of.
+type Loaded = (Vec<Alarm>, Vec<SimpleComponent>);
+
+/// Reads the alarms of the calendars of a user, and the occurrences of their components
+/// overlapping `from..to`.
+///
+/// # Errors
+/// If no connection became available in time, or the alarms or components could not be read.
+fn load(user: &User, from: DateTime<Utc>, to: DateTime<Utc>) -> anyhow::Result<Loaded> {
+    let mut driver_binding = pool().get()?;
+    let driver = driver_binding.deref_mut();
+    let (mut calendar_uuids, alarms): (Vec<Uuid>, Vec<Alarm>) =
+        AlarmRepository::get_alarms_of_user(driver, user)?
+            .into_iter()
+            .unzip();
+    calendar_uuids.sort();
+    calendar_uuids.dedup();
+    if calendar_uuids.is_empty() {
+        return Ok((vec![], vec![]));
+    }
+
+    let reminded = |uuid: Uuid| alarms.iter().any(|alarm| alarm.component_uuid == uuid);
+    let occurrences = SimpleComponent::try_build_in_range(driver, &calendar_uuids, from, to)?
+        .into_iter()
+        .filter(|occurrence| {
+            reminded(occurrence.get_uuid()) || occurrence.get_recurrence_of().is_some_and(reminded)
+        })
+        .collect();
+    Ok((alarms, occurrences))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::alarm::TriggerRelation;
+    use crate::db::model::component::{Component, ComponentType};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_fire() {
+        let at = |hour, minute| Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, 0).unwrap();
+        let component =
+            Component::new(ComponentType::EVENT, Uuid::new_v4()).with_times(at(9, 0), None);
+        let alarms = vec![Alarm::relative(
+            component.uuid,
+            TimeDelta::minutes(-15),
+            TriggerRelation::START,
+        )
+        .with_repeats(1, TimeDelta::minutes(10))];
+        let occurrences = vec![SimpleComponent::new(component, vec![])];
+        let notifier = Arc::new(LogNotifier::new());
+        let mut scheduler = Scheduler::new(notifier.clone(), at(8, 0));
+
+        assert_eq!(
+            scheduler.fire(&alarms, &occurrences, at(8, 30)),
+            Some(at(8, 45))
+        );
+        assert!(notifier.fired().is_empty());
+        assert_eq!(
+            scheduler.fire(&alarms, &occurrences, at(8, 46)),
+            Some(at(8, 55))
+        );
+        assert_eq!(
+            scheduler.fire(&alarms, &occurrences, at(8, 50)),
+            Some(at(8, 55))
+        );
+        assert_eq!(scheduler.fire(&alarms, &occurrences, at(9, 0)), None);
+
+        let fired = notifier
+            .fired()
+            .iter()
+            .map(|due| due.fires_at)
+            .collect::<Vec<_>>();
+        assert_eq!(fired, vec![at(8, 45), at(8, 55)]);
+    }
+
+    #[test]
+    fn test_failed_load() {
+        let at = |hour, minute| Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, 0).unwrap();
+        let component =
+            Component::new(ComponentType::EVENT, Uuid::new_v4()).with_times(at(9, 0), None);
+        let alarms = vec![Alarm::relative(
+            component.uuid,
+            TimeDelta::minutes(-15),
+            TriggerRelation::START,
+        )
+        .with_repeats(1, TimeDelta::minutes(10))];
+        let occurrences = vec![SimpleComponent::new(component, vec![])];
+        let loaded = || Ok((alarms.clone(), occurrences.clone()));
+        let offline = |_: &User, _, _| anyhow::bail!(pg_driver::OfflineError);
+        let user = User::new(String::new(), String::new(), String::new());
+        let notifier = Arc::new(LogNotifier::new());
+        let mut scheduler = Scheduler::new(notifier.clone(), at(8, 0));
+
+        assert_eq!(
+            scheduler.advance(&user, at(8, 0), |_, _, _| loaded()),
+            Some(at(8, 45))
+        );
+        assert_eq!(scheduler.advance(&user, at(8, 50), offline), None);
+        assert_eq!(scheduler.fired_until, at(8, 0));
+        assert!(notifier.fired().is_empty());
+
+        // The alarms due while offline fire once they can be loaded again
+        assert_eq!(scheduler.advance(&user, at(9, 0), |_, _, _| loaded()), None);
+        let fired = notifier
+            .fired()
+            .iter()
+            .map(|due| due.fires_at)
+            .collect::<Vec<_>>();
+        assert_eq!(fired, vec![at(8, 45), at(8, 55)]);
+    }
+}
@@ -5,18 +5,23 @@ use std::ops::DerefMut;
 
 use crate::crypto::crypto_service::CryptoService;
 use crate::crypto::secure_storage::SecureStorage;
-use crate::current::{driver, set_app_handle, set_current_user, set_session_type, SessionType};
+use crate::current::{
+    async_driver, calendar_cache, driver, set_app_handle, set_current_user, set_session_type,
+    SessionType,
+};
+use crate::db::async_db_actions::AsyncDbActions;
 use crate::db::db_actions::DbActions;
 use crate::db::model::client::Client;
 use crate::db::model::user::User;
-use crate::db::repository::client_repository::ClientRepository;
-use crate::db::repository::user_repository::UserRepository;
+use crate::db::repository::client_repository::{AsyncClientRepository, ClientRepository};
+use crate::db::repository::user_repository::{AsyncUserRepository, UserRepository};
 use crate::errors::error_messages::{
-    BCRYPT_DECODING_ERR, USER_ALREADY_EXISTING_ERR, USER_NOT_FOUND_ERR,
+    BCRYPT_DECODING_ERR, JWT_COOKIE_ERR, USER_ALREADY_EXISTING_ERR, USER_NOT_FOUND_ERR,
 };
+use crate::errors::error_utils::to_user_message;
 use bcrypt::{hash, verify, DEFAULT_COST};
 use customs::bench_message;
-use pg_driver::PgDriver;
+use pg_driver::{AsyncPgDriver, PgDriver};
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use uuid::Uuid;
@@ -29,6 +34,10 @@ pub struct Claims {
 
 pub struct AuthUtil;
 
+/// The async counterpart of `AuthUtil`. Hashing and key derivation run on the blocking thread
+/// pool, so they don't stall the async runtime.
+pub struct AsyncAuthUtil;
+
 impl AuthUtil {
     /// Attempts logging in to the system with the given credentials.
     ///
@@ -56,7 +65,7 @@ impl AuthUtil {
         if let Some(handle) = app_handle {
             set_app_handle(handle);
         }
-        let mut driver = driver().lock().unwrap();
+        let mut driver = driver()?;
         let user_exists = UserRepository::is_existing(driver.deref_mut(), &email);
         let user = UserRepository::get_by_email(driver.deref_mut(), email)?;
         let user_pass = &user.get_password();
@@ -74,7 +83,8 @@ impl AuthUtil {
             Err(_) => return Err(BCRYPT_DECODING_ERR),
         }
 
-        Self::create_persistent_session(&user, driver.deref_mut())?;
+        Self::create_persistent_session(&user, driver.deref_mut())
+            .map_err(|e| to_user_message(&e))?;
 
         match remember {
             true => set_session_type(SessionType::PERSISTENT),
@@ -117,15 +127,19 @@ impl AuthUtil {
 
         let hashed_password = hash(&password, DEFAULT_COST).unwrap();
         let user = User::new(username, (&*email).into(), hashed_password);
-        let mut driver = driver().lock().unwrap();
+        let mut driver = driver()?;
 
         if UserRepository::is_existing(driver.deref_mut(), &email) {
             return Err(USER_ALREADY_EXISTING_ERR);
         }
 
-        UserRepository::store(driver.deref_mut(), &user).unwrap();
-
-        Self::create_persistent_session(&user, driver.deref_mut())?;
+        // Without a session the new account would be unusable, so both are stored or neither
+        driver
+            .transaction(|driver| {
+                UserRepository::store(driver, &user)?;
+                Self::create_persistent_session(&user, driver)
+            })
+            .map_err(|e| to_user_message(&e))?;
 
         match remember {
             true => set_session_type(SessionType::PERSISTENT),
@@ -152,8 +166,10 @@ impl AuthUtil {
     pub fn logout(driver: &mut PgDriver) -> Result<(), &'static str> {
         let user_email = SecureStorage::get_system_key(&String::from("user_email")).unwrap();
         let user = UserRepository::get_by_email(driver, user_email).unwrap();
-        let user_clients =
-            ClientRepository::retrieve(driver, Some(format!("user_uuid = '{}'", user.get_uuid())));
+        let user_clients = ClientRepository::retrieve(
+            driver,
+            ClientRepository::USER_UUID.eq(user.get_uuid()).into(),
+        );
         for client in user_clients {
             if client.get_device_name() == whoami::devicename() {
                 if let Err(e) = ClientRepository::remove(driver, &client) {
@@ -161,6 +177,7 @@ impl AuthUtil {
                 }
             }
         }
+        calendar_cache().clear();
         Ok(())
     }
 
@@ -179,8 +196,10 @@ impl AuthUtil {
         }
 
         let user = UserRepository::get_by_email(driver, user_email_res.unwrap()).unwrap();
-        let user_clients =
-            ClientRepository::retrieve(driver, Some(format!("user_uuid = '{}'", user.get_uuid())));
+        let user_clients = ClientRepository::retrieve(
+            driver,
+            ClientRepository::USER_UUID.eq(user.get_uuid()).into(),
+        );
         let prv_key_str = SecureStorage::get_system_key(user.get_email());
         let decrypted_key = CryptoService::decrypt_private_key(
             &prv_key_str.unwrap().as_str(),
@@ -201,8 +220,9 @@ impl AuthUtil {
         false
     }
 
-    /// Creates a local session by generating a jwt token, then storing it once in the database
-    /// and calling the frontend to populate a http cookie containing the token data.
+    /// Creates a local session by generating a key pair for this device, then storing the
+    /// public key as a client in the database and the encrypted private key in the system
+    /// keyring. The client is only kept if the keys could be stored locally as well.
     ///
     /// # Arguments
     /// * `user` - The user associated with the session.
@@ -214,22 +234,210 @@ impl AuthUtil {
     /// The user is prompted to try again.
     ///
     /// ## If something fails, the user sees it via a toast notification.
-    // TODO: Improve error handling
     #[bench_message("Creating a new persistent session")]
-    fn create_persistent_session(user: &User, driver: &mut PgDriver) -> Result<(), &'static str> {
+    fn create_persistent_session(user: &User, driver: &mut PgDriver) -> anyhow::Result<()> {
         let (prv_key, pub_key) = CryptoService::new_ed25519_key_pair();
-        let private_key =
-            CryptoService::encrypt_private_key(&prv_key, &user.get_password()).unwrap();
+        let private_key = CryptoService::encrypt_private_key(&prv_key, user.get_password())
+            .map_err(Self::session_err)?;
+        let client = Client::new(whoami::devicename(), user.get_uuid(), pub_key);
 
-        assert!(SecureStorage::store_system_key(&private_key, &user.get_email()).is_ok());
-        assert!(
-            SecureStorage::store_system_key(&user.get_email(), &String::from("user_email")).is_ok()
-        );
+        driver.transaction(|driver| {
+            ClientRepository::store(driver, &client)?;
+            SecureStorage::store_system_key(&private_key, user.get_email())
+                .map_err(Self::session_err)?;
+            SecureStorage::store_system_key(user.get_email(), &String::from("user_email"))
+                .map_err(Self::session_err)?;
+            Ok(())
+        })
+    }
 
-        let client = Client::new(whoami::devicename(), user.get_uuid(), pub_key);
+    /// Logs why a session could not be created and replaces it with the message shown to the
+    /// user.
+    fn session_err(err: Box<dyn std::error::Error>) -> anyhow::Error {
+        log::error!("Could not create a persistent session: {}", err);
+        anyhow::Error::msg(JWT_COOKIE_ERR)
+    }
+}
+
+impl AsyncAuthUtil {
+    /// Attempts logging in to the system with the given credentials, see
+    /// `AuthUtil::attempt_login`.
+    #[bench_message("Attempting login")]
+    pub async fn attempt_login(
+        app_handle: Option<AppHandle>,
+        email: String,
+        password: String,
+        remember: bool,
+    ) -> Result<(), &'static str> {
+        if let Some(handle) = app_handle {
+            set_app_handle(handle);
+        }
+        let mut driver = async_driver().await?;
+        if !AsyncUserRepository::is_existing(driver.deref_mut(), &email).await {
+            return Err(USER_NOT_FOUND_ERR);
+        }
+        let user = AsyncUserRepository::get_by_email(driver.deref_mut(), email).await?;
+        let user_pass = user.get_password().clone();
+
+        match blocking(move || verify(password, &user_pass)).await {
+            Ok(password_matches) => {
+                if !password_matches {
+                    return Err(USER_NOT_FOUND_ERR);
+                }
+            }
+            Err(_) => return Err(BCRYPT_DECODING_ERR),
+        }
+
+        Self::create_persistent_session(&user, driver.deref_mut())
+            .await
+            .map_err(|e| to_user_message(&e))?;
+
+        match remember {
+            true => set_session_type(SessionType::PERSISTENT),
+            false => set_session_type(SessionType::TEMPORARY),
+        }
+
+        set_current_user(user);
+        Ok(())
+    }
+
+    /// Attempts creating a new account with the given credentials, see
+    /// `AuthUtil::attempt_signup`.
+    #[bench_message("Attempting signup")]
+    pub async fn attempt_signup(
+        app_handle: Option<AppHandle>,
+        username: String,
+        email: String,
+        password: String,
+        remember: bool,
+    ) -> Result<(), &'static str> {
+        if let Some(handle) = app_handle {
+            set_app_handle(handle);
+        }
 
-        assert!(ClientRepository::store(driver, &client).is_ok());
+        let mut driver = async_driver().await?;
+        if AsyncUserRepository::is_existing(driver.deref_mut(), &email).await {
+            return Err(USER_ALREADY_EXISTING_ERR);
+        }
+
+        let hashed_password = blocking(move || hash(&password, DEFAULT_COST).unwrap()).await;
+        let user = User::new(username, (&*email).into(), hashed_password);
+
+        // Without a session the new account would be unusable, so both are stored or neither
+        driver
+            .transaction(async |driver| {
+                AsyncUserRepository::store(driver, &user).await?;
+                Self::create_persistent_session(&user, driver).await
+            })
+            .await
+            .map_err(|e| to_user_message(&e))?;
+
+        match remember {
+            true => set_session_type(SessionType::PERSISTENT),
+            false => set_session_type(SessionType::TEMPORARY),
+        }
+
+        set_current_user(user);
+        Ok(())
+    }
 
+    /// Logs the current user out and deletes any ongoing sessions, see `AuthUtil::logout`.
+    #[bench_message("Logging out")]
+    pub async fn logout(driver: &mut AsyncPgDriver) -> Result<(), &'static str> {
+        let user_email = SecureStorage::get_system_key(&String::from("user_email")).unwrap();
+        let user = AsyncUserRepository::get_by_email(driver, user_email)
+            .await
+            .unwrap();
+        let user_clients = AsyncClientRepository::retrieve(
+            driver,
+            ClientRepository::USER_UUID.eq(user.get_uuid()).into(),
+        )
+        .await;
+        for client in user_clients {
+            if client.get_device_name() == whoami::devicename() {
+                if let Err(e) = AsyncClientRepository::remove(driver, &client).await {
+                    log::error!("{}", e);
+                }
+            }
+        }
+        calendar_cache().clear();
         Ok(())
     }
+
+    /// Checks if there is a valid existing session for this device, see
+    /// `AuthUtil::is_valid_session`.
+    #[bench_message("Validating session")]
+    pub async fn is_valid_session(driver: &mut AsyncPgDriver) -> bool {
+        let user_email_res = SecureStorage::get_system_key(&String::from("user_email"));
+
+        if let Err(e) = user_email_res {
+            println!("{}", e);
+            return false;
+        }
+
+        let user = AsyncUserRepository::get_by_email(driver, user_email_res.unwrap())
+            .await
+            .unwrap();
+        let user_clients = AsyncClientRepository::retrieve(
+            driver,
+            ClientRepository::USER_UUID.eq(user.get_uuid()).into(),
+        )
+        .await;
+        let prv_key_str = SecureStorage::get_system_key(user.get_email()).unwrap();
+        let password = user.get_password().clone();
+        let decrypted_key = blocking(move || {
+            CryptoService::decrypt_private_key(prv_key_str.as_str(), &password)
+                .map_err(|e| e.to_string())
+        })
+        .await;
+
+        for client in user_clients {
+            let sign_successful = CryptoService::attempt_sign(
+                &decrypted_key.as_ref().unwrap().as_bytes().to_vec(),
+                &client.get_pub_key().as_bytes().to_vec(),
+            );
+            if sign_successful {
+                set_current_user(user);
+                return true;
+            };
+        }
+
+        false
+    }
+
+    /// Creates a local session for this device, see `AuthUtil::create_persistent_session`.
+    #[bench_message("Creating a new persistent session")]
+    async fn create_persistent_session(
+        user: &User,
+        driver: &mut AsyncPgDriver,
+    ) -> anyhow::Result<()> {
+        let password = user.get_password().clone();
+        let (private_key, pub_key) = blocking(move || {
+            let (prv_key, pub_key) = CryptoService::new_ed25519_key_pair();
+            CryptoService::encrypt_private_key(&prv_key, &password)
+                .map(|private_key| (private_key, pub_key))
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| AuthUtil::session_err(e.into()))?;
+        let client = Client::new(whoami::devicename(), user.get_uuid(), pub_key);
+
+        driver
+            .transaction(async |driver| {
+                AsyncClientRepository::store(driver, &client).await?;
+                SecureStorage::store_system_key(&private_key, user.get_email())
+                    .map_err(AuthUtil::session_err)?;
+                SecureStorage::store_system_key(user.get_email(), &String::from("user_email"))
+                    .map_err(AuthUtil::session_err)?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+/// Runs CPU heavy work, like hashing, on the blocking thread pool.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(f)
+        .await
+        .expect("A blocking auth task panicked")
 }
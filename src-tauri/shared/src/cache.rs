@@ -0,0 +1,306 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::model::simple::simple_calendar::SimpleCalendar;
+
+/// What a `CalendarCache` has stored for a lookup.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup<T> {
+    /// Loaded within the TTL and not invalidated since.
+    Fresh(T),
+    /// Outdated, but good enough to show until it is reloaded.
+    Stale(T),
+    Missing,
+}
+
+impl<T> Lookup<T> {
+    pub fn is_fresh(&self) -> bool {
+        matches!(self, Self::Fresh(_))
+    }
+
+    /// The cached value, no matter if fresh or stale.
+    pub fn value(self) -> Option<T> {
+        match self {
+            Self::Fresh(value) | Self::Stale(value) => Some(value),
+            Self::Missing => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry<T> {
+    value: T,
+    loaded: SystemTime,
+    /// False once invalidated.
+    valid: bool,
+}
+
+impl<T: Clone> Entry<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            loaded: SystemTime::now(),
+            valid: true,
+        }
+    }
+
+    fn lookup(&self, ttl: Duration) -> Lookup<T> {
+        let expired = self.loaded.elapsed().map_or(true, |age| age >= ttl);
+        match self.valid && !expired {
+            true => Lookup::Fresh(self.value.clone()),
+            false => Lookup::Stale(self.value.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheState {
+    calendars: HashMap<Uuid, Entry<SimpleCalendar>>,
+    /// The uuids of the calendars of each user, by the uuid of the user.
+    users: HashMap<Uuid, Entry<Vec<Uuid>>>,
+}
+
+/// The calendars loaded from the database, by their uuid. Entries expire after the TTL and are
+/// invalidated on changes, but are kept as stale entries until they are reloaded, so the
+/// frontends can show something right away.
+///
+/// A persistent cache is saved to a file whenever it changes and read back on startup.
+pub struct CalendarCache {
+    ttl: Duration,
+    path: Option<PathBuf>,
+    state: Mutex<CacheState>,
+    /// The users whose calendars are currently reloaded in the background.
+    refreshing: Mutex<HashSet<Uuid>>,
+}
+
+impl CalendarCache {
+    /// Creates an empty cache that only lives in memory.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            path: None,
+            state: Mutex::new(CacheState::default()),
+            refreshing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Creates a cache that is saved to the given file, starting with the calendars saved there
+    /// before. They are stale until reloaded, as they may have changed meanwhile.
+    pub fn persistent(path: PathBuf, ttl: Duration) -> Self {
+        let mut state = match Self::read(&path) {
+            Ok(state) => state,
+            Err(e) => {
+                if path.exists() {
+                    log::warn!("Could not read the calendar cache: {:#}", e);
+                }
+                CacheState::default()
+            }
+        };
+        state.calendars.values_mut().for_each(|e| e.valid = false);
+        state.users.values_mut().for_each(|e| e.valid = false);
+
+        Self {
+            ttl,
+            path: Some(path),
+            state: Mutex::new(state),
+            refreshing: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn calendar(&self, uuid: Uuid) -> Lookup<SimpleCalendar> {
+        let state = self.state.lock().unwrap();
+        state
+            .calendars
+            .get(&uuid)
+            .map_or(Lookup::Missing, |entry| entry.lookup(self.ttl))
+    }
+
+    /// The calendars of the given user. They are only fresh if the list of the calendars and all
+    /// of the calendars are, and missing if any of them is.
+    pub fn calendars_of(&self, user_uuid: Uuid) -> Lookup<Vec<SimpleCalendar>> {
+        let state = self.state.lock().unwrap();
+        let (uuids, mut fresh) = match state.users.get(&user_uuid).map(|e| e.lookup(self.ttl)) {
+            Some(Lookup::Fresh(uuids)) => (uuids, true),
+            Some(Lookup::Stale(uuids)) => (uuids, false),
+            _ => return Lookup::Missing,
+        };
+
+        let mut calendars = Vec::with_capacity(uuids.len());
+        for uuid in uuids {
+            match state.calendars.get(&uuid).map(|e| e.lookup(self.ttl)) {
+                Some(Lookup::Fresh(calendar)) => calendars.push(calendar),
+                Some(Lookup::Stale(calendar)) => {
+                    fresh = false;
+                    calendars.push(calendar);
+                }
+                _ => return Lookup::Missing,
+            }
+        }
+
+        match fresh {
+            true => Lookup::Fresh(calendars),
+            false => Lookup::Stale(calendars),
+        }
+    }
+
+    /// Stores the given calendars as the ones of the given user.
+    pub fn store_calendars_of(&self, user_uuid: Uuid, calendars: &[SimpleCalendar]) {
+        let mut state = self.state.lock().unwrap();
+        for calendar in calendars {
+            state
+                .calendars
+                .insert(calendar.get_uuid(), Entry::new(calendar.clone()));
+        }
+        let uuids = calendars.iter().map(SimpleCalendar::get_uuid).collect();
+        state.users.insert(user_uuid, Entry::new(uuids));
+
+        // Drop the calendars no user has anymore, like deleted ones
+        let referenced = state
+            .users
+            .values()
+            .flat_map(|e| e.value.iter().copied())
+            .collect::<HashSet<_>>();
+        state.calendars.retain(|uuid, _| referenced.contains(uuid));
+        self.save(&state);
+    }
+
+    /// Marks the given calendar as outdated.
+    pub fn invalidate(&self, uuid: Uuid) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(entry) = state.calendars.get_mut(&uuid) {
+            entry.valid = false;
+            self.save(&state);
+        }
+    }
+
+    /// Marks which calendars the users have as outdated, e.g. after a calendar was created or
+    /// deleted.
+    pub fn invalidate_users(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.users.values_mut().for_each(|e| e.valid = false);
+        self.save(&state);
+    }
+
+    /// Marks everything as outdated.
+    pub fn invalidate_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.calendars.values_mut().for_each(|e| e.valid = false);
+        state.users.values_mut().for_each(|e| e.valid = false);
+        self.save(&state);
+    }
+
+    /// Drops all entries, e.g. when the user logs out.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        *state = CacheState::default();
+        self.save(&state);
+    }
+
+    /// Marks the calendars of the given user as being reloaded.
+    ///
+    /// # Returns
+    /// False if they are reloaded already, so the caller should not reload them as well.
+    pub fn begin_refresh(&self, user_uuid: Uuid) -> bool {
+        self.refreshing.lock().unwrap().insert(user_uuid)
+    }
+
+    pub fn end_refresh(&self, user_uuid: Uuid) {
+        self.refreshing.lock().unwrap().remove(&user_uuid);
+    }
+
+    fn read(path: &Path) -> anyhow::Result<CacheState> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    fn save(&self, state: &CacheState) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let write = || -> anyhow::Result<()> {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            // Write to a temporary file first, so a crash can't leave a truncated cache behind
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, serde_json::to_vec(state)?)?;
+            std::fs::rename(&tmp, path)?;
+            Ok(())
+        };
+        if let Err(e) = write() {
+            log::warn!("Could not save the calendar cache: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(name: &str) -> SimpleCalendar {
+        SimpleCalendar::new(Uuid::new_v4(), String::from(name), vec![], vec![], vec![])
+    }
+
+    #[test]
+    fn test_lookup() {
+        let cache = CalendarCache::new(Duration::from_secs(60));
+        let user = Uuid::new_v4();
+        let (work, home) = (calendar("Work"), calendar("Home"));
+
+        assert_eq!(cache.calendars_of(user).value().map(|c| c.len()), None);
+        cache.store_calendars_of(user, &[work.clone(), home.clone()]);
+        cache.store_calendars_of(user, &[work.clone(), home.clone()]);
+        assert!(cache.calendars_of(user).is_fresh());
+        assert_eq!(cache.calendars_of(user).value().unwrap().len(), 2);
+
+        cache.store_calendars_of(user, std::slice::from_ref(&work));
+        assert_eq!(cache.calendar(home.get_uuid()), Lookup::Missing);
+        cache.store_calendars_of(user, &[work.clone(), home.clone()]);
+
+        cache.invalidate(work.get_uuid());
+        assert!(!cache.calendar(work.get_uuid()).is_fresh());
+        assert!(cache.calendar(home.get_uuid()).is_fresh());
+        assert!(matches!(cache.calendars_of(user), Lookup::Stale(c) if c.len() == 2));
+
+        cache.clear();
+        assert_eq!(cache.calendars_of(user), Lookup::Missing);
+        assert_eq!(cache.calendar(home.get_uuid()), Lookup::Missing);
+
+        let expired = CalendarCache::new(Duration::ZERO);
+        expired.store_calendars_of(user, &[work]);
+        assert!(matches!(expired.calendars_of(user), Lookup::Stale(_)));
+
+        assert!(cache.begin_refresh(user));
+        assert!(!cache.begin_refresh(user));
+        cache.end_refresh(user);
+        assert!(cache.begin_refresh(user));
+    }
+
+    #[test]
+    fn test_persistent() {
+        let path = std::env::temp_dir().join(format!("sf-cache-{}.json", Uuid::new_v4()));
+        let user = Uuid::new_v4();
+        let work = calendar("Work");
+
+        let cache = CalendarCache::persistent(path.clone(), Duration::from_secs(60));
+        assert_eq!(cache.calendars_of(user), Lookup::Missing);
+        cache.store_calendars_of(user, std::slice::from_ref(&work));
+
+        // Calendars read back are shown right away, but still reloaded
+        let cache = CalendarCache::persistent(path.clone(), Duration::from_secs(60));
+        match cache.calendars_of(user) {
+            Lookup::Stale(calendars) => assert_eq!(calendars[0].get_uuid(), work.get_uuid()),
+            lookup => panic!("Expected stale calendars, got {:?}", lookup),
+        }
+
+        std::fs::write(&path, "garbage").unwrap();
+        let cache = CalendarCache::persistent(path.clone(), Duration::from_secs(60));
+        assert_eq!(cache.calendars_of(user), Lookup::Missing);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,25 +1,32 @@
-use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 
+use crate::alarms;
+use crate::cache::CalendarCache;
 use crate::db::model::client::Client;
-use crate::db::model::simple::simple_calendar::SimpleCalendar;
 use crate::db::model::user::User;
-use crate::errors::error_messages::ERROR_QUEUE_NOT_INITIALIZED_ERR;
+use crate::errors::error_impl::no_database_connection_error::NoDatabaseConnectionError;
+use crate::errors::error_messages::{
+    DB_POOL_EXHAUSTED_ERR, ERROR_QUEUE_NOT_INITIALIZED_ERR, NOT_LOGGED_IN_ERR,
+};
 use crate::errors::error_queue::ErrorQueue;
 use lazy_static::lazy_static;
 use once_cell::sync::OnceCell;
-use pg_driver::PgDriver;
+use pg_driver::{health, AsyncPgPool, AsyncPooledDriver, ConnectionState, PgPool, PooledDriver};
 use tauri::AppHandle;
 
 pub static CURRENT_CLIENT: OnceCell<Mutex<Option<Client>>> = OnceCell::new();
 pub static CURRENT_USER: OnceCell<Mutex<Option<User>>> = OnceCell::new();
 pub static ERROR_QUEUE: OnceCell<Mutex<Option<ErrorQueue>>> = OnceCell::new();
-pub static CACHED_CALENDARS: OnceCell<Mutex<Vec<SimpleCalendar>>> = OnceCell::new();
 pub static SESSION_TYPE: OnceCell<Mutex<SessionType>> = OnceCell::new();
 
 lazy_static! {
     pub static ref APP_HANDLE: Mutex<Option<AppHandle>> = Mutex::new(None);
 }
 
+/// How many seconds loaded calendars are served from the cache before they are reloaded.
+const DEFAULT_CACHE_TTL: u64 = 300;
+
 #[derive(Debug, Clone, Copy)]
 pub enum SessionType {
     TEMPORARY,
@@ -27,23 +34,64 @@ pub enum SessionType {
     NONE,
 }
 
-pub fn driver() -> &'static Mutex<PgDriver> {
-    static PG_DRIVER: OnceLock<Mutex<PgDriver>> = OnceLock::new();
-    PG_DRIVER.get_or_init(|| {
-        let mut driver = PgDriver::setup();
-        driver.connect();
-        Mutex::new(driver)
+/// The connection pool of the configured database.
+///
+/// # Panics
+/// If the database settings are invalid.
+pub fn pool() -> &'static PgPool {
+    static PG_POOL: OnceLock<PgPool> = OnceLock::new();
+    PG_POOL.get_or_init(|| PgPool::from_settings(config::get()).unwrap_or_else(|e| panic!("{}", e)))
+}
+
+/// Checks out a driver of the connection pool. It is handed back once dropped, so keep the
+/// binding only as long as it is needed.
+///
+/// # Returns an error
+///
+/// * `DB_POOL_EXHAUSTED_ERR` - If no connection became available within the checkout timeout.
+pub fn driver() -> Result<PooledDriver<'static>, &'static str> {
+    pool().get().map_err(|e| exhausted(&e))
+}
+
+pub fn async_pool() -> &'static AsyncPgPool {
+    static ASYNC_PG_POOL: OnceLock<AsyncPgPool> = OnceLock::new();
+    ASYNC_PG_POOL.get_or_init(|| {
+        AsyncPgPool::from_settings(config::get()).unwrap_or_else(|e| panic!("{}", e))
     })
 }
 
+/// The async counterpart of `driver`, it waits for a free connection without blocking the
+/// runtime.
+pub async fn async_driver() -> Result<AsyncPooledDriver<'static>, &'static str> {
+    async_pool().get().await.map_err(|e| exhausted(&e))
+}
+
+fn exhausted(e: &anyhow::Error) -> &'static str {
+    log::warn!("Could not check out a database connection: {}", e);
+    DB_POOL_EXHAUSTED_ERR
+}
+
 pub fn set_current_user(user: User) {
     CURRENT_USER.get_or_init(|| Mutex::new(Some(user)));
+    alarms::wake();
 }
 
 pub fn get_current_user() -> &'static Mutex<Option<User>> {
     CURRENT_USER.get().expect("Current user is not initialized")
 }
 
+/// A copy of the user currently logged in, so no lock is held while it is used.
+///
+/// # Returns an error
+///
+/// * `NOT_LOGGED_IN_ERR` - If no user is logged in, like before the login or after a logout.
+pub fn logged_in_user() -> Result<User, &'static str> {
+    CURRENT_USER
+        .get()
+        .and_then(|user| user.lock().unwrap().clone())
+        .ok_or(NOT_LOGGED_IN_ERR)
+}
+
 pub fn reset_current_user() {
     if let Some(user_mutex) = CURRENT_USER.get() {
         let mut user_option = user_mutex.lock().unwrap();
@@ -74,6 +122,17 @@ pub fn reset_current_client() {
 
 pub fn set_error_queue(error_queue: ErrorQueue) {
     ERROR_QUEUE.get_or_init(|| Mutex::new(Some(error_queue)));
+
+    // Let the user know once the database can't be reached anymore
+    health::on_state_change(|state| {
+        let queue = ERROR_QUEUE
+            .get()
+            .and_then(|mutex| mutex.lock().ok())
+            .and_then(|guard| guard.as_ref().cloned());
+        if let (ConnectionState::Offline, Some(queue)) = (state, queue) {
+            queue.enqueue(NoDatabaseConnectionError::new());
+        }
+    });
 }
 
 pub fn get_error_queue() -> ErrorQueue {
@@ -111,17 +170,22 @@ pub fn get_app_handle() -> Option<AppHandle> {
     APP_HANDLE.lock().unwrap().clone()
 }
 
-pub fn cache_calendar(calendar: SimpleCalendar) {
-    if CACHED_CALENDARS.get().is_none() {
-        CACHED_CALENDARS.set(Mutex::new(Vec::new())).unwrap();
-    }
-
-    let mut cached_calendars = CACHED_CALENDARS.get().unwrap().lock().unwrap();
-    cached_calendars.push(calendar);
-}
-
-pub fn get_cached_calendars() -> MutexGuard<'static, Vec<SimpleCalendar>> {
-    CACHED_CALENDARS.get().unwrap().lock().unwrap()
+/// The cache of the calendars loaded from the database. It is kept in the data directory, so
+/// the calendars of the last session can be shown right away on startup.
+///
+/// # Panics
+/// If the `cache.ttl` setting is invalid.
+pub fn calendar_cache() -> &'static CalendarCache {
+    static CALENDAR_CACHE: OnceLock<CalendarCache> = OnceLock::new();
+    CALENDAR_CACHE.get_or_init(|| {
+        let ttl = config::get()
+            .get_or("cache.ttl", DEFAULT_CACHE_TTL)
+            .unwrap_or_else(|e| panic!("{}", e));
+        CalendarCache::persistent(
+            config::data_dir().join("calendars.json"),
+            Duration::from_secs(ttl),
+        )
+    })
 }
 
 pub fn set_session_type(session_type: SessionType) {
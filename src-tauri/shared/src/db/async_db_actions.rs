@@ -0,0 +1,93 @@
+use pg_driver::{params, AsyncPgDriver, Filter, OfflineError, Query, Row, ToSql};
+use uuid::Uuid;
+
+use crate::db::db_actions::{insert_stmt, update_stmt, Page, Table, UUID};
+
+/// The async counterpart of `DbActions`, for callers that must not block, like the Tauri
+/// commands. The statements are the same as the ones of `DbActions`.
+// Only ever called on the concrete repositories, so the futures are `Send` whenever the models are.
+#[allow(async_fn_in_trait)]
+pub trait AsyncDbActions<M: Sync, R: Table<M>> {
+    /// Inserts a new entry into a given table, see `DbActions::insert`.
+    async fn insert(driver: &mut AsyncPgDriver, entry: &M) -> anyhow::Result<()> {
+        let vals = R::get_vals(entry);
+        let stmt = insert_stmt::<M, R>(vals.len());
+        driver.exec(&stmt, &params(&vals)).await?;
+        Ok(())
+    }
+
+    /// Queries a given table, see `DbActions::read`.
+    async fn read(driver: &mut AsyncPgDriver, table: &str, query: &Query) -> Vec<Row> {
+        let (clauses, params) = query.to_sql();
+        let stmt = format!("SELECT * FROM {}{}", table, clauses);
+        match driver.exec(&stmt, &params).await {
+            Ok(rows) => rows,
+            Err(e) if e.is::<OfflineError>() => vec![],
+            // Should not happen!
+            Err(e) => panic!("Query failed: {}", e),
+        }
+    }
+
+    /// Reads a page of a given table, see `DbActions::read_page`.
+    async fn read_page(
+        driver: &mut AsyncPgDriver,
+        table: &str,
+        query: Query,
+        size: u64,
+    ) -> Page<Row> {
+        let query = query.unique_by(UUID).limit(size + 1);
+        let rows = Self::read(driver, table, &query).await;
+        Page::from_rows(&query, rows, size)
+    }
+
+    /// Updates a given entry, see `DbActions::alter`.
+    async fn alter(driver: &mut AsyncPgDriver, entry: &M, uuid: Uuid) -> anyhow::Result<()> {
+        let mut vals = R::get_vals_no_id(entry);
+        vals.push(Box::new(uuid));
+        driver.exec(&update_stmt::<M, R>(), &params(&vals)).await?;
+        Ok(())
+    }
+
+    /// Deletes an entry from a given table using the 'uuid' column.
+    async fn delete(driver: &mut AsyncPgDriver, uuid: Uuid) -> anyhow::Result<()> {
+        let stmt = format!("DELETE FROM {} WHERE uuid = $1", R::get_name());
+        driver.exec(&stmt, &[&uuid]).await?;
+        Ok(())
+    }
+
+    /// Deletes all entries matching the given filter from a given table.
+    async fn delete_where(driver: &mut AsyncPgDriver, filter: Filter) -> anyhow::Result<()> {
+        let query = Query::from(filter);
+        let (clauses, params) = query.to_sql();
+        let stmt = format!("DELETE FROM {}{}", R::get_name(), clauses);
+        driver.exec(&stmt, &params).await?;
+        Ok(())
+    }
+
+    async fn query(
+        driver: &mut AsyncPgDriver,
+        stmt: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> anyhow::Result<Vec<Row>> {
+        driver.exec(stmt, params).await
+    }
+
+    /// The table specific implementation for adding a new entry.
+    async fn store(driver: &mut AsyncPgDriver, model: &M) -> anyhow::Result<()>;
+
+    /// The table specific implementation for editing an entry.
+    async fn update(driver: &mut AsyncPgDriver, model: &M) -> anyhow::Result<()>;
+
+    /// The table specific implementation for removing an entry.
+    async fn remove(driver: &mut AsyncPgDriver, model: &M) -> anyhow::Result<()>;
+
+    /// The table specific implementation for retrieving the entries matching a query.
+    async fn retrieve(driver: &mut AsyncPgDriver, query: Query) -> Vec<M>;
+
+    /// Retrieves a page of the entries matching a query, see `DbActions::retrieve_page`.
+    async fn retrieve_page(driver: &mut AsyncPgDriver, query: Query, size: u64) -> Page<M> {
+        Self::read_page(driver, &R::get_name(), query, size)
+            .await
+            .map(|row| R::from_row(&row))
+    }
+}
@@ -1,14 +1,35 @@
-use pg_driver::PgDriver;
-use postgres::Row;
+use std::marker::PhantomData;
+
+use pg_driver::{
+    params, placeholders, Column, Cursor, Filter, OfflineError, PgDriver, Query, Row, SqlParam,
+    ToSql,
+};
 use uuid::Uuid;
 
+/// The primary key the pages of a table are made unique by, see `Query::unique_by`.
+pub(crate) const UUID: Column<Uuid> = Column::new("uuid");
+
 pub trait Table<M> {
     fn get_name() -> String;
-    fn get_fk_uuid_name() -> String;
     fn get_fmt_cols() -> String;
     fn get_fmt_cols_no_id() -> String;
-    fn get_fmt_vals(model: &M) -> String;
-    fn get_fmt_vals_no_id(model: &M) -> String;
+    /// The values of the model, in the same order as `get_fmt_cols`.
+    fn get_vals(model: &M) -> Vec<SqlParam>;
+    /// The values of the model, in the same order as `get_fmt_cols_no_id`.
+    fn get_vals_no_id(model: &M) -> Vec<SqlParam>;
+    /// Builds the model from a row read from this table.
+    fn from_row(row: &Row) -> M;
+}
+
+/// A table other tables reference by a foreign key, implemented by `#[derive(Table)]` for
+/// tables with an `fk_name`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is referenced by another table, but has no foreign key",
+    note = "add `fk_name = \"...\"` to its `#[table(...)]` attribute"
+)]
+pub trait ForeignKey {
+    /// The column other tables reference this table by.
+    fn get_fk_uuid_name() -> String;
 }
 
 /// This module holds the base implementation for the CRUD operations
@@ -17,17 +38,10 @@ pub trait DbActions<M, R: Table<M>> {
     ///
     /// # Arguments
     /// * `driver` - The database driver.
-    /// * `table` - The table to insert into.
-    /// * `cols` - The columns to insert into.
-    /// * `vals` - The values to insert into the columns.
+    /// * `entry` - The model to insert, its values are bound as parameters.
     fn insert(driver: &mut PgDriver, entry: &M) -> anyhow::Result<()> {
-        let stmt = &format!(
-            "INSERT INTO {} ({}) VALUES ({})",
-            R::get_name(),
-            R::get_fmt_cols(),
-            R::get_fmt_vals(entry)
-        );
-        match driver.exec(stmt) {
+        let vals = R::get_vals(entry);
+        match driver.exec(&insert_stmt::<M, R>(vals.len()), &params(&vals)) {
             Ok(_) => Ok(()),
             Err(e) => Err(e),
         }
@@ -38,55 +52,72 @@ pub trait DbActions<M, R: Table<M>> {
     /// # Arguments
     /// * `driver` - The database driver.
     /// * `table` - The table to query.
-    /// * `cols` - The columns to query.
-    /// * `condition` - The condition to query. Optional.
-    fn read(driver: &mut PgDriver, table: &str, condition: Option<String>) -> Vec<Row> {
-        let rows = match condition {
-            Some(condition) => {
-                let query = format!("SELECT * FROM {} WHERE {}", table, condition);
-                driver
-                    .exec(&query)
-                    // Should not happen!
-                    .expect("Query with condition failed")
-            }
-            None => driver
-                .exec(&format!("SELECT * FROM {}", table))
-                // Should not happen!
-                .expect("Query without condition failed."),
-        };
+    /// * `query` - The filter, order and limits of the rows to read.
+    ///
+    /// # Returns
+    /// The rows read, none while the database is offline. That is reported by the driver already.
+    fn read(driver: &mut PgDriver, table: &str, query: &Query) -> Vec<Row> {
+        match Self::try_read(driver, table, query) {
+            Ok(rows) => rows,
+            Err(e) if e.is::<OfflineError>() => vec![],
+            // Should not happen!
+            Err(e) => panic!("Query failed: {}", e),
+        }
+    }
 
-        rows
+    /// Queries a given table like `read`, but hands every error to the caller, e.g. to tell an
+    /// offline database from a table without matching rows.
+    fn try_read(driver: &mut PgDriver, table: &str, query: &Query) -> anyhow::Result<Vec<Row>> {
+        let (clauses, params) = query.to_sql();
+        driver.exec(&format!("SELECT * FROM {}{}", table, clauses), &params)
+    }
+
+    /// Reads a page of a given table, ordered as the query says and by uuid after that. Pass
+    /// `next` of a page to `Query::after` to read the following one.
+    ///
+    /// # Arguments
+    /// * `driver` - The database driver.
+    /// * `table` - The table to query, it needs a `uuid` column.
+    /// * `query` - The filter, order and cursor of the rows to read.
+    /// * `size` - The maximum amount of rows of the page.
+    fn read_page(driver: &mut PgDriver, table: &str, query: Query, size: u64) -> Page<Row> {
+        // One more row tells whether there is a next page
+        let query = query.unique_by(UUID).limit(size + 1);
+        let rows = Self::read(driver, table, &query);
+        Page::from_rows(&query, rows, size)
+    }
+
+    /// Reads the entries matching a query one after another, fetching `batch` rows at a time.
+    /// Unlike `retrieve`, only one batch is held in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let query = Query::from(ComponentRepository::CALENDAR_UUID.eq(calendar.uuid));
+    /// for component in ComponentRepository::stream(driver, query, 500) {
+    ///     println!("{}", component.uuid);
+    /// }
+    /// ```
+    fn stream(driver: &mut PgDriver, query: Query, batch: u64) -> Stream<'_, M, R> {
+        Stream {
+            driver,
+            query: Some(query.unique_by(UUID).limit(batch)),
+            batch: batch as usize,
+            rows: vec![].into_iter(),
+            _models: PhantomData,
+        }
     }
 
     /// Updates a given entry.
     ///
     /// # Arguments
     /// * `driver` - The database driver.
-    /// * `table` - The table to insert into.
-    /// * `cols` - The columns to insert into.
-    /// * `vals` - The values to insert into the columns.
+    /// * `entry` - The model holding the new values.
+    /// * `uuid` - The uuid of the entry to update.
     fn alter(driver: &mut PgDriver, entry: &M, uuid: Uuid) -> anyhow::Result<()> {
-        let col_binding = R::get_fmt_cols_no_id();
-        let val_binding = R::get_fmt_vals_no_id(&entry);
-        let cols = col_binding.split(", ").collect::<Vec<&str>>();
-        let vals = val_binding.split(',').collect::<Vec<&str>>();
-
-        let update_stmt = cols
-            .iter()
-            .zip(vals.iter())
-            .map(|(c, v)| format!("\"{}\" = {}", c, v))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        driver
-            .exec(&format!(
-                "UPDATE {} SET {} WHERE uuid = '{}'",
-                R::get_name(),
-                update_stmt,
-                uuid
-            ))
-            // Should not happen!
-            .expect("Update failed.");
+        let mut vals = R::get_vals_no_id(entry);
+        vals.push(Box::new(uuid));
+        driver.exec(&update_stmt::<M, R>(), &params(&vals))?;
         Ok(())
     }
 
@@ -94,42 +125,36 @@ pub trait DbActions<M, R: Table<M>> {
     ///
     /// # Arguments
     /// * `driver` - The database driver.
-    /// * `table` - The table to delete from.
-    /// * `user_id` - The id of the user to delete.
+    /// * `uuid` - The uuid of the entry to delete.
     fn delete(driver: &mut PgDriver, uuid: Uuid) -> anyhow::Result<()> {
-        driver
-            .exec(&format!(
-                "DELETE FROM {} WHERE uuid='{}'",
-                R::get_name(),
-                uuid
-            ))
-            // Should not happen!
-            .expect("Deletion failed.");
+        driver.exec(
+            &format!("DELETE FROM {} WHERE uuid = $1", R::get_name()),
+            &[&uuid],
+        )?;
         Ok(())
     }
 
-    /// Deletes an entry from a given table using a specific given column.
+    /// Deletes all entries matching the given filter from a given table.
     ///
     /// # Arguments
     /// * `driver` - The database driver.
-    /// * `table` - The table to delete from.
-    /// * `col` - The name of the column the uuid is matched on.
-    /// * `uuid` - The uuid of the entry to be deleted.
-    fn delete_spec_col(driver: &mut PgDriver, col: String, val: String) -> anyhow::Result<()> {
-        driver
-            .exec(&format!(
-                "DELETE FROM {} WHERE {}='{}'",
-                R::get_name(),
-                col,
-                val
-            ))
-            // Should not happen!
-            .expect("Deletion failed.");
+    /// * `filter` - The filter the entries to be deleted match.
+    fn delete_where(driver: &mut PgDriver, filter: Filter) -> anyhow::Result<()> {
+        let query = Query::from(filter);
+        let (clauses, params) = query.to_sql();
+        driver.exec(
+            &format!("DELETE FROM {}{}", R::get_name(), clauses),
+            &params,
+        )?;
         Ok(())
     }
 
-    fn query(driver: &mut PgDriver, stmt: String) -> anyhow::Result<Vec<Row>> {
-        driver.exec(&stmt)
+    fn query(
+        driver: &mut PgDriver,
+        stmt: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> anyhow::Result<Vec<Row>> {
+        driver.exec(stmt, params)
     }
 
     /// The table specific implementation for adding a new entry.
@@ -141,6 +166,114 @@ pub trait DbActions<M, R: Table<M>> {
     /// The table specific implementation for removing an entry.
     fn remove(driver: &mut PgDriver, model: &M) -> anyhow::Result<()>;
 
-    /// The table specific implementation for retrieving an entry.
-    fn retrieve(driver: &mut PgDriver, condition: Option<String>) -> Vec<M>;
+    /// The table specific implementation for retrieving the entries matching a query.
+    fn retrieve(driver: &mut PgDriver, query: Query) -> Vec<M>;
+
+    /// Retrieves a page of the entries matching a query, see `read_page`.
+    fn retrieve_page(driver: &mut PgDriver, query: Query, size: u64) -> Page<M> {
+        Self::read_page(driver, &R::get_name(), query, size).map(|row| R::from_row(&row))
+    }
+}
+
+/// A part of the entries matching a query.
+#[derive(Debug, Clone)]
+pub struct Page<M> {
+    pub items: Vec<M>,
+    /// Where the next page starts, None if this is the last one.
+    pub next: Option<Cursor>,
+}
+
+impl Page<Row> {
+    /// Builds a page from the rows read by the given query, which asked for one row more than
+    /// the page holds.
+    pub(crate) fn from_rows(query: &Query, mut rows: Vec<Row>, size: u64) -> Self {
+        let next = match rows.len() as u64 > size {
+            true => {
+                rows.truncate(size as usize);
+                rows.last().and_then(|row| query.cursor(row).ok())
+            }
+            false => None,
+        };
+        Self { items: rows, next }
+    }
+}
+
+impl<M> Page<M> {
+    pub fn map<N>(self, f: impl FnMut(M) -> N) -> Page<N> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next: self.next,
+        }
+    }
+}
+
+/// The entries matching a query, read in batches. See `DbActions::stream`.
+pub struct Stream<'d, M, R> {
+    driver: &'d mut PgDriver,
+    /// The query of the next batch, None after the last one.
+    query: Option<Query>,
+    batch: usize,
+    rows: std::vec::IntoIter<Row>,
+    _models: PhantomData<fn() -> (M, R)>,
+}
+
+impl<M, R: Table<M>> Iterator for Stream<'_, M, R> {
+    type Item = M;
+
+    fn next(&mut self) -> Option<M> {
+        if let Some(row) = self.rows.next() {
+            return Some(R::from_row(&row));
+        }
+
+        let query = self.query.take()?;
+        let rows = {
+            let (clauses, params) = query.to_sql();
+            let stmt = format!("SELECT * FROM {}{}", R::get_name(), clauses);
+            match self.driver.exec(&stmt, &params) {
+                Ok(rows) => rows,
+                Err(e) if e.is::<OfflineError>() => vec![],
+                // Should not happen!
+                Err(e) => panic!("Query failed: {}", e),
+            }
+        };
+
+        // A short batch is the last one
+        if rows.len() == self.batch {
+            let cursor = rows.last().and_then(|row| query.cursor(row).ok());
+            self.query = cursor.map(|cursor| query.after(cursor));
+        }
+        self.rows = rows.into_iter();
+        self.rows.next().map(|row| R::from_row(&row))
+    }
+}
+
+/// The statement inserting an entry with the given amount of values into the table of `R`.
+pub(crate) fn insert_stmt<M, R: Table<M>>(count: usize) -> String {
+    format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        R::get_name(),
+        R::get_fmt_cols(),
+        placeholders(1, count)
+    )
+}
+
+/// The statement updating all columns of an entry of the table of `R`, except its uuid. The uuid
+/// is bound as the last parameter.
+pub(crate) fn update_stmt<M, R: Table<M>>() -> String {
+    let col_binding = R::get_fmt_cols_no_id();
+    let cols = col_binding.split(", ").collect::<Vec<&str>>();
+
+    let update_stmt = cols
+        .iter()
+        .enumerate()
+        .map(|(idx, c)| format!("\"{}\" = ${}", c, idx + 1))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "UPDATE {} SET {} WHERE uuid = ${}",
+        R::get_name(),
+        update_stmt,
+        cols.len() + 1
+    )
 }
@@ -0,0 +1,292 @@
+use pg_driver::{BackendKind, Migration, MigrationStatus, Migrator, PgDriver};
+
+/// The migrations building the postgres schema, ordered by version. New migrations are appended
+/// with the next version, migrations that were released once are never edited.
+const POSTGRES_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        up: include_str!("../../migrations/postgres/0001_initial_schema.up.sql"),
+        down: include_str!("../../migrations/postgres/0001_initial_schema.down.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "change_notifications",
+        up: include_str!("../../migrations/postgres/0002_change_notifications.up.sql"),
+        down: include_str!("../../migrations/postgres/0002_change_notifications.down.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "component_times",
+        up: include_str!("../../migrations/postgres/0003_component_times.up.sql"),
+        down: include_str!("../../migrations/postgres/0003_component_times.down.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "property_params",
+        up: include_str!("../../migrations/postgres/0004_property_params.up.sql"),
+        down: include_str!("../../migrations/postgres/0004_property_params.down.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "component_series",
+        up: include_str!("../../migrations/postgres/0005_component_series.up.sql"),
+        down: include_str!("../../migrations/postgres/0005_component_series.down.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "component_overrides",
+        up: include_str!("../../migrations/postgres/0006_component_overrides.up.sql"),
+        down: include_str!("../../migrations/postgres/0006_component_overrides.down.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "time_zones",
+        up: include_str!("../../migrations/postgres/0007_time_zones.up.sql"),
+        down: include_str!("../../migrations/postgres/0007_time_zones.down.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "scheduling_preferences",
+        up: include_str!("../../migrations/postgres/0008_scheduling_preferences.up.sql"),
+        down: include_str!("../../migrations/postgres/0008_scheduling_preferences.down.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "conflict_modes",
+        up: include_str!("../../migrations/postgres/0009_conflict_modes.up.sql"),
+        down: include_str!("../../migrations/postgres/0009_conflict_modes.down.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "alarms",
+        up: include_str!("../../migrations/postgres/0010_alarms.up.sql"),
+        down: include_str!("../../migrations/postgres/0010_alarms.down.sql"),
+    },
+];
+
+/// The same schema as `POSTGRES_MIGRATIONS` for SQLite. Both lists always hold the same
+/// versions.
+const SQLITE_MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        up: include_str!("../../migrations/sqlite/0001_initial_schema.up.sql"),
+        down: include_str!("../../migrations/sqlite/0001_initial_schema.down.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "change_notifications",
+        up: include_str!("../../migrations/sqlite/0002_change_notifications.up.sql"),
+        down: include_str!("../../migrations/sqlite/0002_change_notifications.down.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "component_times",
+        up: include_str!("../../migrations/sqlite/0003_component_times.up.sql"),
+        down: include_str!("../../migrations/sqlite/0003_component_times.down.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "property_params",
+        up: include_str!("../../migrations/sqlite/0004_property_params.up.sql"),
+        down: include_str!("../../migrations/sqlite/0004_property_params.down.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "component_series",
+        up: include_str!("../../migrations/sqlite/0005_component_series.up.sql"),
+        down: include_str!("../../migrations/sqlite/0005_component_series.down.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "component_overrides",
+        up: include_str!("../../migrations/sqlite/0006_component_overrides.up.sql"),
+        down: include_str!("../../migrations/sqlite/0006_component_overrides.down.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "time_zones",
+        up: include_str!("../../migrations/sqlite/0007_time_zones.up.sql"),
+        down: include_str!("../../migrations/sqlite/0007_time_zones.down.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "scheduling_preferences",
+        up: include_str!("../../migrations/sqlite/0008_scheduling_preferences.up.sql"),
+        down: include_str!("../../migrations/sqlite/0008_scheduling_preferences.down.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "conflict_modes",
+        up: include_str!("../../migrations/sqlite/0009_conflict_modes.up.sql"),
+        down: include_str!("../../migrations/sqlite/0009_conflict_modes.down.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "alarms",
+        up: include_str!("../../migrations/sqlite/0010_alarms.up.sql"),
+        down: include_str!("../../migrations/sqlite/0010_alarms.down.sql"),
+    },
+];
+
+/// Keeps the database schema in sync with the version this app was built with.
+pub struct Migrations;
+
+impl Migrations {
+    /// Applies all pending migrations.
+    ///
+    /// # Returns
+    /// The versions of the migrations applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// Migrations::run(driver()?.deref_mut())?;
+    /// ```
+    pub fn run(driver: &mut PgDriver) -> anyhow::Result<Vec<i64>> {
+        let applied = Self::migrator(driver).run(driver)?;
+        for version in &applied {
+            log::info!("Applied database migration {}", version);
+        }
+        Ok(applied)
+    }
+
+    /// Lists all migrations along with whether they are applied.
+    pub fn status(driver: &mut PgDriver) -> anyhow::Result<Vec<MigrationStatus>> {
+        Self::migrator(driver).status(driver)
+    }
+
+    /// Reverts the given amount of the most recently applied migrations.
+    ///
+    /// # Returns
+    /// The versions of the migrations reverted, most recent first.
+    pub fn rollback(driver: &mut PgDriver, steps: usize) -> anyhow::Result<Vec<i64>> {
+        let reverted = Self::migrator(driver).rollback(driver, steps)?;
+        for version in &reverted {
+            log::info!("Reverted database migration {}", version);
+        }
+        Ok(reverted)
+    }
+
+    fn migrator(driver: &PgDriver) -> Migrator<'static> {
+        match driver.kind() {
+            BackendKind::Postgres => Migrator::new(POSTGRES_MIGRATIONS),
+            BackendKind::Sqlite => Migrator::new(SQLITE_MIGRATIONS),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::crypto_service::CryptoService;
+    use crate::db::{
+        db_actions::DbActions,
+        model::{
+            alarm::{Alarm, TriggerRelation},
+            calendar::Calendar,
+            client::Client,
+            component::{Component, ComponentType},
+            conflict::ConflictMode,
+            user::User,
+            user_calendar_combination::UserCalendarCombination,
+        },
+        repository::{
+            alarm_repository::AlarmRepository, calendar_repository::CalendarRepository,
+            client_repository::ClientRepository, component_repository::ComponentRepository,
+            user_calendar_combination_repository::UserCalendarCombinationRepository,
+            user_repository::UserRepository,
+        },
+    };
+    use chrono::{TimeDelta, Utc};
+    use pg_driver::{DriverConfig, Query};
+
+    #[test]
+    fn test_sqlite_schema() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", uuid::Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+
+        assert_eq!(
+            Migrations::run(&mut driver).unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+        );
+        assert!(Migrations::run(&mut driver).unwrap().is_empty());
+
+        let user = User::new(
+            String::from("someone"),
+            String::from("someone@example.com"),
+            String::from("secret"),
+        );
+        let mut calendar = Calendar::new(String::from("Holidays"));
+        UserRepository::store(&mut driver, &user).unwrap();
+        CalendarRepository::store(&mut driver, &calendar).unwrap();
+        UserCalendarCombinationRepository::store(
+            &mut driver,
+            &UserCalendarCombination::new(user.get_uuid(), calendar.uuid),
+        )
+        .unwrap();
+
+        assert!(UserRepository::is_existing(
+            &mut driver,
+            "someone@example.com"
+        ));
+        let stored = UserRepository::get_by_uuid(&mut driver, user.get_uuid()).unwrap();
+        assert_eq!(stored.get_username(), user.get_username());
+        assert_eq!(
+            CalendarRepository::retrieve(&mut driver, Query::new())[0].name,
+            "Holidays"
+        );
+        calendar.conflict_mode = ConflictMode::HARD;
+        CalendarRepository::update(&mut driver, &calendar).unwrap();
+        assert_eq!(
+            UserCalendarCombinationRepository::get_calendars_of_user(&mut driver, &user)[0]
+                .conflict_mode,
+            ConflictMode::HARD
+        );
+
+        let component =
+            Component::new(ComponentType::EVENT, calendar.uuid).with_times(Utc::now(), None);
+        ComponentRepository::store(&mut driver, &component).unwrap();
+        let mut alarm = Alarm::relative(
+            component.uuid,
+            TimeDelta::minutes(-15),
+            TriggerRelation::START,
+        );
+        AlarmRepository::store(&mut driver, &alarm).unwrap();
+        alarm.snoozed_until = Some(Utc::now());
+        AlarmRepository::update(&mut driver, &alarm).unwrap();
+        let alarms = AlarmRepository::get_alarms_of_user(&mut driver, &user).unwrap();
+        assert_eq!(alarms.len(), 1);
+        assert_eq!(alarms[0].0, calendar.uuid);
+        assert_eq!(alarms[0].1.trigger_offset, Some(-15 * 60));
+        assert!(alarms[0].1.snoozed_until.is_some());
+
+        assert!(UserCalendarCombinationRepository::update(
+            &mut driver,
+            &UserCalendarCombination::new(user.get_uuid(), calendar.uuid),
+        )
+        .is_err());
+        let users =
+            UserCalendarCombinationRepository::get_users_of_calendar(&mut driver, calendar.uuid);
+        assert_eq!(users[0].get_uuid(), user.get_uuid());
+
+        let (_, pub_key) = CryptoService::new_ed25519_key_pair();
+        let client = Client::new(String::from("laptop"), user.get_uuid(), pub_key);
+        ClientRepository::store(&mut driver, &client).unwrap();
+        let clients = ClientRepository::retrieve(
+            &mut driver,
+            ClientRepository::USER_UUID.eq(user.get_uuid()).into(),
+        );
+        assert_eq!(clients[0].get_pub_key(), &pub_key);
+        assert_eq!(clients[0].get_device_name(), "laptop");
+
+        assert_eq!(
+            Migrations::rollback(&mut driver, 10).unwrap(),
+            vec![10, 9, 8, 7, 6, 5, 4, 3, 2, 1]
+        );
+        drop(driver);
+        let _ = std::fs::remove_file(path);
+    }
+}
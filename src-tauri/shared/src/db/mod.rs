@@ -1,4 +1,6 @@
+pub mod async_db_actions;
 pub mod db_actions;
+pub mod migrations;
 pub mod model;
 pub mod repository;
 pub mod service;
@@ -0,0 +1,454 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use customs::Table;
+use pg_driver::{FromSql, ToSql, Value};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::model::simple::simple_component::SimpleComponent;
+use crate::db::repository::{
+    alarm_repository::AlarmRepository, component_repository::ComponentRepository,
+};
+
+/// How far from the occurrence it reminds of an alarm may fire, with all of its repetitions.
+/// Alarms reaching farther are rejected when set and left out when imported.
+pub const MAX_ALARM_REACH_DAYS: i64 = 31;
+
+/// The most repetitions of an alarm.
+pub const MAX_ALARM_REPEATS: i32 = 100;
+
+/// What an alarm does when it fires, its ACTION. Every action is shown as a notification, the
+/// action only tells how.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlarmAction {
+    #[default]
+    DISPLAY,
+    AUDIO,
+    EMAIL,
+}
+
+impl AlarmAction {
+    pub fn parse(action: &str) -> Self {
+        match action.to_lowercase().as_str() {
+            "audio" => Self::AUDIO,
+            "email" => Self::EMAIL,
+            _ => Self::DISPLAY,
+        }
+    }
+}
+
+impl ToSql for AlarmAction {
+    fn to_value(&self) -> Value {
+        Value::Text(format!("{:?}", self))
+    }
+}
+
+impl FromSql<'_> for AlarmAction {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        String::from_value(val).map(|action| Self::parse(&action))
+    }
+}
+
+/// What a relative trigger is relative to, its RELATED parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriggerRelation {
+    #[default]
+    START,
+    END,
+}
+
+impl TriggerRelation {
+    pub fn parse(related: &str) -> Self {
+        match related.to_lowercase().as_str() {
+            "end" => Self::END,
+            _ => Self::START,
+        }
+    }
+}
+
+impl ToSql for TriggerRelation {
+    fn to_value(&self) -> Value {
+        Value::Text(format!("{:?}", self))
+    }
+}
+
+impl FromSql<'_> for TriggerRelation {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        String::from_value(val).map(|related| Self::parse(&related))
+    }
+}
+
+/// A reminder of an event or to-do, its VALARM.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Table)]
+#[table(
+    name = "alarms",
+    repository = "AlarmRepository",
+    fk_name = "alarm_uuid"
+)]
+pub struct Alarm {
+    #[table(primary_key)]
+    pub uuid: Uuid,
+    #[table(references = "ComponentRepository")]
+    pub component_uuid: Uuid,
+    pub action: AlarmAction,
+    /// When the alarm fires relative to every occurrence of its component, in seconds,
+    /// negative before it. None for absolute triggers.
+    pub trigger_offset: Option<i64>,
+    pub trigger_related: TriggerRelation,
+    /// When the alarm fires, once, for absolute triggers. None for relative ones.
+    pub trigger_at: Option<DateTime<Utc>>,
+    /// How often the alarm fires again after its trigger.
+    pub repeat_count: i32,
+    /// The seconds between the repetitions, its DURATION.
+    pub repeat_interval: Option<i64>,
+    pub description: Option<String>,
+    /// When the alarm was dismissed or snoozed last, its ACKNOWLEDGED. It doesn't fire again
+    /// for the times before.
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    /// When a snoozed alarm fires again.
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+impl Alarm {
+    /// An alarm firing `offset` from the start or end of every occurrence of a component,
+    /// negative before it.
+    pub fn relative(component_uuid: Uuid, offset: TimeDelta, related: TriggerRelation) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            component_uuid,
+            action: AlarmAction::DISPLAY,
+            trigger_offset: Some(offset.num_seconds()),
+            trigger_related: related,
+            trigger_at: None,
+            repeat_count: 0,
+            repeat_interval: None,
+            description: None,
+            acknowledged_at: None,
+            snoozed_until: None,
+        }
+    }
+
+    /// An alarm firing once at the given time.
+    pub fn absolute(component_uuid: Uuid, at: DateTime<Utc>) -> Self {
+        Self {
+            trigger_offset: None,
+            trigger_at: Some(at),
+            ..Self::relative(component_uuid, TimeDelta::zero(), TriggerRelation::START)
+        }
+    }
+
+    /// Lets the alarm fire `count` more times after its trigger, `interval` apart.
+    pub fn with_repeats(mut self, count: i32, interval: TimeDelta) -> Self {
+        self.repeat_count = count;
+        self.repeat_interval = Some(interval.num_seconds());
+        self
+    }
+
+    /// Whether the alarm has exactly one trigger and its repetitions stay within
+    /// `MAX_ALARM_REPEATS` and `MAX_ALARM_REACH_DAYS`.
+    pub fn is_valid(&self) -> bool {
+        let reach = MAX_ALARM_REACH_DAYS * 24 * 60 * 60;
+        let repeats = match (self.repeat_count, self.repeat_interval) {
+            (0, _) => 0,
+            (1..=MAX_ALARM_REPEATS, Some(interval)) if interval > 0 && interval <= reach => {
+                self.repeat_count as i64 * interval
+            }
+            _ => return false,
+        };
+        match (self.trigger_offset, self.trigger_at) {
+            (Some(offset), None) => offset.abs() + repeats <= reach,
+            (None, Some(_)) => repeats <= reach,
+            _ => false,
+        }
+    }
+
+    /// The times this alarm fires at for an occurrence of its component, its trigger followed
+    /// by its repetitions. Empty if it isn't valid, or for occurrences without the time it is
+    /// relative to.
+    pub fn fire_times(&self, occurrence: &SimpleComponent) -> Vec<DateTime<Utc>> {
+        if !self.is_valid() {
+            return vec![];
+        }
+        let trigger = match (self.trigger_at, self.trigger_offset) {
+            (Some(at), _) => Some(at),
+            (None, Some(offset)) => {
+                let anchor = match self.trigger_related {
+                    TriggerRelation::START => occurrence.get_starts_at(),
+                    TriggerRelation::END => occurrence
+                        .get_ends_at()
+                        .or_else(|| occurrence.get_starts_at()),
+                };
+                anchor.map(|anchor| anchor + TimeDelta::seconds(offset))
+            }
+            (None, None) => None,
+        };
+        let Some(trigger) = trigger else {
+            return vec![];
+        };
+        let interval = TimeDelta::seconds(self.repeat_interval.unwrap_or(0));
+        (0..=self.repeat_count)
+            .map(|repeat| trigger + interval * repeat)
+            .collect()
+    }
+
+    /// Whether this alarm reminds of the given occurrence. Alarms of a recurring component
+    /// remind of its overrides as well, unless they have alarms of their own.
+    fn reminds_of(&self, occurrence: &SimpleComponent, alarms: &[Alarm]) -> bool {
+        occurrence.get_uuid() == self.component_uuid
+            || (occurrence.get_recurrence_of() == Some(self.component_uuid)
+                && !alarms
+                    .iter()
+                    .any(|alarm| alarm.component_uuid == occurrence.get_uuid()))
+    }
+}
+
+/// An alarm firing for an occurrence of its component.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DueAlarm {
+    pub alarm_uuid: Uuid,
+    /// The component reminded of, the recurring one for its occurrences.
+    pub component_uuid: Uuid,
+    pub calendar_uuid: Uuid,
+    pub action: AlarmAction,
+    /// The SUMMARY of the component, if it has one.
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    /// The start of the occurrence reminded of.
+    pub starts_at: Option<DateTime<Utc>>,
+    /// When the alarm fires, a repetition or the end of a snooze included.
+    pub fires_at: DateTime<Utc>,
+}
+
+/// Finds the alarms firing within `after..=until` for the given occurrences, in the order they
+/// fire. Alarms don't fire before they were last acknowledged, snoozed alarms fire once more
+/// when their snooze ends. Absolute triggers fire once, for the first occurrence of their
+/// component.
+///
+/// # Arguments
+///
+/// * `alarms` - The alarms of the components.
+/// * `occurrences` - The expanded occurrences of the components, covering
+///   `MAX_ALARM_REACH_DAYS` around the range.
+/// * `after` - When alarms were fired last, exclusively.
+/// * `until` - Up to when to fire alarms.
+pub fn due_between(
+    alarms: &[Alarm],
+    occurrences: &[SimpleComponent],
+    after: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<DueAlarm> {
+    let mut due = instances(alarms, occurrences)
+        .into_iter()
+        .filter(|instance| instance.fires_at > after && instance.fires_at <= until)
+        .collect::<Vec<_>>();
+    due.sort_by_key(|instance| (instance.fires_at, instance.alarm_uuid));
+    due
+}
+
+/// When the next of the given alarms fires after `after`, see `due_between`.
+pub fn next_due(
+    alarms: &[Alarm],
+    occurrences: &[SimpleComponent],
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    instances(alarms, occurrences)
+        .into_iter()
+        .map(|instance| instance.fires_at)
+        .filter(|fires_at| *fires_at > after)
+        .min()
+}
+
+/// Every time the given alarms fire for the occurrences, unless they were acknowledged since.
+fn instances(alarms: &[Alarm], occurrences: &[SimpleComponent]) -> Vec<DueAlarm> {
+    let mut instances: Vec<DueAlarm> = vec![];
+    for alarm in alarms {
+        let reminded = occurrences
+            .iter()
+            .filter(|occurrence| alarm.reminds_of(occurrence, alarms))
+            .collect::<Vec<_>>();
+        let Some(&first) = reminded.first() else {
+            continue;
+        };
+        let reminded = match alarm.trigger_at {
+            Some(_) => vec![first],
+            None => reminded,
+        };
+
+        let mut fire_times = reminded
+            .iter()
+            .flat_map(|occurrence| {
+                alarm
+                    .fire_times(occurrence)
+                    .into_iter()
+                    .map(move |fires_at| (*occurrence, fires_at))
+            })
+            .collect::<Vec<_>>();
+        if let Some(snoozed_until) = alarm.snoozed_until {
+            // The snooze reminds of the occurrence the alarm fired for last
+            let occurrence = fire_times
+                .iter()
+                .filter(|(_, fires_at)| *fires_at <= snoozed_until)
+                .max_by_key(|(_, fires_at)| *fires_at)
+                .map_or(first, |(occurrence, _)| *occurrence);
+            fire_times.push((occurrence, snoozed_until));
+        }
+
+        for (occurrence, fires_at) in fire_times {
+            let acknowledged = alarm
+                .acknowledged_at
+                .is_some_and(|acknowledged_at| fires_at <= acknowledged_at);
+            let duplicate = instances
+                .iter()
+                .any(|instance| instance.alarm_uuid == alarm.uuid && instance.fires_at == fires_at);
+            if acknowledged || duplicate {
+                continue;
+            }
+            instances.push(DueAlarm {
+                alarm_uuid: alarm.uuid,
+                component_uuid: occurrence
+                    .get_recurrence_of()
+                    .unwrap_or(occurrence.get_uuid()),
+                calendar_uuid: occurrence.get_calendar_uuid(),
+                action: alarm.action,
+                summary: occurrence
+                    .get_properties()
+                    .iter()
+                    .find(|property| property.get_key().eq_ignore_ascii_case("SUMMARY"))
+                    .map(|property| property.get_val().clone()),
+                description: alarm.description.clone(),
+                starts_at: occurrence.get_starts_at(),
+                fires_at,
+            });
+        }
+    }
+    instances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::component::{Component, ComponentType};
+    use crate::db::model::property::{OwnerType, Property};
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, 0).unwrap()
+    }
+
+    fn event(uuid: Uuid, from: DateTime<Utc>, to: DateTime<Utc>) -> SimpleComponent {
+        let mut component =
+            Component::new(ComponentType::EVENT, Uuid::new_v4()).with_times(from, Some(to));
+        component.uuid = uuid;
+        let summary = Property::new(
+            uuid,
+            OwnerType::COMPONENT,
+            String::from("SUMMARY"),
+            String::from("Standup"),
+        );
+        SimpleComponent::new(component, vec![summary])
+    }
+
+    #[test]
+    fn test_fire_times() {
+        let uuid = Uuid::new_v4();
+        let standup = event(uuid, at(9, 0), at(9, 15));
+        let before = Alarm::relative(uuid, TimeDelta::minutes(-15), TriggerRelation::START)
+            .with_repeats(2, TimeDelta::minutes(5));
+        assert_eq!(
+            before.fire_times(&standup),
+            vec![at(8, 45), at(8, 50), at(8, 55)]
+        );
+        let after = Alarm::relative(uuid, TimeDelta::zero(), TriggerRelation::END);
+        assert_eq!(after.fire_times(&standup), vec![at(9, 15)]);
+        assert_eq!(
+            Alarm::absolute(uuid, at(7, 0)).fire_times(&standup),
+            vec![at(7, 0)]
+        );
+
+        let unbounded = Alarm::relative(uuid, TimeDelta::days(40), TriggerRelation::START);
+        assert!(!unbounded.is_valid());
+        assert!(unbounded.fire_times(&standup).is_empty());
+        let repeating_without_interval = Alarm {
+            repeat_count: 2,
+            ..after.clone()
+        };
+        assert!(!repeating_without_interval.is_valid());
+    }
+
+    #[test]
+    fn test_due_between() {
+        let uuid = Uuid::new_v4();
+        let occurrences = vec![
+            event(uuid, at(9, 0), at(9, 15)),
+            event(uuid, at(14, 0), at(14, 15)),
+        ];
+        let mut alarm = Alarm::relative(uuid, TimeDelta::minutes(-10), TriggerRelation::START);
+        let alarms = vec![alarm.clone()];
+
+        let due = due_between(&alarms, &occurrences, at(8, 0), at(12, 0));
+        assert_eq!(
+            due,
+            vec![DueAlarm {
+                alarm_uuid: alarm.uuid,
+                component_uuid: uuid,
+                calendar_uuid: occurrences[0].get_calendar_uuid(),
+                action: AlarmAction::DISPLAY,
+                summary: Some(String::from("Standup")),
+                description: None,
+                starts_at: Some(at(9, 0)),
+                fires_at: at(8, 50),
+            }]
+        );
+        assert!(due_between(&alarms, &occurrences, at(8, 50), at(12, 0)).is_empty());
+        assert_eq!(next_due(&alarms, &occurrences, at(8, 50)), Some(at(13, 50)));
+
+        // Snoozed at 8:52 for five minutes
+        alarm.acknowledged_at = Some(at(8, 52));
+        alarm.snoozed_until = Some(at(8, 57));
+        let snoozed = vec![alarm.clone()];
+        let due = due_between(&snoozed, &occurrences, at(8, 0), at(12, 0));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].fires_at, at(8, 57));
+        assert_eq!(due[0].starts_at, Some(at(9, 0)));
+
+        // Dismissed after that
+        alarm.acknowledged_at = Some(at(8, 58));
+        alarm.snoozed_until = None;
+        let dismissed = vec![alarm];
+        assert!(due_between(&dismissed, &occurrences, at(8, 0), at(12, 0)).is_empty());
+        assert_eq!(
+            next_due(&dismissed, &occurrences, at(8, 58)),
+            Some(at(13, 50))
+        );
+    }
+
+    #[test]
+    fn test_due_between_overrides() {
+        let uuid = Uuid::new_v4();
+        let mut moved = event(Uuid::new_v4(), at(10, 0), at(10, 15));
+        let mut component = Component::new(ComponentType::EVENT, moved.get_calendar_uuid())
+            .with_times(at(10, 0), Some(at(10, 15)));
+        component.uuid = moved.get_uuid();
+        component.recurrence_of = Some(uuid);
+        component.recurrence_id = Some(at(9, 0));
+        moved = SimpleComponent::new(component, moved.get_properties().clone());
+        let occurrences = vec![moved.clone(), event(uuid, at(14, 0), at(14, 15))];
+
+        let series = Alarm::relative(uuid, TimeDelta::minutes(-10), TriggerRelation::START);
+        let fires_at = |alarms: &[Alarm]| {
+            due_between(alarms, &occurrences, at(0, 0), at(23, 0))
+                .iter()
+                .map(|due| (due.component_uuid, due.fires_at))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            fires_at(std::slice::from_ref(&series)),
+            vec![(uuid, at(9, 50)), (uuid, at(13, 50))]
+        );
+
+        let own = Alarm::relative(moved.get_uuid(), TimeDelta::zero(), TriggerRelation::START);
+        assert_eq!(
+            fires_at(&[series, own]),
+            vec![(uuid, at(10, 0)), (uuid, at(13, 50))]
+        );
+    }
+}
@@ -1,10 +1,21 @@
+use crate::db::model::conflict::ConflictMode;
+use crate::db::repository::calendar_repository::CalendarRepository;
+use customs::Table;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Table)]
+#[table(
+    name = "calendars",
+    repository = "CalendarRepository",
+    fk_name = "calendar_uuid"
+)]
 pub struct Calendar {
+    #[table(primary_key)]
     pub uuid: Uuid,
     pub name: String,
+    /// How the calendar treats events overlapping other events of its users.
+    pub conflict_mode: ConflictMode,
 }
 
 impl Calendar {
@@ -12,10 +23,15 @@ impl Calendar {
         Self {
             uuid: Uuid::new_v4(),
             name,
+            conflict_mode: ConflictMode::SOFT,
         }
     }
 
     pub fn from(uuid: Uuid, name: String) -> Self {
-        Self { uuid, name }
+        Self {
+            uuid,
+            name,
+            conflict_mode: ConflictMode::SOFT,
+        }
     }
 }
@@ -1,10 +1,21 @@
+use crate::db::repository::{client_repository::ClientRepository, user_repository::UserRepository};
 use chrono::{DateTime, Utc};
+use customs::Table;
 use ed25519_dalek::VerifyingKey;
 use uuid::Uuid;
 
+#[derive(Table)]
+#[table(
+    name = "clients",
+    repository = "ClientRepository",
+    fk_name = "client_uuid"
+)]
 pub struct Client {
+    #[table(primary_key)]
     uuid: Uuid,
+    #[table(references = "UserRepository")]
     user_uuid: Uuid,
+    #[table(column = "public_key", with = "pub_key_column")]
     pub_key: VerifyingKey,
     device_name: String,
     last_used: DateTime<Utc>,
@@ -65,3 +76,18 @@ impl Client {
         self.registered_at
     }
 }
+
+/// Stores the public key of a client base64 encoded.
+mod pub_key_column {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use ed25519_dalek::VerifyingKey;
+
+    pub fn to_sql(key: &VerifyingKey) -> String {
+        STANDARD.encode(key.to_bytes())
+    }
+
+    pub fn from_sql(encoded: String) -> VerifyingKey {
+        VerifyingKey::try_from(&STANDARD.decode(encoded).unwrap()[..32]).unwrap()
+        // TODO: Improve error handling
+    }
+}
@@ -1,8 +1,16 @@
-use serde::Serialize;
+use crate::db::repository::{
+    calendar_repository::CalendarRepository, component_repository::ComponentRepository,
+};
+use crate::ical::zone;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use customs::Table;
+use pg_driver::{FromSql, ToSql, Value};
+use serde::{Deserialize, Serialize};
 use sqlx::prelude::Type;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Type, PartialEq, Serialize)]
+#[derive(Debug, Clone, Type, PartialEq, Serialize, Deserialize)]
 pub enum ComponentType {
     EVENT,
     TODO,
@@ -27,21 +35,131 @@ impl ComponentType {
     }
 }
 
-#[derive(Clone)]
+impl ToSql for ComponentType {
+    fn to_value(&self) -> Value {
+        Value::Text(format!("{:?}", self))
+    }
+}
+
+impl FromSql<'_> for ComponentType {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        String::from_value(val).map(|c_type| Self::parse(&c_type))
+    }
+}
+
+#[derive(Debug, Clone, Table)]
+#[table(
+    name = "components",
+    repository = "ComponentRepository",
+    fk_name = "component_uuid"
+)]
 pub struct Component {
+    #[table(primary_key)]
     pub uuid: Uuid,
     pub c_type: ComponentType,
+    #[table(references = "CalendarRepository")]
+    pub calendar_uuid: Uuid,
+    /// When the component starts, None for components without a time, like venues.
+    pub starts_at: Option<DateTime<Utc>>,
+    /// When the component ends, exclusively. Equals `starts_at` for components without a
+    /// duration.
+    pub ends_at: Option<DateTime<Utc>>,
+    /// When the last occurrence of the component ends, None for series without an end. Equals
+    /// `ends_at` for components that don't recur.
+    pub series_ends_at: Option<DateTime<Utc>>,
+    /// The recurring component one of whose occurrences this component overrides, like a single
+    /// meeting of a series that was moved. None for all other components.
+    pub recurrence_of: Option<Uuid>,
+    /// The start of the occurrence this component overrides, its RECURRENCE-ID.
+    pub recurrence_id: Option<DateTime<Utc>>,
+    /// The IANA zone of its times, like `Europe/Berlin`, which its occurrences keep their
+    /// wall-clock time in. None for times in UTC and floating times.
+    pub time_zone: Option<String>,
 }
 
 impl Component {
-    pub fn new(c_type: ComponentType) -> Self {
+    pub fn new(c_type: ComponentType, calendar_uuid: Uuid) -> Self {
         Self {
             uuid: Uuid::new_v4(),
             c_type,
+            calendar_uuid,
+            starts_at: None,
+            ends_at: None,
+            series_ends_at: None,
+            recurrence_of: None,
+            recurrence_id: None,
+            time_zone: None,
         }
     }
 
-    pub fn from(uuid: Uuid, c_type: ComponentType) -> Self {
-        Self { uuid, c_type }
+    pub fn from(
+        uuid: Uuid,
+        c_type: ComponentType,
+        calendar_uuid: Uuid,
+        starts_at: Option<DateTime<Utc>>,
+        ends_at: Option<DateTime<Utc>>,
+        series_ends_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            uuid,
+            c_type,
+            calendar_uuid,
+            starts_at,
+            ends_at,
+            series_ends_at,
+            recurrence_of: None,
+            recurrence_id: None,
+            time_zone: None,
+        }
+    }
+
+    /// Sets the times of this component. They have to match its DTSTART and DTEND properties,
+    /// which they are a queryable copy of.
+    ///
+    /// # Arguments
+    ///
+    /// * `starts_at` - When the component starts.
+    /// * `ends_at` - When the component ends, None if it has no duration.
+    pub fn with_times(mut self, starts_at: DateTime<Utc>, ends_at: Option<DateTime<Utc>>) -> Self {
+        self.starts_at = Some(starts_at);
+        self.ends_at = Some(ends_at.unwrap_or(starts_at).max(starts_at));
+        self.series_ends_at = self.ends_at;
+        self
+    }
+
+    /// Marks this component as recurring, see `Recurrence::series_end`. Its times are the ones
+    /// of the first occurrence.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_ends_at` - When the last occurrence ends, None if the series has no end.
+    pub fn recurring_until(mut self, series_ends_at: Option<DateTime<Utc>>) -> Self {
+        self.series_ends_at = series_ends_at;
+        self
+    }
+
+    /// Sets the zone the times of this component are given in, the TZID of its DTSTART.
+    pub fn in_zone(mut self, zone: Tz) -> Self {
+        self.time_zone = Some(zone.name().to_string());
+        self
+    }
+
+    /// The zone the times of this component are given in, None if it has none or it is
+    /// unknown.
+    pub fn zone(&self) -> Option<Tz> {
+        self.time_zone.as_deref().and_then(zone::parse_zone)
+    }
+
+    /// Makes this component override an occurrence of a recurring component, which is left
+    /// out of its series from then on.
+    ///
+    /// # Arguments
+    ///
+    /// * `master_uuid` - The uuid of the recurring component.
+    /// * `recurrence_id` - The start of the overridden occurrence.
+    pub fn overriding(mut self, master_uuid: Uuid, recurrence_id: DateTime<Utc>) -> Self {
+        self.recurrence_of = Some(master_uuid);
+        self.recurrence_id = Some(recurrence_id);
+        self
     }
 }
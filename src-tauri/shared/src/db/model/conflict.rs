@@ -0,0 +1,255 @@
+use chrono::{DateTime, Utc};
+use pg_driver::{FromSql, ToSql, Value};
+use serde::{Deserialize, Serialize};
+use std::slice;
+use uuid::Uuid;
+
+use crate::db::model::free_busy::{self, BusyType};
+use crate::db::model::simple::simple_component::SimpleComponent;
+
+/// How a calendar treats events overlapping other events of its users.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictMode {
+    /// Overlapping events are stored, the conflicts are reported.
+    #[default]
+    SOFT,
+    /// Overlapping events are rejected, like double bookings of a room.
+    HARD,
+}
+
+impl ConflictMode {
+    pub fn parse(mode: &str) -> Self {
+        match mode.to_lowercase().as_str() {
+            "hard" => Self::HARD,
+            _ => Self::SOFT,
+        }
+    }
+}
+
+impl ToSql for ConflictMode {
+    fn to_value(&self) -> Value {
+        Value::Text(format!("{:?}", self))
+    }
+}
+
+impl FromSql<'_> for ConflictMode {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        String::from_value(val).map(|mode| Self::parse(&mode))
+    }
+}
+
+/// An event occurrence overlapping an occurrence of a stored or edited event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    /// The overlapping event, the recurring one for its occurrences.
+    pub component_uuid: Uuid,
+    pub calendar_uuid: Uuid,
+    /// The SUMMARY of the overlapping event, if it has one.
+    pub summary: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    /// When the overlapping occurrence ends, exclusively.
+    pub ends_at: DateTime<Utc>,
+    /// The start of the occurrence of the stored or edited event it overlaps.
+    pub overlapped_at: DateTime<Utc>,
+}
+
+/// A stored event along with the events it overlaps, see `ComponentService::store_typed`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stored {
+    pub uuid: Uuid,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// The occurrences of `after` that take time differently than all occurrences of `before`,
+/// like the ones an edit moved. Occurrences that take no time, see `FreeBusy::of`, are left
+/// out.
+///
+/// # Arguments
+///
+/// * `before` - The expanded occurrences of the events before a change.
+/// * `after` - The expanded occurrences of the events after it.
+pub fn changed_occurrences(
+    before: &[SimpleComponent],
+    after: &[SimpleComponent],
+) -> Vec<SimpleComponent> {
+    let taken = before.iter().filter_map(taken_time).collect::<Vec<_>>();
+    after
+        .iter()
+        .filter(|occurrence| taken_time(occurrence).is_some_and(|time| !taken.contains(&time)))
+        .cloned()
+        .collect()
+}
+
+/// Finds the occurrences of `others` overlapping one of the `changed` occurrences, in order.
+/// Occurrences that take no time don't conflict, see `FreeBusy::of`.
+///
+/// # Arguments
+///
+/// * `changed` - The occurrences of the stored or edited events, see `changed_occurrences`.
+/// * `others` - The expanded occurrences of the events to check against.
+/// * `excluded` - The stored or edited events, whose occurrences in `others` are skipped.
+pub fn find_conflicts(
+    changed: &[SimpleComponent],
+    others: &[SimpleComponent],
+    excluded: &[Uuid],
+) -> Vec<Conflict> {
+    let mut conflicts = vec![];
+    for other in others {
+        if excluded.contains(&other.get_uuid())
+            || other
+                .get_recurrence_of()
+                .is_some_and(|master| excluded.contains(&master))
+        {
+            continue;
+        }
+        let Some((starts_at, ends_at, _)) = taken_time(other) else {
+            continue;
+        };
+        let overlapped = changed.iter().filter_map(taken_time).find(
+            |(changed_starts_at, changed_ends_at, _)| {
+                *changed_starts_at < ends_at && starts_at < *changed_ends_at
+            },
+        );
+        if let Some((overlapped_at, _, _)) = overlapped {
+            conflicts.push(Conflict {
+                component_uuid: other.get_uuid(),
+                calendar_uuid: other.get_calendar_uuid(),
+                summary: other
+                    .get_properties()
+                    .iter()
+                    .find(|property| property.get_key().eq_ignore_ascii_case("SUMMARY"))
+                    .map(|property| property.get_val().clone()),
+                starts_at,
+                ends_at,
+                overlapped_at,
+            });
+        }
+    }
+    conflicts.sort_by_key(|conflict| (conflict.starts_at, conflict.component_uuid));
+    conflicts
+}
+
+/// Finds the occurrences overlapping one of the `imported` occurrences, in order. Imported
+/// occurrences conflict with the `stored` ones and with the imported occurrences of other
+/// events before them, so each overlap of the import itself is reported once.
+///
+/// # Arguments
+///
+/// * `imported` - The expanded occurrences of the imported events, ordered by their start.
+/// * `stored` - The expanded occurrences of the stored events to check against.
+pub fn find_import_conflicts(
+    imported: &[SimpleComponent],
+    stored: &[SimpleComponent],
+) -> Vec<Conflict> {
+    let mut conflicts = find_conflicts(imported, stored, &[]);
+    for (i, occurrence) in imported.iter().enumerate() {
+        let series = occurrence
+            .get_recurrence_of()
+            .unwrap_or(occurrence.get_uuid());
+        conflicts.extend(find_conflicts(
+            slice::from_ref(occurrence),
+            &imported[..i],
+            &[series],
+        ));
+    }
+    conflicts.sort_by_key(|conflict| (conflict.starts_at, conflict.component_uuid));
+    conflicts
+}
+
+/// When an occurrence takes time and how, None if it takes none.
+fn taken_time(occurrence: &SimpleComponent) -> Option<(DateTime<Utc>, DateTime<Utc>, BusyType)> {
+    let (Some(starts_at), Some(ends_at)) = (occurrence.get_starts_at(), occurrence.get_ends_at())
+    else {
+        return None;
+    };
+    if starts_at >= ends_at {
+        return None;
+    }
+    free_busy::busy_type(occurrence).map(|busy_type| (starts_at, ends_at, busy_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::component::{Component, ComponentType};
+    use crate::db::model::property::{OwnerType, Property};
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap()
+    }
+
+    fn event(uuid: Uuid, from: u32, to: u32, properties: &[(&str, &str)]) -> SimpleComponent {
+        let mut component =
+            Component::new(ComponentType::EVENT, Uuid::new_v4()).with_times(at(from), Some(at(to)));
+        component.uuid = uuid;
+        let properties = properties
+            .iter()
+            .map(|(key, val)| {
+                Property::new(uuid, OwnerType::COMPONENT, key.to_string(), val.to_string())
+            })
+            .collect();
+        SimpleComponent::new(component, properties)
+    }
+
+    #[test]
+    fn test_changed_occurrences() {
+        let uuid = Uuid::new_v4();
+        let before = vec![event(uuid, 9, 10, &[]), event(uuid, 14, 15, &[])];
+        let after = vec![
+            event(uuid, 9, 10, &[]),
+            event(uuid, 15, 16, &[]),
+            event(uuid, 17, 18, &[("TRANSP", "TRANSPARENT")]),
+        ];
+        let changed = changed_occurrences(&before, &after);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].get_starts_at(), Some(at(15)));
+
+        let confirmed = vec![event(uuid, 9, 10, &[("STATUS", "CONFIRMED")])];
+        let tentative = vec![event(uuid, 9, 10, &[("STATUS", "TENTATIVE")])];
+        assert_eq!(changed_occurrences(&tentative, &confirmed).len(), 1);
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let edited = Uuid::new_v4();
+        let changed = vec![event(edited, 9, 11, &[]), event(edited, 14, 15, &[])];
+        let lunch = Uuid::new_v4();
+        let others = vec![
+            event(edited, 9, 10, &[]),
+            event(Uuid::new_v4(), 8, 9, &[]),
+            event(lunch, 10, 12, &[("SUMMARY", "Lunch")]),
+            event(Uuid::new_v4(), 12, 13, &[]),
+            event(Uuid::new_v4(), 14, 15, &[("STATUS", "CANCELLED")]),
+        ];
+        let conflicts = find_conflicts(&changed, &others, &[edited]);
+        assert_eq!(
+            conflicts,
+            vec![Conflict {
+                component_uuid: lunch,
+                calendar_uuid: others[2].get_calendar_uuid(),
+                summary: Some(String::from("Lunch")),
+                starts_at: at(10),
+                ends_at: at(12),
+                overlapped_at: at(9),
+            }]
+        );
+        assert!(find_conflicts(&[], &others, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_find_import_conflicts() {
+        let (daily, meeting, stored) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let imported = vec![
+            event(daily, 9, 10, &[]),
+            event(meeting, 10, 12, &[]),
+            event(daily, 11, 12, &[]),
+        ];
+        let conflicts = find_import_conflicts(&imported, &[event(stored, 8, 10, &[])]);
+        let overlaps = conflicts
+            .iter()
+            .map(|conflict| (conflict.component_uuid, conflict.overlapped_at))
+            .collect::<Vec<_>>();
+        assert_eq!(overlaps, vec![(stored, at(9)), (meeting, at(11))]);
+    }
+}
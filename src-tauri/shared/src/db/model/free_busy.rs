@@ -0,0 +1,267 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::model::component::ComponentType;
+use crate::db::model::simple::simple_component::SimpleComponent;
+
+/// How certain it is that the time of a busy period is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BusyType {
+    BUSY,
+    /// Taken by tentative events only.
+    TENTATIVE,
+}
+
+impl BusyType {
+    /// The FBTYPE parameter of the type.
+    pub fn fb_type(&self) -> &'static str {
+        match self {
+            Self::BUSY => "BUSY",
+            Self::TENTATIVE => "BUSY-TENTATIVE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusyPeriod {
+    pub starts_at: DateTime<Utc>,
+    /// When the period ends, exclusively.
+    pub ends_at: DateTime<Utc>,
+    pub busy_type: BusyType,
+}
+
+/// The times some users are busy within a time window, merged across all of their calendars.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FreeBusy {
+    pub user_uuids: Vec<Uuid>,
+    pub from: DateTime<Utc>,
+    /// The end of the window, exclusively.
+    pub to: DateTime<Utc>,
+    /// The busy periods in order. They don't overlap, time taken by tentative and confirmed
+    /// events alike is busy.
+    pub busy: Vec<BusyPeriod>,
+}
+
+impl FreeBusy {
+    /// Computes when the given occurrences take time within `from..to`, cut to the window.
+    ///
+    /// Only events take time. Events marked as TRANSP:TRANSPARENT or STATUS:CANCELLED are
+    /// left out, STATUS:TENTATIVE events take time tentatively.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_uuids` - The users whose calendars the occurrences are of.
+    /// * `occurrences` - The expanded occurrences, see `SimpleComponent::build_in_range`.
+    pub fn of(
+        user_uuids: Vec<Uuid>,
+        occurrences: &[SimpleComponent],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Self {
+        let mut busy = vec![];
+        let mut tentative = vec![];
+        for occurrence in occurrences {
+            let (Some(starts_at), Some(ends_at)) =
+                (occurrence.get_starts_at(), occurrence.get_ends_at())
+            else {
+                continue;
+            };
+            let period = (starts_at.max(from), ends_at.min(to));
+            if period.0 >= period.1 {
+                continue;
+            }
+            match busy_type(occurrence) {
+                Some(BusyType::BUSY) => busy.push(period),
+                Some(BusyType::TENTATIVE) => tentative.push(period),
+                None => {}
+            }
+        }
+
+        let busy = merge(busy);
+        let tentative = subtract(merge(tentative), &busy);
+        let mut periods = busy
+            .into_iter()
+            .map(|period| (period, BusyType::BUSY))
+            .chain(
+                tentative
+                    .into_iter()
+                    .map(|period| (period, BusyType::TENTATIVE)),
+            )
+            .map(|((starts_at, ends_at), busy_type)| BusyPeriod {
+                starts_at,
+                ends_at,
+                busy_type,
+            })
+            .collect::<Vec<_>>();
+        periods.sort_by_key(|period| period.starts_at);
+
+        Self {
+            user_uuids,
+            from,
+            to,
+            busy: periods,
+        }
+    }
+
+    /// The periods within the window no busy period overlaps, tentative ones included.
+    pub fn free(&self) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let taken = self
+            .busy
+            .iter()
+            .map(|period| (period.starts_at, period.ends_at))
+            .collect::<Vec<_>>();
+        subtract(vec![(self.from, self.to)], &merge(taken))
+    }
+}
+
+/// How an occurrence takes time, None if it doesn't.
+pub(crate) fn busy_type(occurrence: &SimpleComponent) -> Option<BusyType> {
+    if *occurrence.get_c_type() != ComponentType::EVENT {
+        return None;
+    }
+    let value = |key: &str| {
+        occurrence
+            .get_properties()
+            .iter()
+            .find(|property| property.get_key().eq_ignore_ascii_case(key))
+            .map(|property| property.get_val().trim().to_uppercase())
+    };
+    if value("TRANSP").as_deref() == Some("TRANSPARENT") {
+        return None;
+    }
+    match value("STATUS").as_deref() {
+        Some("CANCELLED") => None,
+        Some("TENTATIVE") => Some(BusyType::TENTATIVE),
+        _ => Some(BusyType::BUSY),
+    }
+}
+
+/// Merges overlapping and adjacent periods, ordering them by their start.
+fn merge(mut periods: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    periods.sort();
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = vec![];
+    for (starts_at, ends_at) in periods {
+        match merged.last_mut() {
+            Some(last) if starts_at <= last.1 => last.1 = last.1.max(ends_at),
+            _ => merged.push((starts_at, ends_at)),
+        }
+    }
+    merged
+}
+
+/// Cuts the merged `taken` periods out of the merged `periods`.
+fn subtract(
+    periods: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    taken: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut rest = vec![];
+    for (mut starts_at, ends_at) in periods {
+        for (taken_from, taken_to) in taken {
+            if *taken_to <= starts_at || *taken_from >= ends_at {
+                continue;
+            }
+            if *taken_from > starts_at {
+                rest.push((starts_at, *taken_from));
+            }
+            starts_at = starts_at.max(*taken_to);
+        }
+        if starts_at < ends_at {
+            rest.push((starts_at, ends_at));
+        }
+    }
+    rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::component::Component;
+    use crate::db::model::property::{OwnerType, Property};
+    use chrono::{TimeDelta, TimeZone};
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap()
+    }
+
+    fn event(from: u32, to: u32, properties: &[(&str, &str)]) -> SimpleComponent {
+        let component =
+            Component::new(ComponentType::EVENT, Uuid::new_v4()).with_times(at(from), Some(at(to)));
+        let properties = properties
+            .iter()
+            .map(|(key, val)| {
+                Property::new(
+                    component.uuid,
+                    OwnerType::COMPONENT,
+                    key.to_string(),
+                    val.to_string(),
+                )
+            })
+            .collect();
+        SimpleComponent::new(component, properties)
+    }
+
+    fn periods(free_busy: &FreeBusy) -> Vec<(u32, u32, BusyType)> {
+        free_busy
+            .busy
+            .iter()
+            .map(|period| {
+                (
+                    (period.starts_at - at(0)).num_hours() as u32,
+                    (period.ends_at - at(0)).num_hours() as u32,
+                    period.busy_type,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_of() {
+        let todo = SimpleComponent::new(
+            Component::new(ComponentType::TODO, Uuid::new_v4()).with_times(at(9), Some(at(17))),
+            vec![],
+        );
+        let occurrences = vec![
+            event(6, 9, &[]),
+            event(9, 10, &[]),
+            event(11, 12, &[("STATUS", "CANCELLED")]),
+            event(11, 14, &[("TRANSP", "TRANSPARENT")]),
+            event(12, 15, &[("STATUS", "TENTATIVE")]),
+            event(13, 14, &[("STATUS", "CONFIRMED")]),
+            event(16, 20, &[]),
+            todo,
+        ];
+        let free_busy = FreeBusy::of(vec![], &occurrences, at(8), at(18));
+        assert_eq!(
+            periods(&free_busy),
+            vec![
+                (8, 10, BusyType::BUSY),
+                (12, 13, BusyType::TENTATIVE),
+                (13, 14, BusyType::BUSY),
+                (14, 15, BusyType::TENTATIVE),
+                (16, 18, BusyType::BUSY),
+            ]
+        );
+        assert_eq!(free_busy.free(), vec![(at(10), at(12)), (at(15), at(16))]);
+    }
+
+    #[test]
+    fn test_of_recurring() {
+        let series = event(
+            9,
+            10,
+            &[
+                ("DTSTART", "20240501T090000Z"),
+                ("RRULE", "FREQ=DAILY;COUNT=3"),
+                ("EXDATE", "20240502T090000Z"),
+            ],
+        );
+        let to = at(0) + TimeDelta::days(7);
+        let occurrences = series.occurrences(at(0), to);
+        assert_eq!(
+            periods(&FreeBusy::of(vec![], &occurrences, at(0), to)),
+            vec![(9, 10, BusyType::BUSY), (57, 58, BusyType::BUSY)]
+        );
+        assert!(FreeBusy::of(vec![], &[], at(0), at(1)).busy.is_empty());
+    }
+}
@@ -1,7 +1,13 @@
+pub mod alarm;
 pub mod calendar;
 pub mod client;
 pub mod component;
+pub mod conflict;
+pub mod free_busy;
 pub mod property;
+pub mod scheduling;
+pub mod series;
 pub mod simple;
+pub mod typed;
 pub mod user;
 pub mod user_calendar_combination;
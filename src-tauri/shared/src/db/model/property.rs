@@ -1,25 +1,64 @@
-use serde::Serialize;
+use crate::db::repository::property_repository::PropertyRepository;
+use crate::ical;
+use customs::Table;
+use pg_driver::{FromSql, ToSql, Value};
+use serde::{Deserialize, Serialize};
 use sqlx::prelude::Type;
 use uuid::Uuid;
 
-#[derive(Debug, PartialEq, Serialize, Clone)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Table)]
+#[table(
+    name = "properties",
+    repository = "PropertyRepository",
+    fk_name = "property_uuid"
+)]
 pub struct Property {
+    #[table(primary_key)]
     uuid: Uuid,
     key: String,
+    #[table(column = "value")]
     val: String,
+    /// The calendar or component this property belongs to.
+    owner_uuid: Uuid,
+    owner_type: OwnerType,
+    /// The iCalendar parameters, like `TZID=Europe/Berlin`, empty if there are none.
+    params: String,
 }
 
 impl Property {
-    pub fn new(key: String, val: String) -> Self {
+    pub fn new(owner_uuid: Uuid, owner_type: OwnerType, key: String, val: String) -> Self {
         Self {
             uuid: Uuid::new_v4(),
             key,
             val,
+            owner_uuid,
+            owner_type,
+            params: String::new(),
         }
     }
 
-    pub fn from(uuid: Uuid, key: String, val: String) -> Self {
-        Self { uuid, key, val }
+    pub fn from(
+        uuid: Uuid,
+        key: String,
+        val: String,
+        owner_uuid: Uuid,
+        owner_type: OwnerType,
+        params: String,
+    ) -> Self {
+        Self {
+            uuid,
+            key,
+            val,
+            owner_uuid,
+            owner_type,
+            params,
+        }
+    }
+
+    /// Sets the iCalendar parameters of this property, formatted like in a content line.
+    pub fn with_params(mut self, params: String) -> Self {
+        self.params = params;
+        self
     }
 
     pub fn get_uuid(&self) -> Uuid {
@@ -42,16 +81,41 @@ impl Property {
         self.val = val;
     }
 
+    pub fn get_owner_uuid(&self) -> Uuid {
+        self.owner_uuid
+    }
+
+    pub fn get_owner_type(&self) -> OwnerType {
+        self.owner_type
+    }
+
+    pub fn get_params(&self) -> &String {
+        &self.params
+    }
+
+    /// The first value of the parameter with the given name, e.g. the zone of `TZID`.
+    pub fn param(&self, name: &str) -> Option<String> {
+        ical::parser::parse_params(&self.params)
+            .ok()?
+            .into_iter()
+            .find(|param| param.name.eq_ignore_ascii_case(name))
+            .and_then(|param| param.values.into_iter().next())
+    }
+
+    /// Holds a key and value read without the rest of the property.
     pub fn hold(key: String, val: String) -> Self {
         Self {
             uuid: Uuid::nil(),
             key,
             val,
+            owner_uuid: Uuid::nil(),
+            owner_type: OwnerType::COMPONENT,
+            params: String::new(),
         }
     }
 }
 
-#[derive(Debug, Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Type, Serialize, Deserialize)]
 pub enum OwnerType {
     CALENDAR,
     COMPONENT,
@@ -65,3 +129,19 @@ impl OwnerType {
         }
     }
 }
+
+impl ToSql for OwnerType {
+    fn to_value(&self) -> Value {
+        Value::Text(self.to_string())
+    }
+}
+
+impl FromSql<'_> for OwnerType {
+    fn from_value(val: &Value) -> anyhow::Result<Self> {
+        match String::from_value(val)?.as_str() {
+            "Calendar" => Ok(Self::CALENDAR),
+            "Component" => Ok(Self::COMPONENT),
+            owner_type => anyhow::bail!("Unknown owner type {}", owner_type),
+        }
+    }
+}
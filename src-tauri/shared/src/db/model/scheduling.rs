@@ -0,0 +1,375 @@
+use std::cmp::Reverse;
+
+use chrono::{DateTime, Datelike, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::model::free_busy::BusyPeriod;
+use crate::db::model::user::User;
+use crate::ical::zone;
+
+/// The grid proposed meetings start on, in minutes.
+pub const SLOT_STEP_MINUTES: i64 = 15;
+
+/// The days of the week by their iCalendar names.
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+/// The hours a user can meet in, in their time zone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkingHours {
+    pub starts: NaiveTime,
+    /// When the hours end, on the next day if not after `starts`.
+    pub ends: NaiveTime,
+    /// The days the hours start on.
+    pub days: Vec<Weekday>,
+}
+
+impl WorkingHours {
+    /// Reads working hours as they are stored, see `User::working_hours`.
+    ///
+    /// # Arguments
+    ///
+    /// * `starts` - When they start, in minutes after midnight.
+    /// * `ends` - When they end, in minutes after midnight.
+    /// * `days` - The days, like `MO,TU,WE,TH,FR`. None for Monday to Friday.
+    ///
+    /// # Returns
+    /// None if a value is out of range or the hours are invalid, see `is_valid`.
+    pub fn from_minutes(starts: i32, ends: i32, days: Option<&str>) -> Option<Self> {
+        let time = |minutes: i32| {
+            NaiveTime::from_num_seconds_from_midnight_opt(u32::try_from(minutes).ok()? * 60, 0)
+        };
+        let days = match days {
+            Some(days) => days
+                .split(',')
+                .map(|day| {
+                    WEEKDAYS
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(day.trim()))
+                        .map(|(_, weekday)| *weekday)
+                })
+                .collect::<Option<Vec<_>>>()?,
+            None => WEEKDAYS[..5].iter().map(|(_, weekday)| *weekday).collect(),
+        };
+        let hours = Self {
+            starts: time(starts)?,
+            ends: time(ends)?,
+            days,
+        };
+        hours.is_valid().then_some(hours)
+    }
+
+    /// Whether the hours take time on at least one day.
+    pub fn is_valid(&self) -> bool {
+        self.starts != self.ends && !self.days.is_empty()
+    }
+
+    pub fn starts_minutes(&self) -> i32 {
+        (self.starts.num_seconds_from_midnight() / 60) as i32
+    }
+
+    pub fn ends_minutes(&self) -> i32 {
+        (self.ends.num_seconds_from_midnight() / 60) as i32
+    }
+
+    /// The days as they are stored, like `MO,TU,WE,TH,FR`.
+    pub fn days_value(&self) -> String {
+        WEEKDAYS
+            .iter()
+            .filter(|(_, weekday)| self.days.contains(weekday))
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The periods of these hours overlapping `from..to`, cut to the range.
+    ///
+    /// # Arguments
+    ///
+    /// * `zone` - The zone of the hours, None for UTC.
+    pub fn periods(
+        &self,
+        zone: Option<Tz>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let local = |time: DateTime<Utc>| match zone {
+            Some(zone) => zone::to_local(time, zone),
+            None => time.naive_utc(),
+        };
+        let utc = |local: NaiveDateTime| match zone {
+            Some(zone) => zone::from_local(local, zone),
+            None => local.and_utc(),
+        };
+
+        let mut periods = vec![];
+        // Hours ending after midnight may have started the day before
+        let mut day = local(from).date().pred_opt();
+        let last = local(to).date();
+        while let Some(date) = day.filter(|date| *date <= last) {
+            if self.days.contains(&date.weekday()) {
+                let end_date = match self.ends > self.starts {
+                    true => Some(date),
+                    false => date.succ_opt(),
+                };
+                if let Some(end_date) = end_date {
+                    let starts_at = utc(date.and_time(self.starts)).max(from);
+                    let ends_at = utc(end_date.and_time(self.ends)).min(to);
+                    if starts_at < ends_at {
+                        periods.push((starts_at, ends_at));
+                    }
+                }
+            }
+            day = date.succ_opt();
+        }
+        periods
+    }
+}
+
+/// What to find meeting slots for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlotRequest {
+    /// The users who have to attend, besides the requesting user.
+    pub required: Vec<Uuid>,
+    /// The users who should attend if they can.
+    pub optional: Vec<Uuid>,
+    /// How long the meeting lasts.
+    pub duration_minutes: i64,
+    pub from: DateTime<Utc>,
+    /// The end of the searched range, exclusively.
+    pub to: DateTime<Utc>,
+    /// How many slots to propose.
+    pub count: usize,
+}
+
+/// A proposed time for a meeting all required attendees can attend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Slot {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// The optional attendees who can attend as well.
+    pub optional_attendees: Vec<Uuid>,
+}
+
+/// Someone a meeting is planned with, with the times they can't meet at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attendee {
+    pub user_uuid: Uuid,
+    /// Whether the meeting may take place without the attendee.
+    pub optional: bool,
+    /// When the attendee is busy, tentatively or not.
+    pub busy: Vec<BusyPeriod>,
+    /// The periods within the working hours of the attendee, None if they can meet at any time.
+    pub working: Option<Vec<(DateTime<Utc>, DateTime<Utc>)>>,
+    /// The time kept free between a meeting and the other events of the attendee.
+    pub buffer: TimeDelta,
+}
+
+impl Attendee {
+    /// An attendee with the working hours and buffer of a user, searched within `from..to`.
+    /// Working hours are read in the zone of the user, or in UTC if they have none.
+    ///
+    /// # Arguments
+    ///
+    /// * `busy` - When the user is busy, see `FreeBusy::of`.
+    pub fn of(
+        user: &User,
+        optional: bool,
+        busy: Vec<BusyPeriod>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            user_uuid: user.get_uuid(),
+            optional,
+            busy,
+            working: user
+                .working_hours()
+                .map(|hours| hours.periods(user.display_zone(), from, to)),
+            buffer: TimeDelta::minutes(user.get_meeting_buffer().max(0) as i64),
+        }
+    }
+
+    /// Whether the attendee can meet from `starts_at` until `ends_at`.
+    fn can_meet(&self, starts_at: DateTime<Utc>, ends_at: DateTime<Utc>) -> bool {
+        let working = self.working.as_ref().is_none_or(|periods| {
+            periods
+                .iter()
+                .any(|(from, to)| *from <= starts_at && ends_at <= *to)
+        });
+        working
+            && !self.busy.iter().any(|period| {
+                period.starts_at < ends_at + self.buffer && starts_at - self.buffer < period.ends_at
+            })
+    }
+}
+
+/// Proposes up to `count` slots within `from..to` all required attendees can meet in. They
+/// start on a grid of `SLOT_STEP_MINUTES` and don't overlap each other.
+///
+/// Slots more optional attendees can attend rank first, earlier slots before later ones
+/// otherwise. Optional attendees never rule out a slot.
+pub fn find_slots(
+    attendees: &[Attendee],
+    duration: TimeDelta,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    count: usize,
+) -> Vec<Slot> {
+    let step = TimeDelta::minutes(SLOT_STEP_MINUTES);
+    let Some(mut starts_at) = round_up(from, step) else {
+        return vec![];
+    };
+
+    let mut candidates = vec![];
+    while starts_at + duration <= to {
+        let ends_at = starts_at + duration;
+        if attendees
+            .iter()
+            .filter(|attendee| !attendee.optional)
+            .all(|attendee| attendee.can_meet(starts_at, ends_at))
+        {
+            candidates.push(Slot {
+                starts_at,
+                ends_at,
+                optional_attendees: attendees
+                    .iter()
+                    .filter(|attendee| attendee.optional && attendee.can_meet(starts_at, ends_at))
+                    .map(|attendee| attendee.user_uuid)
+                    .collect(),
+            });
+        }
+        starts_at += step;
+    }
+    // Stable, so earlier slots stay first among equally attended ones
+    candidates.sort_by_key(|slot| Reverse(slot.optional_attendees.len()));
+
+    let mut slots: Vec<Slot> = vec![];
+    for candidate in candidates {
+        if slots.len() >= count {
+            break;
+        }
+        if slots.iter().all(|slot| {
+            slot.ends_at <= candidate.starts_at || candidate.ends_at <= slot.starts_at
+        }) {
+            slots.push(candidate);
+        }
+    }
+    slots
+}
+
+/// The first multiple of `step` since the epoch at or after `time`.
+fn round_up(time: DateTime<Utc>, step: TimeDelta) -> Option<DateTime<Utc>> {
+    let step = step.num_seconds();
+    let seconds = time.timestamp() + i64::from(time.timestamp_subsec_nanos() > 0);
+    DateTime::from_timestamp(seconds + (step - seconds.rem_euclid(step)) % step, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::free_busy::BusyType;
+    use chrono::TimeZone;
+
+    /// A time on Wednesday, the 1st of May 2024.
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, minute, 0).unwrap()
+    }
+
+    fn attendee(optional: bool, busy: &[(u32, u32)]) -> Attendee {
+        Attendee {
+            user_uuid: Uuid::new_v4(),
+            optional,
+            busy: busy
+                .iter()
+                .map(|(from, to)| BusyPeriod {
+                    starts_at: at(*from, 0),
+                    ends_at: at(*to, 0),
+                    busy_type: BusyType::BUSY,
+                })
+                .collect(),
+            working: None,
+            buffer: TimeDelta::zero(),
+        }
+    }
+
+    fn starts(slots: &[Slot]) -> Vec<DateTime<Utc>> {
+        slots.iter().map(|slot| slot.starts_at).collect()
+    }
+
+    #[test]
+    fn test_working_hours() {
+        let hours = WorkingHours::from_minutes(9 * 60, 17 * 60, None).unwrap();
+        assert_eq!(hours.days_value(), "MO,TU,WE,TH,FR");
+        assert_eq!(
+            WorkingHours::from_minutes(hours.starts_minutes(), hours.ends_minutes(), Some("mo, FR")),
+            Some(WorkingHours {
+                days: vec![Weekday::Mon, Weekday::Fri],
+                ..hours.clone()
+            })
+        );
+        assert_eq!(WorkingHours::from_minutes(9 * 60, 9 * 60, None), None);
+        assert_eq!(WorkingHours::from_minutes(-60, 9 * 60, None), None);
+        assert_eq!(WorkingHours::from_minutes(9 * 60, 17 * 60, Some("XX")), None);
+
+        // From Wednesday to Monday, in summer time
+        let berlin = zone::parse_zone("Europe/Berlin");
+        let periods = hours.periods(berlin, at(10, 0), at(10, 0) + TimeDelta::days(5));
+        assert_eq!(
+            periods,
+            vec![
+                (at(10, 0), at(15, 0)),
+                (at(7, 0) + TimeDelta::days(1), at(15, 0) + TimeDelta::days(1)),
+                (at(7, 0) + TimeDelta::days(2), at(15, 0) + TimeDelta::days(2)),
+                (at(7, 0) + TimeDelta::days(5), at(10, 0) + TimeDelta::days(5)),
+            ]
+        );
+
+        let night = WorkingHours::from_minutes(22 * 60, 6 * 60, Some("TU")).unwrap();
+        assert_eq!(
+            night.periods(None, at(0, 0), at(12, 0)),
+            vec![(at(0, 0), at(6, 0))]
+        );
+    }
+
+    #[test]
+    fn test_find_slots() {
+        let hour = TimeDelta::hours(1);
+        let required = attendee(false, &[(9, 10), (12, 13)]);
+        let other = attendee(false, &[(10, 11)]);
+        let slots = find_slots(&[required.clone(), other], hour, at(8, 50), at(15, 0), 3);
+        assert_eq!(starts(&slots), vec![at(11, 0), at(13, 0), at(14, 0)]);
+
+        // An optional attendee free later on moves that slot up, but doesn't block any
+        let optional = attendee(true, &[(9, 12), (14, 15)]);
+        let slots = find_slots(
+            &[required.clone(), optional.clone()],
+            hour,
+            at(10, 0),
+            at(15, 0),
+            2,
+        );
+        assert_eq!(starts(&slots), vec![at(13, 0), at(10, 0)]);
+        assert_eq!(slots[0].optional_attendees, vec![optional.user_uuid]);
+        assert!(slots[1].optional_attendees.is_empty());
+
+        let mut buffered = required.clone();
+        buffered.buffer = TimeDelta::minutes(15);
+        let slots = find_slots(&[buffered], hour, at(10, 0), at(12, 0), 5);
+        assert_eq!(starts(&slots), vec![at(10, 15)]);
+
+        let mut working = required;
+        working.working = Some(vec![(at(13, 30), at(17, 0))]);
+        let slots = find_slots(&[working], TimeDelta::minutes(90), at(8, 0), at(18, 0), 5);
+        assert_eq!(starts(&slots), vec![at(13, 30), at(15, 0)]);
+    }
+}
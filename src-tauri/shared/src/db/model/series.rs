@@ -0,0 +1,1432 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use pg_driver::{AsyncPgDriver, PgDriver, Query};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::async_db_actions::AsyncDbActions;
+use crate::db::db_actions::DbActions;
+use crate::db::model::component::{Component, ComponentType};
+use crate::db::model::property::{OwnerType, Property};
+use crate::db::model::simple::simple_component::{self, SimpleComponent};
+use crate::db::repository::component_repository::{AsyncComponentRepository, ComponentRepository};
+use crate::db::repository::property_repository::{AsyncPropertyRepository, PropertyRepository};
+use crate::errors::error_messages::OCCURRENCE_NOT_FOUND_ERR;
+use crate::ical::export::TIME_PROPERTIES;
+use crate::ical::parser;
+use crate::ical::recurrence::{Recurrence, RecurrenceRule};
+use crate::ical::zone;
+
+/// The properties making a component recur. Overrides only replace a single occurrence, so
+/// they don't get them.
+const RECURRENCE_PROPERTIES: &[&str] = &["RRULE", "RDATE", "EXDATE"];
+
+/// The properties tying the components of a series together, edits can't set them.
+const IDENTIFYING_PROPERTIES: &[&str] = &["UID", "RECURRENCE-ID"];
+
+/// Which occurrences of a recurring component an edit applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EditScope {
+    /// Only the edited occurrence.
+    THIS,
+    /// The edited occurrence and all following ones. The series is split in two.
+    FOLLOWING,
+    /// All occurrences.
+    ALL,
+}
+
+/// The changes to an occurrence of a component.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComponentChanges {
+    /// The new start of the occurrence. The other edited occurrences are moved by as much.
+    pub starts_at: Option<DateTime<Utc>>,
+    /// The new end of the occurrence. It keeps its duration if None.
+    pub ends_at: Option<DateTime<Utc>>,
+    /// The properties to set, like SUMMARY, replacing the ones with the same key. An empty value
+    /// removes the property. Times, UIDs and the recurrence are left out, the edit sets them.
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+/// A recurring component along with the components overriding some of its occurrences. A
+/// component that doesn't recur is a series with a single occurrence.
+#[derive(Clone)]
+pub struct Series {
+    pub master: Component,
+    pub properties: Vec<Property>,
+    pub overrides: Vec<(Component, Vec<Property>)>,
+}
+
+/// What to store for an edit of a series, see `Series::edit`.
+#[derive(Clone, Default)]
+pub struct SeriesChanges {
+    /// New components, the ones overridden by others come first.
+    pub stored: Vec<Component>,
+    pub updated: Vec<Component>,
+    /// Components to remove, overrides before the components they override. Their properties
+    /// are among the `removed_properties`.
+    pub removed: Vec<Component>,
+    pub stored_properties: Vec<Property>,
+    pub removed_properties: Vec<Property>,
+}
+
+impl Series {
+    /// Builds the series of a component that overrides no other one, with its properties and
+    /// overrides.
+    pub fn build(driver: &mut PgDriver, master: Component) -> Self {
+        let overrides = ComponentRepository::retrieve_overrides(driver, &[master.uuid]);
+        let mut components = vec![master];
+        components.extend(overrides);
+        let properties = PropertyRepository::retrieve(
+            driver,
+            Query::from(simple_component::owned_by(&components)),
+        );
+        Self::assemble(components, properties)
+    }
+
+    /// The async counterpart of `build`.
+    pub async fn build_async(driver: &mut AsyncPgDriver, master: Component) -> Self {
+        let overrides = AsyncComponentRepository::retrieve_overrides(driver, &[master.uuid]).await;
+        let mut components = vec![master];
+        components.extend(overrides);
+        let properties = AsyncPropertyRepository::retrieve(
+            driver,
+            Query::from(simple_component::owned_by(&components)),
+        )
+        .await;
+        Self::assemble(components, properties)
+    }
+
+    /// Pairs the master and its overrides, the first of the components, with their properties.
+    fn assemble(components: Vec<Component>, properties: Vec<Property>) -> Self {
+        let mut components = components.into_iter().map(|component| {
+            let owned = properties
+                .iter()
+                .filter(|property| property.get_owner_uuid() == component.uuid)
+                .cloned()
+                .collect::<Vec<_>>();
+            (component, owned)
+        });
+        let (master, properties) = components.next().unwrap();
+        Self {
+            master,
+            properties,
+            overrides: components.collect(),
+        }
+    }
+
+    /// The master and its overrides along with their properties.
+    pub fn components(&self) -> Vec<SimpleComponent> {
+        let mut components = vec![SimpleComponent::new(
+            self.master.clone(),
+            self.properties.clone(),
+        )];
+        components.extend(self.overrides.iter().map(|(component, properties)| {
+            SimpleComponent::new(component.clone(), properties.clone())
+        }));
+        components
+    }
+
+    /// Plans an edit of occurrences of this series.
+    ///
+    /// Editing a single occurrence creates an override for it, or changes the existing one.
+    /// Editing all occurrences moves them all by as much as the edited one, along with their
+    /// overrides. Editing the following occurrences ends this series before the edited one and
+    /// starts a new series with it, taking the overrides along.
+    ///
+    /// # Arguments
+    ///
+    /// * `recurrence_id` - The start of the edited occurrence in the series, see
+    ///   `SimpleComponent::get_recurrence_id`. Ignored if the series doesn't recur.
+    /// * `scope` - Which occurrences to edit. Components that don't recur are edited as a whole.
+    /// * `changes` - The changes to the edited occurrence.
+    ///
+    /// # Errors
+    /// `OCCURRENCE_NOT_FOUND_ERR` if the series has no occurrence at `recurrence_id`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let changes = ComponentChanges {
+    ///     starts_at: Some(thursday_at_ten),
+    ///     ..Default::default()
+    /// };
+    /// series
+    ///     .edit(wednesday_at_ten, EditScope::THIS, &changes)?
+    ///     .apply(driver)?;
+    /// ```
+    pub fn edit(
+        &self,
+        recurrence_id: DateTime<Utc>,
+        scope: EditScope,
+        changes: &ComponentChanges,
+    ) -> Result<SeriesChanges, &'static str> {
+        let mut planned = SeriesChanges::default();
+        match (self.recurs_at(recurrence_id)?, scope) {
+            (false, _) => self.edit_all(self.master.starts_at, changes, &mut planned),
+            (true, EditScope::THIS) => self.edit_this(recurrence_id, changes, &mut planned),
+            (true, EditScope::FOLLOWING) if self.master.starts_at != Some(recurrence_id) => {
+                self.truncate(recurrence_id, &mut planned);
+                self.edit_following(recurrence_id, changes, &mut planned);
+            }
+            (true, _) => self.edit_all(Some(recurrence_id), changes, &mut planned),
+        }
+        Ok(planned)
+    }
+
+    /// Plans the cancellation of occurrences of this series. A single occurrence is excluded
+    /// with an EXDATE, following ones by ending the series before them. Cancelling all of
+    /// them removes the series.
+    ///
+    /// # Arguments
+    ///
+    /// * `recurrence_id` - The start of the cancelled occurrence in the series. Ignored if the
+    ///   series doesn't recur.
+    /// * `scope` - Which occurrences to cancel. Components that don't recur are removed.
+    ///
+    /// # Errors
+    /// `OCCURRENCE_NOT_FOUND_ERR` if the series has no occurrence at `recurrence_id`.
+    pub fn cancel(
+        &self,
+        recurrence_id: DateTime<Utc>,
+        scope: EditScope,
+    ) -> Result<SeriesChanges, &'static str> {
+        let mut planned = SeriesChanges::default();
+        match (self.recurs_at(recurrence_id)?, scope) {
+            (true, EditScope::THIS) => self.exclude(recurrence_id, &mut planned),
+            (true, EditScope::FOLLOWING) if self.master.starts_at != Some(recurrence_id) => {
+                self.truncate(recurrence_id, &mut planned);
+                for (component, properties) in self.overrides_from(recurrence_id) {
+                    planned.remove(component, properties);
+                }
+            }
+            _ => {
+                for (component, properties) in &self.overrides {
+                    planned.remove(component, properties);
+                }
+                planned.remove(&self.master, &self.properties);
+            }
+        }
+        Ok(planned)
+    }
+
+    /// Whether the series recurs, checking that it occurs at the given time if it does.
+    pub(crate) fn recurs_at(&self, recurrence_id: DateTime<Utc>) -> Result<bool, &'static str> {
+        let (Some(starts_at), Ok(Some(recurrence))) =
+            (self.master.starts_at, Recurrence::of(&self.properties))
+        else {
+            return Ok(false);
+        };
+
+        let overridden = self
+            .overrides
+            .iter()
+            .any(|(component, _)| component.recurrence_id == Some(recurrence_id));
+        let occurs = recurrence
+            .starts_in(
+                starts_at,
+                self.duration(),
+                recurrence_id,
+                recurrence_id + TimeDelta::seconds(1),
+            )
+            .contains(&recurrence_id);
+        match overridden || occurs {
+            true => Ok(true),
+            false => Err(OCCURRENCE_NOT_FOUND_ERR),
+        }
+    }
+
+    /// How long each occurrence lasts.
+    fn duration(&self) -> TimeDelta {
+        match (self.master.starts_at, self.master.ends_at) {
+            (Some(starts_at), Some(ends_at)) => ends_at - starts_at,
+            _ => TimeDelta::zero(),
+        }
+    }
+
+    /// The UID shared by the components of the series.
+    fn uid(&self) -> String {
+        value(&self.properties, "UID").unwrap_or_else(|| self.master.uuid.to_string())
+    }
+
+    /// The overrides of the occurrences from the given one on.
+    fn overrides_from(
+        &self,
+        recurrence_id: DateTime<Utc>,
+    ) -> impl Iterator<Item = &(Component, Vec<Property>)> {
+        self.overrides
+            .iter()
+            .filter(move |(component, _)| component.recurrence_id >= Some(recurrence_id))
+    }
+
+    /// Overrides a single occurrence, or changes its override.
+    fn edit_this(
+        &self,
+        recurrence_id: DateTime<Utc>,
+        changes: &ComponentChanges,
+        planned: &mut SeriesChanges,
+    ) {
+        let existing = self
+            .overrides
+            .iter()
+            .find(|(component, _)| component.recurrence_id == Some(recurrence_id));
+        if let Some((component, properties)) = existing {
+            let (starts_at, ends_at) = moved(
+                component.starts_at.unwrap_or(recurrence_id),
+                component.ends_at.unwrap_or(recurrence_id),
+                changes,
+            );
+            let edited = component.clone().with_times(starts_at, Some(ends_at));
+            let mut edited_properties = properties.clone();
+            if (edited.starts_at, edited.ends_at) != (component.starts_at, component.ends_at) {
+                set_times(&mut edited_properties, &edited);
+            }
+            set_properties(&mut edited_properties, edited.uuid, changes);
+            planned.update(edited, properties, &edited_properties);
+            return;
+        }
+
+        let (starts_at, ends_at) = moved(recurrence_id, recurrence_id + self.duration(), changes);
+        let mut component = Component::new(self.master.c_type.clone(), self.master.calendar_uuid)
+            .with_times(starts_at, Some(ends_at))
+            .overriding(self.master.uuid, recurrence_id);
+        component.time_zone = self.master.time_zone.clone();
+        let mut properties = self
+            .properties
+            .iter()
+            .filter(|property| {
+                !has_key(property, RECURRENCE_PROPERTIES)
+                    && !has_key(property, IDENTIFYING_PROPERTIES)
+            })
+            .map(|property| copy(property, component.uuid))
+            .collect::<Vec<_>>();
+        properties.push(Property::new(
+            component.uuid,
+            OwnerType::COMPONENT,
+            String::from("UID"),
+            self.uid(),
+        ));
+        properties.push(time_property(
+            component.uuid,
+            "RECURRENCE-ID",
+            recurrence_id,
+            is_date(&self.properties),
+            self.master.zone(),
+        ));
+        set_times(&mut properties, &component);
+        set_properties(&mut properties, component.uuid, changes);
+
+        planned.stored.push(component);
+        planned.stored_properties.extend(properties);
+    }
+
+    /// Moves all occurrences by as much as the edited one, keeping their overrides. They are
+    /// moved in wall-clock time, so they keep their time of day when the clocks change.
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - The start of the edited occurrence, None if the series has no times.
+    fn edit_all(
+        &self,
+        reference: Option<DateTime<Utc>>,
+        changes: &ComponentChanges,
+        planned: &mut SeriesChanges,
+    ) {
+        let mut master = self.master.clone();
+        let mut properties = self.properties.clone();
+        let zone = self.master.zone();
+        let delta = match (master.starts_at, reference) {
+            (Some(starts_at), Some(reference)) => {
+                let (occurrence_starts_at, occurrence_ends_at) =
+                    moved(reference, reference + self.duration(), changes);
+                let delta = wall_clock_delta(reference, occurrence_starts_at, zone);
+                let duration = occurrence_ends_at - occurrence_starts_at;
+                let starts_at = shift_time(starts_at, delta, zone);
+                master = master.with_times(starts_at, Some(starts_at + duration));
+                delta
+            }
+            _ => {
+                if let Some(starts_at) = changes.starts_at {
+                    master = master.with_times(starts_at, changes.ends_at);
+                }
+                TimeDelta::zero()
+            }
+        };
+
+        if (master.starts_at, master.ends_at) != (self.master.starts_at, self.master.ends_at) {
+            set_times(&mut properties, &master);
+        }
+        shift_dates(&mut properties, delta, zone);
+        set_properties(&mut properties, master.uuid, changes);
+        refresh_series_end(&mut master, &properties);
+
+        let uid = self.uid();
+        for (component, component_properties) in &self.overrides {
+            planned.move_override(
+                component,
+                component_properties,
+                (master.uuid, &uid),
+                delta,
+                zone,
+            );
+        }
+        planned.update(master, &self.properties, &properties);
+    }
+
+    /// Starts a new series with the edited occurrence, taking along the following occurrences
+    /// and their overrides. The series has to be truncated before, see `truncate`.
+    fn edit_following(
+        &self,
+        recurrence_id: DateTime<Utc>,
+        changes: &ComponentChanges,
+        planned: &mut SeriesChanges,
+    ) {
+        let (starts_at, ends_at) = moved(recurrence_id, recurrence_id + self.duration(), changes);
+        let zone = self.master.zone();
+        let delta = wall_clock_delta(recurrence_id, starts_at, zone);
+        let mut master = Component::new(self.master.c_type.clone(), self.master.calendar_uuid)
+            .with_times(starts_at, Some(ends_at));
+        master.time_zone = self.master.time_zone.clone();
+        let uid = master.uuid.to_string();
+
+        let mut properties = self
+            .properties
+            .iter()
+            .filter(|property| !has_key(property, IDENTIFYING_PROPERTIES))
+            .map(|property| copy(property, master.uuid))
+            .collect::<Vec<_>>();
+        properties.push(Property::new(
+            master.uuid,
+            OwnerType::COMPONENT,
+            String::from("UID"),
+            uid.clone(),
+        ));
+        // The occurrences before the edited one stay with the old series
+        let dtstart = self.master.starts_at.unwrap_or(recurrence_id);
+        rewrite(&mut properties, "RRULE", |property| {
+            let value = property.get_val();
+            let count = RecurrenceRule::parse(value).map(|rule| {
+                let before = rule
+                    .starts_in_zone(dtstart, zone)
+                    .take_while(|start| *start < recurrence_id)
+                    .count() as u32;
+                rule.count.map(|count| count.saturating_sub(before))
+            });
+            match count {
+                Ok(Some(0)) => None,
+                Ok(Some(remaining)) => {
+                    Some(with_rule_part(value, "COUNT", Some(&remaining.to_string())))
+                }
+                _ => Some(value.to_string()),
+            }
+        });
+        for key in ["RDATE", "EXDATE"] {
+            rewrite(&mut properties, key, |dates| {
+                map_dates(
+                    dates,
+                    |start| start >= recurrence_id,
+                    TimeDelta::zero(),
+                    zone,
+                )
+            });
+        }
+        shift_dates(&mut properties, delta, zone);
+        set_times(&mut properties, &master);
+        set_properties(&mut properties, master.uuid, changes);
+        refresh_series_end(&mut master, &properties);
+
+        for (component, component_properties) in self.overrides_from(recurrence_id) {
+            planned.move_override(
+                component,
+                component_properties,
+                (master.uuid, &uid),
+                delta,
+                zone,
+            );
+        }
+        planned.stored.insert(0, master);
+        planned.stored_properties.extend(properties);
+    }
+
+    /// Ends the series before the given occurrence. The overrides of the following occurrences
+    /// are kept.
+    fn truncate(&self, recurrence_id: DateTime<Utc>, planned: &mut SeriesChanges) {
+        let mut master = self.master.clone();
+        let mut properties = self.properties.clone();
+        let dtstart = master.starts_at.unwrap_or(recurrence_id);
+        let zone = self.master.zone();
+        // UNTIL is in UTC even for series in a zone
+        let until = match is_date(&self.properties) {
+            true => format_time(recurrence_id - TimeDelta::days(1), true, None),
+            false => format_time(recurrence_id - TimeDelta::seconds(1), false, None),
+        };
+
+        rewrite(&mut properties, "RRULE", |property| {
+            let value = property.get_val();
+            // Rules over before the occurrence, like ones of extra dates, are kept as they are
+            let ends_before = RecurrenceRule::parse(value).map_or(true, |rule| {
+                rule.starts_in_zone(dtstart, zone)
+                    .all(|start| start < recurrence_id)
+            });
+            match ends_before {
+                true => Some(value.to_string()),
+                false => Some(with_rule_part(
+                    &with_rule_part(value, "COUNT", None),
+                    "UNTIL",
+                    Some(&until),
+                )),
+            }
+        });
+        for key in ["RDATE", "EXDATE"] {
+            rewrite(&mut properties, key, |dates| {
+                map_dates(
+                    dates,
+                    |start| start < recurrence_id,
+                    TimeDelta::zero(),
+                    zone,
+                )
+            });
+        }
+        refresh_series_end(&mut master, &properties);
+        planned.update(master, &self.properties, &properties);
+    }
+
+    /// Excludes a single occurrence with an EXDATE, removing its override.
+    fn exclude(&self, recurrence_id: DateTime<Utc>, planned: &mut SeriesChanges) {
+        let mut master = self.master.clone();
+        let mut properties = self.properties.clone();
+        properties.push(time_property(
+            master.uuid,
+            "EXDATE",
+            recurrence_id,
+            is_date(&self.properties),
+            self.master.zone(),
+        ));
+        refresh_series_end(&mut master, &properties);
+        planned.update(master, &self.properties, &properties);
+
+        let existing = self
+            .overrides
+            .iter()
+            .find(|(component, _)| component.recurrence_id == Some(recurrence_id));
+        if let Some((component, properties)) = existing {
+            planned.remove(component, properties);
+        }
+    }
+}
+
+impl SeriesChanges {
+    /// Stores the changes, all or nothing.
+    pub fn apply(&self, driver: &mut PgDriver) -> anyhow::Result<()> {
+        driver.transaction(|driver| -> anyhow::Result<()> {
+            for component in &self.stored {
+                ComponentRepository::store(driver, component)?;
+            }
+            for property in &self.stored_properties {
+                PropertyRepository::store(driver, property)?;
+            }
+            for component in &self.updated {
+                ComponentRepository::update(driver, component)?;
+            }
+            for property in &self.removed_properties {
+                PropertyRepository::remove(driver, property)?;
+            }
+            for component in &self.removed {
+                ComponentRepository::remove(driver, component)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// The async counterpart of `apply`.
+    pub async fn apply_async(&self, driver: &mut AsyncPgDriver) -> anyhow::Result<()> {
+        driver
+            .transaction(async |driver| -> anyhow::Result<()> {
+                for component in &self.stored {
+                    AsyncComponentRepository::store(driver, component).await?;
+                }
+                for property in &self.stored_properties {
+                    AsyncPropertyRepository::store(driver, property).await?;
+                }
+                for component in &self.updated {
+                    AsyncComponentRepository::update(driver, component).await?;
+                }
+                for property in &self.removed_properties {
+                    AsyncPropertyRepository::remove(driver, property).await?;
+                }
+                for component in &self.removed {
+                    AsyncComponentRepository::remove(driver, component).await?;
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    /// The components of a series as they would be after the changes, the stored ones
+    /// included, without storing anything.
+    pub fn applied_to(&self, series: &Series) -> Vec<SimpleComponent> {
+        let existing = std::iter::once((&series.master, &series.properties[..]))
+            .chain(
+                series
+                    .overrides
+                    .iter()
+                    .map(|(component, properties)| (component, &properties[..])),
+            )
+            .filter(|(component, _)| !self.removed.iter().any(|c| c.uuid == component.uuid))
+            .map(|(component, properties)| {
+                let component = self
+                    .updated
+                    .iter()
+                    .find(|updated| updated.uuid == component.uuid)
+                    .unwrap_or(component);
+                let properties = properties
+                    .iter()
+                    .filter(|property| !self.removed_properties.contains(property))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (component, properties)
+            });
+        let stored = self.stored.iter().map(|component| (component, vec![]));
+
+        existing
+            .chain(stored)
+            .map(|(component, mut properties)| {
+                properties.extend(
+                    self.stored_properties
+                        .iter()
+                        .filter(|property| property.get_owner_uuid() == component.uuid)
+                        .cloned(),
+                );
+                SimpleComponent::new(component.clone(), properties)
+            })
+            .collect()
+    }
+
+    /// Updates a component, storing its new properties and removing the ones it lost.
+    fn update(&mut self, component: Component, properties: &[Property], updated: &[Property]) {
+        self.updated.push(component);
+        self.removed_properties.extend(
+            properties
+                .iter()
+                .filter(|property| !updated.contains(property))
+                .cloned(),
+        );
+        self.stored_properties.extend(
+            updated
+                .iter()
+                .filter(|property| !properties.contains(property))
+                .cloned(),
+        );
+    }
+
+    fn remove(&mut self, component: &Component, properties: &[Property]) {
+        self.removed.push(component.clone());
+        self.removed_properties.extend(properties.iter().cloned());
+    }
+
+    /// Makes an override override the occurrence of the given series, moved by `delta` in
+    /// wall-clock time, see `shift_time`.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - The uuid and UID of the series.
+    /// * `zone` - The zone of the series.
+    fn move_override(
+        &mut self,
+        component: &Component,
+        properties: &[Property],
+        (master_uuid, uid): (Uuid, &str),
+        delta: TimeDelta,
+        zone: Option<Tz>,
+    ) {
+        let Some(recurrence_id) = component.recurrence_id else {
+            return;
+        };
+        let moved = component
+            .clone()
+            .overriding(master_uuid, shift_time(recurrence_id, delta, zone));
+        let mut moved_properties = properties.to_vec();
+        rewrite(&mut moved_properties, "UID", |_| Some(uid.to_string()));
+        rewrite(&mut moved_properties, "RECURRENCE-ID", |property| {
+            Some(shift(property.get_val(), delta, tzid_of(property), zone))
+        });
+        self.update(moved, properties, &moved_properties);
+    }
+}
+
+/// The times of an occurrence after the changes.
+fn moved(
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    changes: &ComponentChanges,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let new_starts_at = changes.starts_at.unwrap_or(starts_at);
+    let new_ends_at = changes
+        .ends_at
+        .unwrap_or(new_starts_at + (ends_at - starts_at));
+    (new_starts_at, new_ends_at.max(new_starts_at))
+}
+
+fn has_key(property: &Property, keys: &[&str]) -> bool {
+    keys.iter()
+        .any(|key| property.get_key().eq_ignore_ascii_case(key))
+}
+
+fn value(properties: &[Property], key: &str) -> Option<String> {
+    properties
+        .iter()
+        .find(|property| has_key(property, &[key]))
+        .map(|property| property.get_val().clone())
+}
+
+/// Whether the times of a component are whole days, `VALUE=DATE`.
+fn is_date(properties: &[Property]) -> bool {
+    properties.iter().any(|property| {
+        has_key(property, &["DTSTART", "DUE"])
+            && property
+                .param("VALUE")
+                .is_some_and(|value| value.eq_ignore_ascii_case("DATE"))
+    })
+}
+
+/// The zone of the TZID of a property, None if it has none or it is unknown.
+fn tzid_of(property: &Property) -> Option<Tz> {
+    property
+        .param("TZID")
+        .and_then(|tzid| zone::parse_zone(&tzid))
+}
+
+/// Formats a time as a DATE value, a wall-clock DATE-TIME value in the given zone, or a UTC
+/// DATE-TIME value without one.
+fn format_time(time: DateTime<Utc>, is_date: bool, zone: Option<Tz>) -> String {
+    match (is_date, zone) {
+        (true, _) => time.format("%Y%m%d").to_string(),
+        (false, Some(zone)) => zone::format_local(time, zone),
+        (false, None) => parser::format_date_time(time),
+    }
+}
+
+/// Formats a time like the given DATE or DATE-TIME value was: as a DATE, in UTC, in the zone of
+/// its TZID, or floating.
+fn format_like(value: &str, time: DateTime<Utc>, tzid: Option<Tz>) -> String {
+    match (value.len() == 8, value.ends_with(['Z', 'z']), tzid) {
+        (true, _, _) => format_time(time, true, None),
+        (false, true, _) => format_time(time, false, None),
+        (false, false, Some(tzid)) => format_time(time, false, Some(tzid)),
+        (false, false, None) => time.format("%Y%m%dT%H%M%S").to_string(),
+    }
+}
+
+/// A property with a DATE or DATE-TIME value, with a TZID if it is given in a zone.
+pub(crate) fn time_property(
+    owner_uuid: Uuid,
+    key: &str,
+    time: DateTime<Utc>,
+    is_date: bool,
+    zone: Option<Tz>,
+) -> Property {
+    let property = Property::new(
+        owner_uuid,
+        OwnerType::COMPONENT,
+        key.to_string(),
+        format_time(time, is_date, zone),
+    );
+    match (is_date, zone) {
+        (true, _) => property.with_params(String::from("VALUE=DATE")),
+        (false, Some(zone)) => property.with_params(format!("TZID={}", zone.name())),
+        (false, None) => property,
+    }
+}
+
+/// Copies a property to another component.
+fn copy(property: &Property, owner_uuid: Uuid) -> Property {
+    Property::new(
+        owner_uuid,
+        OwnerType::COMPONENT,
+        property.get_key().clone(),
+        property.get_val().clone(),
+    )
+    .with_params(property.get_params().clone())
+}
+
+/// Rewrites the values of the properties with the given key, removing the ones `rewrite`
+/// returns None for. Changed properties are replaced by new ones.
+fn rewrite(
+    properties: &mut Vec<Property>,
+    key: &str,
+    rewrite: impl Fn(&Property) -> Option<String>,
+) {
+    *properties = properties
+        .drain(..)
+        .filter_map(|property| {
+            if !has_key(&property, &[key]) {
+                return Some(property);
+            }
+            match rewrite(&property) {
+                Some(val) if val == *property.get_val() => Some(property),
+                Some(val) => Some(
+                    Property::new(
+                        property.get_owner_uuid(),
+                        property.get_owner_type(),
+                        property.get_key().clone(),
+                        val,
+                    )
+                    .with_params(property.get_params().clone()),
+                ),
+                None => None,
+            }
+        })
+        .collect();
+}
+
+/// Rewrites the stored times of a component after they changed, keeping whether they are
+/// whole days. Components without stored times get them from the component on export.
+fn set_times(properties: &mut Vec<Property>, component: &Component) {
+    let (Some(starts_at), Some(ends_at)) = (component.starts_at, component.ends_at) else {
+        return;
+    };
+    if !properties
+        .iter()
+        .any(|property| has_key(property, TIME_PROPERTIES))
+    {
+        return;
+    }
+    let is_date = is_date(properties);
+    let zone = component.zone();
+    let has = |key: &str| properties.iter().any(|property| has_key(property, &[key]));
+
+    // Whole day events without an end last a day
+    let implicit_end = match (is_date, &component.c_type) {
+        (true, ComponentType::EVENT) => starts_at + TimeDelta::days(1),
+        _ => starts_at,
+    };
+    let end_key = match (has("DUE"), has("DTEND") || has("DURATION")) {
+        (true, _) => Some("DUE"),
+        (false, true) => Some("DTEND"),
+        _ if ends_at == implicit_end => None,
+        _ if component.c_type == ComponentType::TODO => Some("DUE"),
+        _ => Some("DTEND"),
+    };
+    let has_start = has("DTSTART") || end_key != Some("DUE") || starts_at != ends_at;
+
+    let mut times = vec![];
+    if has_start {
+        times.push(time_property(
+            component.uuid,
+            "DTSTART",
+            starts_at,
+            is_date,
+            zone,
+        ));
+    }
+    if let Some(end_key) = end_key {
+        times.push(time_property(
+            component.uuid,
+            end_key,
+            ends_at,
+            is_date,
+            zone,
+        ));
+    }
+    properties.retain(|property| !has_key(property, TIME_PROPERTIES));
+    properties.extend(times);
+}
+
+/// Sets the changed properties, see `ComponentChanges::properties`.
+fn set_properties(properties: &mut Vec<Property>, owner_uuid: Uuid, changes: &ComponentChanges) {
+    for (key, val) in &changes.properties {
+        let key = key.to_uppercase();
+        let reserved = [
+            TIME_PROPERTIES,
+            RECURRENCE_PROPERTIES,
+            IDENTIFYING_PROPERTIES,
+        ];
+        if reserved.iter().any(|keys| keys.contains(&key.as_str())) {
+            continue;
+        }
+        let current = properties
+            .iter()
+            .filter(|property| has_key(property, &[&key]))
+            .collect::<Vec<_>>();
+        if current.len() == 1 && current[0].get_val() == val {
+            continue;
+        }
+
+        properties.retain(|property| !has_key(property, &[&key]));
+        if !val.is_empty() {
+            properties.push(Property::new(
+                owner_uuid,
+                OwnerType::COMPONENT,
+                key,
+                val.clone(),
+            ));
+        }
+    }
+}
+
+/// Sets when the last occurrence of a component ends, see `Component::recurring_until`.
+pub(crate) fn refresh_series_end(component: &mut Component, properties: &[Property]) {
+    component.series_ends_at = match (
+        component.starts_at,
+        component.ends_at,
+        Recurrence::of(properties),
+    ) {
+        (Some(starts_at), Some(ends_at), Ok(Some(recurrence))) => {
+            recurrence.series_end(starts_at, ends_at - starts_at)
+        }
+        _ => component.ends_at,
+    };
+}
+
+/// Moves the extra and excluded dates and the end of the rules of a component by `delta`,
+/// see `shift_time`.
+fn shift_dates(properties: &mut Vec<Property>, delta: TimeDelta, zone: Option<Tz>) {
+    if delta.is_zero() {
+        return;
+    }
+    for key in ["RDATE", "EXDATE"] {
+        rewrite(properties, key, |dates| {
+            map_dates(dates, |_| true, delta, zone)
+        });
+    }
+    rewrite(properties, "RRULE", |property| {
+        let rule = property.get_val();
+        let until = rule.split(';').find_map(|part| part.strip_prefix("UNTIL="));
+        Some(match until {
+            Some(until) => with_rule_part(rule, "UNTIL", Some(&shift(until, delta, None, zone))),
+            None => rule.to_string(),
+        })
+    });
+}
+
+/// Keeps the comma separated times of an RDATE or EXDATE for which `keep` holds, moved by
+/// `delta`. Periods are kept by their start.
+///
+/// # Arguments
+///
+/// * `zone` - The zone of the series, see `shift_time`.
+///
+/// # Returns
+/// None if no time is kept.
+fn map_dates(
+    property: &Property,
+    keep: impl Fn(DateTime<Utc>) -> bool,
+    delta: TimeDelta,
+    zone: Option<Tz>,
+) -> Option<String> {
+    let tzid = tzid_of(property);
+    let dates = property
+        .get_val()
+        .split(',')
+        .filter(|date| {
+            let start = date.split('/').next().unwrap_or(date);
+            zone::parse_date_time_in(start, tzid).map_or(true, |(start, _)| keep(start))
+        })
+        .map(|date| {
+            date.split('/')
+                .map(|part| shift(part, delta, tzid, zone))
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .collect::<Vec<_>>();
+    match dates.is_empty() {
+        true => None,
+        false => Some(dates.join(",")),
+    }
+}
+
+/// Moves a DATE or DATE-TIME value by `delta`, see `shift_time`, keeping its form. Other
+/// values, like durations, are kept.
+///
+/// # Arguments
+///
+/// * `tzid` - The zone of the TZID of the value.
+/// * `zone` - The zone of the series.
+fn shift(value: &str, delta: TimeDelta, tzid: Option<Tz>, zone: Option<Tz>) -> String {
+    match zone::parse_date_time_in(value, tzid) {
+        Ok((time, true)) if !delta.is_zero() => format_like(value, time + delta, tzid),
+        Ok((time, false)) if !delta.is_zero() => {
+            format_like(value, shift_time(time, delta, zone), tzid)
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// Moves a time by `delta` in wall-clock time of the given zone, so it keeps its time of day
+/// when the clocks change in between.
+fn shift_time(time: DateTime<Utc>, delta: TimeDelta, zone: Option<Tz>) -> DateTime<Utc> {
+    match zone {
+        Some(zone) => zone::from_local(zone::to_local(time, zone) + delta, zone),
+        None => time + delta,
+    }
+}
+
+/// How far apart two times are in wall-clock time of the given zone, see `shift_time`.
+fn wall_clock_delta(from: DateTime<Utc>, to: DateTime<Utc>, zone: Option<Tz>) -> TimeDelta {
+    match zone {
+        Some(zone) => zone::to_local(to, zone) - zone::to_local(from, zone),
+        None => to - from,
+    }
+}
+
+/// Sets a part of an RRULE value, like `COUNT=3`, or removes it if None.
+fn with_rule_part(rule: &str, name: &str, value: Option<&str>) -> String {
+    let mut parts = rule
+        .split(';')
+        .filter(|part| {
+            !part.is_empty()
+                && !part
+                    .split_once('=')
+                    .is_some_and(|(part_name, _)| part_name.eq_ignore_ascii_case(name))
+        })
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if let Some(value) = value {
+        parts.push(format!("{}={}", name, value));
+    }
+    parts.join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::Migrations;
+    use crate::db::model::calendar::Calendar;
+    use crate::db::model::simple::simple_component::SimpleComponent;
+    use crate::db::repository::calendar_repository::CalendarRepository;
+    use chrono::TimeZone;
+    use pg_driver::DriverConfig;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    /// A weekly meeting on five Mondays from the 6th of May, 9 to 10.
+    fn weekly(calendar_uuid: Uuid) -> Series {
+        let master = Component::new(ComponentType::EVENT, calendar_uuid)
+            .with_times(at(6, 9), Some(at(6, 10)))
+            .recurring_until(Some(at(6, 10) + TimeDelta::weeks(4)));
+        let property = |key: &str, val: &str| {
+            Property::new(
+                master.uuid,
+                OwnerType::COMPONENT,
+                key.to_string(),
+                val.to_string(),
+            )
+        };
+        let properties = vec![
+            property("UID", "weekly"),
+            property("DTSTART", "20240506T090000Z"),
+            property("DTEND", "20240506T100000Z"),
+            property("RRULE", "FREQ=WEEKLY;COUNT=5"),
+            property("EXDATE", "20240527T090000Z"),
+            property("SUMMARY", "Planning"),
+        ];
+        Series {
+            master,
+            properties,
+            overrides: vec![],
+        }
+    }
+
+    fn values(properties: &[Property], key: &str) -> Vec<String> {
+        properties
+            .iter()
+            .filter(|property| property.get_key() == key)
+            .map(|property| property.get_val().clone())
+            .collect()
+    }
+
+    fn changes(starts_at: Option<DateTime<Utc>>, summary: &str) -> ComponentChanges {
+        ComponentChanges {
+            starts_at,
+            ends_at: None,
+            properties: BTreeMap::from([(String::from("summary"), summary.to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_edit_this() {
+        let mut series = weekly(Uuid::new_v4());
+        assert_eq!(
+            series
+                .edit(at(14, 9), EditScope::THIS, &changes(None, "x"))
+                .err(),
+            Some(OCCURRENCE_NOT_FOUND_ERR)
+        );
+
+        let planned = series
+            .edit(
+                at(13, 9),
+                EditScope::THIS,
+                &changes(Some(at(14, 11)), "Moved"),
+            )
+            .unwrap();
+        assert!(planned.updated.is_empty() && planned.removed.is_empty());
+        let moved = &planned.stored[0];
+        assert_eq!(moved.recurrence_of, Some(series.master.uuid));
+        assert_eq!(moved.recurrence_id, Some(at(13, 9)));
+        assert_eq!(
+            (moved.starts_at, moved.ends_at),
+            (Some(at(14, 11)), Some(at(14, 12)))
+        );
+        let properties = &planned.stored_properties;
+        assert!(properties.iter().all(|p| p.get_owner_uuid() == moved.uuid));
+        assert_eq!(values(properties, "UID"), vec!["weekly"]);
+        assert_eq!(
+            values(properties, "RECURRENCE-ID"),
+            vec!["20240513T090000Z"]
+        );
+        assert_eq!(values(properties, "DTSTART"), vec!["20240514T110000Z"]);
+        assert_eq!(values(properties, "DTEND"), vec!["20240514T120000Z"]);
+        assert_eq!(values(properties, "SUMMARY"), vec!["Moved"]);
+        assert!(values(properties, "RRULE").is_empty() && values(properties, "EXDATE").is_empty());
+
+        // Editing the override again changes it instead of adding another one
+        series.overrides.push((moved.clone(), properties.clone()));
+        let planned = series
+            .edit(at(13, 9), EditScope::THIS, &changes(None, "Moved again"))
+            .unwrap();
+        assert!(planned.stored.is_empty());
+        assert_eq!(planned.updated[0].uuid, moved.uuid);
+        assert_eq!(
+            values(&planned.stored_properties, "SUMMARY"),
+            vec!["Moved again"]
+        );
+        assert_eq!(
+            values(&planned.removed_properties, "SUMMARY"),
+            vec!["Moved"]
+        );
+    }
+
+    #[test]
+    fn test_edit_all() {
+        let mut series = weekly(Uuid::new_v4());
+        let moved = series
+            .edit(
+                at(13, 9),
+                EditScope::THIS,
+                &changes(Some(at(14, 11)), "Moved"),
+            )
+            .unwrap();
+        series
+            .overrides
+            .push((moved.stored[0].clone(), moved.stored_properties));
+
+        let planned = series
+            .edit(
+                at(13, 9),
+                EditScope::ALL,
+                &changes(Some(at(13, 10)), "Review"),
+            )
+            .unwrap();
+        let (master, overridden) = match planned.updated[0].uuid == series.master.uuid {
+            true => (&planned.updated[0], &planned.updated[1]),
+            false => (&planned.updated[1], &planned.updated[0]),
+        };
+        assert_eq!(
+            (master.starts_at, master.ends_at),
+            (Some(at(6, 10)), Some(at(6, 11)))
+        );
+        assert_eq!(master.series_ends_at, Some(at(6, 11) + TimeDelta::weeks(4)));
+        assert_eq!(overridden.recurrence_id, Some(at(13, 10)));
+        let stored = &planned.stored_properties;
+        assert_eq!(values(stored, "DTSTART"), vec!["20240506T100000Z"]);
+        assert!(values(stored, "EXDATE").contains(&String::from("20240527T100000Z")));
+        assert!(values(stored, "RECURRENCE-ID").contains(&String::from("20240513T100000Z")));
+        assert!(values(stored, "SUMMARY").contains(&String::from("Review")));
+        assert!(values(&planned.removed_properties, "RRULE").is_empty());
+    }
+
+    #[test]
+    fn test_applied_to() {
+        let series = weekly(Uuid::new_v4());
+        let planned = series
+            .edit(
+                at(13, 9),
+                EditScope::THIS,
+                &changes(Some(at(14, 11)), "Moved"),
+            )
+            .unwrap();
+        let starts = |components: &[SimpleComponent]| {
+            simple_component::expand(components, at(1, 0), at(1, 0) + TimeDelta::weeks(6))
+                .iter()
+                .map(|occurrence| occurrence.get_starts_at().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            starts(&series.components()),
+            vec![
+                at(6, 9),
+                at(13, 9),
+                at(20, 9),
+                at(6, 9) + TimeDelta::weeks(4)
+            ]
+        );
+        let applied = planned.applied_to(&series);
+        assert_eq!(
+            starts(&applied),
+            vec![
+                at(6, 9),
+                at(14, 11),
+                at(20, 9),
+                at(6, 9) + TimeDelta::weeks(4)
+            ]
+        );
+        assert_eq!(
+            values(applied[1].get_properties(), "SUMMARY"),
+            vec!["Moved"]
+        );
+
+        let planned = series
+            .edit(at(20, 9), EditScope::FOLLOWING, &changes(None, "Retro"))
+            .unwrap();
+        assert_eq!(
+            starts(&planned.applied_to(&series)),
+            vec![
+                at(6, 9),
+                at(13, 9),
+                at(20, 9),
+                at(6, 9) + TimeDelta::weeks(4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edit_following() {
+        let series = weekly(Uuid::new_v4());
+        let planned = series
+            .edit(at(20, 9), EditScope::FOLLOWING, &changes(None, "Retro"))
+            .unwrap();
+
+        let old = &planned.updated[0];
+        assert_eq!(old.uuid, series.master.uuid);
+        assert_eq!(old.series_ends_at, Some(at(13, 10)));
+        let new = &planned.stored[0];
+        assert_eq!(
+            (new.starts_at, new.series_ends_at),
+            (Some(at(20, 9)), Some(at(6, 10) + TimeDelta::weeks(4)))
+        );
+        let owned_by = |uuid: Uuid| {
+            planned
+                .stored_properties
+                .iter()
+                .filter(|p| p.get_owner_uuid() == uuid)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            values(&owned_by(old.uuid), "RRULE"),
+            vec!["FREQ=WEEKLY;UNTIL=20240520T085959Z"]
+        );
+        // The exception lies after the split, it moves to the new series
+        assert_eq!(
+            values(&planned.removed_properties, "EXDATE"),
+            vec!["20240527T090000Z"]
+        );
+        let new_properties = owned_by(new.uuid);
+        assert_eq!(
+            values(&new_properties, "RRULE"),
+            vec!["FREQ=WEEKLY;COUNT=3"]
+        );
+        assert_eq!(values(&new_properties, "EXDATE"), vec!["20240527T090000Z"]);
+        assert_eq!(values(&new_properties, "UID"), vec![new.uuid.to_string()]);
+        assert_eq!(values(&new_properties, "SUMMARY"), vec!["Retro"]);
+
+        // Editing the following occurrences from the first one edits all of them
+        let planned = series
+            .edit(at(6, 9), EditScope::FOLLOWING, &changes(None, "Retro"))
+            .unwrap();
+        assert!(planned.stored.is_empty());
+        assert_eq!(planned.updated[0].uuid, series.master.uuid);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut series = weekly(Uuid::new_v4());
+        let moved = series
+            .edit(
+                at(13, 9),
+                EditScope::THIS,
+                &changes(Some(at(14, 11)), "Moved"),
+            )
+            .unwrap();
+        series
+            .overrides
+            .push((moved.stored[0].clone(), moved.stored_properties));
+
+        let planned = series.cancel(at(13, 9), EditScope::THIS).unwrap();
+        assert_eq!(
+            values(&planned.stored_properties, "EXDATE"),
+            vec!["20240513T090000Z"]
+        );
+        assert_eq!(planned.removed[0].recurrence_id, Some(at(13, 9)));
+
+        let planned = series.cancel(at(20, 9), EditScope::THIS).unwrap();
+        assert!(planned.removed.is_empty());
+        // The last occurrences are cancelled, so the series ends earlier
+        let planned = series.cancel(at(13, 9), EditScope::FOLLOWING).unwrap();
+        assert_eq!(planned.updated[0].series_ends_at, Some(at(6, 10)));
+        assert_eq!(planned.removed.len(), 1);
+
+        let planned = series.cancel(at(6, 9), EditScope::FOLLOWING).unwrap();
+        let removed = planned.removed.iter().map(|c| c.uuid).collect::<Vec<_>>();
+        assert_eq!(
+            removed,
+            vec![series.overrides[0].0.uuid, series.master.uuid]
+        );
+        assert_eq!(
+            planned.removed_properties.len(),
+            series.properties.len() + series.overrides[0].1.len()
+        );
+    }
+
+    #[test]
+    fn test_edit_zoned() {
+        let utc = |month, day, hour| Utc.with_ymd_and_hms(2024, month, day, hour, 0, 0).unwrap();
+        let berlin = zone::parse_zone("Europe/Berlin").unwrap();
+        // Weekly at 9 in Berlin from the 25th of March, the clocks change on the 31st
+        let master = Component::new(ComponentType::EVENT, Uuid::new_v4())
+            .with_times(utc(3, 25, 8), Some(utc(3, 25, 9)))
+            .recurring_until(Some(utc(4, 15, 8)))
+            .in_zone(berlin);
+        let property = |key: &str, val: &str| {
+            Property::new(
+                master.uuid,
+                OwnerType::COMPONENT,
+                key.to_string(),
+                val.to_string(),
+            )
+            .with_params(String::from("TZID=Europe/Berlin"))
+        };
+        let series = Series {
+            properties: vec![
+                property("DTSTART", "20240325T090000"),
+                property("DTEND", "20240325T100000"),
+                Property::new(
+                    master.uuid,
+                    OwnerType::COMPONENT,
+                    String::from("RRULE"),
+                    String::from("FREQ=WEEKLY;COUNT=4"),
+                ),
+                property("EXDATE", "20240408T090000"),
+            ],
+            master,
+            overrides: vec![],
+        };
+
+        let planned = series
+            .edit(utc(4, 1, 7), EditScope::THIS, &changes(None, "Moved"))
+            .unwrap();
+        let recurrence_id = planned
+            .stored_properties
+            .iter()
+            .find(|p| p.get_key() == "RECURRENCE-ID")
+            .unwrap();
+        assert_eq!(recurrence_id.get_val(), "20240401T090000");
+        assert_eq!(recurrence_id.get_params(), "TZID=Europe/Berlin");
+        assert_eq!(
+            planned.stored[0].time_zone.as_deref(),
+            Some("Europe/Berlin")
+        );
+
+        // A day later, still at 9 even though the clocks changed in between
+        let planned = series
+            .edit(
+                utc(4, 1, 7),
+                EditScope::ALL,
+                &changes(Some(utc(4, 2, 7)), "Later"),
+            )
+            .unwrap();
+        assert_eq!(planned.updated[0].starts_at, Some(utc(3, 26, 8)));
+        let stored = &planned.stored_properties;
+        assert_eq!(values(stored, "DTSTART"), vec!["20240326T090000"]);
+        assert_eq!(values(stored, "EXDATE"), vec!["20240409T090000"]);
+        assert!(stored
+            .iter()
+            .all(|p| p.get_key() == "SUMMARY" || p.get_params() == "TZID=Europe/Berlin"));
+
+        let planned = series.cancel(utc(4, 15, 7), EditScope::FOLLOWING).unwrap();
+        assert_eq!(
+            values(&planned.stored_properties, "RRULE"),
+            vec!["FREQ=WEEKLY;UNTIL=20240415T065959Z"]
+        );
+        assert_eq!(planned.updated[0].series_ends_at, Some(utc(4, 1, 8)));
+    }
+
+    #[test]
+    fn test_apply() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+        Migrations::run(&mut driver).unwrap();
+
+        let calendar = Calendar::new(String::from("Work"));
+        CalendarRepository::store(&mut driver, &calendar).unwrap();
+        let series = weekly(calendar.uuid);
+        ComponentRepository::store(&mut driver, &series.master).unwrap();
+        for property in &series.properties {
+            PropertyRepository::store(&mut driver, property).unwrap();
+        }
+        let starts = |driver: &mut PgDriver| {
+            SimpleComponent::build_in_range(driver, &[calendar.uuid], at(1, 0), at(31, 0))
+                .iter()
+                .map(|occurrence| {
+                    (
+                        occurrence.get_starts_at().unwrap(),
+                        occurrence.get_recurrence_id(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        series
+            .edit(
+                at(13, 9),
+                EditScope::THIS,
+                &changes(Some(at(14, 11)), "Moved"),
+            )
+            .unwrap()
+            .apply(&mut driver)
+            .unwrap();
+        assert_eq!(
+            starts(&mut driver),
+            vec![
+                (at(6, 9), Some(at(6, 9))),
+                (at(14, 11), Some(at(13, 9))),
+                (at(20, 9), Some(at(20, 9))),
+            ]
+        );
+
+        let series = Series::build(&mut driver, series.master.clone());
+        assert_eq!(series.overrides.len(), 1);
+        series
+            .edit(
+                at(20, 9),
+                EditScope::FOLLOWING,
+                &changes(Some(at(20, 10)), "Later"),
+            )
+            .unwrap()
+            .apply(&mut driver)
+            .unwrap();
+        assert_eq!(
+            starts(&mut driver),
+            vec![
+                (at(6, 9), Some(at(6, 9))),
+                (at(14, 11), Some(at(13, 9))),
+                (at(20, 10), Some(at(20, 10))),
+            ]
+        );
+
+        let series = Series::build(&mut driver, series.master.clone());
+        series
+            .cancel(at(6, 9), EditScope::ALL)
+            .unwrap()
+            .apply(&mut driver)
+            .unwrap();
+        assert_eq!(starts(&mut driver).len(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,22 +1,29 @@
 use crate::db::{
+    async_db_actions::AsyncDbActions,
     db_actions::DbActions,
     model::{
         calendar::Calendar,
         property::{OwnerType, Property},
-        simple::simple_component::SimpleComponent,
+        simple::simple_component::{self, SimpleComponent},
+        user::User,
     },
     repository::{
-        property_repository::PropertyRepository,
-        user_calendar_combination_repository::UserCalendarCombinationRepository,
+        property_repository::{AsyncPropertyRepository, PropertyRepository},
+        user_calendar_combination_repository::{
+            AsyncUserCalendarCombinationRepository, UserCalendarCombinationRepository,
+        },
     },
 };
-use pg_driver::PgDriver;
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use pg_driver::{AsyncPgDriver, Filter, PgDriver};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use super::simple_user::SimpleUser;
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SimpleCalendar {
+    uuid: Uuid,
     name: String,
     components: Vec<SimpleComponent>,
     properties: Vec<Property>,
@@ -28,6 +35,7 @@ impl SimpleCalendar {
     ///
     /// # Arguments
     ///
+    /// * `uuid` - The uuid of the calendar to convert to.
     /// * `name` - The name of the calendar to convert to.
     /// * `components` - The component dependencies of the calendar.
     /// * `properties` - The properties defining the calendar.
@@ -37,6 +45,7 @@ impl SimpleCalendar {
     ///
     /// ```
     /// let simple_calendar = SimpleCalendar::new(
+    ///     calendar.uuid,
     ///     calendar.get_name(),
     ///     simple_components,
     ///     calenar.get_properties(),
@@ -44,12 +53,14 @@ impl SimpleCalendar {
     /// );
     /// ```
     pub fn new(
+        uuid: Uuid,
         name: String,
         components: Vec<SimpleComponent>,
         properties: Vec<Property>,
         users: Vec<SimpleUser>,
     ) -> Self {
         Self {
+            uuid,
             name,
             components,
             properties,
@@ -69,21 +80,70 @@ impl SimpleCalendar {
     ///
     /// ```
     /// let simple_calendar = SimpleCalendar::build(
-    ///     driver().lock().unwrap().deref_mut(),
+    ///     driver()?.deref_mut(),
     ///     calendar
     /// );
     /// println!("Converted calendar: {}", simple_calendar);
     /// ```
     pub fn build(driver: &mut PgDriver, calendar: Calendar) -> Self {
-        let owned_by_calendar = format!(
-            "owner_type = '{}' and owner_uuid = '{}'",
-            OwnerType::CALENDAR.to_string(),
-            calendar.uuid
-        );
-
         let components = SimpleComponent::build_by_calendar(driver, &calendar);
-        let properties = PropertyRepository::retrieve(driver, Some(owned_by_calendar));
+        let properties = PropertyRepository::retrieve(driver, owned_by(&calendar).into());
+        let users = UserCalendarCombinationRepository::get_users_of_calendar(driver, calendar.uuid);
+
+        Self::assemble(calendar, components, properties, users)
+    }
+
+    /// The async counterpart of `build`.
+    pub async fn build_async(driver: &mut AsyncPgDriver, calendar: Calendar) -> Self {
+        let components = SimpleComponent::build_by_calendar_async(driver, &calendar).await;
+        let properties =
+            AsyncPropertyRepository::retrieve(driver, owned_by(&calendar).into()).await;
+        let users =
+            AsyncUserCalendarCombinationRepository::get_users_of_calendar(driver, calendar.uuid)
+                .await;
+
+        Self::assemble(calendar, components, properties, users)
+    }
+
+    /// Like `build`, but only with the components with occurrences in a time range, see
+    /// `SimpleComponent::build_series_in_range`. Use `occurrences` to expand them.
+    pub fn build_in_range(
+        driver: &mut PgDriver,
+        calendar: Calendar,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Self {
+        let components = SimpleComponent::build_series_in_range(driver, &[calendar.uuid], from, to);
+        let properties = PropertyRepository::retrieve(driver, owned_by(&calendar).into());
         let users = UserCalendarCombinationRepository::get_users_of_calendar(driver, calendar.uuid);
+
+        Self::assemble(calendar, components, properties, users)
+    }
+
+    /// The async counterpart of `build_in_range`.
+    pub async fn build_in_range_async(
+        driver: &mut AsyncPgDriver,
+        calendar: Calendar,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Self {
+        let components =
+            SimpleComponent::build_series_in_range_async(driver, &[calendar.uuid], from, to).await;
+        let properties =
+            AsyncPropertyRepository::retrieve(driver, owned_by(&calendar).into()).await;
+        let users =
+            AsyncUserCalendarCombinationRepository::get_users_of_calendar(driver, calendar.uuid)
+                .await;
+
+        Self::assemble(calendar, components, properties, users)
+    }
+
+    fn assemble(
+        calendar: Calendar,
+        components: Vec<SimpleComponent>,
+        properties: Vec<Property>,
+        users: Vec<User>,
+    ) -> Self {
         let mut simple_users = vec![];
 
         // Convert users to SimpleUsers
@@ -94,6 +154,41 @@ impl SimpleCalendar {
             ));
         }
 
-        Self::new(calendar.name, components, properties, simple_users)
+        Self::new(
+            calendar.uuid,
+            calendar.name,
+            components,
+            properties,
+            simple_users,
+        )
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
     }
+
+    pub fn get_components(&self) -> &Vec<SimpleComponent> {
+        &self.components
+    }
+
+    pub fn get_properties(&self) -> &Vec<Property> {
+        &self.properties
+    }
+
+    /// The occurrences of the components of this calendar overlapping `from..to`, ordered by
+    /// their start, with recurring components expanded.
+    pub fn occurrences(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<SimpleComponent> {
+        simple_component::expand(&self.components, from, to)
+    }
+}
+
+/// Matches the properties of the given calendar.
+fn owned_by(calendar: &Calendar) -> Filter {
+    PropertyRepository::OWNER_TYPE
+        .eq(OwnerType::CALENDAR.to_string())
+        .and(PropertyRepository::OWNER_UUID.eq(calendar.uuid))
 }
@@ -1,34 +1,65 @@
-use crate::{
-    current::get_error_queue,
-    db::model::{
+use std::collections::HashMap;
+
+use crate::db::{
+    async_db_actions::AsyncDbActions,
+    db_actions::{DbActions, Page, Table},
+    model::{
         calendar::Calendar,
-        component::ComponentType,
+        component::{Component, ComponentType},
         property::{OwnerType, Property},
     },
-    errors::{
-        error_impl::database_operation_failed_error::DatabaseOperationFailedError,
-        error_utils::Error,
+    repository::{
+        component_repository::{AsyncComponentRepository, ComponentRepository},
+        property_repository::{AsyncPropertyRepository, PropertyRepository},
     },
 };
-use pg_driver::PgDriver;
-use serde::Serialize;
+use crate::ical::recurrence::Recurrence;
+use chrono::{DateTime, Utc};
+use pg_driver::{AsyncPgDriver, Cursor, Filter, PgDriver, Query, Row, ToSql};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Default, PartialEq, Serialize, Clone)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
 pub struct SimpleComponent {
+    uuid: Uuid,
     c_type: ComponentType,
+    calendar_uuid: Uuid,
+    starts_at: Option<DateTime<Utc>>,
+    ends_at: Option<DateTime<Utc>>,
+    /// The start of the occurrence in its series, if this is an occurrence of a recurring
+    /// component, see `occurrences`, or the occurrence this component overrides.
+    #[serde(default)]
+    recurrence_id: Option<DateTime<Utc>>,
+    /// The recurring component one of whose occurrences this component overrides.
+    #[serde(default)]
+    recurrence_of: Option<Uuid>,
+    /// The IANA zone of its times, see `Component::time_zone`.
+    #[serde(default)]
+    time_zone: Option<String>,
     properties: Vec<Property>,
 }
 
-impl SimpleComponent {
-    pub fn new(c_type: ComponentType, properties: Vec<Property>) -> Self {
-        Self { c_type, properties }
-    }
+/// A page of the components of a calendar, see `SimpleComponent::page_by_calendar`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ComponentPage {
+    pub components: Vec<SimpleComponent>,
+    /// The uuid of the last component of this page, to request the next page with. None if this
+    /// is the last page.
+    pub next: Option<Uuid>,
+}
 
-    pub fn empty() -> Self {
+impl SimpleComponent {
+    pub fn new(component: Component, properties: Vec<Property>) -> Self {
         Self {
-            c_type: ComponentType::OTHER,
-            properties: vec![],
+            uuid: component.uuid,
+            c_type: component.c_type,
+            calendar_uuid: component.calendar_uuid,
+            starts_at: component.starts_at,
+            ends_at: component.ends_at,
+            recurrence_id: component.recurrence_id,
+            recurrence_of: component.recurrence_of,
+            time_zone: component.time_zone,
+            properties,
         }
     }
 
@@ -36,67 +67,565 @@ impl SimpleComponent {
         self.properties.push(property);
     }
 
-    pub fn build_by_calendar(driver: &mut PgDriver, calendar: &Calendar) -> Vec<Self> {
-        let mut simple_components: Vec<Self> = vec![];
-        let stmt = format!(
-            r#"
-            select c.uuid, c.c_type, p.key, p.value
-            from components c
-            inner join properties p
-            on c.uuid = p.owner_uuid
-            where c.calendar_uuid = '{}'
-            and p.owner_type = '{}'
-        "#,
-            calendar.uuid,
-            OwnerType::COMPONENT.to_string()
-        );
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn get_c_type(&self) -> &ComponentType {
+        &self.c_type
+    }
+
+    pub fn get_calendar_uuid(&self) -> Uuid {
+        self.calendar_uuid
+    }
+
+    pub fn get_starts_at(&self) -> Option<DateTime<Utc>> {
+        self.starts_at
+    }
+
+    pub fn get_ends_at(&self) -> Option<DateTime<Utc>> {
+        self.ends_at
+    }
+
+    pub fn get_recurrence_id(&self) -> Option<DateTime<Utc>> {
+        self.recurrence_id
+    }
+
+    pub fn get_recurrence_of(&self) -> Option<Uuid> {
+        self.recurrence_of
+    }
+
+    pub fn get_time_zone(&self) -> Option<&String> {
+        self.time_zone.as_ref()
+    }
+
+    pub fn get_properties(&self) -> &Vec<Property> {
+        &self.properties
+    }
+
+    /// The occurrences of this component overlapping `from..to`, in order. A component that
+    /// doesn't recur is its only occurrence, components without a time have none. Occurrences
+    /// replaced by overrides are included, `expand` leaves them out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for meeting in team_meeting.occurrences(monday, next_monday) {
+    ///     println!("{:?}", meeting.get_starts_at());
+    /// }
+    /// ```
+    pub fn occurrences(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Self> {
+        self.occurrences_except(from, to, &[])
+    }
+
+    /// Like `occurrences`, without the ones starting at the given times.
+    fn occurrences_except(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        overridden: &[DateTime<Utc>],
+    ) -> Vec<Self> {
+        let Some(starts_at) = self.starts_at else {
+            return vec![];
+        };
+        let duration = self.ends_at.unwrap_or(starts_at) - starts_at;
 
-        let res = match driver.exec(&stmt) {
-            Ok(r) => r,
+        let recurrence = match Recurrence::of(&self.properties) {
+            Ok(recurrence) => recurrence,
             Err(e) => {
-                let mut err = DatabaseOperationFailedError::new();
-                err.set_message(format!("Could not retrieve components of calendar: {}", e));
-                get_error_queue().enqueue(err);
-                return vec![];
+                log::warn!("Could not expand component {}: {}", self.uuid, e);
+                None
             }
         };
+        let Some(recurrence) = recurrence else {
+            let overlaps = starts_at < to && (starts_at + duration > from || starts_at >= from);
+            return match overlaps {
+                true => vec![self.clone()],
+                false => vec![],
+            };
+        };
+
+        recurrence
+            .starts_in(starts_at, duration, from, to)
+            .into_iter()
+            .filter(|start| !overridden.contains(start))
+            .map(|start| Self {
+                starts_at: Some(start),
+                ends_at: Some(start + duration),
+                recurrence_id: Some(start),
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// Builds all components of a calendar, ordered by their uuid. Components without
+    /// properties are included.
+    pub fn build_by_calendar(driver: &mut PgDriver, calendar: &Calendar) -> Vec<Self> {
+        let components = ComponentRepository::retrieve(driver, by_calendar(calendar.uuid));
+        Self::load_properties(driver, components)
+    }
+
+    /// The async counterpart of `build_by_calendar`.
+    pub async fn build_by_calendar_async(
+        driver: &mut AsyncPgDriver,
+        calendar: &Calendar,
+    ) -> Vec<Self> {
+        let components =
+            AsyncComponentRepository::retrieve(driver, by_calendar(calendar.uuid)).await;
+        Self::load_properties_async(driver, components).await
+    }
+
+    /// Builds a page of the components of a calendar, instead of all of them like
+    /// `build_by_calendar`. The components are ordered by their uuid.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - The driver to use for database access ops.
+    /// * `calendar_uuid` - The uuid of the calendar the components belong to.
+    /// * `after` - The `next` uuid of the page before, None for the first page.
+    /// * `size` - The maximum amount of components of the page.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut page = SimpleComponent::page_by_calendar(driver, calendar.uuid, None, 100);
+    /// while let Some(after) = page.next {
+    ///     page = SimpleComponent::page_by_calendar(driver, calendar.uuid, Some(after), 100);
+    /// }
+    /// ```
+    pub fn page_by_calendar(
+        driver: &mut PgDriver,
+        calendar_uuid: Uuid,
+        after: Option<Uuid>,
+        size: u64,
+    ) -> ComponentPage {
+        let page =
+            ComponentRepository::retrieve_page(driver, page_query(calendar_uuid, after), size);
+        let properties = match page.items.is_empty() {
+            true => vec![],
+            false => PropertyRepository::read(
+                driver,
+                &PropertyRepository::get_name(),
+                &owned_by(&page.items).into(),
+            ),
+        };
+        Self::assemble_page(page, properties)
+    }
 
-        let mut component_uuid_before: String = String::default();
-        let mut current_component: SimpleComponent = SimpleComponent::empty();
-
-        let mut idx = 0;
-        let res_len = res.len();
-
-        for row in &res {
-            idx = idx + 1;
-            let component_uuid: Uuid = row.get("uuid");
-            let property_key: String = row.get("key");
-            let property_val: String = row.get("value");
-
-            if component_uuid_before.eq(&component_uuid.to_string()) {
-                // same component as before, so we just add the new property
-                current_component
-                    .properties
-                    .push(Property::hold(property_key, property_val));
-
-                if idx == res_len {
-                    simple_components.push(std::mem::take(&mut current_component));
-                }
-            } else {
-                // new component, add the old one to the result
-                if current_component != SimpleComponent::empty() {
-                    simple_components.push(std::mem::take(&mut current_component));
-                }
-
-                let c_type: String = row.get("c_type");
-
-                let first_property = vec![Property::hold(property_key, property_val)];
-                current_component =
-                    SimpleComponent::new(ComponentType::parse(&c_type), first_property);
-                component_uuid_before = component_uuid.to_string();
+    /// The async counterpart of `page_by_calendar`.
+    pub async fn page_by_calendar_async(
+        driver: &mut AsyncPgDriver,
+        calendar_uuid: Uuid,
+        after: Option<Uuid>,
+        size: u64,
+    ) -> ComponentPage {
+        let page =
+            AsyncComponentRepository::retrieve_page(driver, page_query(calendar_uuid, after), size)
+                .await;
+        let properties = match page.items.is_empty() {
+            true => vec![],
+            false => {
+                AsyncPropertyRepository::read(
+                    driver,
+                    &PropertyRepository::get_name(),
+                    &owned_by(&page.items).into(),
+                )
+                .await
             }
+        };
+        Self::assemble_page(page, properties)
+    }
+
+    /// Builds the occurrences of the components of the given calendars overlapping a time
+    /// range, ordered by their start. Recurring components are expanded, see `occurrences`.
+    pub fn build_in_range(
+        driver: &mut PgDriver,
+        calendars: &[Uuid],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Self> {
+        let components = Self::build_series_in_range(driver, calendars, from, to);
+        expand(&components, from, to)
+    }
+
+    /// Builds the occurrences of the components of the given calendars overlapping a time range
+    /// like `build_in_range`, but fails if they can't be read, e.g. to tell an offline database
+    /// from calendars without components.
+    pub fn try_build_in_range(
+        driver: &mut PgDriver,
+        calendars: &[Uuid],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let components = ComponentRepository::try_retrieve_in_range(driver, calendars, from, to)?;
+        let overrides = ComponentRepository::try_retrieve_overrides(driver, &masters(&components))?;
+        let components = with_overrides(components, overrides);
+        let properties = match components.is_empty() {
+            true => vec![],
+            false => PropertyRepository::try_read(
+                driver,
+                &PropertyRepository::get_name(),
+                &owned_by(&components).into(),
+            )?,
+        };
+        Ok(expand(
+            &Self::with_properties(components, properties),
+            from,
+            to,
+        ))
+    }
+
+    /// The async counterpart of `build_in_range`.
+    pub async fn build_in_range_async(
+        driver: &mut AsyncPgDriver,
+        calendars: &[Uuid],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Self> {
+        let components = Self::build_series_in_range_async(driver, calendars, from, to).await;
+        expand(&components, from, to)
+    }
+
+    /// Builds the components of the given calendars with occurrences in a time range, without
+    /// expanding them, e.g. to export them along with their recurrence rules. See
+    /// `ComponentRepository::retrieve_in_range`. The overrides of recurring components are
+    /// included even if they were moved out of the range, to leave out what they replace.
+    pub fn build_series_in_range(
+        driver: &mut PgDriver,
+        calendars: &[Uuid],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Self> {
+        let components = ComponentRepository::retrieve_in_range(driver, calendars, from, to);
+        let overrides = ComponentRepository::retrieve_overrides(driver, &masters(&components));
+        Self::load_properties(driver, with_overrides(components, overrides))
+    }
+
+    /// The async counterpart of `build_series_in_range`.
+    pub async fn build_series_in_range_async(
+        driver: &mut AsyncPgDriver,
+        calendars: &[Uuid],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Self> {
+        let components =
+            AsyncComponentRepository::retrieve_in_range(driver, calendars, from, to).await;
+        let overrides =
+            AsyncComponentRepository::retrieve_overrides(driver, &masters(&components)).await;
+        Self::load_properties_async(driver, with_overrides(components, overrides)).await
+    }
+
+    /// Reads the properties of the given components and pairs them up.
+    fn load_properties(driver: &mut PgDriver, components: Vec<Component>) -> Vec<Self> {
+        let properties = match components.is_empty() {
+            true => vec![],
+            false => PropertyRepository::read(
+                driver,
+                &PropertyRepository::get_name(),
+                &owned_by(&components).into(),
+            ),
+        };
+        Self::with_properties(components, properties)
+    }
+
+    /// The async counterpart of `load_properties`.
+    async fn load_properties_async(
+        driver: &mut AsyncPgDriver,
+        components: Vec<Component>,
+    ) -> Vec<Self> {
+        let properties = match components.is_empty() {
+            true => vec![],
+            false => {
+                AsyncPropertyRepository::read(
+                    driver,
+                    &PropertyRepository::get_name(),
+                    &owned_by(&components).into(),
+                )
+                .await
+            }
+        };
+        Self::with_properties(components, properties)
+    }
+
+    /// Pairs the components of a page with their properties.
+    fn assemble_page(page: Page<Component>, properties: Vec<Row>) -> ComponentPage {
+        let next = match page.next {
+            Some(_) => page.items.last().map(|component| component.uuid),
+            None => None,
+        };
+        ComponentPage {
+            components: Self::with_properties(page.items, properties),
+            next,
+        }
+    }
+
+    /// Pairs the components with their properties, read from the properties table.
+    fn with_properties(components: Vec<Component>, properties: Vec<Row>) -> Vec<Self> {
+        let mut properties_by_owner: HashMap<Uuid, Vec<Property>> = HashMap::new();
+        for row in &properties {
+            properties_by_owner
+                .entry(row.get("owner_uuid"))
+                .or_default()
+                .push(PropertyRepository::from_row(row));
+        }
+
+        components
+            .into_iter()
+            .map(|component| {
+                let properties = properties_by_owner
+                    .remove(&component.uuid)
+                    .unwrap_or_default();
+                SimpleComponent::new(component, properties)
+            })
+            .collect()
+    }
+}
+
+/// The occurrences of the given components overlapping `from..to`, ordered by their start.
+/// Occurrences overridden by one of the components are replaced by it.
+pub(crate) fn expand(
+    components: &[SimpleComponent],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<SimpleComponent> {
+    let mut overridden: HashMap<Uuid, Vec<DateTime<Utc>>> = HashMap::new();
+    for component in components {
+        if let (Some(master), Some(recurrence_id)) =
+            (component.recurrence_of, component.recurrence_id)
+        {
+            overridden.entry(master).or_default().push(recurrence_id);
+        }
+    }
+
+    let mut occurrences = components
+        .iter()
+        .flat_map(|component| {
+            let except = overridden
+                .get(&component.uuid)
+                .map_or(&[][..], Vec::as_slice);
+            component.occurrences_except(from, to, except)
+        })
+        .collect::<Vec<_>>();
+    occurrences.sort_by_key(|occurrence| occurrence.starts_at);
+    occurrences
+}
+
+/// The uuids of the given components that may have overrides.
+fn masters(components: &[Component]) -> Vec<Uuid> {
+    components
+        .iter()
+        .filter(|component| component.recurrence_of.is_none())
+        .map(|component| component.uuid)
+        .collect()
+}
+
+/// Adds the overrides that are not among the components yet.
+fn with_overrides(mut components: Vec<Component>, overrides: Vec<Component>) -> Vec<Component> {
+    for component in overrides {
+        if !components.iter().any(|c| c.uuid == component.uuid) {
+            components.push(component);
         }
+    }
+    components
+}
+
+/// All components of a calendar, ordered by their uuid.
+fn by_calendar(calendar_uuid: Uuid) -> Query {
+    Query::from(ComponentRepository::CALENDAR_UUID.eq(calendar_uuid))
+        .unique_by(ComponentRepository::UUID)
+}
 
-        simple_components
+/// The components of a calendar following the given one.
+fn page_query(calendar_uuid: Uuid, after: Option<Uuid>) -> Query {
+    let query = by_calendar(calendar_uuid);
+    match after {
+        Some(uuid) => query.after(Cursor::new(vec![uuid.to_value()])),
+        None => query,
+    }
+}
+
+/// Matches the properties of the given components.
+pub(crate) fn owned_by(components: &[Component]) -> Filter {
+    PropertyRepository::OWNER_TYPE
+        .eq(OwnerType::COMPONENT.to_string())
+        .and(PropertyRepository::OWNER_UUID.is_in(components.iter().map(|c| c.uuid)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::Migrations;
+    use crate::db::repository::calendar_repository::CalendarRepository;
+    use chrono::TimeZone;
+    use pg_driver::DriverConfig;
+
+    #[test]
+    fn test_page_by_calendar() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+        Migrations::run(&mut driver).unwrap();
+
+        let calendar = Calendar::new(String::from("Holidays"));
+        CalendarRepository::store(&mut driver, &calendar).unwrap();
+        for idx in 0..5 {
+            let uuid = Uuid::new_v4();
+            driver
+                .exec(
+                    "INSERT INTO components (uuid, c_type, calendar_uuid) VALUES ($1, $2, $3)",
+                    &[&uuid, &"EVENT", &calendar.uuid],
+                )
+                .unwrap();
+            driver
+                .exec(
+                    "INSERT INTO properties (uuid, key, value, owner_uuid, owner_type) \
+                     VALUES ($1, $2, $3, $4, $5)",
+                    &[
+                        &Uuid::new_v4(),
+                        &"SUMMARY",
+                        &format!("Event {}", idx),
+                        &uuid,
+                        &OwnerType::COMPONENT.to_string(),
+                    ],
+                )
+                .unwrap();
+        }
+
+        let mut sizes = vec![];
+        let mut summaries = vec![];
+        let mut after = None;
+        loop {
+            let page = SimpleComponent::page_by_calendar(&mut driver, calendar.uuid, after, 2);
+            sizes.push(page.components.len());
+            for component in page.components {
+                assert_eq!(component.properties.len(), 1);
+                summaries.push(component.properties[0].get_val().clone());
+            }
+            match page.next {
+                Some(next) => after = Some(next),
+                None => break,
+            }
+        }
+        summaries.sort();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert_eq!(
+            summaries,
+            (0..5).map(|i| format!("Event {}", i)).collect::<Vec<_>>()
+        );
+
+        let query = Query::from(ComponentRepository::CALENDAR_UUID.eq(calendar.uuid));
+        let streamed = ComponentRepository::stream(&mut driver, query, 2).collect::<Vec<_>>();
+        assert_eq!(streamed.len(), 5);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_build_in_range() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+        Migrations::run(&mut driver).unwrap();
+
+        let (work, home) = (
+            Calendar::new(String::from("Work")),
+            Calendar::new(String::from("Home")),
+        );
+        CalendarRepository::store(&mut driver, &work).unwrap();
+        CalendarRepository::store(&mut driver, &home).unwrap();
+        let at = |day, hour| Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap();
+        let components = [
+            // Ends when the range starts
+            Component::new(ComponentType::EVENT, work.uuid).with_times(at(5, 9), Some(at(6, 0))),
+            Component::new(ComponentType::EVENT, work.uuid).with_times(at(6, 0), None),
+            Component::new(ComponentType::EVENT, work.uuid).with_times(at(5, 9), Some(at(7, 9))),
+            Component::new(ComponentType::TODO, home.uuid).with_times(at(12, 23), None),
+            // Starts when the range ends
+            Component::new(ComponentType::EVENT, work.uuid).with_times(at(13, 0), None),
+            Component::new(ComponentType::VENUE, work.uuid),
+        ];
+        for component in &components {
+            ComponentRepository::store(&mut driver, component).unwrap();
+        }
+
+        let week = SimpleComponent::build_in_range(
+            &mut driver,
+            &[work.uuid, home.uuid],
+            at(6, 0),
+            at(13, 0),
+        );
+        let uuids = week.iter().map(|c| c.uuid).collect::<Vec<_>>();
+        assert_eq!(
+            uuids,
+            vec![components[2].uuid, components[1].uuid, components[3].uuid]
+        );
+        assert_eq!(week[0].ends_at, Some(at(7, 9)));
+
+        let home_only =
+            SimpleComponent::build_in_range(&mut driver, &[home.uuid], at(6, 0), at(13, 0));
+        assert_eq!(home_only.len(), 1);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_build_recurring_in_range() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+        Migrations::run(&mut driver).unwrap();
+
+        let calendar = Calendar::new(String::from("Work"));
+        CalendarRepository::store(&mut driver, &calendar).unwrap();
+        let at = |day, hour| Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap();
+        let series = [
+            ("FREQ=WEEKLY;BYDAY=MO,TH", None),
+            // Over before the range starts
+            ("FREQ=WEEKLY;COUNT=1", Some(at(1, 10))),
+        ];
+        let mut components = vec![];
+        for (rule, series_ends_at) in series {
+            let component = Component::new(ComponentType::EVENT, calendar.uuid)
+                .with_times(at(1, 9), Some(at(1, 10)))
+                .recurring_until(series_ends_at);
+            let rrule = Property::new(
+                component.uuid,
+                OwnerType::COMPONENT,
+                String::from("RRULE"),
+                rule.to_string(),
+            );
+            ComponentRepository::store(&mut driver, &component).unwrap();
+            PropertyRepository::store(&mut driver, &rrule).unwrap();
+            components.push(component);
+        }
+
+        let week =
+            SimpleComponent::build_in_range(&mut driver, &[calendar.uuid], at(6, 0), at(13, 0));
+        let starts = week
+            .iter()
+            .map(|occurrence| {
+                (
+                    occurrence.uuid,
+                    occurrence.starts_at,
+                    occurrence.recurrence_id,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            vec![
+                (components[0].uuid, Some(at(6, 9)), Some(at(6, 9))),
+                (components[0].uuid, Some(at(9, 9)), Some(at(9, 9))),
+            ]
+        );
+        assert_eq!(week[1].ends_at, Some(at(9, 10)));
+
+        let series = SimpleComponent::build_series_in_range(
+            &mut driver,
+            &[calendar.uuid],
+            at(6, 0),
+            at(13, 0),
+        );
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].recurrence_id, None);
+        let _ = std::fs::remove_file(path);
     }
 }
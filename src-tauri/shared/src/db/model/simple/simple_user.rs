@@ -1,8 +1,8 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// SimpleUser objects represent the same data as 'normal' users, but without any access functions, so
 /// SimpleUser objects can really only be used for holding data.
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SimpleUser {
     username: String,
     email: String,
@@ -0,0 +1,284 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::model::component::{Component, ComponentType};
+use crate::db::model::property::Property;
+use crate::db::model::simple::simple_component::SimpleComponent;
+
+use super::{End, PropertyError, Writer};
+
+/// The properties read by an event, all others are kept in `other`.
+const KNOWN: &[&str] = &[
+    "SUMMARY",
+    "DESCRIPTION",
+    "LOCATION",
+    "STATUS",
+    "PRIORITY",
+    "DTSTART",
+    "DTEND",
+    "DURATION",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EventStatus {
+    TENTATIVE,
+    CONFIRMED,
+    CANCELLED,
+}
+
+impl EventStatus {
+    /// The statuses by their STATUS values.
+    const VALUES: &'static [(&'static str, Self)] = &[
+        ("TENTATIVE", Self::TENTATIVE),
+        ("CONFIRMED", Self::CONFIRMED),
+        ("CANCELLED", Self::CANCELLED),
+    ];
+}
+
+/// An event component with its properties read into fields.
+///
+/// Occurrences of a recurring event are read as the whole series, since they share its
+/// properties.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    /// The uuid of the component, nil for an event not stored yet.
+    pub uuid: Uuid,
+    pub calendar_uuid: Uuid,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub status: Option<EventStatus>,
+    /// From 1, the highest, to 9.
+    pub priority: Option<u8>,
+    pub starts_at: DateTime<Utc>,
+    /// The DTEND or DURATION, None for events lasting no time, or a day if `all_day` is set.
+    pub end: Option<End>,
+    /// Whether its times are dates without a time of day.
+    pub all_day: bool,
+    /// The IANA zone its times are written in, see `Component::time_zone`.
+    pub time_zone: Option<String>,
+    /// The properties not read into fields, like RRULE or custom `X-` properties.
+    pub other: Vec<Property>,
+}
+
+impl Event {
+    /// Reads an event from the properties of a component.
+    ///
+    /// # Errors
+    /// - If it has no DTSTART.
+    /// - If one of the read properties is malformed or given several times.
+    /// - If both DTEND and DURATION are given, or it ends before it starts.
+    pub fn from_component(component: &SimpleComponent) -> Result<Self, PropertyError> {
+        let properties = component.get_properties();
+        let (starts_at, all_day) = super::time(properties, "DTSTART")?
+            .ok_or_else(|| PropertyError::new("DTSTART", "is missing"))?;
+        let end = super::end(properties, "DTEND")?;
+        super::check_end(starts_at, end, "DTEND")?;
+
+        Ok(Self {
+            uuid: component.get_uuid(),
+            calendar_uuid: component.get_calendar_uuid(),
+            summary: super::text(properties, "SUMMARY")?,
+            description: super::text(properties, "DESCRIPTION")?,
+            location: super::text(properties, "LOCATION")?,
+            status: super::status(properties, EventStatus::VALUES)?,
+            priority: super::priority(properties)?,
+            starts_at,
+            end,
+            all_day,
+            time_zone: component.get_time_zone().cloned(),
+            other: super::others(properties, KNOWN),
+        })
+    }
+
+    /// The component of this event along with its properties. A new uuid is given to events
+    /// with a nil one.
+    ///
+    /// # Errors
+    /// - If it ends before it starts.
+    /// - If its priority is above 9.
+    /// - If its time zone is unknown.
+    pub fn to_component(&self) -> Result<(Component, Vec<Property>), PropertyError> {
+        super::check_end(self.starts_at, self.end, "DTEND")?;
+        super::check_priority(self.priority)?;
+        let zone = super::zone_of(&self.time_zone)?;
+
+        let mut writer = Writer::new(self.uuid);
+        writer.text("SUMMARY", &self.summary);
+        writer.text("DESCRIPTION", &self.description);
+        writer.text("LOCATION", &self.location);
+        writer.status(self.status, EventStatus::VALUES);
+        writer.priority(self.priority);
+        writer.time("DTSTART", self.starts_at, self.all_day, zone);
+        writer.end("DTEND", self.end, self.all_day, zone);
+        writer.others(&self.other);
+
+        let ends_at = match self.end {
+            Some(end) => end.after(self.starts_at),
+            None if self.all_day => self.starts_at + TimeDelta::days(1),
+            None => self.starts_at,
+        };
+        Ok(writer.finish(
+            ComponentType::EVENT,
+            self.calendar_uuid,
+            Some((self.starts_at, ends_at)),
+            zone,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::property::OwnerType;
+    use crate::ical::parser;
+
+    fn event(properties: &[(&str, &str, &str)]) -> SimpleComponent {
+        let component = Component::new(ComponentType::EVENT, Uuid::new_v4());
+        let properties = properties
+            .iter()
+            .map(|(key, params, val)| {
+                Property::new(
+                    component.uuid,
+                    OwnerType::COMPONENT,
+                    key.to_string(),
+                    val.to_string(),
+                )
+                .with_params(params.to_string())
+            })
+            .collect();
+        SimpleComponent::new(component, properties)
+    }
+
+    fn value<'a>(properties: &'a [Property], key: &str) -> Option<&'a String> {
+        properties
+            .iter()
+            .find(|property| property.get_key() == key)
+            .map(|property| property.get_val())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let component = event(&[
+            ("SUMMARY", "", "Standup"),
+            ("DTSTART", "", "20240501T090000Z"),
+            ("DURATION", "", "PT15M"),
+            ("STATUS", "", "confirmed"),
+            ("PRIORITY", "", "0"),
+            ("RRULE", "", "FREQ=DAILY;COUNT=5"),
+            ("X-ROOM", "FLOOR=2", "Blue"),
+        ]);
+        let event = Event::from_component(&component).unwrap();
+        assert_eq!(event.summary, Some(String::from("Standup")));
+        assert_eq!(event.status, Some(EventStatus::CONFIRMED));
+        assert_eq!(event.priority, None);
+        assert_eq!(event.end, Some(End::LASTING(15 * 60)));
+        assert_eq!(event.other.len(), 2);
+
+        let (stored, properties) = event.to_component().unwrap();
+        assert_eq!(stored.uuid, component.get_uuid());
+        assert_eq!(
+            stored.ends_at.map(parser::format_date_time),
+            Some(String::from("20240501T091500Z"))
+        );
+        // The daily series ends with its fifth occurrence
+        assert_eq!(
+            stored.series_ends_at.map(parser::format_date_time),
+            Some(String::from("20240505T091500Z"))
+        );
+        assert_eq!(value(&properties, "DURATION"), Some(&String::from("PT15M")));
+        assert_eq!(
+            value(&properties, "STATUS"),
+            Some(&String::from("CONFIRMED"))
+        );
+        let room = properties
+            .iter()
+            .find(|property| property.get_key() == "X-ROOM")
+            .unwrap();
+        assert_eq!(
+            (room.get_val().as_str(), room.get_params().as_str()),
+            ("Blue", "FLOOR=2")
+        );
+
+        let reread = Event::from_component(&SimpleComponent::new(stored, properties)).unwrap();
+        assert_eq!(reread, event);
+    }
+
+    #[test]
+    fn test_zoned() {
+        let component = event(&[
+            ("DTSTART", "TZID=Europe/Berlin", "20240501T090000"),
+            ("DTEND", "TZID=Europe/Berlin", "20240501T100000"),
+        ]);
+        let mut event = Event::from_component(&component).unwrap();
+        assert_eq!(
+            parser::format_date_time(event.starts_at),
+            "20240501T070000Z"
+        );
+
+        event.time_zone = Some(String::from("Europe/Berlin"));
+        event.uuid = Uuid::nil();
+        let (stored, properties) = event.to_component().unwrap();
+        assert!(!stored.uuid.is_nil());
+        assert_eq!(stored.time_zone, Some(String::from("Europe/Berlin")));
+        assert_eq!(
+            value(&properties, "DTEND"),
+            Some(&String::from("20240501T100000"))
+        );
+        assert!(properties
+            .iter()
+            .all(|property| property.get_owner_uuid() == stored.uuid));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let error = |properties: &[(&str, &str, &str)]| {
+            Event::from_component(&event(properties)).unwrap_err().key
+        };
+        assert_eq!(error(&[("SUMMARY", "", "No start")]), "DTSTART");
+        assert_eq!(error(&[("DTSTART", "", "tomorrow")]), "DTSTART");
+        assert_eq!(
+            error(&[
+                ("DTSTART", "", "20240501T090000Z"),
+                ("DTEND", "", "20240501T100000Z"),
+                ("DURATION", "", "PT1H"),
+            ]),
+            "DURATION"
+        );
+        assert_eq!(
+            error(&[
+                ("DTSTART", "", "20240501T090000Z"),
+                ("DTEND", "", "20240501T080000Z"),
+            ]),
+            "DTEND"
+        );
+        assert_eq!(
+            error(&[
+                ("DTSTART", "", "20240501T090000Z"),
+                ("PRIORITY", "", "high")
+            ]),
+            "PRIORITY"
+        );
+        assert_eq!(
+            error(&[("DTSTART", "", "20240501T090000Z"), ("STATUS", "", "MAYBE")]),
+            "STATUS"
+        );
+        assert_eq!(
+            error(&[
+                ("DTSTART", "", "20240501T090000Z"),
+                ("SUMMARY", "", "One"),
+                ("SUMMARY", "", "Two"),
+            ]),
+            "SUMMARY"
+        );
+
+        let mut event = Event::from_component(&event(&[("DTSTART", "", "20240501")])).unwrap();
+        assert!(event.all_day);
+        event.priority = Some(10);
+        assert_eq!(event.to_component().unwrap_err().key, "PRIORITY");
+        event.priority = None;
+        event.time_zone = Some(String::from("Mars/Olympus"));
+        assert_eq!(event.to_component().unwrap_err().key, "DTSTART");
+    }
+}
@@ -0,0 +1,375 @@
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::model::component::{Component, ComponentType};
+use crate::db::model::property::{OwnerType, Property};
+use crate::db::model::series;
+use crate::db::model::simple::simple_component::SimpleComponent;
+use crate::ical::{parser, zone};
+
+use event::Event;
+use todo::Todo;
+use venue::Venue;
+
+pub mod event;
+pub mod todo;
+pub mod venue;
+
+/// A property of a component that can't be read into its typed view, or a field of a view that
+/// can't be written.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PropertyError {
+    /// The key of the property, like `DTEND`.
+    pub key: String,
+    pub message: String,
+}
+
+impl PropertyError {
+    fn new(key: &str, message: impl Into<String>) -> Self {
+        Self {
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for PropertyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.key, self.message)
+    }
+}
+
+impl std::error::Error for PropertyError {}
+
+/// When an event or to-do ends, given by a time or by how long it lasts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum End {
+    /// The DTEND of an event or the DUE of a to-do.
+    AT(DateTime<Utc>),
+    /// The DURATION, in seconds.
+    LASTING(i64),
+}
+
+impl End {
+    /// The end of something starting at `starts_at`.
+    pub fn after(&self, starts_at: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::AT(ends_at) => *ends_at,
+            Self::LASTING(seconds) => starts_at + TimeDelta::seconds(*seconds),
+        }
+    }
+}
+
+/// A component as the typed view matching its type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TypedComponent {
+    EVENT(Event),
+    TODO(Todo),
+    VENUE(Venue),
+}
+
+impl TypedComponent {
+    /// Reads a component into the view matching its type.
+    ///
+    /// # Returns
+    /// None for components without a typed view, like journals.
+    ///
+    /// # Errors
+    /// If one of the properties read by the view is malformed.
+    pub fn from_component(component: &SimpleComponent) -> Result<Option<Self>, PropertyError> {
+        Ok(Some(match component.get_c_type() {
+            ComponentType::EVENT => Self::EVENT(Event::from_component(component)?),
+            ComponentType::TODO => Self::TODO(Todo::from_component(component)?),
+            ComponentType::VENUE => Self::VENUE(Venue::from_component(component)?),
+            ComponentType::OTHER => return Ok(None),
+        }))
+    }
+
+    /// The component of this view along with its properties, see `Event::to_component`.
+    ///
+    /// # Errors
+    /// If a field of the view is invalid.
+    pub fn to_component(&self) -> Result<(Component, Vec<Property>), PropertyError> {
+        match self {
+            Self::EVENT(event) => event.to_component(),
+            Self::TODO(todo) => todo.to_component(),
+            Self::VENUE(venue) => Ok(venue.to_component()),
+        }
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        match self {
+            Self::EVENT(event) => event.uuid,
+            Self::TODO(todo) => todo.uuid,
+            Self::VENUE(venue) => venue.uuid,
+        }
+    }
+
+    pub fn calendar_uuid(&self) -> Uuid {
+        match self {
+            Self::EVENT(event) => event.calendar_uuid,
+            Self::TODO(todo) => todo.calendar_uuid,
+            Self::VENUE(venue) => venue.calendar_uuid,
+        }
+    }
+}
+
+fn has_key(property: &Property, keys: &[&str]) -> bool {
+    keys.iter()
+        .any(|key| property.get_key().eq_ignore_ascii_case(key))
+}
+
+/// The property with the given key.
+///
+/// # Errors
+/// If there are several of them.
+fn single<'a>(
+    properties: &'a [Property],
+    key: &str,
+) -> Result<Option<&'a Property>, PropertyError> {
+    let mut matching = properties
+        .iter()
+        .filter(|property| has_key(property, &[key]));
+    match (matching.next(), matching.next()) {
+        (_, Some(_)) => Err(PropertyError::new(key, "may only be given once")),
+        (property, None) => Ok(property),
+    }
+}
+
+fn text(properties: &[Property], key: &str) -> Result<Option<String>, PropertyError> {
+    Ok(single(properties, key)?.map(|property| property.get_val().clone()))
+}
+
+/// Reads a DATE or DATE-TIME property in the zone of its TZID. Unknown zones are read as UTC,
+/// like on import.
+///
+/// # Returns
+/// The time, and whether it is a DATE without a time.
+fn time(
+    properties: &[Property],
+    key: &str,
+) -> Result<Option<(DateTime<Utc>, bool)>, PropertyError> {
+    let Some(property) = single(properties, key)? else {
+        return Ok(None);
+    };
+    let zone = property
+        .param("TZID")
+        .and_then(|tzid| zone::parse_zone(&tzid));
+    zone::parse_date_time_in(property.get_val(), zone)
+        .map(Some)
+        .map_err(|e| PropertyError::new(key, e.to_string()))
+}
+
+/// Reads the end of an event or to-do, given by `end_key` or DURATION.
+fn end(properties: &[Property], end_key: &str) -> Result<Option<End>, PropertyError> {
+    let duration = match single(properties, "DURATION")? {
+        Some(property) => {
+            let duration = parser::parse_duration(property.get_val())
+                .map_err(|e| PropertyError::new("DURATION", e.to_string()))?;
+            Some(duration.num_seconds())
+        }
+        None => None,
+    };
+    match (time(properties, end_key)?, duration) {
+        (Some(_), Some(_)) => Err(PropertyError::new(
+            "DURATION",
+            format!("can't be given along with {}", end_key),
+        )),
+        (_, Some(seconds)) if seconds < 0 => {
+            Err(PropertyError::new("DURATION", "must not be negative"))
+        }
+        (Some((ends_at, _)), None) => Ok(Some(End::AT(ends_at))),
+        (None, Some(seconds)) => Ok(Some(End::LASTING(seconds))),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Checks that something doesn't end before it starts.
+fn check_end(
+    starts_at: DateTime<Utc>,
+    end: Option<End>,
+    end_key: &str,
+) -> Result<(), PropertyError> {
+    match end {
+        Some(End::AT(ends_at)) if ends_at < starts_at => {
+            Err(PropertyError::new(end_key, "lies before DTSTART"))
+        }
+        Some(End::LASTING(seconds)) if seconds < 0 => {
+            Err(PropertyError::new("DURATION", "must not be negative"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reads a PRIORITY from 1, the highest, to 9. A priority of 0 means none.
+fn priority(properties: &[Property]) -> Result<Option<u8>, PropertyError> {
+    let Some(value) = text(properties, "PRIORITY")? else {
+        return Ok(None);
+    };
+    match value.trim().parse::<u8>() {
+        Ok(0) => Ok(None),
+        Ok(priority) if priority <= 9 => Ok(Some(priority)),
+        _ => Err(PropertyError::new(
+            "PRIORITY",
+            format!("'{}' is no number from 0 to 9", value),
+        )),
+    }
+}
+
+fn check_priority(priority: Option<u8>) -> Result<(), PropertyError> {
+    match priority {
+        Some(priority) if priority > 9 => Err(PropertyError::new(
+            "PRIORITY",
+            format!("'{}' is no number from 0 to 9", priority),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reads a STATUS, one of the given values.
+fn status<T: Copy>(
+    properties: &[Property],
+    values: &[(&str, T)],
+) -> Result<Option<T>, PropertyError> {
+    let Some(value) = text(properties, "STATUS")? else {
+        return Ok(None);
+    };
+    values
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value.trim()))
+        .map(|(_, status)| Some(*status))
+        .ok_or_else(|| PropertyError::new("STATUS", format!("'{}' is no valid status", value)))
+}
+
+/// The zone of a view, checking that it is known.
+fn zone_of(time_zone: &Option<String>) -> Result<Option<Tz>, PropertyError> {
+    match time_zone {
+        Some(time_zone) => zone::parse_zone(time_zone).map(Some).ok_or_else(|| {
+            PropertyError::new(
+                "DTSTART",
+                format!("has the unknown time zone {}", time_zone),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+/// The properties not read by a view, kept as they are.
+fn others(properties: &[Property], known: &[&str]) -> Vec<Property> {
+    properties
+        .iter()
+        .filter(|property| !has_key(property, known))
+        .cloned()
+        .collect()
+}
+
+/// Writes the properties of a view.
+struct Writer {
+    owner_uuid: Uuid,
+    properties: Vec<Property>,
+}
+
+impl Writer {
+    /// Starts writing the properties of the component with the given uuid. A nil uuid stands
+    /// for a new component.
+    fn new(uuid: Uuid) -> Self {
+        Self {
+            owner_uuid: match uuid.is_nil() {
+                true => Uuid::new_v4(),
+                false => uuid,
+            },
+            properties: vec![],
+        }
+    }
+
+    /// Writes a TEXT property, if it is set and not empty.
+    fn text(&mut self, key: &str, value: &Option<String>) {
+        if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+            self.properties.push(Property::new(
+                self.owner_uuid,
+                OwnerType::COMPONENT,
+                key.to_string(),
+                value.clone(),
+            ));
+        }
+    }
+
+    fn status<T: PartialEq>(&mut self, status: Option<T>, values: &[(&str, T)]) {
+        let name = values
+            .iter()
+            .find(|(_, value)| Some(value) == status.as_ref())
+            .map(|(name, _)| name.to_string());
+        self.text("STATUS", &name);
+    }
+
+    fn priority(&mut self, priority: Option<u8>) {
+        self.text("PRIORITY", &priority.map(|priority| priority.to_string()));
+    }
+
+    fn time(&mut self, key: &str, time: DateTime<Utc>, all_day: bool, zone: Option<Tz>) {
+        self.properties.push(series::time_property(
+            self.owner_uuid,
+            key,
+            time,
+            all_day,
+            zone,
+        ));
+    }
+
+    /// Writes the end of an event or to-do as `end_key` or DURATION.
+    fn end(&mut self, end_key: &str, end: Option<End>, all_day: bool, zone: Option<Tz>) {
+        match end {
+            Some(End::AT(ends_at)) => self.time(end_key, ends_at, all_day, zone),
+            Some(End::LASTING(seconds)) => self.text(
+                "DURATION",
+                &Some(parser::format_duration(TimeDelta::seconds(seconds))),
+            ),
+            None => {}
+        }
+    }
+
+    /// Keeps the properties not read by the view, moved to this component if needed.
+    fn others(&mut self, others: &[Property]) {
+        for property in others {
+            self.properties
+                .push(match property.get_owner_uuid() == self.owner_uuid {
+                    true => property.clone(),
+                    false => Property::new(
+                        self.owner_uuid,
+                        OwnerType::COMPONENT,
+                        property.get_key().clone(),
+                        property.get_val().clone(),
+                    )
+                    .with_params(property.get_params().clone()),
+                });
+        }
+    }
+
+    /// The component of the written properties.
+    ///
+    /// # Arguments
+    ///
+    /// * `times` - Its start and end, None if it has no time.
+    fn finish(
+        self,
+        c_type: ComponentType,
+        calendar_uuid: Uuid,
+        times: Option<(DateTime<Utc>, DateTime<Utc>)>,
+        zone: Option<Tz>,
+    ) -> (Component, Vec<Property>) {
+        let mut component =
+            Component::from(self.owner_uuid, c_type, calendar_uuid, None, None, None);
+        if let Some((starts_at, ends_at)) = times {
+            component = component.with_times(starts_at, Some(ends_at));
+        }
+        if let Some(zone) = zone {
+            component = component.in_zone(zone);
+        }
+        series::refresh_series_end(&mut component, &self.properties);
+        (component, self.properties)
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::model::component::{Component, ComponentType};
+use crate::db::model::property::Property;
+use crate::db::model::simple::simple_component::SimpleComponent;
+
+use super::{End, PropertyError, Writer};
+
+/// The properties read by a to-do, all others are kept in `other`.
+const KNOWN: &[&str] = &[
+    "SUMMARY",
+    "DESCRIPTION",
+    "LOCATION",
+    "STATUS",
+    "PRIORITY",
+    "DTSTART",
+    "DUE",
+    "DURATION",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TodoStatus {
+    NEEDSACTION,
+    INPROCESS,
+    COMPLETED,
+    CANCELLED,
+}
+
+impl TodoStatus {
+    /// The statuses by their STATUS values.
+    const VALUES: &'static [(&'static str, Self)] = &[
+        ("NEEDS-ACTION", Self::NEEDSACTION),
+        ("IN-PROCESS", Self::INPROCESS),
+        ("COMPLETED", Self::COMPLETED),
+        ("CANCELLED", Self::CANCELLED),
+    ];
+}
+
+/// A to-do component with its properties read into fields, see `Event`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Todo {
+    /// The uuid of the component, nil for a to-do not stored yet.
+    pub uuid: Uuid,
+    pub calendar_uuid: Uuid,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub status: Option<TodoStatus>,
+    /// From 1, the highest, to 9.
+    pub priority: Option<u8>,
+    pub starts_at: Option<DateTime<Utc>>,
+    /// The DUE or DURATION. A DURATION needs a start.
+    pub due: Option<End>,
+    /// Whether its times are dates without a time of day.
+    pub all_day: bool,
+    /// The IANA zone its times are written in, see `Component::time_zone`.
+    pub time_zone: Option<String>,
+    /// The properties not read into fields, like COMPLETED or custom `X-` properties.
+    pub other: Vec<Property>,
+}
+
+impl Todo {
+    /// Reads a to-do from the properties of a component.
+    ///
+    /// # Errors
+    /// - If one of the read properties is malformed or given several times.
+    /// - If both DUE and DURATION are given, or it is due before it starts.
+    /// - If it has a DURATION but no DTSTART.
+    pub fn from_component(component: &SimpleComponent) -> Result<Self, PropertyError> {
+        let properties = component.get_properties();
+        let start = super::time(properties, "DTSTART")?;
+        let due = super::end(properties, "DUE")?;
+        let all_day = match start {
+            Some((_, is_date)) => is_date,
+            None => super::time(properties, "DUE")?.is_some_and(|(_, is_date)| is_date),
+        };
+        let starts_at = start.map(|(starts_at, _)| starts_at);
+        check_times(starts_at, due)?;
+
+        Ok(Self {
+            uuid: component.get_uuid(),
+            calendar_uuid: component.get_calendar_uuid(),
+            summary: super::text(properties, "SUMMARY")?,
+            description: super::text(properties, "DESCRIPTION")?,
+            location: super::text(properties, "LOCATION")?,
+            status: super::status(properties, TodoStatus::VALUES)?,
+            priority: super::priority(properties)?,
+            starts_at,
+            due,
+            all_day,
+            time_zone: component.get_time_zone().cloned(),
+            other: super::others(properties, KNOWN),
+        })
+    }
+
+    /// The component of this to-do along with its properties, see `Event::to_component`. It
+    /// starts at its DTSTART, or else at its DUE.
+    ///
+    /// # Errors
+    /// - If it is due before it starts, or has a DURATION but no start.
+    /// - If its priority is above 9.
+    /// - If its time zone is unknown.
+    pub fn to_component(&self) -> Result<(Component, Vec<Property>), PropertyError> {
+        check_times(self.starts_at, self.due)?;
+        super::check_priority(self.priority)?;
+        let zone = super::zone_of(&self.time_zone)?;
+
+        let mut writer = Writer::new(self.uuid);
+        writer.text("SUMMARY", &self.summary);
+        writer.text("DESCRIPTION", &self.description);
+        writer.text("LOCATION", &self.location);
+        writer.status(self.status, TodoStatus::VALUES);
+        writer.priority(self.priority);
+        if let Some(starts_at) = self.starts_at {
+            writer.time("DTSTART", starts_at, self.all_day, zone);
+        }
+        writer.end("DUE", self.due, self.all_day, zone);
+        writer.others(&self.other);
+
+        let times = match (self.starts_at, self.due) {
+            (Some(starts_at), due) => {
+                Some((starts_at, due.map_or(starts_at, |due| due.after(starts_at))))
+            }
+            (None, Some(End::AT(due))) => Some((due, due)),
+            (None, _) => None,
+        };
+        Ok(writer.finish(ComponentType::TODO, self.calendar_uuid, times, zone))
+    }
+}
+
+/// Checks that a to-do isn't due before it starts, and only has a DURATION along with a start.
+fn check_times(starts_at: Option<DateTime<Utc>>, due: Option<End>) -> Result<(), PropertyError> {
+    match (starts_at, due) {
+        (Some(starts_at), due) => super::check_end(starts_at, due, "DUE"),
+        (None, Some(End::LASTING(_))) => Err(PropertyError::new("DURATION", "needs a DTSTART")),
+        (None, _) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::property::OwnerType;
+    use crate::ical::parser;
+
+    fn todo(properties: &[(&str, &str)]) -> SimpleComponent {
+        let component = Component::new(ComponentType::TODO, Uuid::new_v4());
+        let properties = properties
+            .iter()
+            .map(|(key, val)| {
+                Property::new(
+                    component.uuid,
+                    OwnerType::COMPONENT,
+                    key.to_string(),
+                    val.to_string(),
+                )
+            })
+            .collect();
+        SimpleComponent::new(component, properties)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let component = todo(&[
+            ("SUMMARY", "File taxes"),
+            ("DUE", "20240531"),
+            ("STATUS", "NEEDS-ACTION"),
+            ("PRIORITY", "1"),
+            ("X-ESTIMATE", "3h"),
+        ]);
+        let todo = Todo::from_component(&component).unwrap();
+        assert_eq!(todo.starts_at, None);
+        assert!(todo.all_day);
+        assert_eq!(todo.status, Some(TodoStatus::NEEDSACTION));
+        assert_eq!(todo.priority, Some(1));
+
+        let (stored, properties) = todo.to_component().unwrap();
+        // Without a start, it is placed at its due date
+        assert_eq!(
+            stored.starts_at.map(parser::format_date_time),
+            Some(String::from("20240531T000000Z"))
+        );
+        assert!(properties.iter().any(|property| {
+            property.get_key() == "DUE" && property.get_params() == "VALUE=DATE"
+        }));
+        assert!(properties
+            .iter()
+            .any(|property| property.get_key() == "X-ESTIMATE" && property.get_val() == "3h"));
+
+        let reread = Todo::from_component(&SimpleComponent::new(stored, properties)).unwrap();
+        assert_eq!(reread, todo);
+
+        let mut undated = todo.clone();
+        undated.due = None;
+        let (stored, _) = undated.to_component().unwrap();
+        assert_eq!(stored.starts_at, None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let error =
+            |properties: &[(&str, &str)]| Todo::from_component(&todo(properties)).unwrap_err().key;
+        assert_eq!(error(&[("DURATION", "PT1H")]), "DURATION");
+        assert_eq!(
+            error(&[("DTSTART", "20240501T090000Z"), ("DUE", "20240430T090000Z")]),
+            "DUE"
+        );
+        assert_eq!(error(&[("PRIORITY", "12")]), "PRIORITY");
+
+        let mut todo = Todo::from_component(&todo(&[("DTSTART", "20240501T090000Z")])).unwrap();
+        todo.due = Some(End::LASTING(-60));
+        assert_eq!(todo.to_component().unwrap_err().key, "DURATION");
+        todo.starts_at = None;
+        todo.due = Some(End::LASTING(60));
+        assert_eq!(todo.to_component().unwrap_err().key, "DURATION");
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::model::component::{Component, ComponentType};
+use crate::db::model::property::Property;
+use crate::db::model::simple::simple_component::SimpleComponent;
+
+use super::{PropertyError, Writer};
+
+/// The properties read by a venue, all others are kept in `other`.
+const KNOWN: &[&str] = &[
+    "NAME",
+    "DESCRIPTION",
+    "STREET-ADDRESS",
+    "LOCALITY",
+    "REGION",
+    "POSTAL-CODE",
+    "COUNTRY",
+];
+
+/// A venue component with its properties read into fields, see `Event`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Venue {
+    /// The uuid of the component, nil for a venue not stored yet.
+    pub uuid: Uuid,
+    pub calendar_uuid: Uuid,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub street_address: Option<String>,
+    pub locality: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+    /// The properties not read into fields, like CATEGORIES or custom `X-` properties.
+    pub other: Vec<Property>,
+}
+
+impl Venue {
+    /// Reads a venue from the properties of a component.
+    ///
+    /// # Errors
+    /// If one of the read properties is given several times.
+    pub fn from_component(component: &SimpleComponent) -> Result<Self, PropertyError> {
+        let properties = component.get_properties();
+        Ok(Self {
+            uuid: component.get_uuid(),
+            calendar_uuid: component.get_calendar_uuid(),
+            name: super::text(properties, "NAME")?,
+            description: super::text(properties, "DESCRIPTION")?,
+            street_address: super::text(properties, "STREET-ADDRESS")?,
+            locality: super::text(properties, "LOCALITY")?,
+            region: super::text(properties, "REGION")?,
+            postal_code: super::text(properties, "POSTAL-CODE")?,
+            country: super::text(properties, "COUNTRY")?,
+            other: super::others(properties, KNOWN),
+        })
+    }
+
+    /// The component of this venue along with its properties, see `Event::to_component`.
+    /// Venues have no time.
+    pub fn to_component(&self) -> (Component, Vec<Property>) {
+        let mut writer = Writer::new(self.uuid);
+        writer.text("NAME", &self.name);
+        writer.text("DESCRIPTION", &self.description);
+        writer.text("STREET-ADDRESS", &self.street_address);
+        writer.text("LOCALITY", &self.locality);
+        writer.text("REGION", &self.region);
+        writer.text("POSTAL-CODE", &self.postal_code);
+        writer.text("COUNTRY", &self.country);
+        writer.others(&self.other);
+        writer.finish(ComponentType::VENUE, self.calendar_uuid, None, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::property::OwnerType;
+
+    #[test]
+    fn test_round_trip() {
+        let component = Component::new(ComponentType::VENUE, Uuid::new_v4());
+        let property = |key: &str, val: &str| {
+            Property::new(
+                component.uuid,
+                OwnerType::COMPONENT,
+                key.to_string(),
+                val.to_string(),
+            )
+        };
+        let properties = vec![
+            property("NAME", "Town hall"),
+            property("LOCALITY", "Berlin"),
+            property("X-ACCESSIBLE", "TRUE"),
+        ];
+        let venue =
+            Venue::from_component(&SimpleComponent::new(component.clone(), properties)).unwrap();
+        assert_eq!(venue.name, Some(String::from("Town hall")));
+        assert_eq!(venue.country, None);
+        assert_eq!(venue.other.len(), 1);
+
+        let (stored, properties) = venue.to_component();
+        assert_eq!(stored.starts_at, None);
+        assert_eq!(properties.len(), 3);
+        assert_eq!(
+            Venue::from_component(&SimpleComponent::new(stored, properties)).unwrap(),
+            venue
+        );
+
+        let twice = vec![property("NAME", "Town hall"), property("NAME", "City hall")];
+        assert_eq!(
+            Venue::from_component(&SimpleComponent::new(component, twice))
+                .unwrap_err()
+                .key,
+            "NAME"
+        );
+    }
+}
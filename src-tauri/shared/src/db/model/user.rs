@@ -1,11 +1,31 @@
+use crate::db::model::scheduling::WorkingHours;
+use crate::db::repository::user_repository::UserRepository;
+use crate::ical::zone;
+use chrono_tz::Tz;
+use customs::Table;
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Table)]
+#[table(name = "users", repository = "UserRepository", fk_name = "user_uuid")]
 pub struct User {
+    #[table(primary_key)]
     uuid: Uuid,
     username: String,
     password: String,
     email: String,
+    /// The IANA zone the user wants times shown in, like `Europe/Berlin`. None for the zone of
+    /// the device.
+    time_zone: Option<String>,
+    /// When the working hours of the user start and end, in minutes after midnight in their
+    /// zone. None if they can meet at any time, see `WorkingHours`.
+    work_starts: Option<i32>,
+    work_ends: Option<i32>,
+    /// The days of the week the user works on, like `MO,TU,WE,TH,FR`. None for Monday to
+    /// Friday.
+    work_days: Option<String>,
+    /// How many minutes the user wants kept free between proposed meetings and their other
+    /// events.
+    meeting_buffer: i32,
 }
 
 impl User {
@@ -16,6 +36,11 @@ impl User {
             username,
             password,
             email,
+            time_zone: None,
+            work_starts: None,
+            work_ends: None,
+            work_days: None,
+            meeting_buffer: 0,
         }
     }
 
@@ -26,6 +51,11 @@ impl User {
             username,
             password,
             email,
+            time_zone: None,
+            work_starts: None,
+            work_ends: None,
+            work_days: None,
+            meeting_buffer: 0,
         }
     }
 
@@ -56,4 +86,37 @@ impl User {
     pub fn set_email(&mut self, email: String) {
         self.email = email;
     }
+
+    pub fn get_time_zone(&self) -> Option<&String> {
+        self.time_zone.as_ref()
+    }
+
+    pub fn set_time_zone(&mut self, time_zone: Option<String>) {
+        self.time_zone = time_zone;
+    }
+
+    /// The zone to show times in, None for the zone of the device.
+    pub fn display_zone(&self) -> Option<Tz> {
+        self.time_zone.as_deref().and_then(zone::parse_zone)
+    }
+
+    /// The hours the user can meet in, None if they can meet at any time. Invalid stored hours
+    /// count as none.
+    pub fn working_hours(&self) -> Option<WorkingHours> {
+        WorkingHours::from_minutes(self.work_starts?, self.work_ends?, self.work_days.as_deref())
+    }
+
+    pub fn set_working_hours(&mut self, working_hours: Option<&WorkingHours>) {
+        self.work_starts = working_hours.map(WorkingHours::starts_minutes);
+        self.work_ends = working_hours.map(WorkingHours::ends_minutes);
+        self.work_days = working_hours.map(WorkingHours::days_value);
+    }
+
+    pub fn get_meeting_buffer(&self) -> i32 {
+        self.meeting_buffer
+    }
+
+    pub fn set_meeting_buffer(&mut self, meeting_buffer: i32) {
+        self.meeting_buffer = meeting_buffer;
+    }
 }
@@ -1,7 +1,20 @@
+use crate::db::repository::{
+    calendar_repository::CalendarRepository,
+    user_calendar_combination_repository::UserCalendarCombinationRepository,
+    user_repository::UserRepository,
+};
+use customs::Table;
 use uuid::Uuid;
 
+#[derive(Table)]
+#[table(
+    name = "users_calendars",
+    repository = "UserCalendarCombinationRepository"
+)]
 pub struct UserCalendarCombination {
+    #[table(references = "UserRepository")]
     pub user_uuid: Uuid,
+    #[table(references = "CalendarRepository")]
     pub calendar_uuid: Uuid,
 }
 
@@ -0,0 +1,128 @@
+use crate::db::async_db_actions::AsyncDbActions;
+use crate::db::db_actions::{DbActions, Table};
+use crate::db::model::alarm::Alarm;
+use crate::db::model::user::User;
+use customs::bench_message;
+use pg_driver::{AsyncPgDriver, Column, PgDriver, Query, Row};
+use uuid::Uuid;
+
+pub struct AlarmRepository;
+
+/// The async counterpart of `AlarmRepository`.
+pub struct AsyncAlarmRepository;
+
+const ALARMS_OF_USER_STMT: &str = r#"
+    SELECT a.*, c.calendar_uuid FROM alarms a
+    INNER JOIN components c
+    ON a.component_uuid = c.uuid
+    INNER JOIN users_calendars uc
+    ON c.calendar_uuid = uc.calendar_uuid
+    WHERE uc.user_uuid = $1
+    "#;
+
+impl AlarmRepository {
+    pub const UUID: Column<Uuid> = Column::new("uuid");
+    pub const COMPONENT_UUID: Column<Uuid> = Column::new("component_uuid");
+
+    /// Retrieves the alarms of all components of the calendars the given user has access to,
+    /// each along with the calendar of its component.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for (calendar_uuid, alarm) in get_alarms_of_user(driver()?.deref_mut(), &user)? {
+    ///     println!("{} reminds of {}", alarm.uuid, alarm.component_uuid);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// If the alarms could not be read, e.g. while the database is offline.
+    pub fn get_alarms_of_user(
+        driver: &mut PgDriver,
+        user: &User,
+    ) -> anyhow::Result<Vec<(Uuid, Alarm)>> {
+        let rows = Self::query(driver, ALARMS_OF_USER_STMT, &[&user.get_uuid()])?;
+        Ok(alarms_of_user(rows))
+    }
+
+    /// Retrieves the alarms of the given component.
+    pub fn retrieve_of_component(driver: &mut PgDriver, component_uuid: Uuid) -> Vec<Alarm> {
+        Self::retrieve(driver, Self::COMPONENT_UUID.eq(component_uuid).into())
+    }
+}
+
+impl AsyncAlarmRepository {
+    /// Retrieves the alarms of the calendars of a user, see
+    /// `AlarmRepository::get_alarms_of_user`.
+    pub async fn get_alarms_of_user(
+        driver: &mut AsyncPgDriver,
+        user: &User,
+    ) -> anyhow::Result<Vec<(Uuid, Alarm)>> {
+        let rows = Self::query(driver, ALARMS_OF_USER_STMT, &[&user.get_uuid()]).await?;
+        Ok(alarms_of_user(rows))
+    }
+
+    /// The async counterpart of `AlarmRepository::retrieve_of_component`.
+    pub async fn retrieve_of_component(
+        driver: &mut AsyncPgDriver,
+        component_uuid: Uuid,
+    ) -> Vec<Alarm> {
+        Self::retrieve(
+            driver,
+            AlarmRepository::COMPONENT_UUID.eq(component_uuid).into(),
+        )
+        .await
+    }
+}
+
+fn alarms_of_user(rows: Vec<Row>) -> Vec<(Uuid, Alarm)> {
+    rows.iter()
+        .map(|row| (row.get("calendar_uuid"), AlarmRepository::from_row(row)))
+        .collect()
+}
+
+impl DbActions<Alarm, Self> for AlarmRepository {
+    #[bench_message("Storing alarm")]
+    fn store(driver: &mut PgDriver, model: &Alarm) -> anyhow::Result<()> {
+        Self::insert(driver, model)
+    }
+
+    #[bench_message("Updating alarm")]
+    fn update(driver: &mut PgDriver, model: &Alarm) -> anyhow::Result<()> {
+        Self::alter(driver, model, model.uuid)
+    }
+
+    #[bench_message("Deleting alarm")]
+    fn remove(driver: &mut PgDriver, model: &Alarm) -> anyhow::Result<()> {
+        Self::delete(driver, model.uuid)
+    }
+
+    #[bench_message("Retrieving alarms")]
+    fn retrieve(driver: &mut PgDriver, query: Query) -> Vec<Alarm> {
+        let rows = Self::read(driver, &Self::get_name(), &query);
+        rows.iter().map(Self::from_row).collect()
+    }
+}
+
+impl AsyncDbActions<Alarm, AlarmRepository> for AsyncAlarmRepository {
+    #[bench_message("Storing alarm")]
+    async fn store(driver: &mut AsyncPgDriver, model: &Alarm) -> anyhow::Result<()> {
+        Self::insert(driver, model).await
+    }
+
+    #[bench_message("Updating alarm")]
+    async fn update(driver: &mut AsyncPgDriver, model: &Alarm) -> anyhow::Result<()> {
+        Self::alter(driver, model, model.uuid).await
+    }
+
+    #[bench_message("Deleting alarm")]
+    async fn remove(driver: &mut AsyncPgDriver, model: &Alarm) -> anyhow::Result<()> {
+        Self::delete(driver, model.uuid).await
+    }
+
+    #[bench_message("Retrieving alarms")]
+    async fn retrieve(driver: &mut AsyncPgDriver, query: Query) -> Vec<Alarm> {
+        let rows = Self::read(driver, &AlarmRepository::get_name(), &query).await;
+        rows.iter().map(AlarmRepository::from_row).collect()
+    }
+}
@@ -1,35 +1,61 @@
 use crate::db::{
+    async_db_actions::AsyncDbActions,
     db_actions::{DbActions, Table},
     model::calendar::Calendar,
 };
 use customs::bench_message;
-use pg_driver::PgDriver;
+use pg_driver::{AsyncPgDriver, BackendKind, Column, PgDriver, Query};
+use uuid::Uuid;
 
 pub struct CalendarRepository;
 
-impl Table<Calendar> for CalendarRepository {
-    fn get_name() -> String {
-        String::from("calendars")
-    }
+/// The async counterpart of `CalendarRepository`.
+pub struct AsyncCalendarRepository;
 
-    fn get_fk_uuid_name() -> String {
-        String::from("calendar_uuid")
-    }
+impl CalendarRepository {
+    pub const UUID: Column<Uuid> = Column::new("uuid");
+    pub const NAME: Column<String> = Column::new("name");
 
-    fn get_fmt_cols() -> String {
-        String::from("uuid, name")
+    /// Locks calendars until the open transaction ends, so concurrent changes of their
+    /// components are checked for conflicts and stored one after another. They are locked in
+    /// the order of their uuids, so transactions locking the same calendars can't deadlock.
+    ///
+    /// # Errors
+    /// If a lock could not be taken in time or a statement fails.
+    pub fn lock(driver: &mut PgDriver, calendar_uuids: &[Uuid]) -> anyhow::Result<()> {
+        for calendar_uuid in lock_order(calendar_uuids) {
+            driver.exec(lock_stmt(driver.kind()), &[&calendar_uuid])?;
+        }
+        Ok(())
     }
+}
 
-    fn get_fmt_cols_no_id() -> String {
-        String::from("name")
+impl AsyncCalendarRepository {
+    /// The async counterpart of `CalendarRepository::lock`.
+    pub async fn lock(driver: &mut AsyncPgDriver, calendar_uuids: &[Uuid]) -> anyhow::Result<()> {
+        for calendar_uuid in lock_order(calendar_uuids) {
+            driver
+                .exec(lock_stmt(driver.kind()), &[&calendar_uuid])
+                .await?;
+        }
+        Ok(())
     }
+}
 
-    fn get_fmt_vals(calendar: &Calendar) -> String {
-        format!("'{}', '{}'", calendar.uuid, calendar.name)
-    }
+/// The calendars to lock, sorted and without duplicates.
+fn lock_order(calendar_uuids: &[Uuid]) -> Vec<Uuid> {
+    let mut calendar_uuids = calendar_uuids.to_vec();
+    calendar_uuids.sort();
+    calendar_uuids.dedup();
+    calendar_uuids
+}
 
-    fn get_fmt_vals_no_id(calendar: &Calendar) -> String {
-        format!("'{}'", calendar.name)
+/// The statement locking the calendar `$1` until the open transaction ends.
+fn lock_stmt(kind: BackendKind) -> &'static str {
+    match kind {
+        BackendKind::Postgres => "SELECT uuid FROM calendars WHERE uuid = $1 FOR UPDATE",
+        // SQLite has no row locks, writing takes the lock of the whole database instead
+        BackendKind::Sqlite => "UPDATE calendars SET conflict_mode = conflict_mode WHERE uuid = $1",
     }
 }
 
@@ -39,9 +65,9 @@ impl DbActions<Calendar, Self> for CalendarRepository {
         Self::insert(driver, calendar)
     }
 
-    /// As a calendar db entry only has an uuid, it should have no reason to change.
-    fn update(_driver: &mut PgDriver, _model: &Calendar) -> anyhow::Result<()> {
-        unimplemented!("You can't update a calendar db entry, as it does only consist of an uuid.")
+    #[bench_message("Updating calendar")]
+    fn update(driver: &mut PgDriver, calendar: &Calendar) -> anyhow::Result<()> {
+        Self::alter(driver, calendar, calendar.uuid)
     }
 
     #[bench_message("Deleting calendar")]
@@ -50,17 +76,31 @@ impl DbActions<Calendar, Self> for CalendarRepository {
     }
 
     #[bench_message("Retrieving calendars")]
-    fn retrieve(driver: &mut PgDriver, condition: Option<String>) -> Vec<Calendar> {
-        let mut res: Vec<Calendar> = vec![];
+    fn retrieve(driver: &mut PgDriver, query: Query) -> Vec<Calendar> {
+        let rows = Self::read(driver, &Self::get_name(), &query);
+        rows.iter().map(Self::from_row).collect()
+    }
+}
 
-        let rows = Self::read(driver, &Self::get_name(), condition);
+impl AsyncDbActions<Calendar, CalendarRepository> for AsyncCalendarRepository {
+    #[bench_message("Storing calendar")]
+    async fn store(driver: &mut AsyncPgDriver, calendar: &Calendar) -> anyhow::Result<()> {
+        Self::insert(driver, calendar).await
+    }
 
-        for row in rows {
-            let uuid = row.get("uuid");
-            let name = row.get("name");
-            res.push(Calendar::from(uuid, name));
-        }
+    #[bench_message("Updating calendar")]
+    async fn update(driver: &mut AsyncPgDriver, calendar: &Calendar) -> anyhow::Result<()> {
+        Self::alter(driver, calendar, calendar.uuid).await
+    }
 
-        res
+    #[bench_message("Deleting calendar")]
+    async fn remove(driver: &mut AsyncPgDriver, calendar: &Calendar) -> anyhow::Result<()> {
+        Self::delete(driver, calendar.uuid).await
+    }
+
+    #[bench_message("Retrieving calendars")]
+    async fn retrieve(driver: &mut AsyncPgDriver, query: Query) -> Vec<Calendar> {
+        let rows = Self::read(driver, &CalendarRepository::get_name(), &query).await;
+        rows.iter().map(CalendarRepository::from_row).collect()
     }
 }
@@ -1,54 +1,22 @@
-use base64::{engine::general_purpose::STANDARD, Engine};
-use chrono::{DateTime, NaiveDateTime, Utc};
 use customs::bench_message;
-use ed25519_dalek::VerifyingKey;
+use pg_driver::{AsyncPgDriver, Column, Query};
+use uuid::Uuid;
 
 use crate::db::{
+    async_db_actions::AsyncDbActions,
     db_actions::{DbActions, Table},
     model::client::Client,
 };
 
 pub struct ClientRepository;
 
-impl Table<Client> for ClientRepository {
-    fn get_name() -> String {
-        String::from("clients")
-    }
-
-    fn get_fk_uuid_name() -> String {
-        String::from("client_uuid")
-    }
-
-    fn get_fmt_cols() -> String {
-        String::from("uuid, user_uuid, public_key, device_name, last_used, registered_at")
-    }
+/// The async counterpart of `ClientRepository`.
+pub struct AsyncClientRepository;
 
-    fn get_fmt_cols_no_id() -> String {
-        String::from("user_uuid, public_key, device_name, last_used, registered_at")
-    }
-
-    fn get_fmt_vals(model: &Client) -> String {
-        format!(
-            "'{}', '{}', '{}', '{}', '{}', '{}'",
-            model.get_uuid(),
-            model.get_user_uuid(),
-            STANDARD.encode(model.get_pub_key().to_bytes()),
-            model.get_device_name(),
-            model.get_last_used(),
-            model.get_registered_at()
-        )
-    }
-
-    fn get_fmt_vals_no_id(model: &Client) -> String {
-        format!(
-            "'{}', '{}', '{}', '{}', '{}'",
-            model.get_user_uuid(),
-            STANDARD.encode(model.get_pub_key().to_bytes()),
-            model.get_device_name(),
-            model.get_last_used(),
-            model.get_registered_at()
-        )
-    }
+impl ClientRepository {
+    pub const UUID: Column<Uuid> = Column::new("uuid");
+    pub const USER_UUID: Column<Uuid> = Column::new("user_uuid");
+    pub const DEVICE_NAME: Column<String> = Column::new("device_name");
 }
 
 impl DbActions<Client, Self> for ClientRepository {
@@ -68,30 +36,31 @@ impl DbActions<Client, Self> for ClientRepository {
     }
 
     #[bench_message("Retrieving clients")]
-    fn retrieve(driver: &mut pg_driver::PgDriver, condition: Option<String>) -> Vec<Client> {
-        let mut res = vec![];
-        let rows = Self::read(driver, &Self::get_name(), condition);
+    fn retrieve(driver: &mut pg_driver::PgDriver, query: Query) -> Vec<Client> {
+        let rows = Self::read(driver, &Self::get_name(), &query);
+        rows.iter().map(Self::from_row).collect()
+    }
+}
+
+impl AsyncDbActions<Client, ClientRepository> for AsyncClientRepository {
+    #[bench_message("Storing client")]
+    async fn store(driver: &mut AsyncPgDriver, model: &Client) -> anyhow::Result<()> {
+        Self::insert(driver, model).await
+    }
 
-        for row in rows {
-            let uuid = row.get("uuid");
-            let user_uuid = row.get("user_uuid");
-            let pub_key_str: String = row.get("public_key");
-            let pub_key =
-                VerifyingKey::try_from(&STANDARD.decode(pub_key_str).unwrap()[..32]).unwrap(); // TODO: Improve error handling
-            let name = row.get("device_name");
-            let last_used: NaiveDateTime = row.get("last_used");
-            let registered_at: NaiveDateTime = row.get("registered_at");
+    #[bench_message("Updating client")]
+    async fn update(driver: &mut AsyncPgDriver, model: &Client) -> anyhow::Result<()> {
+        Self::alter(driver, model, model.get_uuid()).await
+    }
 
-            res.push(Client::from(
-                uuid,
-                user_uuid,
-                pub_key,
-                name,
-                DateTime::from_naive_utc_and_offset(last_used, Utc),
-                DateTime::from_naive_utc_and_offset(registered_at, Utc),
-            ))
-        }
+    #[bench_message("Deleting client")]
+    async fn remove(driver: &mut AsyncPgDriver, model: &Client) -> anyhow::Result<()> {
+        Self::delete(driver, model.get_uuid()).await
+    }
 
-        res
+    #[bench_message("Retrieving clients")]
+    async fn retrieve(driver: &mut AsyncPgDriver, query: Query) -> Vec<Client> {
+        let rows = Self::read(driver, &ClientRepository::get_name(), &query).await;
+        rows.iter().map(ClientRepository::from_row).collect()
     }
 }
@@ -1,40 +1,114 @@
+use crate::db::async_db_actions::AsyncDbActions;
 use crate::db::db_actions::{DbActions, Table};
-use crate::db::model::component::{Component, ComponentType};
+use crate::db::model::component::Component;
+use chrono::{DateTime, Utc};
 use customs::bench_message;
-use pg_driver::PgDriver;
+use pg_driver::{AsyncPgDriver, Column, Order, PgDriver, Query};
+use uuid::Uuid;
 
 pub struct ComponentRepository;
 
+/// The async counterpart of `ComponentRepository`.
+pub struct AsyncComponentRepository;
+
 impl ComponentRepository {
-    pub fn retrieve_single(driver: &mut PgDriver, condition: Option<String>) -> Component {
-        Self::retrieve(driver, condition).first().cloned().unwrap()
-    }
-}
+    pub const UUID: Column<Uuid> = Column::new("uuid");
+    pub const C_TYPE: Column<String> = Column::new("c_type");
+    pub const CALENDAR_UUID: Column<Uuid> = Column::new("calendar_uuid");
+    pub const STARTS_AT: Column<DateTime<Utc>> = Column::new("starts_at");
+    pub const ENDS_AT: Column<DateTime<Utc>> = Column::new("ends_at");
+    pub const SERIES_ENDS_AT: Column<DateTime<Utc>> = Column::new("series_ends_at");
+    pub const RECURRENCE_OF: Column<Uuid> = Column::new("recurrence_of");
+    pub const RECURRENCE_ID: Column<DateTime<Utc>> = Column::new("recurrence_id");
+    pub const TIME_ZONE: Column<String> = Column::new("time_zone");
 
-impl Table<Component> for ComponentRepository {
-    fn get_name() -> String {
-        String::from("components")
+    pub fn retrieve_single(driver: &mut PgDriver, query: Query) -> Component {
+        Self::retrieve(driver, query.limit(1))
+            .first()
+            .cloned()
+            .unwrap()
     }
 
-    fn get_fk_uuid_name() -> String {
-        String::from("component_uuid")
+    /// Retrieves the components of the given calendars overlapping a time range, ordered by
+    /// their start. Components without a time are left out. Recurring components are retrieved
+    /// if any of their occurrences may overlap the range, they still have to be expanded.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - The database driver.
+    /// * `calendars` - The uuids of the calendars the components belong to.
+    /// * `from` - The start of the range.
+    /// * `to` - The end of the range, exclusively.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let week =
+    ///     ComponentRepository::retrieve_in_range(driver, &[calendar.uuid], monday, next_monday);
+    /// ```
+    pub fn retrieve_in_range(
+        driver: &mut PgDriver,
+        calendars: &[Uuid],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Component> {
+        Self::retrieve(driver, in_range(calendars, from, to))
     }
 
-    fn get_fmt_cols() -> String {
-        String::from("uuid, c_type")
+    /// Retrieves the components overriding occurrences of the given recurring components.
+    pub fn retrieve_overrides(driver: &mut PgDriver, masters: &[Uuid]) -> Vec<Component> {
+        match masters.is_empty() {
+            true => vec![],
+            false => Self::retrieve(driver, overrides_of(masters)),
+        }
     }
 
-    fn get_fmt_cols_no_id() -> String {
-        String::from("c_type")
+    /// Retrieves the components of the given calendars overlapping `from..to` like
+    /// `retrieve_in_range`, but fails if they can't be read, e.g. while the database is offline.
+    pub fn try_retrieve_in_range(
+        driver: &mut PgDriver,
+        calendars: &[Uuid],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Component>> {
+        let rows = Self::try_read(driver, &Self::get_name(), &in_range(calendars, from, to))?;
+        Ok(rows.iter().map(Self::from_row).collect())
     }
 
-    fn get_fmt_vals(component: &Component) -> String {
-        format!("'{}', '{:?}'", component.uuid.to_string(), component.c_type)
+    /// Retrieves the overrides of the given recurring components like `retrieve_overrides`, but
+    /// fails if they can't be read.
+    pub fn try_retrieve_overrides(
+        driver: &mut PgDriver,
+        masters: &[Uuid],
+    ) -> anyhow::Result<Vec<Component>> {
+        if masters.is_empty() {
+            return Ok(vec![]);
+        }
+        let rows = Self::try_read(driver, &Self::get_name(), &overrides_of(masters))?;
+        Ok(rows.iter().map(Self::from_row).collect())
     }
+}
 
-    fn get_fmt_vals_no_id(component: &Component) -> String {
-        format!("'{:?}'", component.c_type)
-    }
+/// Matches the components of the given calendars overlapping `from..to`, ordered by their
+/// start. A component without a duration overlaps if it starts within the range. Series overlap
+/// from their first occurrence to the end of their last one.
+fn in_range(calendars: &[Uuid], from: DateTime<Utc>, to: DateTime<Utc>) -> Query {
+    let overlapping = ComponentRepository::STARTS_AT.lt(to).and(
+        ComponentRepository::SERIES_ENDS_AT
+            .is_null()
+            .or(ComponentRepository::SERIES_ENDS_AT.gt(from))
+            .or(ComponentRepository::STARTS_AT.ge(from)),
+    );
+    Query::from(ComponentRepository::CALENDAR_UUID.is_in(calendars.iter().copied()))
+        .filter(overlapping)
+        .order_by(ComponentRepository::STARTS_AT, Order::Asc)
+        .unique_by(ComponentRepository::UUID)
+}
+
+/// Matches the overrides of the given components, ordered by the occurrence they override.
+fn overrides_of(masters: &[Uuid]) -> Query {
+    Query::from(ComponentRepository::RECURRENCE_OF.is_in(masters.iter().copied()))
+        .order_by(ComponentRepository::RECURRENCE_ID, Order::Asc)
 }
 
 impl DbActions<Component, Self> for ComponentRepository {
@@ -54,16 +128,63 @@ impl DbActions<Component, Self> for ComponentRepository {
     }
 
     #[bench_message("Retrieving components")]
-    fn retrieve(driver: &mut PgDriver, condition: Option<String>) -> Vec<Component> {
-        let mut matches: Vec<Component> = vec![];
+    fn retrieve(driver: &mut PgDriver, query: Query) -> Vec<Component> {
+        let rows = Self::read(driver, &Self::get_name(), &query);
+        rows.iter().map(Self::from_row).collect()
+    }
+}
+
+impl AsyncComponentRepository {
+    pub async fn retrieve_single(driver: &mut AsyncPgDriver, query: Query) -> Component {
+        Self::retrieve(driver, query.limit(1))
+            .await
+            .into_iter()
+            .next()
+            .unwrap()
+    }
 
-        let rows = Self::read(driver, &Self::get_name(), condition);
+    /// Retrieves the components of the given calendars overlapping a time range, see
+    /// `ComponentRepository::retrieve_in_range`.
+    pub async fn retrieve_in_range(
+        driver: &mut AsyncPgDriver,
+        calendars: &[Uuid],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Component> {
+        Self::retrieve(driver, in_range(calendars, from, to)).await
+    }
 
-        for row in rows {
-            let c_type = ComponentType::parse(row.get("c_type"));
-            matches.push(Component::from(row.get("uuid"), c_type))
+    /// The async counterpart of `ComponentRepository::retrieve_overrides`.
+    pub async fn retrieve_overrides(
+        driver: &mut AsyncPgDriver,
+        masters: &[Uuid],
+    ) -> Vec<Component> {
+        match masters.is_empty() {
+            true => vec![],
+            false => Self::retrieve(driver, overrides_of(masters)).await,
         }
+    }
+}
 
-        matches
+impl AsyncDbActions<Component, ComponentRepository> for AsyncComponentRepository {
+    #[bench_message("Storing component")]
+    async fn store(driver: &mut AsyncPgDriver, component: &Component) -> anyhow::Result<()> {
+        Self::insert(driver, component).await
+    }
+
+    #[bench_message("Updating component")]
+    async fn update(driver: &mut AsyncPgDriver, component: &Component) -> anyhow::Result<()> {
+        Self::alter(driver, component, component.uuid).await
+    }
+
+    #[bench_message("Deleting component")]
+    async fn remove(driver: &mut AsyncPgDriver, component: &Component) -> anyhow::Result<()> {
+        Self::delete(driver, component.uuid).await
+    }
+
+    #[bench_message("Retrieving components")]
+    async fn retrieve(driver: &mut AsyncPgDriver, query: Query) -> Vec<Component> {
+        let rows = Self::read(driver, &ComponentRepository::get_name(), &query).await;
+        rows.iter().map(ComponentRepository::from_row).collect()
     }
 }
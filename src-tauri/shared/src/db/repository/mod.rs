@@ -1,3 +1,4 @@
+pub mod alarm_repository;
 pub mod calendar_repository;
 pub mod client_repository;
 pub mod component_repository;
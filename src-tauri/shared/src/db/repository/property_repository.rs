@@ -1,90 +1,80 @@
+use crate::db::async_db_actions::AsyncDbActions;
 use crate::db::db_actions::{DbActions, Table};
 use crate::db::model::property::Property;
 use customs::bench_message;
-use pg_driver::PgDriver;
+use pg_driver::{AsyncPgDriver, Column, PgDriver, Query};
+use uuid::Uuid;
 
 pub struct PropertyRepository;
 
-impl PropertyRepository {
-    pub fn retrieve_first(driver: &mut PgDriver, condition: Option<String>) -> Option<Property> {
-        let mut res;
-
-        if let Some(condition) = condition {
-            let limit_condition = format!("{} {}", condition, "LIMIT 1");
-            res = PropertyRepository::retrieve(driver, Some(limit_condition));
-        } else {
-            res = PropertyRepository::retrieve(driver, condition);
-        }
+/// The async counterpart of `PropertyRepository`.
+pub struct AsyncPropertyRepository;
 
-        if res.len() >= 1 {
-            Some(res.remove(0))
-        } else {
-            None
-        }
+impl PropertyRepository {
+    pub const UUID: Column<Uuid> = Column::new("uuid");
+    pub const KEY: Column<String> = Column::new("key");
+    pub const VALUE: Column<String> = Column::new("value");
+    pub const OWNER_UUID: Column<Uuid> = Column::new("owner_uuid");
+    pub const OWNER_TYPE: Column<String> = Column::new("owner_type");
+
+    pub fn retrieve_first(driver: &mut PgDriver, query: Query) -> Option<Property> {
+        PropertyRepository::retrieve(driver, query.limit(1))
+            .into_iter()
+            .next()
     }
 }
 
-impl Table<Property> for PropertyRepository {
-    fn get_name() -> String {
-        String::from("properties")
-    }
-
-    fn get_fk_uuid_name() -> String {
-        String::from("property_uuid")
+impl DbActions<Property, Self> for PropertyRepository {
+    #[bench_message("Storing property")]
+    fn store(driver: &mut PgDriver, model: &Property) -> anyhow::Result<()> {
+        Self::insert(driver, model)
     }
 
-    fn get_fmt_cols() -> String {
-        String::from("uuid, key, value")
+    #[bench_message("Updating property")]
+    fn update(driver: &mut PgDriver, model: &Property) -> anyhow::Result<()> {
+        Self::alter(driver, &model, model.get_uuid())
     }
 
-    fn get_fmt_cols_no_id() -> String {
-        String::from("key, value")
+    #[bench_message("Deleting property")]
+    fn remove(driver: &mut PgDriver, model: &Property) -> anyhow::Result<()> {
+        Self::delete(driver, model.get_uuid())
     }
 
-    fn get_fmt_vals(property: &Property) -> String {
-        format!(
-            "'{}', '{}', '{}'",
-            property.get_uuid(),
-            property.get_key(),
-            property.get_val()
-        )
+    #[bench_message("Retrieving properties")]
+    fn retrieve(driver: &mut PgDriver, query: Query) -> Vec<Property> {
+        let rows = Self::read(driver, &Self::get_name(), &query);
+        rows.iter().map(Self::from_row).collect()
     }
+}
 
-    fn get_fmt_vals_no_id(property: &Property) -> String {
-        format!("'{}', '{}'", property.get_key(), property.get_val())
+impl AsyncPropertyRepository {
+    pub async fn retrieve_first(driver: &mut AsyncPgDriver, query: Query) -> Option<Property> {
+        Self::retrieve(driver, query.limit(1))
+            .await
+            .into_iter()
+            .next()
     }
 }
 
-impl DbActions<Property, Self> for PropertyRepository {
+impl AsyncDbActions<Property, PropertyRepository> for AsyncPropertyRepository {
     #[bench_message("Storing property")]
-    fn store(driver: &mut PgDriver, model: &Property) -> anyhow::Result<()> {
-        Self::insert(driver, model)
+    async fn store(driver: &mut AsyncPgDriver, model: &Property) -> anyhow::Result<()> {
+        Self::insert(driver, model).await
     }
 
     #[bench_message("Updating property")]
-    fn update(driver: &mut PgDriver, model: &Property) -> anyhow::Result<()> {
-        Self::alter(driver, &model, model.get_uuid())
+    async fn update(driver: &mut AsyncPgDriver, model: &Property) -> anyhow::Result<()> {
+        Self::alter(driver, model, model.get_uuid()).await
     }
 
     #[bench_message("Deleting property")]
-    fn remove(driver: &mut PgDriver, model: &Property) -> anyhow::Result<()> {
-        Self::delete(driver, model.get_uuid())
+    async fn remove(driver: &mut AsyncPgDriver, model: &Property) -> anyhow::Result<()> {
+        Self::delete(driver, model.get_uuid()).await
     }
 
     #[bench_message("Retrieving properties")]
-    fn retrieve(driver: &mut PgDriver, condition: Option<String>) -> Vec<Property> {
-        let mut matches: Vec<Property> = vec![];
-
-        let rows = Self::read(driver, &Self::get_name(), condition);
-
-        for row in rows {
-            matches.push(Property::from(
-                row.get("uuid"),
-                row.get("key"),
-                row.get("value"),
-            ));
-        }
-
-        matches
+    async fn retrieve(driver: &mut AsyncPgDriver, query: Query) -> Vec<Property> {
+        let rows = Self::read(driver, &PropertyRepository::get_name(), &query).await;
+        rows.iter().map(PropertyRepository::from_row).collect()
     }
 }
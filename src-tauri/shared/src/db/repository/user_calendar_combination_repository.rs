@@ -1,6 +1,7 @@
 use crate::{
     current::get_error_queue,
     db::{
+        async_db_actions::AsyncDbActions,
         db_actions::{DbActions, Table},
         model::{
             calendar::Calendar, user::User, user_calendar_combination::UserCalendarCombination,
@@ -12,13 +13,38 @@ use crate::{
         error_utils::Error,
     },
 };
-use pg_driver::PgDriver;
-use postgres::Row;
+use pg_driver::{AsyncPgDriver, Column, PgDriver, Query, Row};
 use uuid::Uuid;
 
 pub struct UserCalendarCombinationRepository;
 
+/// The async counterpart of `UserCalendarCombinationRepository`.
+pub struct AsyncUserCalendarCombinationRepository;
+
+/// Combinations consist of their key only, so there is nothing to update.
+const UPDATE_UNSUPPORTED_ERR: &str =
+    "A combination of a user and a calendar can't be updated, remove it and store a new one";
+
+const CALENDARS_OF_USER_STMT: &str = r#"
+    select c.uuid, c.name, c.conflict_mode from users_calendars uc 
+    INNER JOIN calendars c 
+    ON uc.calendar_uuid = c.uuid 
+    where uc.user_uuid = $1
+    "#;
+
+const USERS_OF_CALENDAR_STMT: &str = r#"
+    SELECT u.uuid, u.email, u.password, u.username, u.time_zone, u.work_starts, u.work_ends,
+        u.work_days, u.meeting_buffer
+    FROM users_calendars uc
+    INNER JOIN users u
+    ON uc.user_uuid = u.uuid
+    WHERE uc.calendar_uuid = $1
+    "#;
+
 impl UserCalendarCombinationRepository {
+    pub const USER_UUID: Column<Uuid> = Column::new("user_uuid");
+    pub const CALENDAR_UUID: Column<Uuid> = Column::new("calendar_uuid");
+
     /// Retrieves all calendars the given user has access to.
     ///
     /// # Arguments
@@ -29,36 +55,11 @@ impl UserCalendarCombinationRepository {
     /// # Examples
     ///
     /// ```
-    /// let calendars_of_user = get_calendars_of_user(driver().lock().unwrap().deref_mut(), user);
+    /// let calendars_of_user = get_calendars_of_user(driver()?.deref_mut(), user);
     /// ```
     pub fn get_calendars_of_user(driver: &mut PgDriver, user: &User) -> Vec<Calendar> {
-        let mut res: Vec<Calendar> = vec![];
-        let stmt = format!(
-            r#"
-            select c.uuid, c.name from users_calendars uc 
-            INNER JOIN calendars c 
-            ON uc.calendar_uuid = c.uuid 
-            where uc.user_uuid = '{}'
-            "#,
-            user.get_uuid()
-        );
-        let query_res = Self::query(driver, stmt);
-
-        if let Err(e) = query_res {
-            let mut err = DatabaseOperationFailedError::new();
-            err.set_message(format!("Could not retrieve calendars of user: {}", e));
-            get_error_queue().enqueue(err);
-            return vec![];
-        }
-
-        for calendar_row in query_res.unwrap() {
-            res.push(Calendar::from(
-                calendar_row.get("uuid"),
-                calendar_row.get("name"),
-            ));
-        }
-
-        res
+        let query_res = Self::query(driver, CALENDARS_OF_USER_STMT, &[&user.get_uuid()]);
+        calendars_of_user(query_res)
     }
 
     /// Retrieves all users that have access to the calendar matchig the given uuid.
@@ -71,76 +72,57 @@ impl UserCalendarCombinationRepository {
     /// # Examples
     ///
     /// ```
-    /// let calenar_users = get_users_of_calendar(driver().lock().unwrap().deref_mut(), calendar_uuid);
+    /// let calenar_users = get_users_of_calendar(driver()?.deref_mut(), calendar_uuid);
     /// ```
     pub fn get_users_of_calendar(driver: &mut PgDriver, calendar_uuid: Uuid) -> Vec<User> {
-        let mut res: Vec<User> = vec![];
-        let stmt = format!(
-            r#"
-            SELECT u.uuid, u.email, u.password, u.username 
-            FROM users_calendars uc
-            INNER JOIN users u
-            ON uc.user_uuid = u.uuid
-            WHERE uc.calendar_uuid = '{}'
-            "#,
-            calendar_uuid
-        );
-
-        let query_res = Self::query(driver, stmt);
-        if let Err(e) = query_res {
-            let mut err = DatabaseOperationFailedError::new();
-            err.set_message(format!(
-                "Could not retrieve users of calendar uuid={} because of: {}",
-                calendar_uuid, e
-            ));
-            get_error_queue().enqueue(err);
-            return vec![];
-        }
-
-        for user_row in query_res.unwrap() {
-            res.push(User::from(
-                user_row.get("uuid"),
-                user_row.get("username"),
-                user_row.get("email"),
-                user_row.get("password"),
-            ))
-        }
-
-        res
+        let query_res = Self::query(driver, USERS_OF_CALENDAR_STMT, &[&calendar_uuid]);
+        users_of_calendar(query_res, calendar_uuid)
     }
 }
 
-impl Table<UserCalendarCombination> for UserCalendarCombinationRepository {
-    fn get_name() -> String {
-        format!(
-            "{}_{}",
-            UserRepository::get_name(),
-            CalendarRepository::get_name()
-        )
-    }
-
-    fn get_fk_uuid_name() -> String {
-        unimplemented!()
+impl AsyncUserCalendarCombinationRepository {
+    /// Retrieves all calendars the given user has access to, see
+    /// `UserCalendarCombinationRepository::get_calendars_of_user`.
+    pub async fn get_calendars_of_user(driver: &mut AsyncPgDriver, user: &User) -> Vec<Calendar> {
+        let query_res = Self::query(driver, CALENDARS_OF_USER_STMT, &[&user.get_uuid()]).await;
+        calendars_of_user(query_res)
     }
 
-    fn get_fmt_cols() -> String {
-        format!(
-            "{}, {}",
-            UserRepository::get_fk_uuid_name(),
-            CalendarRepository::get_fk_uuid_name()
-        )
-    }
-
-    fn get_fmt_cols_no_id() -> String {
-        unimplemented!()
+    /// Retrieves all users that have access to the calendar matching the given uuid, see
+    /// `UserCalendarCombinationRepository::get_users_of_calendar`.
+    pub async fn get_users_of_calendar(
+        driver: &mut AsyncPgDriver,
+        calendar_uuid: Uuid,
+    ) -> Vec<User> {
+        let query_res = Self::query(driver, USERS_OF_CALENDAR_STMT, &[&calendar_uuid]).await;
+        users_of_calendar(query_res, calendar_uuid)
     }
+}
 
-    fn get_fmt_vals(model: &UserCalendarCombination) -> String {
-        format!("'{}', '{}'", model.user_uuid, model.calendar_uuid)
+fn calendars_of_user(query_res: anyhow::Result<Vec<Row>>) -> Vec<Calendar> {
+    match query_res {
+        Ok(rows) => rows.iter().map(CalendarRepository::from_row).collect(),
+        Err(e) => {
+            let mut err = DatabaseOperationFailedError::new();
+            err.set_message(format!("Could not retrieve calendars of user: {}", e));
+            get_error_queue().enqueue(err);
+            vec![]
+        }
     }
+}
 
-    fn get_fmt_vals_no_id(_model: &UserCalendarCombination) -> String {
-        unimplemented!()
+fn users_of_calendar(query_res: anyhow::Result<Vec<Row>>, calendar_uuid: Uuid) -> Vec<User> {
+    match query_res {
+        Ok(rows) => rows.iter().map(UserRepository::from_row).collect(),
+        Err(e) => {
+            let mut err = DatabaseOperationFailedError::new();
+            err.set_message(format!(
+                "Could not retrieve users of calendar uuid={} because of: {}",
+                calendar_uuid, e
+            ));
+            get_error_queue().enqueue(err);
+            vec![]
+        }
     }
 }
 
@@ -149,32 +131,64 @@ impl DbActions<UserCalendarCombination, Self> for UserCalendarCombinationReposit
         Self::insert(driver, model)
     }
 
+    /// # Errors
+    /// Always, see `UPDATE_UNSUPPORTED_ERR`.
     fn update(_driver: &mut PgDriver, _model: &UserCalendarCombination) -> anyhow::Result<()> {
-        unimplemented!(
-            "You can't update a combination entry, as it does only consist of two uuids."
-        )
+        anyhow::bail!(UPDATE_UNSUPPORTED_ERR)
     }
 
     fn remove(driver: &mut PgDriver, model: &UserCalendarCombination) -> anyhow::Result<()> {
-        let col_name: String = UserRepository::get_fk_uuid_name();
-        let col_value: String = model.user_uuid.to_string();
-        Self::delete_spec_col(driver, col_name, col_value)
+        Self::delete_where(
+            driver,
+            Self::USER_UUID
+                .eq(model.user_uuid)
+                .and(Self::CALENDAR_UUID.eq(model.calendar_uuid)),
+        )
+    }
+
+    fn retrieve(driver: &mut PgDriver, query: Query) -> Vec<UserCalendarCombination> {
+        let rows: Vec<Row> = Self::read(driver, Self::get_name().as_str(), &query);
+        rows.iter().map(Self::from_row).collect()
     }
+}
 
-    fn retrieve(driver: &mut PgDriver, condition: Option<String>) -> Vec<UserCalendarCombination> {
-        let mut res: Vec<UserCalendarCombination> = vec![];
+impl AsyncDbActions<UserCalendarCombination, UserCalendarCombinationRepository>
+    for AsyncUserCalendarCombinationRepository
+{
+    async fn store(
+        driver: &mut AsyncPgDriver,
+        model: &UserCalendarCombination,
+    ) -> anyhow::Result<()> {
+        Self::insert(driver, model).await
+    }
 
-        let rows: Vec<Row> = Self::read(driver, Self::get_name().as_str(), condition);
+    /// # Errors
+    /// Always, see `UPDATE_UNSUPPORTED_ERR`.
+    async fn update(
+        _driver: &mut AsyncPgDriver,
+        _model: &UserCalendarCombination,
+    ) -> anyhow::Result<()> {
+        anyhow::bail!(UPDATE_UNSUPPORTED_ERR)
+    }
 
-        for row in rows {
-            let user_uuid: String = row.get(UserRepository::get_fk_uuid_name().as_str());
-            let calendar_uuid: String = row.get(CalendarRepository::get_fk_uuid_name().as_str());
-            res.push(UserCalendarCombination::new(
-                user_uuid.parse().unwrap(),
-                calendar_uuid.parse().unwrap(),
-            ))
-        }
+    async fn remove(
+        driver: &mut AsyncPgDriver,
+        model: &UserCalendarCombination,
+    ) -> anyhow::Result<()> {
+        Self::delete_where(
+            driver,
+            UserCalendarCombinationRepository::USER_UUID
+                .eq(model.user_uuid)
+                .and(UserCalendarCombinationRepository::CALENDAR_UUID.eq(model.calendar_uuid)),
+        )
+        .await
+    }
 
-        res
+    async fn retrieve(driver: &mut AsyncPgDriver, query: Query) -> Vec<UserCalendarCombination> {
+        let name = UserCalendarCombinationRepository::get_name();
+        let rows = Self::read(driver, &name, &query).await;
+        rows.iter()
+            .map(UserCalendarCombinationRepository::from_row)
+            .collect()
     }
 }
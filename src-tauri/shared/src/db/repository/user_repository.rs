@@ -1,46 +1,50 @@
 use crate::db::{
+    async_db_actions::AsyncDbActions,
     db_actions::{DbActions, Table},
     model::user::User,
 };
 use crate::errors::error_messages::USER_NOT_FOUND_ERR;
 use customs::bench_message;
-use pg_driver::PgDriver;
+use pg_driver::{AsyncPgDriver, Column, Filter, OfflineError, PgDriver, Query};
 use uuid::Uuid;
 
 pub struct UserRepository;
 
+/// The async counterpart of `UserRepository`.
+pub struct AsyncUserRepository;
+
 impl UserRepository {
+    pub const UUID: Column<Uuid> = Column::new("uuid");
+    pub const USERNAME: Column<String> = Column::new("username");
+    pub const EMAIL: Column<String> = Column::new("email");
+
     /// Checks if a user with a given email already exists.
     ///
     /// # Returns
     /// True, if there is a user with the given email, otherwise false.
     pub fn is_existing(driver: &mut PgDriver, email: &str) -> bool {
-        let stmt = format!(
-            "SELECT EXISTS(SELECT 1 FROM users WHERE email = '{}') as exists",
-            email
-        );
+        let stmt = "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1) as \"exists\"";
 
-        let res = Self::query(driver, stmt);
+        let res = Self::query(driver, stmt, &[&email]);
 
         match res {
+            Err(e) if e.is::<OfflineError>() => false,
             Err(e) => panic!("{}", e), // Should not happen
             Ok(res) => return res.get(0).unwrap().get("exists"),
         }
     }
 
     pub fn get_by_email(driver: &mut PgDriver, email: String) -> Result<User, &'static str> {
-        let condition = format!("email = '{}'", email);
-        Self::get(driver, condition)
+        Self::get(driver, Self::EMAIL.eq(email))
     }
 
     pub fn get_by_uuid(driver: &mut PgDriver, uuid: Uuid) -> Result<User, &'static str> {
-        let condition = format!("uuid = '{}'", uuid);
-        Self::get(driver, condition)
+        Self::get(driver, Self::UUID.eq(uuid))
     }
 
-    pub fn get(driver: &mut PgDriver, condition: String) -> Result<User, &'static str> {
-        let user_opt = UserRepository::retrieve(driver, Some(condition))
-            .get(0)
+    pub fn get(driver: &mut PgDriver, filter: Filter) -> Result<User, &'static str> {
+        let user_opt = UserRepository::retrieve(driver, Query::from(filter).limit(1))
+            .first()
             .cloned();
         if let Some(user) = user_opt {
             Ok(user)
@@ -50,74 +54,80 @@ impl UserRepository {
     }
 }
 
-impl Table<User> for UserRepository {
-    fn get_name() -> String {
-        String::from("users")
+impl DbActions<User, Self> for UserRepository {
+    #[bench_message("Storing user")]
+    fn store(driver: &mut PgDriver, model: &User) -> anyhow::Result<()> {
+        Self::insert(driver, model)
+    }
+
+    #[bench_message("Updating user")]
+    fn update(driver: &mut PgDriver, model: &User) -> anyhow::Result<()> {
+        Self::alter(driver, &model, model.get_uuid())
+    }
+
+    #[bench_message("Removing user")]
+    fn remove(driver: &mut PgDriver, model: &User) -> anyhow::Result<()> {
+        Self::delete(driver, model.get_uuid())
     }
 
-    fn get_fk_uuid_name() -> String {
-        String::from("user_uuid")
+    #[bench_message("Retrieving users")]
+    fn retrieve(driver: &mut PgDriver, query: Query) -> Vec<User> {
+        let rows = Self::read(driver, &Self::get_name(), &query);
+        rows.iter().map(Self::from_row).collect()
     }
+}
+
+impl AsyncUserRepository {
+    /// Checks if a user with a given email already exists, see `UserRepository::is_existing`.
+    pub async fn is_existing(driver: &mut AsyncPgDriver, email: &str) -> bool {
+        let stmt = "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1) as \"exists\"";
 
-    fn get_fmt_cols() -> String {
-        String::from("uuid, username, password, email")
+        match Self::query(driver, stmt, &[&email]).await {
+            Err(e) if e.is::<OfflineError>() => false,
+            Err(e) => panic!("{}", e), // Should not happen
+            Ok(res) => res.first().unwrap().get("exists"),
+        }
     }
 
-    fn get_fmt_cols_no_id() -> String {
-        String::from("username, password, email")
+    pub async fn get_by_email(
+        driver: &mut AsyncPgDriver,
+        email: String,
+    ) -> Result<User, &'static str> {
+        Self::get(driver, UserRepository::EMAIL.eq(email)).await
     }
 
-    fn get_fmt_vals(user: &User) -> String {
-        format!(
-            "'{}', '{}', '{}', '{}'",
-            &user.get_uuid(),
-            &user.get_username(),
-            &user.get_password(),
-            &user.get_email()
-        )
+    pub async fn get_by_uuid(driver: &mut AsyncPgDriver, uuid: Uuid) -> Result<User, &'static str> {
+        Self::get(driver, UserRepository::UUID.eq(uuid)).await
     }
 
-    fn get_fmt_vals_no_id(user: &User) -> String {
-        format!(
-            "'{}', '{}', '{}'",
-            &user.get_username(),
-            &user.get_password(),
-            &user.get_email()
-        )
+    pub async fn get(driver: &mut AsyncPgDriver, filter: Filter) -> Result<User, &'static str> {
+        Self::retrieve(driver, Query::from(filter).limit(1))
+            .await
+            .into_iter()
+            .next()
+            .ok_or(USER_NOT_FOUND_ERR)
     }
 }
 
-impl DbActions<User, Self> for UserRepository {
+impl AsyncDbActions<User, UserRepository> for AsyncUserRepository {
     #[bench_message("Storing user")]
-    fn store(driver: &mut PgDriver, model: &User) -> anyhow::Result<()> {
-        Self::insert(driver, model)
+    async fn store(driver: &mut AsyncPgDriver, model: &User) -> anyhow::Result<()> {
+        Self::insert(driver, model).await
     }
 
     #[bench_message("Updating user")]
-    fn update(driver: &mut PgDriver, model: &User) -> anyhow::Result<()> {
-        Self::alter(driver, &model, model.get_uuid())
+    async fn update(driver: &mut AsyncPgDriver, model: &User) -> anyhow::Result<()> {
+        Self::alter(driver, model, model.get_uuid()).await
     }
 
     #[bench_message("Removing user")]
-    fn remove(driver: &mut PgDriver, model: &User) -> anyhow::Result<()> {
-        Self::delete(driver, model.get_uuid())
+    async fn remove(driver: &mut AsyncPgDriver, model: &User) -> anyhow::Result<()> {
+        Self::delete(driver, model.get_uuid()).await
     }
 
     #[bench_message("Retrieving users")]
-    fn retrieve(driver: &mut PgDriver, condition: Option<String>) -> Vec<User> {
-        let mut res: Vec<User> = vec![];
-
-        let rows = Self::read(driver, &Self::get_name(), condition);
-
-        for row in rows {
-            res.push(User::from(
-                row.get("uuid"),
-                row.get("username"),
-                row.get("email"),
-                row.get("password"),
-            ));
-        }
-
-        res
+    async fn retrieve(driver: &mut AsyncPgDriver, query: Query) -> Vec<User> {
+        let rows = Self::read(driver, &UserRepository::get_name(), &query).await;
+        rows.iter().map(UserRepository::from_row).collect()
     }
 }
@@ -0,0 +1,308 @@
+use crate::alarms;
+use crate::current::{async_driver, driver};
+use crate::db::async_db_actions::AsyncDbActions;
+use crate::db::db_actions::DbActions;
+use crate::db::model::alarm::Alarm;
+use crate::db::model::component::{Component, ComponentType};
+use crate::db::model::user::User;
+use crate::db::repository::alarm_repository::{AlarmRepository, AsyncAlarmRepository};
+use crate::db::repository::component_repository::{AsyncComponentRepository, ComponentRepository};
+use crate::db::repository::user_calendar_combination_repository::{
+    AsyncUserCalendarCombinationRepository, UserCalendarCombinationRepository,
+};
+use crate::db::service::calendar_service::access_of;
+use crate::errors::error_messages::{
+    ALARM_NOT_FOUND_ERR, COMPONENT_NOT_FOUND_ERR, INVALID_ALARM_ERR, INVALID_SNOOZE_ERR,
+};
+use crate::errors::error_utils::to_user_message;
+use chrono::{DateTime, TimeDelta, Utc};
+use pg_driver::{AsyncPgDriver, PgDriver, Query};
+use std::ops::DerefMut;
+use uuid::Uuid;
+
+/// The longest an alarm can be snoozed for.
+pub const MAX_SNOOZE_MINUTES: i64 = 24 * 60;
+
+pub struct AlarmService;
+
+/// The async counterpart of `AlarmService`.
+pub struct AsyncAlarmService;
+
+impl AlarmService {
+    /// Returns the alarms of a component of one of the calendars of the given user.
+    ///
+    /// # Returns an error
+    ///
+    /// * `COMPONENT_NOT_FOUND_ERR` - If the user has no access to the component.
+    pub fn get_alarms(user: &User, component_uuid: Uuid) -> Result<Vec<Alarm>, &'static str> {
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        Self::component_of(driver, user, component_uuid)?;
+        Ok(AlarmRepository::retrieve_of_component(
+            driver,
+            component_uuid,
+        ))
+    }
+
+    /// Replaces the alarms of an event or to-do of one of the calendars of the given user. The
+    /// alarms of a recurring component remind of all of its occurrences, see
+    /// `alarm::due_between`.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user editing the component.
+    /// * `component_uuid` - The component the alarms remind of, their own `component_uuid` is
+    ///   ignored.
+    /// * `alarms` - The new alarms, none to remove all of them.
+    ///
+    /// # Returns
+    /// The stored alarms.
+    ///
+    /// # Returns an error
+    ///
+    /// * `COMPONENT_NOT_FOUND_ERR` - If the user has no access to the component.
+    /// * `INVALID_ALARM_ERR` - If the component is no event or to-do, or one of the alarms isn't
+    ///   valid, see `Alarm::is_valid`.
+    /// * `QUERY_FAILED_ERR` - If storing the alarms failed.
+    pub fn set_alarms(
+        user: &User,
+        component_uuid: Uuid,
+        alarms: Vec<Alarm>,
+    ) -> Result<Vec<Alarm>, &'static str> {
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let component = Self::component_of(driver, user, component_uuid)?;
+        let alarms = checked(&component, alarms)?;
+
+        driver
+            .transaction(|driver| -> anyhow::Result<()> {
+                AlarmRepository::delete_where(
+                    driver,
+                    AlarmRepository::COMPONENT_UUID.eq(component_uuid),
+                )?;
+                for alarm in &alarms {
+                    AlarmRepository::store(driver, alarm)?;
+                }
+                Ok(())
+            })
+            .map_err(|e| to_user_message(&e))?;
+        alarms::wake();
+
+        Ok(alarms)
+    }
+
+    /// Snoozes an alarm of one of the calendars of the given user. It fires again after the
+    /// given minutes, the times it fired at before are acknowledged.
+    ///
+    /// # Returns
+    /// The snoozed alarm.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_SNOOZE_ERR` - If the minutes are not within 1 to `MAX_SNOOZE_MINUTES`.
+    /// * `ALARM_NOT_FOUND_ERR` - If the user has no access to the alarm.
+    /// * `QUERY_FAILED_ERR` - If storing the alarm failed.
+    pub fn snooze(user: &User, alarm_uuid: Uuid, minutes: i64) -> Result<Alarm, &'static str> {
+        let snoozed_until = snoozed_until(Utc::now(), minutes)?;
+        Self::acknowledge(user, alarm_uuid, Some(snoozed_until))
+    }
+
+    /// Dismisses an alarm of one of the calendars of the given user. The times it fired at
+    /// are acknowledged and a snooze is ended, it fires again at its next trigger.
+    ///
+    /// # Returns
+    /// The dismissed alarm.
+    ///
+    /// # Returns an error
+    ///
+    /// * `ALARM_NOT_FOUND_ERR` - If the user has no access to the alarm.
+    /// * `QUERY_FAILED_ERR` - If storing the alarm failed.
+    pub fn dismiss(user: &User, alarm_uuid: Uuid) -> Result<Alarm, &'static str> {
+        Self::acknowledge(user, alarm_uuid, None)
+    }
+
+    /// Acknowledges an alarm now, snoozing it until the given time if there is one.
+    fn acknowledge(
+        user: &User,
+        alarm_uuid: Uuid,
+        snoozed_until: Option<DateTime<Utc>>,
+    ) -> Result<Alarm, &'static str> {
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let mut alarm = AlarmRepository::retrieve(driver, by_uuid(alarm_uuid))
+            .into_iter()
+            .next()
+            .ok_or(ALARM_NOT_FOUND_ERR)?;
+        Self::component_of(driver, user, alarm.component_uuid).map_err(|_| ALARM_NOT_FOUND_ERR)?;
+
+        alarm.acknowledged_at = Some(Utc::now());
+        alarm.snoozed_until = snoozed_until;
+        AlarmRepository::update(driver, &alarm).map_err(|e| to_user_message(&e))?;
+        alarms::wake();
+
+        Ok(alarm)
+    }
+
+    /// Reads a component, if the given user has access to it.
+    fn component_of(
+        driver: &mut PgDriver,
+        user: &User,
+        component_uuid: Uuid,
+    ) -> Result<Component, &'static str> {
+        let component = ComponentRepository::retrieve(driver, component_by_uuid(component_uuid))
+            .into_iter()
+            .next()
+            .ok_or(COMPONENT_NOT_FOUND_ERR)?;
+        let access = UserCalendarCombinationRepository::retrieve(
+            driver,
+            access_of(user, component.calendar_uuid),
+        );
+        match access.is_empty() {
+            true => Err(COMPONENT_NOT_FOUND_ERR),
+            false => Ok(component),
+        }
+    }
+}
+
+impl AsyncAlarmService {
+    /// Returns the alarms of a component of one of the calendars of the given user, see
+    /// `AlarmService::get_alarms`.
+    pub async fn get_alarms(user: &User, component_uuid: Uuid) -> Result<Vec<Alarm>, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        Self::component_of(driver, user, component_uuid).await?;
+        Ok(AsyncAlarmRepository::retrieve_of_component(driver, component_uuid).await)
+    }
+
+    /// Replaces the alarms of an event or to-do of one of the calendars of the given user, see
+    /// `AlarmService::set_alarms`.
+    pub async fn set_alarms(
+        user: &User,
+        component_uuid: Uuid,
+        alarms: Vec<Alarm>,
+    ) -> Result<Vec<Alarm>, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let component = Self::component_of(driver, user, component_uuid).await?;
+        let alarms = checked(&component, alarms)?;
+
+        driver
+            .transaction(async |driver| -> anyhow::Result<()> {
+                AsyncAlarmRepository::delete_where(
+                    driver,
+                    AlarmRepository::COMPONENT_UUID.eq(component_uuid),
+                )
+                .await?;
+                for alarm in &alarms {
+                    AsyncAlarmRepository::store(driver, alarm).await?;
+                }
+                Ok(())
+            })
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        alarms::wake();
+
+        Ok(alarms)
+    }
+
+    /// Snoozes an alarm of one of the calendars of the given user, see `AlarmService::snooze`.
+    pub async fn snooze(
+        user: &User,
+        alarm_uuid: Uuid,
+        minutes: i64,
+    ) -> Result<Alarm, &'static str> {
+        let snoozed_until = snoozed_until(Utc::now(), minutes)?;
+        Self::acknowledge(user, alarm_uuid, Some(snoozed_until)).await
+    }
+
+    /// Dismisses an alarm of one of the calendars of the given user, see
+    /// `AlarmService::dismiss`.
+    pub async fn dismiss(user: &User, alarm_uuid: Uuid) -> Result<Alarm, &'static str> {
+        Self::acknowledge(user, alarm_uuid, None).await
+    }
+
+    /// The async counterpart of `AlarmService::acknowledge`.
+    async fn acknowledge(
+        user: &User,
+        alarm_uuid: Uuid,
+        snoozed_until: Option<DateTime<Utc>>,
+    ) -> Result<Alarm, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let mut alarm = AsyncAlarmRepository::retrieve(driver, by_uuid(alarm_uuid))
+            .await
+            .into_iter()
+            .next()
+            .ok_or(ALARM_NOT_FOUND_ERR)?;
+        Self::component_of(driver, user, alarm.component_uuid)
+            .await
+            .map_err(|_| ALARM_NOT_FOUND_ERR)?;
+
+        alarm.acknowledged_at = Some(Utc::now());
+        alarm.snoozed_until = snoozed_until;
+        AsyncAlarmRepository::update(driver, &alarm)
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        alarms::wake();
+
+        Ok(alarm)
+    }
+
+    /// The async counterpart of `AlarmService::component_of`.
+    async fn component_of(
+        driver: &mut AsyncPgDriver,
+        user: &User,
+        component_uuid: Uuid,
+    ) -> Result<Component, &'static str> {
+        let component =
+            AsyncComponentRepository::retrieve(driver, component_by_uuid(component_uuid))
+                .await
+                .into_iter()
+                .next()
+                .ok_or(COMPONENT_NOT_FOUND_ERR)?;
+        let access = AsyncUserCalendarCombinationRepository::retrieve(
+            driver,
+            access_of(user, component.calendar_uuid),
+        )
+        .await;
+        match access.is_empty() {
+            true => Err(COMPONENT_NOT_FOUND_ERR),
+            false => Ok(component),
+        }
+    }
+}
+
+/// Validates new alarms of a component and assigns them to it.
+fn checked(component: &Component, alarms: Vec<Alarm>) -> Result<Vec<Alarm>, &'static str> {
+    if !matches!(component.c_type, ComponentType::EVENT | ComponentType::TODO) {
+        return Err(INVALID_ALARM_ERR);
+    }
+    alarms
+        .into_iter()
+        .map(|alarm| match alarm.is_valid() {
+            true => Ok(Alarm {
+                component_uuid: component.uuid,
+                ..alarm
+            }),
+            false => Err(INVALID_ALARM_ERR),
+        })
+        .collect()
+}
+
+/// When a snooze of the given minutes, starting at `now`, ends.
+fn snoozed_until(now: DateTime<Utc>, minutes: i64) -> Result<DateTime<Utc>, &'static str> {
+    match minutes {
+        1..=MAX_SNOOZE_MINUTES => Ok(now + TimeDelta::minutes(minutes)),
+        _ => Err(INVALID_SNOOZE_ERR),
+    }
+}
+
+/// Matches the alarm with the given uuid.
+fn by_uuid(uuid: Uuid) -> Query {
+    Query::from(AlarmRepository::UUID.eq(uuid)).limit(1)
+}
+
+/// Matches the component with the given uuid.
+fn component_by_uuid(uuid: Uuid) -> Query {
+    Query::from(ComponentRepository::UUID.eq(uuid)).limit(1)
+}
@@ -1,13 +1,44 @@
-use crate::current::{self, driver};
+use crate::cache::{CalendarCache, Lookup};
+use crate::current::{self, async_driver, driver};
+use crate::db::async_db_actions::AsyncDbActions;
+use crate::db::db_actions::DbActions;
+use crate::db::model::calendar::Calendar;
+use crate::db::model::conflict::{Conflict, ConflictMode};
 use crate::db::model::simple::simple_calendar::SimpleCalendar;
+use crate::db::model::simple::simple_component::{ComponentPage, SimpleComponent};
 use crate::db::model::user::User;
-use crate::db::repository::user_calendar_combination_repository::UserCalendarCombinationRepository;
+use crate::db::model::user_calendar_combination::UserCalendarCombination;
+use crate::db::repository::alarm_repository::{AlarmRepository, AsyncAlarmRepository};
+use crate::db::repository::calendar_repository::{AsyncCalendarRepository, CalendarRepository};
+use crate::db::repository::user_calendar_combination_repository::{
+    AsyncUserCalendarCombinationRepository, UserCalendarCombinationRepository,
+};
+use crate::db::repository::user_repository::{AsyncUserRepository, UserRepository};
+use crate::db::service::component_service::{AsyncComponentService, ComponentService};
+use crate::errors::error_messages::{
+    CALENDAR_NOT_FOUND_ERR, INVALID_ICS_ERR, INVALID_TIME_RANGE_ERR,
+};
+use crate::errors::error_utils::to_user_message;
+use crate::ical::export;
+use crate::ical::import::{self, Import, ImportReport};
+use crate::notifications::{self, ChangeEvent};
+use chrono::{DateTime, Utc};
+use pg_driver::{health, AsyncPgDriver, ConnectionState, PgDriver, Query};
 use std::ops::DerefMut;
+use std::thread;
+use uuid::Uuid;
+
+/// The most components a page may hold, larger requests are cut down to it.
+pub const MAX_PAGE_SIZE: u64 = 500;
 
 pub struct CalendarService;
 
+/// The async counterpart of `CalendarService`.
+pub struct AsyncCalendarService;
+
 impl CalendarService {
-    /// Returns all calendars that are associated to the current user.
+    /// Returns all calendars that are associated to the current user. They are served from the
+    /// calendar cache while it is fresh, outdated calendars are reloaded.
     ///
     /// # Examples
     ///
@@ -19,23 +50,801 @@ impl CalendarService {
     /// }
     /// ```
     pub fn get_user_calendars(user: &User) -> Vec<SimpleCalendar> {
-        let mut simple_user_calendars: Vec<SimpleCalendar> = vec![];
-        let mut driver_binding = driver().lock().unwrap();
+        match current::calendar_cache().calendars_of(user.get_uuid()) {
+            Lookup::Fresh(calendars) => calendars,
+            _ => Self::load_user_calendars(user),
+        }
+    }
+
+    /// Like `get_user_calendars`, but returns outdated calendars right away and reloads them in
+    /// the background. A `ChangeEvent::Reloaded` is published once they are reloaded.
+    pub fn get_user_calendars_or_stale(user: &User) -> Vec<SimpleCalendar> {
+        let cache = current::calendar_cache();
+        match cache.calendars_of(user.get_uuid()) {
+            Lookup::Fresh(calendars) => calendars,
+            Lookup::Stale(calendars) => {
+                if cache.begin_refresh(user.get_uuid()) {
+                    let user = user.clone();
+                    thread::spawn(move || {
+                        Self::load_user_calendars(&user);
+                        cache.end_refresh(user.get_uuid());
+                        notifications::publish(&ChangeEvent::Reloaded {
+                            user_uuid: user.get_uuid(),
+                        });
+                    });
+                }
+                calendars
+            }
+            Lookup::Missing => Self::load_user_calendars(user),
+        }
+    }
+
+    /// Loads the calendars of the given user into the cache. Only the calendars that are not
+    /// fresh in the cache are read from the database. While no connection is free, the cached
+    /// calendars are returned instead.
+    fn load_user_calendars(user: &User) -> Vec<SimpleCalendar> {
+        let cache = current::calendar_cache();
+        let Ok(mut driver_binding) = driver() else {
+            return cached_calendars(cache, user);
+        };
+
+        let calendars = UserCalendarCombinationRepository::get_calendars_of_user(
+            driver_binding.deref_mut(),
+            user,
+        );
+        let simple_user_calendars = calendars
+            .into_iter()
+            .map(|calendar| match cache.calendar(calendar.uuid) {
+                Lookup::Fresh(simple_calendar) => simple_calendar,
+                _ => SimpleCalendar::build(driver_binding.deref_mut(), calendar),
+            })
+            .collect::<Vec<_>>();
+
+        store_if_online(cache, user, simple_user_calendars)
+    }
+
+    /// Creates a new calendar and grants the users with the given emails access to it.
+    /// Either the calendar is stored together with all of its users, or nothing is stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the new calendar.
+    /// * `users_emails` - The emails of the users that get access to the calendar.
+    ///
+    /// # Returns an error
+    ///
+    /// * `USER_NOT_FOUND_ERR` - If there is no user with one of the given emails.
+    /// * `QUERY_FAILED_ERR` - If storing the calendar failed.
+    pub fn create_calendar(
+        name: String,
+        users_emails: Vec<String>,
+    ) -> Result<Calendar, &'static str> {
+        let calendar = Calendar::new(name);
+
+        driver()?
+            .transaction(|driver| -> anyhow::Result<()> {
+                CalendarRepository::store(driver, &calendar)?;
+
+                for email in users_emails {
+                    let user =
+                        UserRepository::get_by_email(driver, email).map_err(anyhow::Error::msg)?;
+                    UserCalendarCombinationRepository::store(
+                        driver,
+                        &UserCalendarCombination::new(user.get_uuid(), calendar.uuid),
+                    )?;
+                }
+
+                Ok(())
+            })
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate_users();
+
+        Ok(calendar)
+    }
+
+    /// Returns how one of the calendars of the given user treats overlapping events.
+    ///
+    /// # Returns an error
+    ///
+    /// * `CALENDAR_NOT_FOUND_ERR` - If the user has no access to the calendar.
+    pub fn get_conflict_mode(
+        user: &User,
+        calendar_uuid: Uuid,
+    ) -> Result<ConflictMode, &'static str> {
+        let calendars =
+            UserCalendarCombinationRepository::get_calendars_of_user(driver()?.deref_mut(), user);
+        calendar_of(calendars, calendar_uuid).map(|calendar| calendar.conflict_mode)
+    }
+
+    /// Sets how one of the calendars of the given user treats events overlapping other events
+    /// of its users, see `ComponentService::store_typed`.
+    ///
+    /// # Returns an error
+    ///
+    /// * `CALENDAR_NOT_FOUND_ERR` - If the user has no access to the calendar.
+    /// * `QUERY_FAILED_ERR` - If storing the mode failed.
+    pub fn set_conflict_mode(
+        user: &User,
+        calendar_uuid: Uuid,
+        conflict_mode: ConflictMode,
+    ) -> Result<(), &'static str> {
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let calendars = UserCalendarCombinationRepository::get_calendars_of_user(driver, user);
+        let mut calendar = calendar_of(calendars, calendar_uuid)?;
+        calendar.conflict_mode = conflict_mode;
+        CalendarRepository::update(driver, &calendar).map_err(|e| to_user_message(&e))
+    }
+
+    /// Returns a page of the components of one of the calendars of the given user, so
+    /// calendars with many components can be loaded bit by bit.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user requesting the components.
+    /// * `calendar_uuid` - The calendar to read the components of.
+    /// * `after` - The `next` uuid of the page before, None for the first page.
+    /// * `size` - The maximum amount of components, at most `MAX_PAGE_SIZE`.
+    ///
+    /// # Errors
+    /// If the user has no access to the calendar.
+    pub fn get_components_page(
+        user: &User,
+        calendar_uuid: Uuid,
+        after: Option<Uuid>,
+        size: u64,
+    ) -> Result<ComponentPage, &'static str> {
+        let mut driver_binding = driver()?;
+        let access = UserCalendarCombinationRepository::retrieve(
+            driver_binding.deref_mut(),
+            access_of(user, calendar_uuid),
+        );
+        if access.is_empty() {
+            return Err(CALENDAR_NOT_FOUND_ERR);
+        }
+
+        Ok(SimpleComponent::page_by_calendar(
+            driver_binding.deref_mut(),
+            calendar_uuid,
+            after,
+            size.clamp(1, MAX_PAGE_SIZE),
+        ))
+    }
 
+    /// Returns the components of calendars of the given user overlapping a time range, like
+    /// the events of a week.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user requesting the components.
+    /// * `calendar_uuids` - The calendars to read the components of, all calendars of the user
+    ///   if empty.
+    /// * `from` - The start of the range.
+    /// * `to` - The end of the range, exclusively.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_TIME_RANGE_ERR` - If the range ends before it starts.
+    /// * `CALENDAR_NOT_FOUND_ERR` - If the user has no access to one of the calendars.
+    pub fn get_components_in_range(
+        user: &User,
+        calendar_uuids: Vec<Uuid>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<SimpleComponent>, &'static str> {
+        if to < from {
+            return Err(INVALID_TIME_RANGE_ERR);
+        }
+        let mut driver_binding = driver()?;
         let calendars = UserCalendarCombinationRepository::get_calendars_of_user(
             driver_binding.deref_mut(),
             user,
         );
+        let calendar_uuids = accessible(&calendars, calendar_uuids)?;
 
+        Ok(SimpleComponent::build_in_range(
+            driver_binding.deref_mut(),
+            &calendar_uuids,
+            from,
+            to,
+        ))
+    }
+
+    /// Imports the calendars of an iCalendar file as new calendars of the given user.
+    /// Components that can't be read are left out and listed in the report, the calendars are
+    /// stored with all of their other components, or none of them. The new calendars accept
+    /// overlapping events, the events the imported ones overlap are reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user getting access to the imported calendars.
+    /// * `contents` - The content of the .ics file.
+    /// * `name` - The name of calendars without an `X-WR-CALNAME`, like the file name.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_ICS_ERR` - If the contents are no iCalendar file.
+    /// * `QUERY_FAILED_ERR` - If storing a calendar failed.
+    pub fn import_ics(
+        user: &User,
+        contents: &str,
+        name: &str,
+    ) -> Result<ImportReport, &'static str> {
+        let import = read_ics(contents, name)?;
+
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let conflicts = driver
+            .transaction(|driver| -> anyhow::Result<Vec<Conflict>> {
+                // Checked while the calendars are locked, so concurrent changes can't overlap
+                ComponentService::lock_calendars_of(driver, user)?;
+                let mut conflicts = vec![];
+                for imported in &import.calendars {
+                    conflicts.extend(ComponentService::import_conflicts_of(
+                        driver,
+                        user,
+                        &imported.simple_components(),
+                    ));
+                    import::store(driver, user.get_uuid(), imported)?;
+                }
+                Ok(conflicts)
+            })
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate_users();
+
+        Ok(import.report(conflicts))
+    }
+
+    /// Imports the components of an iCalendar file into one of the calendars of the given
+    /// user. Like storing a component, see `ComponentService::store_typed`, a calendar with
+    /// `ConflictMode::HARD` rejects the import if an imported event overlaps another event,
+    /// other calendars store it and report the conflicts.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user importing the file.
+    /// * `calendar_uuid` - The calendar to import the components into.
+    /// * `contents` - The content of the .ics file.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_ICS_ERR` - If the contents are no iCalendar file.
+    /// * `CALENDAR_NOT_FOUND_ERR` - If the user has no access to the calendar.
+    /// * `CONFLICTING_EVENTS_ERR` - If the calendar rejects overlapping events and an imported
+    ///   event overlaps another one.
+    /// * `QUERY_FAILED_ERR` - If storing the components failed.
+    pub fn import_ics_into(
+        user: &User,
+        calendar_uuid: Uuid,
+        contents: &str,
+    ) -> Result<ImportReport, &'static str> {
+        let mut driver_binding = driver()?;
+        Self::import_ics_into_with(driver_binding.deref_mut(), user, calendar_uuid, contents)
+    }
+
+    /// Imports the components of an iCalendar file with the given driver, see
+    /// `import_ics_into`.
+    fn import_ics_into_with(
+        driver: &mut PgDriver,
+        user: &User,
+        calendar_uuid: Uuid,
+        contents: &str,
+    ) -> Result<ImportReport, &'static str> {
+        let calendars = UserCalendarCombinationRepository::get_calendars_of_user(driver, user);
+        let calendar = calendar_of(calendars, calendar_uuid)?;
+        let import = read_ics(contents, &calendar.name)?.merge_into(calendar);
+        let imported = &import.calendars[0];
+
+        let conflicts = driver
+            .transaction(|driver| -> anyhow::Result<Vec<Conflict>> {
+                // Checked while the calendars are locked, so concurrent changes can't overlap
+                ComponentService::lock_calendars_of(driver, user)?;
+                let conflicts = ComponentService::import_conflicts_of(
+                    driver,
+                    user,
+                    &imported.simple_components(),
+                );
+                let conflicts = ComponentService::accepted(driver, calendar_uuid, conflicts)
+                    .map_err(anyhow::Error::msg)?;
+                import::store_components(driver, imported)?;
+                Ok(conflicts)
+            })
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate(calendar_uuid);
+
+        Ok(import.report(conflicts))
+    }
+
+    /// Exports calendars of the given user to an iCalendar file, one VCALENDAR per calendar.
+    /// Alarms are written as VALARMs of their components.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user requesting the export.
+    /// * `calendar_uuids` - The calendars to export, all calendars of the user if empty.
+    /// * `range` - Only export the components overlapping `from..to`, all if None.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_TIME_RANGE_ERR` - If the range ends before it starts.
+    /// * `CALENDAR_NOT_FOUND_ERR` - If the user has no access to one of the calendars.
+    /// * `NO_DB_CONNECTION_ERR` - If the alarms could not be read, as the database is offline.
+    pub fn export_ics(
+        user: &User,
+        calendar_uuids: Vec<Uuid>,
+        range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) -> Result<String, &'static str> {
+        if range.is_some_and(|(from, to)| to < from) {
+            return Err(INVALID_TIME_RANGE_ERR);
+        }
+        let mut driver_binding = driver()?;
+        let calendars = UserCalendarCombinationRepository::get_calendars_of_user(
+            driver_binding.deref_mut(),
+            user,
+        );
+        let calendar_uuids = accessible(&calendars, calendar_uuids)?;
+
+        let simple_calendars = calendars
+            .into_iter()
+            .filter(|calendar| calendar_uuids.contains(&calendar.uuid))
+            .map(|calendar| match range {
+                Some((from, to)) => {
+                    SimpleCalendar::build_in_range(driver_binding.deref_mut(), calendar, from, to)
+                }
+                None => SimpleCalendar::build(driver_binding.deref_mut(), calendar),
+            })
+            .collect::<Vec<_>>();
+        let alarms = AlarmRepository::get_alarms_of_user(driver_binding.deref_mut(), user)
+            .map_err(|e| to_user_message(&e))?
+            .into_iter()
+            .map(|(_, alarm)| alarm)
+            .collect::<Vec<_>>();
+
+        Ok(export::write_with_alarms(&simple_calendars, &alarms))
+    }
+}
+
+impl AsyncCalendarService {
+    /// Returns all calendars that are associated to the current user, see
+    /// `CalendarService::get_user_calendars`.
+    pub async fn get_user_calendars(user: &User) -> Vec<SimpleCalendar> {
+        match current::calendar_cache().calendars_of(user.get_uuid()) {
+            Lookup::Fresh(calendars) => calendars,
+            _ => Self::load_user_calendars(user).await,
+        }
+    }
+
+    /// Returns the calendars of the given user, reloading outdated ones in the background, see
+    /// `CalendarService::get_user_calendars_or_stale`.
+    pub async fn get_user_calendars_or_stale(user: &User) -> Vec<SimpleCalendar> {
+        let cache = current::calendar_cache();
+        match cache.calendars_of(user.get_uuid()) {
+            Lookup::Fresh(calendars) => calendars,
+            Lookup::Stale(calendars) => {
+                if cache.begin_refresh(user.get_uuid()) {
+                    let user = user.clone();
+                    tauri::async_runtime::spawn(async move {
+                        Self::load_user_calendars(&user).await;
+                        cache.end_refresh(user.get_uuid());
+                        notifications::publish(&ChangeEvent::Reloaded {
+                            user_uuid: user.get_uuid(),
+                        });
+                    });
+                }
+                calendars
+            }
+            Lookup::Missing => Self::load_user_calendars(user).await,
+        }
+    }
+
+    /// The async counterpart of `CalendarService::load_user_calendars`.
+    async fn load_user_calendars(user: &User) -> Vec<SimpleCalendar> {
+        let cache = current::calendar_cache();
+        let Ok(mut driver_binding) = async_driver().await else {
+            return cached_calendars(cache, user);
+        };
+
+        let calendars = AsyncUserCalendarCombinationRepository::get_calendars_of_user(
+            driver_binding.deref_mut(),
+            user,
+        )
+        .await;
+        let mut simple_user_calendars = Vec::with_capacity(calendars.len());
         for calendar in calendars {
-            let simple_calendar = SimpleCalendar::build(driver_binding.deref_mut(), calendar);
-            simple_user_calendars.push(simple_calendar.clone());
-            // Caching loaded calendars
-            current::cache_calendar(simple_calendar)
+            simple_user_calendars.push(match cache.calendar(calendar.uuid) {
+                Lookup::Fresh(simple_calendar) => simple_calendar,
+                _ => SimpleCalendar::build_async(driver_binding.deref_mut(), calendar).await,
+            });
         }
 
-        println!("{:?}", simple_user_calendars);
+        store_if_online(cache, user, simple_user_calendars)
+    }
+
+    /// Creates a new calendar and grants the users with the given emails access to it, see
+    /// `CalendarService::create_calendar`.
+    pub async fn create_calendar(
+        name: String,
+        users_emails: Vec<String>,
+    ) -> Result<Calendar, &'static str> {
+        let calendar = Calendar::new(name);
+
+        async_driver()
+            .await?
+            .transaction(async |driver| -> anyhow::Result<()> {
+                AsyncCalendarRepository::store(driver, &calendar).await?;
+
+                for email in users_emails {
+                    let user = AsyncUserRepository::get_by_email(driver, email)
+                        .await
+                        .map_err(anyhow::Error::msg)?;
+                    AsyncUserCalendarCombinationRepository::store(
+                        driver,
+                        &UserCalendarCombination::new(user.get_uuid(), calendar.uuid),
+                    )
+                    .await?;
+                }
+
+                Ok(())
+            })
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate_users();
+
+        Ok(calendar)
+    }
+
+    /// Returns how one of the calendars of the given user treats overlapping events, see
+    /// `CalendarService::get_conflict_mode`.
+    pub async fn get_conflict_mode(
+        user: &User,
+        calendar_uuid: Uuid,
+    ) -> Result<ConflictMode, &'static str> {
+        let calendars = AsyncUserCalendarCombinationRepository::get_calendars_of_user(
+            async_driver().await?.deref_mut(),
+            user,
+        )
+        .await;
+        calendar_of(calendars, calendar_uuid).map(|calendar| calendar.conflict_mode)
+    }
+
+    /// Sets how one of the calendars of the given user treats overlapping events, see
+    /// `CalendarService::set_conflict_mode`.
+    pub async fn set_conflict_mode(
+        user: &User,
+        calendar_uuid: Uuid,
+        conflict_mode: ConflictMode,
+    ) -> Result<(), &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let calendars =
+            AsyncUserCalendarCombinationRepository::get_calendars_of_user(driver, user).await;
+        let mut calendar = calendar_of(calendars, calendar_uuid)?;
+        calendar.conflict_mode = conflict_mode;
+        AsyncCalendarRepository::update(driver, &calendar)
+            .await
+            .map_err(|e| to_user_message(&e))
+    }
+
+    /// Returns a page of the components of one of the calendars of the given user, see
+    /// `CalendarService::get_components_page`.
+    pub async fn get_components_page(
+        user: &User,
+        calendar_uuid: Uuid,
+        after: Option<Uuid>,
+        size: u64,
+    ) -> Result<ComponentPage, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let access = AsyncUserCalendarCombinationRepository::retrieve(
+            driver_binding.deref_mut(),
+            access_of(user, calendar_uuid),
+        )
+        .await;
+        if access.is_empty() {
+            return Err(CALENDAR_NOT_FOUND_ERR);
+        }
+
+        Ok(SimpleComponent::page_by_calendar_async(
+            driver_binding.deref_mut(),
+            calendar_uuid,
+            after,
+            size.clamp(1, MAX_PAGE_SIZE),
+        )
+        .await)
+    }
+
+    /// Returns the components of calendars of the given user overlapping a time range, see
+    /// `CalendarService::get_components_in_range`.
+    pub async fn get_components_in_range(
+        user: &User,
+        calendar_uuids: Vec<Uuid>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<SimpleComponent>, &'static str> {
+        if to < from {
+            return Err(INVALID_TIME_RANGE_ERR);
+        }
+        let mut driver_binding = async_driver().await?;
+        let calendars = AsyncUserCalendarCombinationRepository::get_calendars_of_user(
+            driver_binding.deref_mut(),
+            user,
+        )
+        .await;
+        let calendar_uuids = accessible(&calendars, calendar_uuids)?;
+
+        Ok(SimpleComponent::build_in_range_async(
+            driver_binding.deref_mut(),
+            &calendar_uuids,
+            from,
+            to,
+        )
+        .await)
+    }
+
+    /// Imports the calendars of an iCalendar file as new calendars of the given user, see
+    /// `CalendarService::import_ics`.
+    pub async fn import_ics(
+        user: &User,
+        contents: &str,
+        name: &str,
+    ) -> Result<ImportReport, &'static str> {
+        let import = read_ics(contents, name)?;
+
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let conflicts = driver
+            .transaction(async |driver| -> anyhow::Result<Vec<Conflict>> {
+                AsyncComponentService::lock_calendars_of(driver, user).await?;
+                let mut conflicts = vec![];
+                for imported in &import.calendars {
+                    conflicts.extend(
+                        AsyncComponentService::import_conflicts_of(
+                            driver,
+                            user,
+                            &imported.simple_components(),
+                        )
+                        .await,
+                    );
+                    import::store_async(driver, user.get_uuid(), imported).await?;
+                }
+                Ok(conflicts)
+            })
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate_users();
+
+        Ok(import.report(conflicts))
+    }
+
+    /// Imports the components of an iCalendar file into one of the calendars of the given
+    /// user, see `CalendarService::import_ics_into`.
+    pub async fn import_ics_into(
+        user: &User,
+        calendar_uuid: Uuid,
+        contents: &str,
+    ) -> Result<ImportReport, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        Self::import_ics_into_with(driver_binding.deref_mut(), user, calendar_uuid, contents).await
+    }
+
+    /// The async counterpart of `CalendarService::import_ics_into_with`.
+    async fn import_ics_into_with(
+        driver: &mut AsyncPgDriver,
+        user: &User,
+        calendar_uuid: Uuid,
+        contents: &str,
+    ) -> Result<ImportReport, &'static str> {
+        let calendars =
+            AsyncUserCalendarCombinationRepository::get_calendars_of_user(driver, user).await;
+        let calendar = calendar_of(calendars, calendar_uuid)?;
+        let import = read_ics(contents, &calendar.name)?.merge_into(calendar);
+        let imported = &import.calendars[0];
+
+        let conflicts = driver
+            .transaction(async |driver| -> anyhow::Result<Vec<Conflict>> {
+                AsyncComponentService::lock_calendars_of(driver, user).await?;
+                let conflicts = AsyncComponentService::import_conflicts_of(
+                    driver,
+                    user,
+                    &imported.simple_components(),
+                )
+                .await;
+                let conflicts = AsyncComponentService::accepted(driver, calendar_uuid, conflicts)
+                    .await
+                    .map_err(anyhow::Error::msg)?;
+                import::store_components_async(driver, imported).await?;
+                Ok(conflicts)
+            })
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate(calendar_uuid);
+
+        Ok(import.report(conflicts))
+    }
+
+    /// Exports calendars of the given user to an iCalendar file, see
+    /// `CalendarService::export_ics`.
+    pub async fn export_ics(
+        user: &User,
+        calendar_uuids: Vec<Uuid>,
+        range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    ) -> Result<String, &'static str> {
+        if range.is_some_and(|(from, to)| to < from) {
+            return Err(INVALID_TIME_RANGE_ERR);
+        }
+        let mut driver_binding = async_driver().await?;
+        let calendars = AsyncUserCalendarCombinationRepository::get_calendars_of_user(
+            driver_binding.deref_mut(),
+            user,
+        )
+        .await;
+        let calendar_uuids = accessible(&calendars, calendar_uuids)?;
+
+        let mut simple_calendars = Vec::with_capacity(calendar_uuids.len());
+        for calendar in calendars {
+            if !calendar_uuids.contains(&calendar.uuid) {
+                continue;
+            }
+            simple_calendars.push(match range {
+                Some((from, to)) => {
+                    SimpleCalendar::build_in_range_async(
+                        driver_binding.deref_mut(),
+                        calendar,
+                        from,
+                        to,
+                    )
+                    .await
+                }
+                None => SimpleCalendar::build_async(driver_binding.deref_mut(), calendar).await,
+            });
+        }
+        let alarms = AsyncAlarmRepository::get_alarms_of_user(driver_binding.deref_mut(), user)
+            .await
+            .map_err(|e| to_user_message(&e))?
+            .into_iter()
+            .map(|(_, alarm)| alarm)
+            .collect::<Vec<_>>();
+
+        Ok(export::write_with_alarms(&simple_calendars, &alarms))
+    }
+}
+
+/// Caches the calendars loaded for the given user. While the database is offline, nothing could
+/// be loaded, so the cached calendars are kept and returned instead.
+fn store_if_online(
+    cache: &CalendarCache,
+    user: &User,
+    calendars: Vec<SimpleCalendar>,
+) -> Vec<SimpleCalendar> {
+    if health::connection_state() == ConnectionState::Offline {
+        if let Some(cached) = cache.calendars_of(user.get_uuid()).value() {
+            return cached;
+        }
+    }
+    cache.store_calendars_of(user.get_uuid(), &calendars);
+    calendars
+}
+
+/// The cached calendars of the given user, even outdated ones, for when none can be loaded.
+fn cached_calendars(cache: &CalendarCache, user: &User) -> Vec<SimpleCalendar> {
+    cache
+        .calendars_of(user.get_uuid())
+        .value()
+        .unwrap_or_default()
+}
+
+/// Reads an iCalendar file to import, see `import::read`.
+fn read_ics(contents: &str, name: &str) -> Result<Import, &'static str> {
+    import::read(contents, name).map_err(|e| {
+        log::warn!("Could not import an iCalendar file: {}", e);
+        INVALID_ICS_ERR
+    })
+}
+
+/// Picks the requested calendar from the calendars of a user.
+///
+/// # Returns an error
+///
+/// * `CALENDAR_NOT_FOUND_ERR` - If the user has no access to the calendar.
+fn calendar_of(calendars: Vec<Calendar>, calendar_uuid: Uuid) -> Result<Calendar, &'static str> {
+    calendars
+        .into_iter()
+        .find(|calendar| calendar.uuid == calendar_uuid)
+        .ok_or(CALENDAR_NOT_FOUND_ERR)
+}
+
+/// Checks that the requested calendars are among the given ones of a user.
+///
+/// # Returns
+/// The requested calendars, or all given ones if none were requested.
+fn accessible(calendars: &[Calendar], requested: Vec<Uuid>) -> Result<Vec<Uuid>, &'static str> {
+    if requested.is_empty() {
+        return Ok(calendars.iter().map(|calendar| calendar.uuid).collect());
+    }
+    match requested
+        .iter()
+        .all(|uuid| calendars.iter().any(|calendar| calendar.uuid == *uuid))
+    {
+        true => Ok(requested),
+        false => Err(CALENDAR_NOT_FOUND_ERR),
+    }
+}
+
+/// Matches the access of the given user to the given calendar.
+pub(crate) fn access_of(user: &User, calendar_uuid: Uuid) -> Query {
+    Query::from(
+        UserCalendarCombinationRepository::USER_UUID
+            .eq(user.get_uuid())
+            .and(UserCalendarCombinationRepository::CALENDAR_UUID.eq(calendar_uuid)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::Migrations;
+    use crate::errors::error_messages::CONFLICTING_EVENTS_ERR;
+    use pg_driver::DriverConfig;
+
+    const MEETING: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:meeting\r\n\
+        SUMMARY:Meeting\r\n\
+        DTSTART:20240501T090000Z\r\n\
+        DTEND:20240501T100000Z\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_import_ics_into() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+        Migrations::run(&mut driver).unwrap();
+
+        let user = User::new(
+            String::from("someone"),
+            String::from("someone@example.com"),
+            String::from("secret"),
+        );
+        let mut calendar = Calendar::new(String::from("Room"));
+        calendar.conflict_mode = ConflictMode::HARD;
+        UserRepository::store(&mut driver, &user).unwrap();
+        CalendarRepository::store(&mut driver, &calendar).unwrap();
+        UserCalendarCombinationRepository::store(
+            &mut driver,
+            &UserCalendarCombination::new(user.get_uuid(), calendar.uuid),
+        )
+        .unwrap();
+
+        let report =
+            CalendarService::import_ics_into_with(&mut driver, &user, calendar.uuid, MEETING)
+                .unwrap();
+        assert_eq!(report.calendars[0].uuid, calendar.uuid);
+        assert_eq!(report.calendars[0].components, 1);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(
+            CalendarService::import_ics_into_with(&mut driver, &user, calendar.uuid, MEETING),
+            Err(CONFLICTING_EVENTS_ERR)
+        );
+        assert_eq!(
+            SimpleComponent::build_by_calendar(&mut driver, &calendar).len(),
+            1
+        );
+
+        calendar.conflict_mode = ConflictMode::SOFT;
+        CalendarRepository::update(&mut driver, &calendar).unwrap();
+        let report =
+            CalendarService::import_ics_into_with(&mut driver, &user, calendar.uuid, MEETING)
+                .unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].summary.as_deref(), Some("Meeting"));
+        assert_eq!(
+            CalendarService::import_ics_into_with(&mut driver, &user, Uuid::new_v4(), MEETING),
+            Err(CALENDAR_NOT_FOUND_ERR)
+        );
 
-        simple_user_calendars
+        drop(driver);
+        let _ = std::fs::remove_file(path);
     }
 }
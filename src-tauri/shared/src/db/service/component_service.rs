@@ -1,3 +1,962 @@
+use crate::current::{self, async_driver, driver};
+use crate::db::async_db_actions::AsyncDbActions;
+use crate::db::db_actions::DbActions;
+use crate::db::model::calendar::Calendar;
+use crate::db::model::component::Component;
+use crate::db::model::conflict::{self, Conflict, ConflictMode, Stored};
+use crate::db::model::property::Property;
+use crate::db::model::series::{ComponentChanges, EditScope, Series};
+use crate::db::model::simple::simple_component::{self, SimpleComponent};
+use crate::db::model::typed::TypedComponent;
+use crate::db::model::user::User;
+use crate::db::repository::calendar_repository::{AsyncCalendarRepository, CalendarRepository};
+use crate::db::repository::component_repository::{AsyncComponentRepository, ComponentRepository};
+use crate::db::repository::property_repository::{AsyncPropertyRepository, PropertyRepository};
+use crate::db::repository::user_calendar_combination_repository::{
+    AsyncUserCalendarCombinationRepository, UserCalendarCombinationRepository,
+};
+use crate::db::service::calendar_service::access_of;
+use crate::errors::error_messages::{
+    CALENDAR_NOT_FOUND_ERR, COMPONENT_NOT_FOUND_ERR, CONFLICTING_EVENTS_ERR, INVALID_COMPONENT_ERR,
+    UNSUPPORTED_COMPONENT_ERR,
+};
+use crate::errors::error_utils::to_user_message;
+use chrono::{DateTime, TimeDelta, Utc};
+use pg_driver::{AsyncPgDriver, PgDriver, Query};
+use std::ops::DerefMut;
+use std::slice;
+use uuid::Uuid;
+
+/// How long after the first changed occurrence recurring events are checked for conflicts.
+pub const CONFLICT_WINDOW_DAYS: i64 = 365;
+
 pub struct ComponentService;
 
-impl ComponentService {}
+/// The async counterpart of `ComponentService`.
+pub struct AsyncComponentService;
+
+impl ComponentService {
+    /// Edits occurrences of a component of one of the calendars of the given user, like moving
+    /// a single meeting of a series, or all of the following ones. See `Series::edit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user editing the component.
+    /// * `component_uuid` - The component to edit, a recurring one or one of its overrides.
+    /// * `recurrence_id` - The start of the edited occurrence in its series, see
+    ///   `SimpleComponent::get_recurrence_id`. Ignored for components that don't recur.
+    /// * `scope` - Which occurrences to edit.
+    /// * `changes` - The changes to the edited occurrence.
+    ///
+    /// # Returns
+    /// The events the edited occurrences now overlap, see `check_occurrence`.
+    ///
+    /// # Returns an error
+    ///
+    /// * `COMPONENT_NOT_FOUND_ERR` - If the user has no access to the component.
+    /// * `OCCURRENCE_NOT_FOUND_ERR` - If the component doesn't occur at `recurrence_id`.
+    /// * `CONFLICTING_EVENTS_ERR` - If the edited occurrences overlap other events and the
+    ///   calendar of the component is a `ConflictMode::HARD` one.
+    /// * `QUERY_FAILED_ERR` - If storing the changes failed.
+    pub fn edit_occurrence(
+        user: &User,
+        component_uuid: Uuid,
+        recurrence_id: DateTime<Utc>,
+        scope: EditScope,
+        changes: &ComponentChanges,
+    ) -> Result<Vec<Conflict>, &'static str> {
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let series = Self::series_of(driver, user, component_uuid)?;
+        let planned = series.edit(recurrence_id, scope, changes)?;
+        let calendar_uuid = series.master.calendar_uuid;
+        let conflicts = driver
+            .transaction(|driver| -> anyhow::Result<Vec<Conflict>> {
+                // Checked while the calendars are locked, so concurrent changes can't overlap
+                Self::lock_calendars_of(driver, user)?;
+                let conflicts = Self::conflicts_of(
+                    driver,
+                    user,
+                    &series.components(),
+                    &planned.applied_to(&series),
+                    edited_from(&series, recurrence_id, changes),
+                );
+                let conflicts =
+                    Self::accepted(driver, calendar_uuid, conflicts).map_err(anyhow::Error::msg)?;
+                planned.apply(driver)?;
+                Ok(conflicts)
+            })
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate(calendar_uuid);
+
+        Ok(conflicts)
+    }
+
+    /// Finds the events an edit of occurrences would overlap, without storing it. Only the
+    /// occurrences the edit changes are checked, for `CONFLICT_WINDOW_DAYS` from the earliest
+    /// of them, against all calendars of the user. See `edit_occurrence`.
+    ///
+    /// # Returns an error
+    ///
+    /// * `COMPONENT_NOT_FOUND_ERR` - If the user has no access to the component.
+    /// * `OCCURRENCE_NOT_FOUND_ERR` - If the component doesn't occur at `recurrence_id`.
+    pub fn check_occurrence(
+        user: &User,
+        component_uuid: Uuid,
+        recurrence_id: DateTime<Utc>,
+        scope: EditScope,
+        changes: &ComponentChanges,
+    ) -> Result<Vec<Conflict>, &'static str> {
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let series = Self::series_of(driver, user, component_uuid)?;
+        let planned = series.edit(recurrence_id, scope, changes)?;
+        Ok(Self::conflicts_of(
+            driver,
+            user,
+            &series.components(),
+            &planned.applied_to(&series),
+            edited_from(&series, recurrence_id, changes),
+        ))
+    }
+
+    /// Cancels occurrences of a component of one of the calendars of the given user, or
+    /// removes it if it doesn't recur. See `Series::cancel`.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user cancelling the occurrences.
+    /// * `component_uuid` - The component to cancel, a recurring one or one of its overrides.
+    /// * `recurrence_id` - The start of the cancelled occurrence in its series. Ignored for
+    ///   components that don't recur.
+    /// * `scope` - Which occurrences to cancel.
+    ///
+    /// # Returns an error
+    ///
+    /// * `COMPONENT_NOT_FOUND_ERR` - If the user has no access to the component.
+    /// * `OCCURRENCE_NOT_FOUND_ERR` - If the component doesn't occur at `recurrence_id`.
+    /// * `QUERY_FAILED_ERR` - If storing the changes failed.
+    pub fn cancel_occurrence(
+        user: &User,
+        component_uuid: Uuid,
+        recurrence_id: DateTime<Utc>,
+        scope: EditScope,
+    ) -> Result<(), &'static str> {
+        let mut driver_binding = driver()?;
+        let series = Self::series_of(driver_binding.deref_mut(), user, component_uuid)?;
+        series
+            .cancel(recurrence_id, scope)?
+            .apply(driver_binding.deref_mut())
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate(series.master.calendar_uuid);
+
+        Ok(())
+    }
+
+    /// Builds the series a component belongs to, if the given user has access to it.
+    fn series_of(
+        driver: &mut PgDriver,
+        user: &User,
+        component_uuid: Uuid,
+    ) -> Result<Series, &'static str> {
+        let component = ComponentRepository::retrieve(driver, by_uuid(component_uuid))
+            .into_iter()
+            .next()
+            .ok_or(COMPONENT_NOT_FOUND_ERR)?;
+        let master = match component.recurrence_of {
+            Some(master_uuid) => ComponentRepository::retrieve(driver, by_uuid(master_uuid))
+                .into_iter()
+                .next()
+                .ok_or(COMPONENT_NOT_FOUND_ERR)?,
+            None => component,
+        };
+
+        let access = UserCalendarCombinationRepository::retrieve(
+            driver,
+            access_of(user, master.calendar_uuid),
+        );
+        if access.is_empty() {
+            return Err(COMPONENT_NOT_FOUND_ERR);
+        }
+        Ok(Series::build(driver, master))
+    }
+
+    /// Reads a component of one of the calendars of the given user into its typed view, like
+    /// an `Event`. Overrides of recurring components are read on their own.
+    ///
+    /// # Returns an error
+    ///
+    /// * `COMPONENT_NOT_FOUND_ERR` - If the user has no access to the component.
+    /// * `UNSUPPORTED_COMPONENT_ERR` - If there is no typed view of its type.
+    /// * `INVALID_COMPONENT_ERR` - If one of its properties is malformed.
+    pub fn get_typed(user: &User, component_uuid: Uuid) -> Result<TypedComponent, &'static str> {
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let component = ComponentRepository::retrieve(driver, by_uuid(component_uuid))
+            .into_iter()
+            .next()
+            .ok_or(COMPONENT_NOT_FOUND_ERR)?;
+        if !Self::has_access(driver, user, component.calendar_uuid) {
+            return Err(COMPONENT_NOT_FOUND_ERR);
+        }
+        let properties = PropertyRepository::retrieve(
+            driver,
+            Query::from(simple_component::owned_by(slice::from_ref(&component))),
+        );
+        typed(SimpleComponent::new(component, properties))
+    }
+
+    /// Stores a typed view of a component in one of the calendars of the given user, replacing
+    /// the properties it read if the component exists. Its other properties are kept.
+    ///
+    /// # Returns
+    /// The uuid of the component, a new one if the view had a nil uuid, along with the events
+    /// it now overlaps, see `check_typed`.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_COMPONENT_ERR` - If a field of the view is invalid.
+    /// * `CALENDAR_NOT_FOUND_ERR` - If the user has no access to the calendar of the view.
+    /// * `COMPONENT_NOT_FOUND_ERR` - If the user has no access to the existing component.
+    /// * `CONFLICTING_EVENTS_ERR` - If the component overlaps other events and its calendar is
+    ///   a `ConflictMode::HARD` one.
+    /// * `QUERY_FAILED_ERR` - If storing the component failed.
+    pub fn store_typed(user: &User, typed: &TypedComponent) -> Result<Stored, &'static str> {
+        let mut driver_binding = driver()?;
+        Self::store_typed_with(driver_binding.deref_mut(), user, typed)
+    }
+
+    /// Stores a typed view of a component with the given driver, see `store_typed`.
+    fn store_typed_with(
+        driver: &mut PgDriver,
+        user: &User,
+        typed: &TypedComponent,
+    ) -> Result<Stored, &'static str> {
+        let planned = Self::plan_typed(driver, user, typed)?;
+        let calendar_uuid = planned.component.calendar_uuid;
+        let conflicts = driver
+            .transaction(|driver| -> anyhow::Result<Vec<Conflict>> {
+                // Checked while the calendars are locked, so concurrent stores can't overlap
+                Self::lock_calendars_of(driver, user)?;
+                let conflicts = Self::conflicts_of(
+                    driver,
+                    user,
+                    &planned.before(),
+                    &planned.after(),
+                    planned.component.starts_at,
+                );
+                let conflicts =
+                    Self::accepted(driver, calendar_uuid, conflicts).map_err(anyhow::Error::msg)?;
+                planned.store(driver)?;
+                Ok(conflicts)
+            })
+            .map_err(|e| to_user_message(&e))?;
+        invalidate(&planned.component, planned.existing.as_ref());
+
+        Ok(Stored {
+            uuid: planned.component.uuid,
+            conflicts,
+        })
+    }
+
+    /// Finds the events a typed view of a component would overlap, without storing it. Its
+    /// occurrences are checked for `CONFLICT_WINDOW_DAYS` from its start, against all
+    /// calendars of the user. See `store_typed`.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_COMPONENT_ERR` - If a field of the view is invalid.
+    /// * `CALENDAR_NOT_FOUND_ERR` - If the user has no access to the calendar of the view.
+    /// * `COMPONENT_NOT_FOUND_ERR` - If the user has no access to the existing component.
+    pub fn check_typed(user: &User, typed: &TypedComponent) -> Result<Vec<Conflict>, &'static str> {
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let planned = Self::plan_typed(driver, user, typed)?;
+        Ok(Self::conflicts_of(
+            driver,
+            user,
+            &planned.before(),
+            &planned.after(),
+            planned.component.starts_at,
+        ))
+    }
+
+    /// Reads what storing a typed view of a component changes, if the given user may store it.
+    fn plan_typed(
+        driver: &mut PgDriver,
+        user: &User,
+        typed: &TypedComponent,
+    ) -> Result<PlannedStore, &'static str> {
+        let (component, properties) = to_component(typed)?;
+        if !Self::has_access(driver, user, component.calendar_uuid) {
+            return Err(CALENDAR_NOT_FOUND_ERR);
+        }
+        let existing = ComponentRepository::retrieve(driver, by_uuid(component.uuid))
+            .into_iter()
+            .next();
+        let stored_properties = match &existing {
+            Some(existing) => {
+                if !Self::has_access(driver, user, existing.calendar_uuid) {
+                    return Err(COMPONENT_NOT_FOUND_ERR);
+                }
+                PropertyRepository::retrieve(
+                    driver,
+                    Query::from(simple_component::owned_by(slice::from_ref(existing))),
+                )
+            }
+            None => vec![],
+        };
+        Ok(PlannedStore::new(
+            component,
+            properties,
+            existing,
+            stored_properties,
+        ))
+    }
+
+    /// Finds the events changed components overlap, see `conflict::find_conflicts`. Only
+    /// the occurrences that change are checked, for `CONFLICT_WINDOW_DAYS` from `from`, against
+    /// all calendars of the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - The changed components before the change, with their properties.
+    /// * `after` - The changed components after the change.
+    /// * `from` - The start of the checked window, None if the components have no time.
+    fn conflicts_of(
+        driver: &mut PgDriver,
+        user: &User,
+        before: &[SimpleComponent],
+        after: &[SimpleComponent],
+        from: Option<DateTime<Utc>>,
+    ) -> Vec<Conflict> {
+        let Some((from, to, changed)) = changed_in_window(before, after, from) else {
+            return vec![];
+        };
+        let calendar_uuids = uuids_of_calendars(
+            UserCalendarCombinationRepository::get_calendars_of_user(driver, user),
+        );
+        let others = SimpleComponent::build_in_range(driver, &calendar_uuids, from, to);
+        conflict::find_conflicts(&changed, &others, &uuids_of(before, after))
+    }
+
+    /// Finds the events imported components would overlap, including each other, see
+    /// `conflict::find_import_conflicts`. Their occurrences are checked from the earliest start
+    /// until `CONFLICT_WINDOW_DAYS` after the latest one, against all calendars of the user.
+    pub(crate) fn import_conflicts_of(
+        driver: &mut PgDriver,
+        user: &User,
+        imported: &[SimpleComponent],
+    ) -> Vec<Conflict> {
+        let Some((from, to)) = import_window(imported) else {
+            return vec![];
+        };
+        let calendar_uuids = uuids_of_calendars(
+            UserCalendarCombinationRepository::get_calendars_of_user(driver, user),
+        );
+        let stored = SimpleComponent::build_in_range(driver, &calendar_uuids, from, to);
+        conflict::find_import_conflicts(&simple_component::expand(imported, from, to), &stored)
+    }
+
+    /// Locks all calendars of the given user until the open transaction ends, see
+    /// `CalendarRepository::lock`. Changes are checked for conflicts against all of them, so
+    /// concurrent changes of any of them must wait for each other.
+    pub(crate) fn lock_calendars_of(driver: &mut PgDriver, user: &User) -> anyhow::Result<()> {
+        let calendar_uuids = uuids_of_calendars(
+            UserCalendarCombinationRepository::get_calendars_of_user(driver, user),
+        );
+        CalendarRepository::lock(driver, &calendar_uuids)
+    }
+
+    /// Passes the conflicts of a change to a calendar on, unless the calendar rejects them.
+    pub(crate) fn accepted(
+        driver: &mut PgDriver,
+        calendar_uuid: Uuid,
+        conflicts: Vec<Conflict>,
+    ) -> Result<Vec<Conflict>, &'static str> {
+        if conflicts.is_empty() {
+            return Ok(conflicts);
+        }
+        let calendar = CalendarRepository::retrieve(driver, calendar_by_uuid(calendar_uuid));
+        accepted_by(calendar.first(), conflicts)
+    }
+
+    /// Whether the given user has access to a calendar.
+    fn has_access(driver: &mut PgDriver, user: &User, calendar_uuid: Uuid) -> bool {
+        !UserCalendarCombinationRepository::retrieve(driver, access_of(user, calendar_uuid))
+            .is_empty()
+    }
+}
+
+impl AsyncComponentService {
+    /// Edits occurrences of a component of one of the calendars of the given user, see
+    /// `ComponentService::edit_occurrence`.
+    pub async fn edit_occurrence(
+        user: &User,
+        component_uuid: Uuid,
+        recurrence_id: DateTime<Utc>,
+        scope: EditScope,
+        changes: &ComponentChanges,
+    ) -> Result<Vec<Conflict>, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let series = Self::series_of(driver, user, component_uuid).await?;
+        let planned = series.edit(recurrence_id, scope, changes)?;
+        let calendar_uuid = series.master.calendar_uuid;
+        let conflicts = driver
+            .transaction(async |driver| -> anyhow::Result<Vec<Conflict>> {
+                Self::lock_calendars_of(driver, user).await?;
+                let conflicts = Self::conflicts_of(
+                    driver,
+                    user,
+                    &series.components(),
+                    &planned.applied_to(&series),
+                    edited_from(&series, recurrence_id, changes),
+                )
+                .await;
+                let conflicts = Self::accepted(driver, calendar_uuid, conflicts)
+                    .await
+                    .map_err(anyhow::Error::msg)?;
+                planned.apply_async(driver).await?;
+                Ok(conflicts)
+            })
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate(calendar_uuid);
+
+        Ok(conflicts)
+    }
+
+    /// Finds the events an edit of occurrences would overlap, see
+    /// `ComponentService::check_occurrence`.
+    pub async fn check_occurrence(
+        user: &User,
+        component_uuid: Uuid,
+        recurrence_id: DateTime<Utc>,
+        scope: EditScope,
+        changes: &ComponentChanges,
+    ) -> Result<Vec<Conflict>, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let series = Self::series_of(driver, user, component_uuid).await?;
+        let planned = series.edit(recurrence_id, scope, changes)?;
+        Ok(Self::conflicts_of(
+            driver,
+            user,
+            &series.components(),
+            &planned.applied_to(&series),
+            edited_from(&series, recurrence_id, changes),
+        )
+        .await)
+    }
+
+    /// Cancels occurrences of a component of one of the calendars of the given user, see
+    /// `ComponentService::cancel_occurrence`.
+    pub async fn cancel_occurrence(
+        user: &User,
+        component_uuid: Uuid,
+        recurrence_id: DateTime<Utc>,
+        scope: EditScope,
+    ) -> Result<(), &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let series = Self::series_of(driver_binding.deref_mut(), user, component_uuid).await?;
+        series
+            .cancel(recurrence_id, scope)?
+            .apply_async(driver_binding.deref_mut())
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        current::calendar_cache().invalidate(series.master.calendar_uuid);
+
+        Ok(())
+    }
+
+    /// The async counterpart of `ComponentService::series_of`.
+    async fn series_of(
+        driver: &mut AsyncPgDriver,
+        user: &User,
+        component_uuid: Uuid,
+    ) -> Result<Series, &'static str> {
+        let component = AsyncComponentRepository::retrieve(driver, by_uuid(component_uuid))
+            .await
+            .into_iter()
+            .next()
+            .ok_or(COMPONENT_NOT_FOUND_ERR)?;
+        let master = match component.recurrence_of {
+            Some(master_uuid) => AsyncComponentRepository::retrieve(driver, by_uuid(master_uuid))
+                .await
+                .into_iter()
+                .next()
+                .ok_or(COMPONENT_NOT_FOUND_ERR)?,
+            None => component,
+        };
+
+        let access = AsyncUserCalendarCombinationRepository::retrieve(
+            driver,
+            access_of(user, master.calendar_uuid),
+        )
+        .await;
+        if access.is_empty() {
+            return Err(COMPONENT_NOT_FOUND_ERR);
+        }
+        Ok(Series::build_async(driver, master).await)
+    }
+
+    /// Reads a component of one of the calendars of the given user into its typed view, see
+    /// `ComponentService::get_typed`.
+    pub async fn get_typed(
+        user: &User,
+        component_uuid: Uuid,
+    ) -> Result<TypedComponent, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let component = AsyncComponentRepository::retrieve(driver, by_uuid(component_uuid))
+            .await
+            .into_iter()
+            .next()
+            .ok_or(COMPONENT_NOT_FOUND_ERR)?;
+        if !Self::has_access(driver, user, component.calendar_uuid).await {
+            return Err(COMPONENT_NOT_FOUND_ERR);
+        }
+        let properties = AsyncPropertyRepository::retrieve(
+            driver,
+            Query::from(simple_component::owned_by(slice::from_ref(&component))),
+        )
+        .await;
+        typed(SimpleComponent::new(component, properties))
+    }
+
+    /// Stores a typed view of a component in one of the calendars of the given user, see
+    /// `ComponentService::store_typed`.
+    pub async fn store_typed(user: &User, typed: &TypedComponent) -> Result<Stored, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let planned = Self::plan_typed(driver, user, typed).await?;
+        let calendar_uuid = planned.component.calendar_uuid;
+        let conflicts = driver
+            .transaction(async |driver| -> anyhow::Result<Vec<Conflict>> {
+                Self::lock_calendars_of(driver, user).await?;
+                let conflicts = Self::conflicts_of(
+                    driver,
+                    user,
+                    &planned.before(),
+                    &planned.after(),
+                    planned.component.starts_at,
+                )
+                .await;
+                let conflicts = Self::accepted(driver, calendar_uuid, conflicts)
+                    .await
+                    .map_err(anyhow::Error::msg)?;
+                planned.store_async(driver).await?;
+                Ok(conflicts)
+            })
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        invalidate(&planned.component, planned.existing.as_ref());
+
+        Ok(Stored {
+            uuid: planned.component.uuid,
+            conflicts,
+        })
+    }
+
+    /// Finds the events a typed view of a component would overlap, see
+    /// `ComponentService::check_typed`.
+    pub async fn check_typed(
+        user: &User,
+        typed: &TypedComponent,
+    ) -> Result<Vec<Conflict>, &'static str> {
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let planned = Self::plan_typed(driver, user, typed).await?;
+        Ok(Self::conflicts_of(
+            driver,
+            user,
+            &planned.before(),
+            &planned.after(),
+            planned.component.starts_at,
+        )
+        .await)
+    }
+
+    /// The async counterpart of `ComponentService::plan_typed`.
+    async fn plan_typed(
+        driver: &mut AsyncPgDriver,
+        user: &User,
+        typed: &TypedComponent,
+    ) -> Result<PlannedStore, &'static str> {
+        let (component, properties) = to_component(typed)?;
+        if !Self::has_access(driver, user, component.calendar_uuid).await {
+            return Err(CALENDAR_NOT_FOUND_ERR);
+        }
+        let existing = AsyncComponentRepository::retrieve(driver, by_uuid(component.uuid))
+            .await
+            .into_iter()
+            .next();
+        let stored_properties = match &existing {
+            Some(existing) => {
+                if !Self::has_access(driver, user, existing.calendar_uuid).await {
+                    return Err(COMPONENT_NOT_FOUND_ERR);
+                }
+                AsyncPropertyRepository::retrieve(
+                    driver,
+                    Query::from(simple_component::owned_by(slice::from_ref(existing))),
+                )
+                .await
+            }
+            None => vec![],
+        };
+        Ok(PlannedStore::new(
+            component,
+            properties,
+            existing,
+            stored_properties,
+        ))
+    }
+
+    /// The async counterpart of `ComponentService::conflicts_of`.
+    async fn conflicts_of(
+        driver: &mut AsyncPgDriver,
+        user: &User,
+        before: &[SimpleComponent],
+        after: &[SimpleComponent],
+        from: Option<DateTime<Utc>>,
+    ) -> Vec<Conflict> {
+        let Some((from, to, changed)) = changed_in_window(before, after, from) else {
+            return vec![];
+        };
+        let calendar_uuids = uuids_of_calendars(
+            AsyncUserCalendarCombinationRepository::get_calendars_of_user(driver, user).await,
+        );
+        let others = SimpleComponent::build_in_range_async(driver, &calendar_uuids, from, to).await;
+        conflict::find_conflicts(&changed, &others, &uuids_of(before, after))
+    }
+
+    /// The async counterpart of `ComponentService::import_conflicts_of`.
+    pub(crate) async fn import_conflicts_of(
+        driver: &mut AsyncPgDriver,
+        user: &User,
+        imported: &[SimpleComponent],
+    ) -> Vec<Conflict> {
+        let Some((from, to)) = import_window(imported) else {
+            return vec![];
+        };
+        let calendar_uuids = uuids_of_calendars(
+            AsyncUserCalendarCombinationRepository::get_calendars_of_user(driver, user).await,
+        );
+        let stored = SimpleComponent::build_in_range_async(driver, &calendar_uuids, from, to).await;
+        conflict::find_import_conflicts(&simple_component::expand(imported, from, to), &stored)
+    }
+
+    /// The async counterpart of `ComponentService::lock_calendars_of`.
+    pub(crate) async fn lock_calendars_of(
+        driver: &mut AsyncPgDriver,
+        user: &User,
+    ) -> anyhow::Result<()> {
+        let calendar_uuids = uuids_of_calendars(
+            AsyncUserCalendarCombinationRepository::get_calendars_of_user(driver, user).await,
+        );
+        AsyncCalendarRepository::lock(driver, &calendar_uuids).await
+    }
+
+    /// The async counterpart of `ComponentService::accepted`.
+    pub(crate) async fn accepted(
+        driver: &mut AsyncPgDriver,
+        calendar_uuid: Uuid,
+        conflicts: Vec<Conflict>,
+    ) -> Result<Vec<Conflict>, &'static str> {
+        if conflicts.is_empty() {
+            return Ok(conflicts);
+        }
+        let calendar =
+            AsyncCalendarRepository::retrieve(driver, calendar_by_uuid(calendar_uuid)).await;
+        accepted_by(calendar.first(), conflicts)
+    }
+
+    /// The async counterpart of `ComponentService::has_access`.
+    async fn has_access(driver: &mut AsyncPgDriver, user: &User, calendar_uuid: Uuid) -> bool {
+        !AsyncUserCalendarCombinationRepository::retrieve(driver, access_of(user, calendar_uuid))
+            .await
+            .is_empty()
+    }
+}
+
+/// What storing a typed view of a component changes, see `ComponentService::store_typed`.
+struct PlannedStore {
+    component: Component,
+    properties: Vec<Property>,
+    /// The stored component, None if the view is a new one.
+    existing: Option<Component>,
+    stored_properties: Vec<Property>,
+}
+
+impl PlannedStore {
+    /// Plans to store a component over the existing one, keeping its place in its series,
+    /// which views don't hold.
+    fn new(
+        mut component: Component,
+        properties: Vec<Property>,
+        existing: Option<Component>,
+        stored_properties: Vec<Property>,
+    ) -> Self {
+        if let Some(existing) = &existing {
+            component.recurrence_of = existing.recurrence_of;
+            component.recurrence_id = existing.recurrence_id;
+        }
+        Self {
+            component,
+            properties,
+            existing,
+            stored_properties,
+        }
+    }
+
+    /// The stored component with its properties, if there is one.
+    fn before(&self) -> Vec<SimpleComponent> {
+        self.existing
+            .iter()
+            .map(|existing| SimpleComponent::new(existing.clone(), self.stored_properties.clone()))
+            .collect()
+    }
+
+    /// The component as it is stored.
+    fn after(&self) -> Vec<SimpleComponent> {
+        vec![SimpleComponent::new(
+            self.component.clone(),
+            self.properties.clone(),
+        )]
+    }
+
+    /// Stores the component, replacing the properties that changed.
+    fn store(&self, driver: &mut PgDriver) -> anyhow::Result<()> {
+        match self.existing {
+            Some(_) => ComponentRepository::update(driver, &self.component)?,
+            None => ComponentRepository::store(driver, &self.component)?,
+        }
+        for property in self.removed_properties() {
+            PropertyRepository::remove(driver, property)?;
+        }
+        for property in self.added_properties() {
+            PropertyRepository::store(driver, property)?;
+        }
+        Ok(())
+    }
+
+    /// The async counterpart of `store`.
+    async fn store_async(&self, driver: &mut AsyncPgDriver) -> anyhow::Result<()> {
+        match self.existing {
+            Some(_) => AsyncComponentRepository::update(driver, &self.component).await?,
+            None => AsyncComponentRepository::store(driver, &self.component).await?,
+        }
+        for property in self.removed_properties() {
+            AsyncPropertyRepository::remove(driver, property).await?;
+        }
+        for property in self.added_properties() {
+            AsyncPropertyRepository::store(driver, property).await?;
+        }
+        Ok(())
+    }
+
+    /// The stored properties the view doesn't have anymore.
+    fn removed_properties(&self) -> impl Iterator<Item = &Property> {
+        self.stored_properties
+            .iter()
+            .filter(|property| !self.properties.contains(property))
+    }
+
+    /// The properties of the view that aren't stored yet.
+    fn added_properties(&self) -> impl Iterator<Item = &Property> {
+        self.properties
+            .iter()
+            .filter(|property| !self.stored_properties.contains(property))
+    }
+}
+
+/// Matches the component with the given uuid.
+fn by_uuid(uuid: Uuid) -> Query {
+    Query::from(ComponentRepository::UUID.eq(uuid)).limit(1)
+}
+
+/// Matches the calendar with the given uuid.
+fn calendar_by_uuid(uuid: Uuid) -> Query {
+    Query::from(CalendarRepository::UUID.eq(uuid)).limit(1)
+}
+
+/// The start of the first occurrence an edit changes, None if the series has no time.
+fn edited_from(
+    series: &Series,
+    recurrence_id: DateTime<Utc>,
+    changes: &ComponentChanges,
+) -> Option<DateTime<Utc>> {
+    let edited = match series.recurs_at(recurrence_id) {
+        Ok(true) => Some(recurrence_id),
+        _ => series.master.starts_at,
+    }?;
+    Some(
+        changes
+            .starts_at
+            .map_or(edited, |starts_at| starts_at.min(edited)),
+    )
+}
+
+/// The occurrences a change moves, within the window they are checked for conflicts in, see
+/// `ComponentService::conflicts_of`.
+///
+/// # Returns
+/// The window and the changed occurrences in it, None if no occurrence that takes time
+/// changes.
+fn changed_in_window(
+    before: &[SimpleComponent],
+    after: &[SimpleComponent],
+    from: Option<DateTime<Utc>>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>, Vec<SimpleComponent>)> {
+    let from = from?;
+    let to = from + TimeDelta::days(CONFLICT_WINDOW_DAYS);
+    let changed = conflict::changed_occurrences(
+        &simple_component::expand(before, from, to),
+        &simple_component::expand(after, from, to),
+    );
+    (!changed.is_empty()).then_some((from, to, changed))
+}
+
+/// Passes the conflicts of a change to the given calendar on, unless it rejects them.
+fn accepted_by(
+    calendar: Option<&Calendar>,
+    conflicts: Vec<Conflict>,
+) -> Result<Vec<Conflict>, &'static str> {
+    match calendar.map(|calendar| calendar.conflict_mode) {
+        Some(ConflictMode::HARD) if !conflicts.is_empty() => Err(CONFLICTING_EVENTS_ERR),
+        _ => Ok(conflicts),
+    }
+}
+
+/// The uuids of the given calendars.
+fn uuids_of_calendars(calendars: Vec<Calendar>) -> Vec<Uuid> {
+    calendars.iter().map(|calendar| calendar.uuid).collect()
+}
+
+/// The time imported components are checked for conflicts in, None if none of them starts.
+fn import_window(imported: &[SimpleComponent]) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let starts = imported
+        .iter()
+        .filter_map(|component| component.get_starts_at());
+    let from = starts.clone().min()?;
+    let to = starts.max()? + TimeDelta::days(CONFLICT_WINDOW_DAYS);
+    Some((from, to))
+}
+
+/// The uuids of the components before and after a change.
+fn uuids_of(before: &[SimpleComponent], after: &[SimpleComponent]) -> Vec<Uuid> {
+    before
+        .iter()
+        .chain(after)
+        .map(SimpleComponent::get_uuid)
+        .collect()
+}
+
+/// Reads a component into its typed view.
+fn typed(component: SimpleComponent) -> Result<TypedComponent, &'static str> {
+    match TypedComponent::from_component(&component) {
+        Ok(Some(typed)) => Ok(typed),
+        Ok(None) => Err(UNSUPPORTED_COMPONENT_ERR),
+        Err(e) => {
+            log::warn!(
+                "Could not read the component {}: {}",
+                component.get_uuid(),
+                e
+            );
+            Err(INVALID_COMPONENT_ERR)
+        }
+    }
+}
+
+/// The component of a typed view along with its properties.
+fn to_component(typed: &TypedComponent) -> Result<(Component, Vec<Property>), &'static str> {
+    typed.to_component().map_err(|e| {
+        log::warn!("Could not store the component {}: {}", typed.uuid(), e);
+        INVALID_COMPONENT_ERR
+    })
+}
+
+/// Drops the cached calendars of a stored component, the one it was moved from included.
+fn invalidate(component: &Component, existing: Option<&Component>) {
+    current::calendar_cache().invalidate(component.calendar_uuid);
+    if let Some(existing) = existing.filter(|e| e.calendar_uuid != component.calendar_uuid) {
+        current::calendar_cache().invalidate(existing.calendar_uuid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::Migrations;
+    use crate::db::model::typed::{event::Event, End};
+    use crate::db::model::user_calendar_combination::UserCalendarCombination;
+    use crate::db::repository::user_repository::UserRepository;
+    use chrono::TimeZone;
+    use pg_driver::DriverConfig;
+
+    fn meeting(calendar_uuid: Uuid) -> TypedComponent {
+        TypedComponent::EVENT(Event {
+            uuid: Uuid::nil(),
+            calendar_uuid,
+            summary: Some(String::from("Meeting")),
+            description: None,
+            location: None,
+            status: None,
+            priority: None,
+            starts_at: Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap(),
+            end: Some(End::LASTING(3600)),
+            all_day: false,
+            time_zone: None,
+            other: vec![],
+        })
+    }
+
+    #[test]
+    fn test_store_typed_conflicts() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+        Migrations::run(&mut driver).unwrap();
+
+        let user = User::new(
+            String::from("someone"),
+            String::from("someone@example.com"),
+            String::from("secret"),
+        );
+        let mut calendar = Calendar::new(String::from("Room"));
+        calendar.conflict_mode = ConflictMode::HARD;
+        UserRepository::store(&mut driver, &user).unwrap();
+        CalendarRepository::store(&mut driver, &calendar).unwrap();
+        UserCalendarCombinationRepository::store(
+            &mut driver,
+            &UserCalendarCombination::new(user.get_uuid(), calendar.uuid),
+        )
+        .unwrap();
+
+        let stored =
+            ComponentService::store_typed_with(&mut driver, &user, &meeting(calendar.uuid))
+                .unwrap();
+        assert!(stored.conflicts.is_empty());
+        assert_eq!(
+            ComponentService::store_typed_with(&mut driver, &user, &meeting(calendar.uuid)),
+            Err(CONFLICTING_EVENTS_ERR)
+        );
+        let components = ComponentRepository::retrieve(
+            &mut driver,
+            ComponentRepository::CALENDAR_UUID.eq(calendar.uuid).into(),
+        );
+        assert_eq!(components.len(), 1);
+
+        calendar.conflict_mode = ConflictMode::SOFT;
+        CalendarRepository::update(&mut driver, &calendar).unwrap();
+        let stored =
+            ComponentService::store_typed_with(&mut driver, &user, &meeting(calendar.uuid))
+                .unwrap();
+        assert_eq!(stored.conflicts.len(), 1);
+        assert_eq!(stored.conflicts[0].component_uuid, components[0].uuid);
+
+        drop(driver);
+        let _ = std::fs::remove_file(path);
+    }
+}
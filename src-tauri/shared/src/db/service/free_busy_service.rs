@@ -0,0 +1,205 @@
+use crate::current::{async_driver, driver};
+use crate::db::model::free_busy::FreeBusy;
+use crate::db::model::simple::simple_component::SimpleComponent;
+use crate::db::model::user::User;
+use crate::db::repository::user_calendar_combination_repository::{
+    AsyncUserCalendarCombinationRepository, UserCalendarCombinationRepository,
+};
+use crate::errors::error_messages::{INVALID_TIME_RANGE_ERR, MEMBER_NOT_FOUND_ERR};
+use crate::ical::export;
+use chrono::{DateTime, Utc};
+use pg_driver::{AsyncPgDriver, PgDriver};
+use std::ops::DerefMut;
+use uuid::Uuid;
+
+pub struct FreeBusyService;
+
+/// The async counterpart of `FreeBusyService`.
+pub struct AsyncFreeBusyService;
+
+impl FreeBusyService {
+    /// Computes when users are busy within a time window, merged across all of their
+    /// calendars, see `FreeBusy::of`. Only the times are revealed, not what the users are busy
+    /// with, so calendars the requesting user can't read count as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user requesting the free/busy times.
+    /// * `user_uuids` - The users to compute the times of. They have to share a calendar with
+    ///   `user`, or be `user` itself. Only `user` if empty.
+    /// * `from` - The start of the window.
+    /// * `to` - The end of the window, exclusively.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_TIME_RANGE_ERR` - If the window ends before it starts.
+    /// * `MEMBER_NOT_FOUND_ERR` - If one of the users shares no calendar with `user`.
+    pub fn get_free_busy(
+        user: &User,
+        user_uuids: Vec<Uuid>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<FreeBusy, &'static str> {
+        if to < from {
+            return Err(INVALID_TIME_RANGE_ERR);
+        }
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+        let users = requested(Self::members(driver, user), user, user_uuids)?;
+        Ok(Self::free_busy_of(driver, &users, from, to))
+    }
+
+    /// Exports when users are busy within a time window as the content of an .ics file with a
+    /// VFREEBUSY, see `get_free_busy`.
+    pub fn export_free_busy(
+        user: &User,
+        user_uuids: Vec<Uuid>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<String, &'static str> {
+        let free_busy = Self::get_free_busy(user, user_uuids, from, to)?;
+        Ok(export::write_free_busy(&free_busy))
+    }
+
+    /// The given user and the users sharing a calendar with them, possibly several times.
+    pub(crate) fn members(driver: &mut PgDriver, user: &User) -> Vec<User> {
+        let mut members = vec![user.clone()];
+        for calendar in UserCalendarCombinationRepository::get_calendars_of_user(driver, user) {
+            members.extend(UserCalendarCombinationRepository::get_users_of_calendar(
+                driver,
+                calendar.uuid,
+            ));
+        }
+        members
+    }
+
+    /// Computes when the given users are busy, see `get_free_busy`.
+    pub(crate) fn free_busy_of(
+        driver: &mut PgDriver,
+        users: &[User],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> FreeBusy {
+        let mut calendar_uuids = vec![];
+        for user in users {
+            for calendar in UserCalendarCombinationRepository::get_calendars_of_user(driver, user) {
+                if !calendar_uuids.contains(&calendar.uuid) {
+                    calendar_uuids.push(calendar.uuid);
+                }
+            }
+        }
+        let occurrences = match calendar_uuids.is_empty() {
+            true => vec![],
+            false => SimpleComponent::build_in_range(driver, &calendar_uuids, from, to),
+        };
+        FreeBusy::of(
+            users.iter().map(User::get_uuid).collect(),
+            &occurrences,
+            from,
+            to,
+        )
+    }
+}
+
+impl AsyncFreeBusyService {
+    /// Computes when users are busy within a time window, see
+    /// `FreeBusyService::get_free_busy`.
+    pub async fn get_free_busy(
+        user: &User,
+        user_uuids: Vec<Uuid>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<FreeBusy, &'static str> {
+        if to < from {
+            return Err(INVALID_TIME_RANGE_ERR);
+        }
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+        let users = requested(Self::members(driver, user).await, user, user_uuids)?;
+        Ok(Self::free_busy_of(driver, &users, from, to).await)
+    }
+
+    /// Exports when users are busy within a time window as a VFREEBUSY, see
+    /// `FreeBusyService::export_free_busy`.
+    pub async fn export_free_busy(
+        user: &User,
+        user_uuids: Vec<Uuid>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<String, &'static str> {
+        let free_busy = Self::get_free_busy(user, user_uuids, from, to).await?;
+        Ok(export::write_free_busy(&free_busy))
+    }
+
+    /// The async counterpart of `FreeBusyService::members`.
+    pub(crate) async fn members(driver: &mut AsyncPgDriver, user: &User) -> Vec<User> {
+        let mut members = vec![user.clone()];
+        for calendar in
+            AsyncUserCalendarCombinationRepository::get_calendars_of_user(driver, user).await
+        {
+            members.extend(
+                AsyncUserCalendarCombinationRepository::get_users_of_calendar(
+                    driver,
+                    calendar.uuid,
+                )
+                .await,
+            );
+        }
+        members
+    }
+
+    /// The async counterpart of `FreeBusyService::free_busy_of`.
+    pub(crate) async fn free_busy_of(
+        driver: &mut AsyncPgDriver,
+        users: &[User],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> FreeBusy {
+        let mut calendar_uuids = vec![];
+        for user in users {
+            for calendar in
+                AsyncUserCalendarCombinationRepository::get_calendars_of_user(driver, user).await
+            {
+                if !calendar_uuids.contains(&calendar.uuid) {
+                    calendar_uuids.push(calendar.uuid);
+                }
+            }
+        }
+        let occurrences = match calendar_uuids.is_empty() {
+            true => vec![],
+            false => SimpleComponent::build_in_range_async(driver, &calendar_uuids, from, to).await,
+        };
+        FreeBusy::of(
+            users.iter().map(User::get_uuid).collect(),
+            &occurrences,
+            from,
+            to,
+        )
+    }
+}
+
+/// Picks the requested users among the members of the calendars of a user.
+///
+/// # Returns
+/// The requested users without duplicates, or only the given user if none were requested.
+pub(crate) fn requested(
+    members: Vec<User>,
+    user: &User,
+    requested: Vec<Uuid>,
+) -> Result<Vec<User>, &'static str> {
+    if requested.is_empty() {
+        return Ok(vec![user.clone()]);
+    }
+    let mut users: Vec<User> = vec![];
+    for uuid in requested {
+        if users.iter().any(|user| user.get_uuid() == uuid) {
+            continue;
+        }
+        let member = members
+            .iter()
+            .find(|member| member.get_uuid() == uuid)
+            .ok_or(MEMBER_NOT_FOUND_ERR)?;
+        users.push(member.clone());
+    }
+    Ok(users)
+}
@@ -1,4 +1,7 @@
+pub mod alarm_service;
 pub mod calendar_service;
 pub mod component_service;
+pub mod free_busy_service;
 pub mod property_service;
+pub mod scheduling_service;
 pub mod user_service;
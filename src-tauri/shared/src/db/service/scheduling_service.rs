@@ -0,0 +1,147 @@
+use crate::current::{async_driver, driver};
+use crate::db::model::scheduling::{self, Attendee, Slot, SlotRequest};
+use crate::db::model::user::User;
+use crate::db::service::free_busy_service::{requested, AsyncFreeBusyService, FreeBusyService};
+use crate::errors::error_messages::{INVALID_MEETING_DURATION_ERR, INVALID_TIME_RANGE_ERR};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::ops::DerefMut;
+use uuid::Uuid;
+
+/// The most slots proposed at once, larger requests are cut down to it.
+pub const MAX_SLOTS: usize = 50;
+
+/// The longest range searched for slots, longer ranges are cut down to it.
+pub const MAX_SLOT_WINDOW_DAYS: i64 = 62;
+
+pub struct SchedulingService;
+
+/// The async counterpart of `SchedulingService`.
+pub struct AsyncSchedulingService;
+
+impl SchedulingService {
+    /// Proposes the earliest slots a meeting can take place in, see `scheduling::find_slots`.
+    ///
+    /// A slot has to lie within the working hours of every required attendee, and keep their
+    /// meeting buffer free from their other events. Optional attendees rank slots they can
+    /// attend first, but never rule one out.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user planning the meeting. They always attend.
+    /// * `request` - The attendees, the duration and the range to search. The attendees have
+    ///   to share a calendar with `user`.
+    ///
+    /// # Returns
+    /// The slots, ranked.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_TIME_RANGE_ERR` - If the range ends before it starts.
+    /// * `INVALID_MEETING_DURATION_ERR` - If the meeting lasts less than a minute.
+    /// * `MEMBER_NOT_FOUND_ERR` - If one of the attendees shares no calendar with `user`.
+    pub fn find_slots(user: &User, request: &SlotRequest) -> Result<Vec<Slot>, &'static str> {
+        let (from, to, duration) = checked(request)?;
+        let mut driver_binding = driver()?;
+        let driver = driver_binding.deref_mut();
+
+        let members = FreeBusyService::members(driver, user);
+        let (required, optional) = attendee_uuids(user, request);
+        let mut attendees = vec![];
+        for (uuids, is_optional) in [(required, false), (optional, true)] {
+            if uuids.is_empty() {
+                continue;
+            }
+            for member in requested(members.clone(), user, uuids)? {
+                let buffer = TimeDelta::minutes(member.get_meeting_buffer().max(0) as i64);
+                let free_busy = FreeBusyService::free_busy_of(
+                    driver,
+                    std::slice::from_ref(&member),
+                    from - buffer,
+                    to + buffer,
+                );
+                attendees.push(Attendee::of(&member, is_optional, free_busy.busy, from, to));
+            }
+        }
+
+        Ok(scheduling::find_slots(
+            &attendees,
+            duration,
+            from,
+            to,
+            request.count.clamp(1, MAX_SLOTS),
+        ))
+    }
+}
+
+impl AsyncSchedulingService {
+    /// Proposes the earliest slots a meeting can take place in, see
+    /// `SchedulingService::find_slots`.
+    pub async fn find_slots(user: &User, request: &SlotRequest) -> Result<Vec<Slot>, &'static str> {
+        let (from, to, duration) = checked(request)?;
+        let mut driver_binding = async_driver().await?;
+        let driver = driver_binding.deref_mut();
+
+        let members = AsyncFreeBusyService::members(driver, user).await;
+        let (required, optional) = attendee_uuids(user, request);
+        let mut attendees = vec![];
+        for (uuids, is_optional) in [(required, false), (optional, true)] {
+            if uuids.is_empty() {
+                continue;
+            }
+            for member in requested(members.clone(), user, uuids)? {
+                let buffer = TimeDelta::minutes(member.get_meeting_buffer().max(0) as i64);
+                let free_busy = AsyncFreeBusyService::free_busy_of(
+                    driver,
+                    std::slice::from_ref(&member),
+                    from - buffer,
+                    to + buffer,
+                )
+                .await;
+                attendees.push(Attendee::of(&member, is_optional, free_busy.busy, from, to));
+            }
+        }
+
+        Ok(scheduling::find_slots(
+            &attendees,
+            duration,
+            from,
+            to,
+            request.count.clamp(1, MAX_SLOTS),
+        ))
+    }
+}
+
+/// Validates a request.
+///
+/// # Returns
+/// The searched range, cut down to `MAX_SLOT_WINDOW_DAYS`, and the duration of the meeting.
+fn checked(
+    request: &SlotRequest,
+) -> Result<(DateTime<Utc>, DateTime<Utc>, TimeDelta), &'static str> {
+    if request.to < request.from {
+        return Err(INVALID_TIME_RANGE_ERR);
+    }
+    if request.duration_minutes < 1 {
+        return Err(INVALID_MEETING_DURATION_ERR);
+    }
+    let duration =
+        TimeDelta::try_minutes(request.duration_minutes).ok_or(INVALID_MEETING_DURATION_ERR)?;
+    let to = request
+        .to
+        .min(request.from + TimeDelta::days(MAX_SLOT_WINDOW_DAYS));
+    Ok((request.from, to, duration))
+}
+
+/// The required attendees, the planning user first, and the optional ones who aren't required
+/// as well.
+fn attendee_uuids(user: &User, request: &SlotRequest) -> (Vec<Uuid>, Vec<Uuid>) {
+    let mut required = vec![user.get_uuid()];
+    required.extend(&request.required);
+    let optional = request
+        .optional
+        .iter()
+        .filter(|uuid| !required.contains(uuid))
+        .copied()
+        .collect::<Vec<_>>();
+    (required, optional)
+}
@@ -1,3 +1,144 @@
+use crate::current::{async_driver, driver, CURRENT_USER};
+use crate::db::async_db_actions::AsyncDbActions;
+use crate::db::db_actions::DbActions;
+use crate::db::model::scheduling::WorkingHours;
+use crate::db::model::user::User;
+use crate::db::repository::user_repository::{AsyncUserRepository, UserRepository};
+use crate::errors::error_messages::{INVALID_TIME_ZONE_ERR, INVALID_WORKING_HOURS_ERR};
+use crate::errors::error_utils::to_user_message;
+use crate::ical::zone;
+use std::ops::DerefMut;
+
 pub struct UserService;
 
-impl UserService {}
+/// The async counterpart of `UserService`.
+pub struct AsyncUserService;
+
+impl UserService {
+    /// Sets the zone the given user wants times shown in. The current user is updated if it
+    /// is them.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user to set the zone of.
+    /// * `time_zone` - The IANA zone, like `Europe/Berlin`, None for the zone of the device.
+    ///
+    /// # Returns
+    /// The updated user.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_TIME_ZONE_ERR` - If there is no IANA zone with the given name.
+    /// * `QUERY_FAILED_ERR` - If storing the zone failed.
+    pub fn set_time_zone(user: &User, time_zone: Option<&str>) -> Result<User, &'static str> {
+        let updated = with_time_zone(user, time_zone)?;
+        UserRepository::update(driver()?.deref_mut(), &updated).map_err(|e| to_user_message(&e))?;
+        refresh_current_user(&updated);
+
+        Ok(updated)
+    }
+
+    /// Sets when the given user can be proposed meetings at, see
+    /// `SchedulingService::find_slots`. The current user is updated if it is them.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user to set the preferences of.
+    /// * `working_hours` - The hours the user works, in their zone. None if they can meet at
+    ///   any time.
+    /// * `meeting_buffer` - How many minutes to keep free between a proposed meeting and the
+    ///   other events of the user.
+    ///
+    /// # Returns
+    /// The updated user.
+    ///
+    /// # Returns an error
+    ///
+    /// * `INVALID_WORKING_HOURS_ERR` - If the working hours are empty or the buffer negative.
+    /// * `QUERY_FAILED_ERR` - If storing the preferences failed.
+    pub fn set_scheduling_preferences(
+        user: &User,
+        working_hours: Option<&WorkingHours>,
+        meeting_buffer: i32,
+    ) -> Result<User, &'static str> {
+        let updated = with_scheduling_preferences(user, working_hours, meeting_buffer)?;
+        UserRepository::update(driver()?.deref_mut(), &updated).map_err(|e| to_user_message(&e))?;
+        refresh_current_user(&updated);
+
+        Ok(updated)
+    }
+}
+
+impl AsyncUserService {
+    /// Sets the zone the given user wants times shown in, see `UserService::set_time_zone`.
+    pub async fn set_time_zone(user: &User, time_zone: Option<&str>) -> Result<User, &'static str> {
+        let updated = with_time_zone(user, time_zone)?;
+        AsyncUserRepository::update(async_driver().await?.deref_mut(), &updated)
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        refresh_current_user(&updated);
+
+        Ok(updated)
+    }
+
+    /// Sets when the given user can be proposed meetings at, see
+    /// `UserService::set_scheduling_preferences`.
+    pub async fn set_scheduling_preferences(
+        user: &User,
+        working_hours: Option<&WorkingHours>,
+        meeting_buffer: i32,
+    ) -> Result<User, &'static str> {
+        let updated = with_scheduling_preferences(user, working_hours, meeting_buffer)?;
+        AsyncUserRepository::update(async_driver().await?.deref_mut(), &updated)
+            .await
+            .map_err(|e| to_user_message(&e))?;
+        refresh_current_user(&updated);
+
+        Ok(updated)
+    }
+}
+
+/// A copy of the user with the given zone, named like in the IANA database.
+fn with_time_zone(user: &User, time_zone: Option<&str>) -> Result<User, &'static str> {
+    let time_zone = match time_zone.filter(|time_zone| !time_zone.trim().is_empty()) {
+        Some(time_zone) => Some(
+            zone::parse_zone(time_zone)
+                .ok_or(INVALID_TIME_ZONE_ERR)?
+                .name()
+                .to_string(),
+        ),
+        None => None,
+    };
+    let mut updated = user.clone();
+    updated.set_time_zone(time_zone);
+    Ok(updated)
+}
+
+/// A copy of the user with the given scheduling preferences.
+fn with_scheduling_preferences(
+    user: &User,
+    working_hours: Option<&WorkingHours>,
+    meeting_buffer: i32,
+) -> Result<User, &'static str> {
+    if meeting_buffer < 0 || working_hours.is_some_and(|hours| !hours.is_valid()) {
+        return Err(INVALID_WORKING_HOURS_ERR);
+    }
+    let mut updated = user.clone();
+    updated.set_working_hours(working_hours);
+    updated.set_meeting_buffer(meeting_buffer);
+    Ok(updated)
+}
+
+/// Replaces the current user by the updated one, if it is the same user.
+fn refresh_current_user(updated: &User) {
+    let Some(current) = CURRENT_USER.get() else {
+        return;
+    };
+    let mut current = current.lock().unwrap();
+    if current
+        .as_ref()
+        .is_some_and(|user| user.get_uuid() == updated.get_uuid())
+    {
+        *current = Some(updated.clone());
+    }
+}
@@ -7,6 +7,28 @@ pub const ENV_VAR_NOT_SET: &str = "The environment variable attempted to access
 pub const USER_ALREADY_EXISTING_ERR: &str =
     "User is already existing. Try logging in with your account";
 pub const USER_NOT_FOUND_ERR: &str = "Email or password is incorrect";
+pub const NOT_LOGGED_IN_ERR: &str = "You are not logged in. Please log in first.";
+pub const INVALID_TIME_ZONE_ERR: &str =
+    "The time zone is unknown. Use an IANA zone, like Europe/Berlin.";
+pub const MEMBER_NOT_FOUND_ERR: &str = "The user does not exist or shares no calendar with you.";
+pub const INVALID_WORKING_HOURS_ERR: &str =
+    "The working hours are empty or the buffer between meetings is negative.";
+
+// Calendar errors
+pub const CALENDAR_NOT_FOUND_ERR: &str = "The calendar does not exist or you have no access to it.";
+pub const INVALID_TIME_RANGE_ERR: &str = "The end of the time range lies before its start.";
+pub const INVALID_ICS_ERR: &str = "The file is no valid iCalendar file.";
+pub const COMPONENT_NOT_FOUND_ERR: &str = "The entry does not exist or you have no access to it.";
+pub const OCCURRENCE_NOT_FOUND_ERR: &str = "The entry does not occur at the given time.";
+pub const INVALID_COMPONENT_ERR: &str = "The entry has malformed or contradicting properties.";
+pub const UNSUPPORTED_COMPONENT_ERR: &str = "The entry is neither an event, a to-do nor a venue.";
+pub const CONFLICTING_EVENTS_ERR: &str =
+    "The entry overlaps other events, which its calendar doesn't allow.";
+pub const INVALID_MEETING_DURATION_ERR: &str = "A meeting has to last at least one minute.";
+pub const ALARM_NOT_FOUND_ERR: &str = "The reminder does not exist or you have no access to it.";
+pub const INVALID_ALARM_ERR: &str =
+    "The reminder is malformed, fires too far from its entry or belongs to no event or to-do.";
+pub const INVALID_SNOOZE_ERR: &str = "A reminder can be snoozed for one minute up to one day.";
 
 // Encoding
 pub const BCRYPT_ENCODING_ERR: &str = "There was an error while an encoding process.";
@@ -18,4 +40,5 @@ pub const JWT_COOKIE_ERR: &str = "There was an error storing your session. Pleas
 pub const NO_DB_CONNECTION_ERR: &str =
     "Unable to connect to the database. Please connect a database.";
 pub const ERROR_QUEUE_NOT_INITIALIZED_ERR: &str = "No error queue running at the moment";
+pub const DB_POOL_EXHAUSTED_ERR: &str = "No database connection became available in time.";
 pub const QUERY_FAILED_ERR: &str = "The attempted database operation failed. Please try again.";
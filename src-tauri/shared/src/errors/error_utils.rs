@@ -1,6 +1,8 @@
 use tauri::{Emitter, Manager};
 
 use crate::current::get_app_handle;
+use crate::errors::error_messages::{NO_DB_CONNECTION_ERR, QUERY_FAILED_ERR};
+use pg_driver::{AbortedTransactionError, OfflineError};
 use std::time::Duration;
 
 pub enum ErrorCode {
@@ -72,3 +74,23 @@ impl ErrorHandler {
         Box::new(move || panic!("{}", message))
     }
 }
+
+/// Recovers the message shown to the user from an error that passed through a database
+/// operation, like a transaction. Errors that don't carry one of the `error_messages` are logged
+/// and reported as `QUERY_FAILED_ERR`, or `NO_DB_CONNECTION_ERR` if the database is offline or
+/// the connection was lost during a transaction.
+///
+/// # Params
+/// * `err` - The error to convert.
+pub fn to_user_message(err: &anyhow::Error) -> &'static str {
+    match err.downcast_ref::<&'static str>() {
+        Some(message) => message,
+        None if err.is::<OfflineError>() || err.is::<AbortedTransactionError>() => {
+            NO_DB_CONNECTION_ERR
+        }
+        None => {
+            log::error!("{:#}", err);
+            QUERY_FAILED_ERR
+        }
+    }
+}
@@ -0,0 +1,581 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+use crate::db::model::alarm::{Alarm, TriggerRelation};
+use crate::db::model::component::ComponentType;
+use crate::db::model::free_busy::FreeBusy;
+use crate::db::model::property::Property;
+use crate::db::model::simple::simple_calendar::SimpleCalendar;
+use crate::db::model::simple::simple_component::SimpleComponent;
+
+use super::import::TEXT_PROPERTIES;
+use super::parser;
+use super::zone;
+
+/// Identifies this app as the producer of exported files.
+pub const PRODID: &str = "-//ScheduleFlow//ScheduleFlow//EN";
+
+/// The longest a line may be, in octets and without the line break.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// The properties setting the times of a component.
+pub(crate) const TIME_PROPERTIES: &[&str] = &["DTSTART", "DTEND", "DUE", "DURATION"];
+
+/// The calendar properties written from the calendar itself instead of its stored properties.
+const OWN_CALENDAR_PROPERTIES: &[&str] = &["VERSION", "PRODID", "X-WR-CALNAME"];
+
+/// How many years after the latest start, or the export if later, VTIMEZONEs describe, so
+/// series going on from there are covered.
+const ZONE_YEARS_AHEAD: i32 = 5;
+
+/// Writes the given calendars to an iCalendar file, one VCALENDAR each.
+///
+/// Stored properties are written as they were imported, so unknown properties and parameters
+/// are kept. Components without a UID get one derived from their uuid, components without
+/// stored times get them from their start and end. Every zone referred to by a TZID gets a
+/// VTIMEZONE.
+///
+/// # Examples
+///
+/// ```
+/// let calendar = SimpleCalendar::build(driver()?.deref_mut(), calendar);
+/// std::fs::write("calendar.ics", export::write(&[calendar]))?;
+/// ```
+pub fn write(calendars: &[SimpleCalendar]) -> String {
+    write_with_alarms(calendars, &[])
+}
+
+/// Like `write`, with the given alarms as VALARMs of their components.
+pub fn write_with_alarms(calendars: &[SimpleCalendar], alarms: &[Alarm]) -> String {
+    write_stamped(calendars, alarms, Utc::now())
+}
+
+/// Like `write_with_alarms`, with the given time as DTSTAMP of components without one.
+fn write_stamped(calendars: &[SimpleCalendar], alarms: &[Alarm], stamp: DateTime<Utc>) -> String {
+    let mut ics = String::new();
+    for calendar in calendars {
+        write_calendar(&mut ics, calendar, alarms, stamp);
+    }
+    ics
+}
+
+fn write_calendar(
+    ics: &mut String,
+    calendar: &SimpleCalendar,
+    alarms: &[Alarm],
+    stamp: DateTime<Utc>,
+) {
+    ics.push_str(&fold("BEGIN:VCALENDAR"));
+    ics.push_str(&fold("VERSION:2.0"));
+    ics.push_str(&fold(&format!("PRODID:{}", PRODID)));
+    ics.push_str(&fold(&format!(
+        "X-WR-CALNAME:{}",
+        parser::escape_text(calendar.get_name())
+    )));
+    for property in calendar.get_properties() {
+        if !OWN_CALENDAR_PROPERTIES.contains(&name_of(property).as_str()) {
+            ics.push_str(&fold(&content_line(property)));
+        }
+    }
+    write_zones(ics, calendar, stamp);
+
+    for component in calendar.get_components() {
+        write_component(ics, component, alarms, stamp);
+    }
+    ics.push_str(&fold("END:VCALENDAR"));
+}
+
+/// Writes the busy periods of users to an iCalendar file as a VFREEBUSY, to be shared without
+/// revealing what the users are busy with.
+pub fn write_free_busy(free_busy: &FreeBusy) -> String {
+    write_free_busy_stamped(free_busy, Uuid::new_v4(), Utc::now())
+}
+
+/// Like `write_free_busy`, with the given UID and DTSTAMP.
+fn write_free_busy_stamped(free_busy: &FreeBusy, uid: Uuid, stamp: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:{}", PRODID),
+        String::from("METHOD:PUBLISH"),
+        String::from("BEGIN:VFREEBUSY"),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", parser::format_date_time(stamp)),
+        format!("DTSTART:{}", parser::format_date_time(free_busy.from)),
+        format!("DTEND:{}", parser::format_date_time(free_busy.to)),
+    ];
+    for period in &free_busy.busy {
+        lines.push(format!(
+            "FREEBUSY;FBTYPE={}:{}/{}",
+            period.busy_type.fb_type(),
+            parser::format_date_time(period.starts_at),
+            parser::format_date_time(period.ends_at)
+        ));
+    }
+    lines.push(String::from("END:VFREEBUSY"));
+    lines.push(String::from("END:VCALENDAR"));
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Writes the VTIMEZONEs of the zones the components of a calendar refer to, from the year of
+/// the earliest start on.
+fn write_zones(ics: &mut String, calendar: &SimpleCalendar, stamp: DateTime<Utc>) {
+    let mut zones = BTreeMap::new();
+    for component in calendar.get_components() {
+        let tzids = component
+            .get_properties()
+            .iter()
+            .filter_map(|property| property.param("TZID"))
+            .chain(component.get_time_zone().cloned());
+        for zone in tzids.filter_map(|tzid| zone::parse_zone(&tzid)) {
+            zones.insert(zone.name(), zone);
+        }
+    }
+
+    let starts = calendar
+        .get_components()
+        .iter()
+        .filter_map(|component| component.get_starts_at());
+    let first = starts.clone().min().unwrap_or(stamp);
+    let last = starts.max().unwrap_or(stamp).max(stamp);
+    let year_start = |year: i32| Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).single();
+    let (Some(from), Some(to)) = (
+        year_start(first.year()),
+        year_start((last.year() + ZONE_YEARS_AHEAD + 1).min(9999)),
+    ) else {
+        return;
+    };
+    for zone in zones.into_values() {
+        for line in zone::vtimezone(zone, from, to) {
+            ics.push_str(&fold(&line));
+        }
+    }
+}
+
+fn write_component(
+    ics: &mut String,
+    component: &SimpleComponent,
+    alarms: &[Alarm],
+    stamp: DateTime<Utc>,
+) {
+    let name = match component.get_c_type() {
+        ComponentType::EVENT => "VEVENT",
+        ComponentType::TODO => "VTODO",
+        ComponentType::VENUE => "VVENUE",
+        ComponentType::OTHER => "VJOURNAL",
+    };
+    let has = |key: &str| {
+        component
+            .get_properties()
+            .iter()
+            .any(|property| name_of(property) == key)
+    };
+
+    ics.push_str(&fold(&format!("BEGIN:{}", name)));
+    if !has("UID") {
+        ics.push_str(&fold(&format!("UID:{}", component.get_uuid())));
+    }
+    if !has("DTSTAMP") && name != "VVENUE" {
+        ics.push_str(&fold(&format!(
+            "DTSTAMP:{}",
+            parser::format_date_time(stamp)
+        )));
+    }
+    // Stored times are written as they were imported, with their zones and value types
+    let has_times = TIME_PROPERTIES.iter().any(|key| has(key));
+    if let (Some(starts_at), false) = (component.get_starts_at(), has_times) {
+        let zone = component
+            .get_time_zone()
+            .and_then(|tzid| zone::parse_zone(tzid));
+        ics.push_str(&fold(&time_line("DTSTART", starts_at, zone)));
+        let end_name = match component.get_c_type() {
+            ComponentType::TODO => "DUE",
+            _ => "DTEND",
+        };
+        match component.get_ends_at() {
+            Some(ends_at) if ends_at > starts_at && !has(end_name) && !has("DURATION") => {
+                ics.push_str(&fold(&time_line(end_name, ends_at, zone)))
+            }
+            _ => {}
+        }
+    }
+    for property in component.get_properties() {
+        ics.push_str(&fold(&content_line(property)));
+    }
+    for alarm in alarms
+        .iter()
+        .filter(|alarm| alarm.component_uuid == component.get_uuid())
+    {
+        write_alarm(ics, alarm);
+    }
+    ics.push_str(&fold(&format!("END:{}", name)));
+}
+
+/// Writes an alarm as a VALARM. Snoozes aren't written, only when the alarm was acknowledged.
+fn write_alarm(ics: &mut String, alarm: &Alarm) {
+    let trigger = match (alarm.trigger_at, alarm.trigger_offset) {
+        (Some(at), _) => format!("TRIGGER;VALUE=DATE-TIME:{}", parser::format_date_time(at)),
+        (None, Some(offset)) => {
+            let related = match alarm.trigger_related {
+                TriggerRelation::START => "",
+                TriggerRelation::END => ";RELATED=END",
+            };
+            format!(
+                "TRIGGER{}:{}",
+                related,
+                parser::format_duration(TimeDelta::seconds(offset))
+            )
+        }
+        (None, None) => return,
+    };
+
+    ics.push_str(&fold("BEGIN:VALARM"));
+    ics.push_str(&fold(&format!("ACTION:{:?}", alarm.action)));
+    ics.push_str(&fold(&trigger));
+    if let (1.., Some(interval)) = (alarm.repeat_count, alarm.repeat_interval) {
+        ics.push_str(&fold(&format!("REPEAT:{}", alarm.repeat_count)));
+        ics.push_str(&fold(&format!(
+            "DURATION:{}",
+            parser::format_duration(TimeDelta::seconds(interval))
+        )));
+    }
+    if let Some(description) = &alarm.description {
+        ics.push_str(&fold(&format!(
+            "DESCRIPTION:{}",
+            parser::escape_text(description)
+        )));
+    }
+    if let Some(acknowledged_at) = alarm.acknowledged_at {
+        ics.push_str(&fold(&format!(
+            "ACKNOWLEDGED:{}",
+            parser::format_date_time(acknowledged_at)
+        )));
+    }
+    ics.push_str(&fold("END:VALARM"));
+}
+
+/// Formats a time as a content line, in the given zone or in UTC.
+fn time_line(name: &str, time: DateTime<Utc>, zone: Option<Tz>) -> String {
+    match zone {
+        Some(zone) => format!(
+            "{};TZID={}:{}",
+            name,
+            zone.name(),
+            zone::format_local(time, zone)
+        ),
+        None => format!("{}:{}", name, parser::format_date_time(time)),
+    }
+}
+
+/// Formats a property as a content line, `NAME;PARAMS:VALUE`. TEXT values are escaped.
+fn content_line(property: &Property) -> String {
+    let name = name_of(property);
+    let value = match TEXT_PROPERTIES.contains(&name.as_str()) {
+        true => parser::escape_text(property.get_val()),
+        // Line breaks can't be written as they are, only TEXT values can escape them
+        false => property.get_val().replace(['\r', '\n'], " "),
+    };
+    match property.get_params().is_empty() {
+        true => format!("{}:{}", name, value),
+        false => format!("{};{}:{}", name, property.get_params(), value),
+    }
+}
+
+/// The name of a property as written to a file, upper case and without characters invalid in
+/// names.
+fn name_of(property: &Property) -> String {
+    property
+        .get_key()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '-',
+        })
+        .collect()
+}
+
+/// Folds a content line to lines of at most 75 octets, continued with a space, and ends it
+/// with a line break. Characters are never split.
+pub fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_LENGTH * 3 + 2);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::component::Component;
+    use crate::db::model::free_busy::{BusyPeriod, BusyType};
+    use crate::db::model::property::OwnerType;
+    use crate::ical::import::{self, Import};
+    use chrono::TimeZone;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        PRODID:-//Other//Tool//EN\r\n\
+        X-WR-CALNAME:Team\\; shared\r\n\
+        METHOD:PUBLISH\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:event-1\r\n\
+        DTSTAMP:20240401T000000Z\r\n\
+        DTSTART;VALUE=DATE:20240509\r\n\
+        SUMMARY:Ausflug\\, Grillen & Übernachtung im Zelt am See mit allen aus dem Team\r\n\
+        DESCRIPTION:Bring:\\n- Zelt\\n- Schlafsack\r\n\
+        X-CUSTOM;X-PARAM=\"a:b\",c:kept\\,as is\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VTODO\r\n\
+        UID:todo-1\r\n\
+        DTSTAMP:20240401T000000Z\r\n\
+        DUE:20240510T170000Z\r\n\
+        SUMMARY:Pack\r\n\
+        END:VTODO\r\n\
+        END:VCALENDAR\r\n";
+
+    fn to_simple(import: Import) -> Vec<SimpleCalendar> {
+        import
+            .calendars
+            .into_iter()
+            .map(|imported| {
+                let components = imported
+                    .components
+                    .into_iter()
+                    .map(|(component, properties)| SimpleComponent::new(component, properties))
+                    .collect();
+                SimpleCalendar::new(
+                    imported.calendar.uuid,
+                    imported.calendar.name,
+                    components,
+                    imported.properties,
+                    vec![],
+                )
+            })
+            .collect()
+    }
+
+    /// The parts of a calendar that must survive a round trip.
+    fn contents(calendar: &SimpleCalendar) -> Vec<String> {
+        let mut contents = vec![calendar.get_name().clone()];
+        for component in calendar.get_components() {
+            contents.push(format!(
+                "{:?} {:?} {:?}",
+                component.get_c_type(),
+                component.get_starts_at(),
+                component.get_ends_at()
+            ));
+            for property in component.get_properties() {
+                contents.push(format!(
+                    "{};{}:{}",
+                    property.get_key(),
+                    property.get_params(),
+                    property.get_val()
+                ));
+            }
+        }
+        contents
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let imported = to_simple(import::read(ICS, "team").unwrap());
+        let ics = write(&imported);
+        assert!(ics.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert!(ics.contains(&format!("PRODID:{}\r\n", PRODID)));
+        assert!(ics.contains("METHOD:PUBLISH\r\n"));
+        assert!(!ics.contains("-//Other//Tool//EN"));
+
+        let exported = import::read(&ics, "other").unwrap();
+        assert!(exported.errors.is_empty() && exported.warnings.is_empty());
+        let exported = to_simple(exported);
+        assert_eq!(contents(&exported[0]), contents(&imported[0]));
+        assert_eq!(write(&exported), ics);
+    }
+
+    #[test]
+    fn test_write_new_components() {
+        let at = |hour| Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap();
+        let calendar_uuid = Uuid::new_v4();
+        let event =
+            Component::new(ComponentType::EVENT, calendar_uuid).with_times(at(9), Some(at(10)));
+        let note = Component::new(ComponentType::OTHER, calendar_uuid);
+        let summary = Property::new(
+            event.uuid,
+            OwnerType::COMPONENT,
+            String::from("summary"),
+            String::from("Stand-up, daily"),
+        );
+        let calendar = SimpleCalendar::new(
+            calendar_uuid,
+            String::from("Work"),
+            vec![
+                SimpleComponent::new(event.clone(), vec![summary]),
+                SimpleComponent::new(note.clone(), vec![]),
+            ],
+            vec![],
+            vec![],
+        );
+
+        let ics = write_stamped(&[calendar], &[], at(8));
+        let expected = format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:{}\r\nX-WR-CALNAME:Work\r\n\
+             BEGIN:VEVENT\r\nUID:{}\r\nDTSTAMP:20240501T080000Z\r\n\
+             DTSTART:20240501T090000Z\r\nDTEND:20240501T100000Z\r\n\
+             SUMMARY:Stand-up\\, daily\r\nEND:VEVENT\r\n\
+             BEGIN:VJOURNAL\r\nUID:{}\r\nDTSTAMP:20240501T080000Z\r\nEND:VJOURNAL\r\n\
+             END:VCALENDAR\r\n",
+            PRODID, event.uuid, note.uuid
+        );
+        assert_eq!(ics, expected);
+    }
+
+    #[test]
+    fn test_write_zones() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:zoned\r\n\
+            DTSTART;TZID=Europe/Berlin:20240701T090000\r\n\
+            RRULE:FREQ=DAILY;COUNT=2\r\n\
+            EXDATE;TZID=Europe/Berlin:20240702T090000\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let mut imported = to_simple(import::read(ics, "zones").unwrap());
+        let berlin = zone::parse_zone("Europe/Berlin").unwrap();
+        let at = Utc.with_ymd_and_hms(2024, 7, 2, 9, 0, 0).unwrap();
+        let new = Component::new(ComponentType::EVENT, imported[0].get_uuid())
+            .with_times(at, None)
+            .in_zone(berlin);
+        let mut components = imported[0].get_components().clone();
+        components.push(SimpleComponent::new(new, vec![]));
+        imported[0] = SimpleCalendar::new(
+            imported[0].get_uuid(),
+            imported[0].get_name().clone(),
+            components,
+            vec![],
+            vec![],
+        );
+
+        let ics = write_stamped(&imported, &[], at);
+        assert_eq!(ics.matches("BEGIN:VTIMEZONE\r\n").count(), 1);
+        assert!(ics.find("TZID:Europe/Berlin\r\n") < ics.find("BEGIN:VEVENT"));
+        // From the start of 2024 to the end of 2029, two changes a year
+        assert_eq!(ics.matches("BEGIN:DAYLIGHT\r\n").count(), 6);
+        assert!(ics.contains("DTSTART;TZID=Europe/Berlin:20240702T110000\r\n"));
+
+        let exported = import::read(&ics, "zones").unwrap();
+        assert!(exported.errors.is_empty() && exported.warnings.is_empty());
+        let (zoned, _) = &exported.calendars[0].components[0];
+        assert_eq!(
+            zoned.starts_at,
+            Some(Utc.with_ymd_and_hms(2024, 7, 1, 7, 0, 0).unwrap())
+        );
+        let (new, _) = &exported.calendars[0].components[1];
+        assert_eq!(new.starts_at, Some(at));
+        assert_eq!(new.time_zone.as_deref(), Some("Europe/Berlin"));
+    }
+
+    #[test]
+    fn test_write_alarms() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:standup\r\n\
+            DTSTART:20240501T090000Z\r\n\
+            BEGIN:VALARM\r\n\
+            ACTION:DISPLAY\r\n\
+            TRIGGER:-PT10M\r\n\
+            REPEAT:1\r\n\
+            DURATION:PT5M\r\n\
+            DESCRIPTION:Stand-up\\, soon\r\n\
+            ACKNOWLEDGED:20240501T085100Z\r\n\
+            END:VALARM\r\n\
+            BEGIN:VALARM\r\n\
+            ACTION:AUDIO\r\n\
+            TRIGGER;RELATED=END:PT0S\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let import = import::read(ics, "alarms").unwrap();
+        let alarms = import.calendars[0].alarms.clone();
+        let written = write_with_alarms(&to_simple(import), &alarms);
+        assert!(written.contains(
+            "BEGIN:VALARM\r\nACTION:AUDIO\r\nTRIGGER;RELATED=END:PT0S\r\nEND:VALARM\r\n"
+        ));
+
+        let exported = import::read(&written, "alarms").unwrap();
+        assert!(exported.errors.is_empty() && exported.warnings.is_empty());
+        let without_uuids = |alarms: &[Alarm]| {
+            alarms
+                .iter()
+                .map(|alarm| Alarm {
+                    uuid: Uuid::nil(),
+                    component_uuid: Uuid::nil(),
+                    ..alarm.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            without_uuids(&exported.calendars[0].alarms),
+            without_uuids(&alarms)
+        );
+        assert_eq!(alarms.len(), 2);
+    }
+
+    #[test]
+    fn test_write_free_busy() {
+        let at = |hour| Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap();
+        let free_busy = FreeBusy {
+            user_uuids: vec![],
+            from: at(8),
+            to: at(18),
+            busy: vec![
+                BusyPeriod {
+                    starts_at: at(9),
+                    ends_at: at(10),
+                    busy_type: BusyType::BUSY,
+                },
+                BusyPeriod {
+                    starts_at: at(12),
+                    ends_at: at(13),
+                    busy_type: BusyType::TENTATIVE,
+                },
+            ],
+        };
+        let uid = Uuid::new_v4();
+
+        let expected = format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:{}\r\nMETHOD:PUBLISH\r\n\
+             BEGIN:VFREEBUSY\r\nUID:{}\r\nDTSTAMP:20240501T070000Z\r\n\
+             DTSTART:20240501T080000Z\r\nDTEND:20240501T180000Z\r\n\
+             FREEBUSY;FBTYPE=BUSY:20240501T090000Z/20240501T100000Z\r\n\
+             FREEBUSY;FBTYPE=BUSY-TENTATIVE:20240501T120000Z/20240501T130000Z\r\n\
+             END:VFREEBUSY\r\nEND:VCALENDAR\r\n",
+            PRODID, uid
+        );
+        assert_eq!(write_free_busy_stamped(&free_busy, uid, at(7)), expected);
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("SUMMARY:short"), "SUMMARY:short\r\n");
+
+        let long = format!("DESCRIPTION:{}", "ä".repeat(80));
+        let folded = fold(&long);
+        let lines = folded.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].len(), 74);
+        assert!(lines[1].starts_with(' ') && lines[1].len() <= MAX_LINE_LENGTH);
+        assert_eq!(parser::unfold(&folded), vec![(1, long)]);
+    }
+}
@@ -0,0 +1,858 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use pg_driver::{AsyncPgDriver, PgDriver};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::db::async_db_actions::AsyncDbActions;
+use crate::db::db_actions::DbActions;
+use crate::db::model::alarm::{Alarm, AlarmAction, TriggerRelation};
+use crate::db::model::calendar::Calendar;
+use crate::db::model::component::{Component, ComponentType};
+use crate::db::model::conflict::Conflict;
+use crate::db::model::property::{OwnerType, Property};
+use crate::db::model::simple::simple_component::SimpleComponent;
+use crate::db::model::user_calendar_combination::UserCalendarCombination;
+use crate::db::repository::alarm_repository::{AlarmRepository, AsyncAlarmRepository};
+use crate::db::repository::calendar_repository::{AsyncCalendarRepository, CalendarRepository};
+use crate::db::repository::component_repository::{AsyncComponentRepository, ComponentRepository};
+use crate::db::repository::property_repository::{AsyncPropertyRepository, PropertyRepository};
+use crate::db::repository::user_calendar_combination_repository::{
+    AsyncUserCalendarCombinationRepository, UserCalendarCombinationRepository,
+};
+
+use super::parser::{self, ContentLine, IcalComponent, ParseError};
+use super::recurrence::Recurrence;
+use super::zone;
+
+/// The properties holding TEXT values. They are stored unescaped, all other values are stored
+/// as written in the file, so they are exported unchanged.
+pub const TEXT_PROPERTIES: &[&str] = &[
+    "SUMMARY",
+    "DESCRIPTION",
+    "LOCATION",
+    "COMMENT",
+    "CONTACT",
+    "NAME",
+    "STREET-ADDRESS",
+    "EXTENDED-ADDRESS",
+    "LOCALITY",
+    "REGION",
+    "POSTAL-CODE",
+    "COUNTRY",
+    "X-WR-CALNAME",
+    "X-WR-CALDESC",
+];
+
+/// A component, or a part of one, that could not be imported.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportIssue {
+    /// The kind of the component, like `VEVENT`.
+    pub component: String,
+    /// The UID of the component, if it has one.
+    pub uid: Option<String>,
+    /// The line of the problem, or of the BEGIN of the component.
+    pub line: usize,
+    pub message: String,
+}
+
+/// A calendar read from an iCalendar file, not stored yet.
+pub struct ImportedCalendar {
+    pub calendar: Calendar,
+    pub properties: Vec<Property>,
+    pub components: Vec<(Component, Vec<Property>)>,
+    /// The alarms of the events and to-dos, their VALARMs.
+    pub alarms: Vec<Alarm>,
+}
+
+/// The calendars read from an iCalendar file.
+pub struct Import {
+    pub calendars: Vec<ImportedCalendar>,
+    /// The components that were left out.
+    pub errors: Vec<ImportIssue>,
+    /// The parts of components that were left out, the rest of them is imported.
+    pub warnings: Vec<ImportIssue>,
+}
+
+/// What an import stored, for the frontends.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportReport {
+    pub calendars: Vec<ImportedSummary>,
+    pub errors: Vec<ImportIssue>,
+    pub warnings: Vec<ImportIssue>,
+    /// The events the imported ones overlap, see `ComponentService::import_conflicts_of`.
+    pub conflicts: Vec<Conflict>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportedSummary {
+    pub uuid: Uuid,
+    pub name: String,
+    /// The amount of components imported into the calendar.
+    pub components: usize,
+}
+
+impl ImportedCalendar {
+    /// The imported components along with their properties.
+    pub fn simple_components(&self) -> Vec<SimpleComponent> {
+        self.components
+            .iter()
+            .map(|(component, properties)| {
+                SimpleComponent::new(component.clone(), properties.clone())
+            })
+            .collect()
+    }
+}
+
+impl Import {
+    /// Moves the components and alarms of all imported calendars into the given one. The
+    /// properties of the imported calendars are left out, the calendar keeps its own.
+    pub fn merge_into(self, calendar: Calendar) -> Import {
+        let mut merged = ImportedCalendar {
+            calendar,
+            properties: vec![],
+            components: vec![],
+            alarms: vec![],
+        };
+        for imported in self.calendars {
+            for (mut component, properties) in imported.components {
+                component.calendar_uuid = merged.calendar.uuid;
+                merged.components.push((component, properties));
+            }
+            merged.alarms.extend(imported.alarms);
+        }
+
+        Import {
+            calendars: vec![merged],
+            errors: self.errors,
+            warnings: self.warnings,
+        }
+    }
+
+    pub fn report(&self, conflicts: Vec<Conflict>) -> ImportReport {
+        ImportReport {
+            calendars: self
+                .calendars
+                .iter()
+                .map(|imported| ImportedSummary {
+                    uuid: imported.calendar.uuid,
+                    name: imported.calendar.name.clone(),
+                    components: imported.components.len(),
+                })
+                .collect(),
+            errors: self.errors.clone(),
+            warnings: self.warnings.clone(),
+            conflicts,
+        }
+    }
+}
+
+/// Reads the calendars of an iCalendar file. Every VCALENDAR becomes a `Calendar`, every
+/// VEVENT, VTODO, VVENUE and VJOURNAL a `Component`, and their properties `Property` records,
+/// including the ones unknown to this app. The VALARMs of events and to-dos become `Alarm`s.
+///
+/// A component that can't be read is reported in the `errors` of the import, the other
+/// components are imported anyway.
+///
+/// # Arguments
+///
+/// * `text` - The content of the file.
+/// * `default_name` - The name of calendars without an `X-WR-CALNAME`, like the file name.
+///
+/// # Errors
+/// If the text is no iCalendar file.
+pub fn read(text: &str, default_name: &str) -> Result<Import, ParseError> {
+    let mut import = Import {
+        calendars: vec![],
+        errors: vec![],
+        warnings: vec![],
+    };
+
+    for root in parser::parse(text)? {
+        if root.name != "VCALENDAR" {
+            import
+                .errors
+                .push(issue(&root, root.line, "Expected a VCALENDAR"));
+            continue;
+        }
+        let calendar = read_calendar(root, default_name, &mut import);
+        import.calendars.push(calendar);
+    }
+
+    if import.calendars.is_empty() {
+        return Err(ParseError {
+            line: 1,
+            message: String::from("The file holds no VCALENDAR"),
+        });
+    }
+    Ok(import)
+}
+
+fn read_calendar(root: IcalComponent, default_name: &str, import: &mut Import) -> ImportedCalendar {
+    let name = root
+        .property("X-WR-CALNAME")
+        .map(|name| parser::unescape_text(&name.value))
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| default_name.to_string());
+    let calendar = Calendar::new(name);
+    let zones = zones(&root);
+
+    for error in &root.errors {
+        import
+            .warnings
+            .push(issue(&root, error.line, &error.message));
+    }
+    let properties = root
+        .properties
+        .iter()
+        .map(|line| to_property(calendar.uuid, OwnerType::CALENDAR, line, &zones))
+        .collect();
+
+    let mut components = vec![];
+    let mut alarms = vec![];
+    for ical in root.components {
+        let c_type = match ical.name.as_str() {
+            "VEVENT" => ComponentType::EVENT,
+            "VTODO" => ComponentType::TODO,
+            "VVENUE" => ComponentType::VENUE,
+            "VJOURNAL" => ComponentType::OTHER,
+            // Zones are read up front, times refer to them by their TZID
+            "VTIMEZONE" => continue,
+            name => {
+                let message = format!("{} is not supported, it was skipped", name);
+                import.errors.push(issue(&ical, ical.line, &message));
+                continue;
+            }
+        };
+        let has_alarms = matches!(c_type, ComponentType::EVENT | ComponentType::TODO);
+        match read_component(&ical, c_type, calendar.uuid, &zones, &mut import.warnings) {
+            Ok(component) => {
+                for nested in &ical.components {
+                    if nested.name != "VALARM" || !has_alarms {
+                        let message = format!("{} is not supported, it was skipped", nested.name);
+                        import.warnings.push(issue(&ical, nested.line, &message));
+                        continue;
+                    }
+                    match read_alarm(&ical, nested, component.0.uuid, &zones) {
+                        Ok(alarm) => alarms.push(alarm),
+                        Err(e) => import.warnings.push(e),
+                    }
+                }
+                components.push(component);
+            }
+            Err(e) => import.errors.push(e),
+        }
+    }
+    link_overrides(&mut components);
+
+    ImportedCalendar {
+        calendar,
+        properties,
+        components,
+        alarms,
+    }
+}
+
+/// The IANA zones of the VTIMEZONEs of a calendar by their TZID. Zones named differently, like
+/// the ones of Outlook, are looked up by their X-LIC-LOCATION.
+fn zones(root: &IcalComponent) -> HashMap<String, Tz> {
+    root.components
+        .iter()
+        .filter(|ical| ical.name == "VTIMEZONE")
+        .filter_map(|ical| {
+            let tzid = ical.property("TZID")?.value.clone();
+            let zone = zone::parse_zone(&tzid).or_else(|| {
+                ical.property("X-LIC-LOCATION")
+                    .and_then(|location| zone::parse_zone(&location.value))
+            })?;
+            Some((tzid, zone))
+        })
+        .collect()
+}
+
+/// The IANA zone of the TZID of a content line. TZIDs without a VTIMEZONE are looked up as
+/// IANA names.
+///
+/// # Returns
+/// None if the line has no TZID or the zone is unknown.
+fn zone_of(line: &ContentLine, zones: &HashMap<String, Tz>) -> Option<Tz> {
+    let tzid = line.param("TZID")?;
+    zones.get(tzid).copied().or_else(|| zone::parse_zone(tzid))
+}
+
+/// Reads a component and its properties. A recurrence that can't be expanded and times in
+/// unknown zones are reported in the `warnings`, the component is imported with its first
+/// occurrence only, or with its times read as UTC.
+fn read_component(
+    ical: &IcalComponent,
+    c_type: ComponentType,
+    calendar_uuid: Uuid,
+    zones: &HashMap<String, Tz>,
+    warnings: &mut Vec<ImportIssue>,
+) -> Result<(Component, Vec<Property>), ImportIssue> {
+    if let Some(error) = ical.errors.first() {
+        return Err(issue(ical, error.line, &error.message));
+    }
+
+    let mut component = Component::new(c_type.clone(), calendar_uuid);
+    let properties = ical
+        .properties
+        .iter()
+        .map(|line| to_property(component.uuid, OwnerType::COMPONENT, line, zones))
+        .collect::<Vec<_>>();
+
+    let mut unknown_zones = vec![];
+    for line in &ical.properties {
+        match line.param("TZID") {
+            Some(tzid) if zone_of(line, zones).is_none() && !unknown_zones.contains(&tzid) => {
+                let message = format!(
+                    "The time zone {} is unknown, its times are read as UTC",
+                    tzid
+                );
+                warnings.push(issue(ical, line.line, &message));
+                unknown_zones.push(tzid);
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((starts_at, ends_at)) = times(ical, &c_type, zones)? {
+        component = component.with_times(starts_at, ends_at);
+        let start = ical.property("DTSTART").or_else(|| ical.property("DUE"));
+        if let Some(zone) = start.and_then(|start| zone_of(start, zones)) {
+            component = component.in_zone(zone);
+        }
+        match Recurrence::of(&properties) {
+            Ok(Some(recurrence)) => {
+                let duration = ends_at.unwrap_or(starts_at) - starts_at;
+                let series_ends_at = recurrence.series_end(starts_at, duration);
+                component = component.recurring_until(series_ends_at);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let message = format!("{}, only the first occurrence is shown", e);
+                warnings.push(issue(ical, ical.line, &message));
+            }
+        }
+    }
+
+    Ok((component, properties))
+}
+
+/// Reads a VALARM of a component. Triggers are relative to the start of the component, unless
+/// they are a DATE-TIME or RELATED to its end. Alarms that can't fire, see `Alarm::is_valid`, are
+/// reported instead.
+fn read_alarm(
+    parent: &IcalComponent,
+    ical: &IcalComponent,
+    component_uuid: Uuid,
+    zones: &HashMap<String, Tz>,
+) -> Result<Alarm, ImportIssue> {
+    if let Some(error) = ical.errors.first() {
+        return Err(issue(parent, error.line, &error.message));
+    }
+    let invalid = |line: &ContentLine, e: anyhow::Error| {
+        issue(parent, line.line, &format!("{}: {}", line.name, e))
+    };
+
+    let trigger = ical
+        .property("TRIGGER")
+        .ok_or_else(|| issue(parent, ical.line, "The alarm has no TRIGGER"))?;
+    let mut alarm = match trigger.param("VALUE") {
+        Some(value) if value.eq_ignore_ascii_case("DATE-TIME") => {
+            let (at, _) = zone::parse_date_time_in(&trigger.value, zone_of(trigger, zones))
+                .map_err(|e| invalid(trigger, e))?;
+            Alarm::absolute(component_uuid, at)
+        }
+        _ => {
+            let offset = parser::parse_duration(&trigger.value).map_err(|e| invalid(trigger, e))?;
+            let related = trigger.param("RELATED").map(TriggerRelation::parse);
+            Alarm::relative(component_uuid, offset, related.unwrap_or_default())
+        }
+    };
+
+    if let Some(action) = ical.property("ACTION") {
+        alarm.action = AlarmAction::parse(&action.value);
+    }
+    if let (Some(repeat), Some(duration)) = (ical.property("REPEAT"), ical.property("DURATION")) {
+        let count = repeat
+            .value
+            .parse()
+            .map_err(|e| invalid(repeat, anyhow::Error::new(e)))?;
+        let interval = parser::parse_duration(&duration.value).map_err(|e| invalid(duration, e))?;
+        alarm = alarm.with_repeats(count, interval);
+    }
+    alarm.description = ical
+        .property("DESCRIPTION")
+        .map(|description| parser::unescape_text(&description.value));
+    if let Some(acknowledged) = ical.property("ACKNOWLEDGED") {
+        let (acknowledged_at, _) =
+            parser::parse_date_time(&acknowledged.value).map_err(|e| invalid(acknowledged, e))?;
+        alarm.acknowledged_at = Some(acknowledged_at);
+    }
+
+    match alarm.is_valid() {
+        true => Ok(alarm),
+        false => Err(issue(
+            parent,
+            ical.line,
+            "The alarm fires too far from the component or repeats too often, it was skipped",
+        )),
+    }
+}
+
+/// Links the components with a RECURRENCE-ID to the recurring component with the same UID,
+/// whose occurrence they override. Overrides are moved behind the components they override, so
+/// those are stored first. Overrides without a recurring component are kept as they are.
+fn link_overrides(components: &mut [(Component, Vec<Property>)]) {
+    let find = |properties: &[Property], key: &str| {
+        properties
+            .iter()
+            .find(|property| property.get_key() == key)
+            .cloned()
+    };
+    let masters = components
+        .iter()
+        .filter(|(_, properties)| find(properties, "RECURRENCE-ID").is_none())
+        .filter_map(|(component, properties)| {
+            find(properties, "UID").map(|uid| (uid.get_val().clone(), component.uuid))
+        })
+        .collect::<HashMap<_, _>>();
+
+    for (component, properties) in components.iter_mut() {
+        let (Some(uid), Some(recurrence_id)) =
+            (find(properties, "UID"), find(properties, "RECURRENCE-ID"))
+        else {
+            continue;
+        };
+        let zone = recurrence_id
+            .param("TZID")
+            .and_then(|tzid| zone::parse_zone(&tzid));
+        if let (Some(master), Ok((recurrence_id, _))) = (
+            masters.get(uid.get_val()),
+            zone::parse_date_time_in(recurrence_id.get_val(), zone),
+        ) {
+            *component = component.clone().overriding(*master, recurrence_id);
+        }
+    }
+    components.sort_by_key(|(component, _)| component.recurrence_of.is_some());
+}
+
+/// The start of a component and its end, if it has one.
+type Times = (DateTime<Utc>, Option<DateTime<Utc>>);
+
+/// The start and end of a component. Events need a start, a date without a time lasts the
+/// whole day. To-dos start at their DTSTART or DUE. Times with a TZID are read in its zone.
+fn times(
+    ical: &IcalComponent,
+    c_type: &ComponentType,
+    zones: &HashMap<String, Tz>,
+) -> Result<Option<Times>, ImportIssue> {
+    let read = |line: &ContentLine| {
+        zone::parse_date_time_in(&line.value, zone_of(line, zones))
+            .map_err(|e| issue(ical, line.line, &format!("{}: {}", line.name, e)))
+    };
+    let end_name = match c_type {
+        ComponentType::TODO => "DUE",
+        _ => "DTEND",
+    };
+
+    let start = ical.property("DTSTART").map(read).transpose()?;
+    let end = ical.property(end_name).map(read).transpose()?;
+    let duration = match ical.property("DURATION") {
+        Some(line) => Some(
+            parser::parse_duration(&line.value)
+                .map_err(|e| issue(ical, line.line, &format!("DURATION: {}", e)))?,
+        ),
+        None => None,
+    };
+
+    let (starts_at, is_date) = match (start, end, c_type) {
+        (Some(start), _, _) => start,
+        (None, Some(due), ComponentType::TODO) => due,
+        (None, _, ComponentType::EVENT) => {
+            return Err(issue(ical, ical.line, "The event has no DTSTART"))
+        }
+        (None, _, _) => return Ok(None),
+    };
+    let ends_at = match (end, duration) {
+        (Some((ends_at, _)), _) => Some(ends_at),
+        (None, Some(duration)) => Some(starts_at + duration),
+        (None, None) if is_date && *c_type == ComponentType::EVENT => {
+            Some(starts_at + TimeDelta::days(1))
+        }
+        (None, None) => None,
+    };
+
+    if ends_at.is_some_and(|ends_at| ends_at < starts_at) {
+        return Err(issue(
+            ical,
+            ical.line,
+            "The component ends before it starts",
+        ));
+    }
+    Ok(Some((starts_at, ends_at)))
+}
+
+/// Converts a content line to a property. TZIDs are replaced by the name of their IANA zone,
+/// which exports write the VTIMEZONE of.
+fn to_property(
+    owner_uuid: Uuid,
+    owner_type: OwnerType,
+    line: &ContentLine,
+    zones: &HashMap<String, Tz>,
+) -> Property {
+    let value = match TEXT_PROPERTIES.contains(&line.name.as_str()) {
+        true => parser::unescape_text(&line.value),
+        false => line.value.clone(),
+    };
+    let mut params = line.params.clone();
+    if let Some(zone) = zone_of(line, zones) {
+        for param in params.iter_mut().filter(|param| param.name == "TZID") {
+            param.values = vec![zone.name().to_string()];
+        }
+    }
+    Property::new(owner_uuid, owner_type, line.name.clone(), value)
+        .with_params(parser::format_params(&params))
+}
+
+fn issue(ical: &IcalComponent, line: usize, message: &str) -> ImportIssue {
+    ImportIssue {
+        component: ical.name.clone(),
+        uid: ical.property("UID").map(|uid| uid.value.clone()),
+        line,
+        message: message.to_string(),
+    }
+}
+
+/// Stores an imported calendar and grants the given user access to it, all or nothing.
+pub fn store(
+    driver: &mut PgDriver,
+    user_uuid: Uuid,
+    imported: &ImportedCalendar,
+) -> anyhow::Result<()> {
+    driver.transaction(|driver| -> anyhow::Result<()> {
+        CalendarRepository::store(driver, &imported.calendar)?;
+        UserCalendarCombinationRepository::store(
+            driver,
+            &UserCalendarCombination::new(user_uuid, imported.calendar.uuid),
+        )?;
+        for property in &imported.properties {
+            PropertyRepository::store(driver, property)?;
+        }
+        store_components(driver, imported)
+    })
+}
+
+/// Stores the components of an imported calendar and their alarms into its calendar, which
+/// is stored already. Call it within a transaction, so a failed import stores nothing.
+pub fn store_components(driver: &mut PgDriver, imported: &ImportedCalendar) -> anyhow::Result<()> {
+    for (component, properties) in &imported.components {
+        ComponentRepository::store(driver, component)?;
+        for property in properties {
+            PropertyRepository::store(driver, property)?;
+        }
+    }
+    for alarm in &imported.alarms {
+        AlarmRepository::store(driver, alarm)?;
+    }
+    Ok(())
+}
+
+/// The async counterpart of `store`.
+pub async fn store_async(
+    driver: &mut AsyncPgDriver,
+    user_uuid: Uuid,
+    imported: &ImportedCalendar,
+) -> anyhow::Result<()> {
+    driver
+        .transaction(async |driver| -> anyhow::Result<()> {
+            AsyncCalendarRepository::store(driver, &imported.calendar).await?;
+            AsyncUserCalendarCombinationRepository::store(
+                driver,
+                &UserCalendarCombination::new(user_uuid, imported.calendar.uuid),
+            )
+            .await?;
+            for property in &imported.properties {
+                AsyncPropertyRepository::store(driver, property).await?;
+            }
+            store_components_async(driver, imported).await
+        })
+        .await
+}
+
+/// The async counterpart of `store_components`.
+pub async fn store_components_async(
+    driver: &mut AsyncPgDriver,
+    imported: &ImportedCalendar,
+) -> anyhow::Result<()> {
+    for (component, properties) in &imported.components {
+        AsyncComponentRepository::store(driver, component).await?;
+        for property in properties {
+            AsyncPropertyRepository::store(driver, property).await?;
+        }
+    }
+    for alarm in &imported.alarms {
+        AsyncAlarmRepository::store(driver, alarm).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::Migrations;
+    use crate::db::model::user::User;
+    use crate::db::repository::user_repository::UserRepository;
+    use pg_driver::{DriverConfig, Query};
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        PRODID:-//Example//Test//EN\r\n\
+        X-WR-CALNAME:Team\\, shared\r\n\
+        BEGIN:VTIMEZONE\r\n\
+        TZID:Europe/Berlin\r\n\
+        END:VTIMEZONE\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:event-1\r\n\
+        DTSTART:20240501T090000Z\r\n\
+        DURATION:PT1H30M\r\n\
+        SUMMARY:Planning\\, part 1\r\n\
+        DESCRIPTION:First line\\nsecond \r\n line\r\n\
+        X-CUSTOM;X-PARAM=\"a:b\":kept as is\\,\r\n\
+        BEGIN:VALARM\r\n\
+        ACTION:DISPLAY\r\n\
+        TRIGGER:-PT15M\r\n\
+        REPEAT:2\r\n\
+        DURATION:PT5M\r\n\
+        DESCRIPTION:Planning starts\\, soon\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:holiday\r\n\
+        DTSTART;VALUE=DATE:20240509\r\n\
+        SUMMARY:Holiday\r\n\
+        BEGIN:VALARM\r\n\
+        ACTION:DISPLAY\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VTODO\r\n\
+        UID:todo-1\r\n\
+        DUE:20240510T170000Z\r\n\
+        BEGIN:VALARM\r\n\
+        ACTION:AUDIO\r\n\
+        TRIGGER;RELATED=END;VALUE=DATE-TIME:20240510T160000Z\r\n\
+        END:VALARM\r\n\
+        END:VTODO\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:broken\r\n\
+        DTSTART:tomorrow\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:malformed\r\n\
+        DTSTART:20240501T090000Z\r\n\
+        SUMMARY\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VFREEBUSY\r\n\
+        END:VFREEBUSY\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:weekly\r\n\
+        DTSTART:20240506T080000Z\r\n\
+        DTEND:20240506T083000Z\r\n\
+        RRULE:FREQ=WEEKLY;COUNT=3\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:hourly\r\n\
+        DTSTART:20240506T080000Z\r\n\
+        RRULE:FREQ=HOURLY\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:weekly\r\n\
+        RECURRENCE-ID:20240513T080000Z\r\n\
+        DTSTART:20240514T080000Z\r\n\
+        DTEND:20240514T083000Z\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VTIMEZONE\r\n\
+        TZID:W. Europe Standard Time\r\n\
+        X-LIC-LOCATION:Europe/Berlin\r\n\
+        END:VTIMEZONE\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:zoned\r\n\
+        DTSTART;TZID=W. Europe Standard Time:20240701T090000\r\n\
+        DTEND;TZID=W. Europe Standard Time:20240701T100000\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:unknown-zone\r\n\
+        DTSTART;TZID=Mars/Olympus:20240701T090000\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    fn value<'a>(properties: &'a [Property], key: &str) -> &'a str {
+        properties
+            .iter()
+            .find(|property| property.get_key() == key)
+            .map(|property| property.get_val().as_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_read() {
+        let import = read(ICS, "work.ics").unwrap();
+        let imported = &import.calendars[0];
+        assert_eq!(imported.calendar.name, "Team, shared");
+        assert_eq!(
+            value(&imported.properties, "PRODID"),
+            "-//Example//Test//EN"
+        );
+
+        let (event, properties) = &imported.components[0];
+        assert_eq!(event.c_type, ComponentType::EVENT);
+        assert_eq!(
+            event.starts_at.unwrap().to_rfc3339(),
+            "2024-05-01T09:00:00+00:00"
+        );
+        assert_eq!(
+            event.ends_at.unwrap().to_rfc3339(),
+            "2024-05-01T10:30:00+00:00"
+        );
+        assert_eq!(value(properties, "SUMMARY"), "Planning, part 1");
+        assert_eq!(value(properties, "DESCRIPTION"), "First line\nsecond line");
+        let custom = properties
+            .iter()
+            .find(|p| p.get_key() == "X-CUSTOM")
+            .unwrap();
+        assert_eq!(custom.get_val(), "kept as is\\,");
+        assert_eq!(custom.param("x-param").as_deref(), Some("a:b"));
+        assert!(properties.iter().all(|p| p.get_owner_uuid() == event.uuid));
+
+        let (holiday, _) = &imported.components[1];
+        assert_eq!(
+            holiday.ends_at.unwrap() - holiday.starts_at.unwrap(),
+            TimeDelta::days(1)
+        );
+        let (todo, _) = &imported.components[2];
+        assert_eq!(todo.c_type, ComponentType::TODO);
+        assert_eq!(todo.starts_at, todo.ends_at);
+        let (weekly, _) = &imported.components[3];
+        assert_eq!(
+            weekly.series_ends_at.unwrap().to_rfc3339(),
+            "2024-05-20T08:30:00+00:00"
+        );
+        let (hourly, _) = &imported.components[4];
+        assert_eq!(hourly.series_ends_at, hourly.ends_at);
+        assert_eq!(hourly.recurrence_of, None);
+        let (zoned, properties) = &imported.components[5];
+        assert_eq!(
+            zoned.starts_at.unwrap().to_rfc3339(),
+            "2024-07-01T07:00:00+00:00"
+        );
+        assert_eq!(zoned.time_zone.as_deref(), Some("Europe/Berlin"));
+        let dtstart = properties
+            .iter()
+            .find(|p| p.get_key() == "DTSTART")
+            .unwrap();
+        assert_eq!(dtstart.get_params(), "TZID=Europe/Berlin");
+        let (unknown, _) = &imported.components[6];
+        assert_eq!(
+            unknown.starts_at.unwrap().to_rfc3339(),
+            "2024-07-01T09:00:00+00:00"
+        );
+        assert_eq!(unknown.time_zone, None);
+        // Overrides are moved behind the other components
+        let (moved, _) = &imported.components[7];
+        assert_eq!(moved.recurrence_of, Some(weekly.uuid));
+        assert_eq!(
+            moved.recurrence_id.unwrap().to_rfc3339(),
+            "2024-05-13T08:00:00+00:00"
+        );
+        assert_eq!(imported.components.len(), 8);
+
+        let planning = &imported.alarms[0];
+        assert_eq!(planning.component_uuid, event.uuid);
+        assert_eq!(planning.trigger_offset, Some(-15 * 60));
+        assert_eq!(planning.trigger_related, TriggerRelation::START);
+        assert_eq!(
+            (planning.repeat_count, planning.repeat_interval),
+            (2, Some(5 * 60))
+        );
+        assert_eq!(
+            planning.description.as_deref(),
+            Some("Planning starts, soon")
+        );
+        let due = &imported.alarms[1];
+        assert_eq!(due.component_uuid, todo.uuid);
+        assert_eq!(due.action, AlarmAction::AUDIO);
+        assert_eq!(due.trigger_offset, None);
+        assert_eq!(
+            due.trigger_at.unwrap().to_rfc3339(),
+            "2024-05-10T16:00:00+00:00"
+        );
+        assert_eq!(imported.alarms.len(), 2);
+
+        let errors = import
+            .errors
+            .iter()
+            .map(|e| (e.uid.as_deref(), e.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![(Some("broken"), 42), (Some("malformed"), 47), (None, 49)]
+        );
+        let warnings = import
+            .warnings
+            .iter()
+            .map(|w| w.uid.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![Some("holiday"), Some("hourly"), Some("unknown-zone")]
+        );
+
+        assert!(read("BEGIN:VEVENT\r\nEND:VEVENT\r\n", "x").is_err());
+        assert!(read("not a calendar", "x").is_err());
+    }
+
+    #[test]
+    fn test_store() {
+        let path = std::env::temp_dir().join(format!("sf-test-{}.db", Uuid::new_v4()));
+        let mut driver = PgDriver::from_config(&DriverConfig::Sqlite { path: path.clone() });
+        driver.connect();
+        Migrations::run(&mut driver).unwrap();
+
+        let user = User::new(
+            String::from("someone"),
+            String::from("someone@example.com"),
+            String::from("secret"),
+        );
+        UserRepository::store(&mut driver, &user).unwrap();
+        let import = read(ICS, "work.ics").unwrap();
+        store(&mut driver, user.get_uuid(), &import.calendars[0]).unwrap();
+
+        let calendars =
+            UserCalendarCombinationRepository::get_calendars_of_user(&mut driver, &user);
+        assert_eq!(calendars[0].name, "Team, shared");
+        let components = ComponentRepository::retrieve(
+            &mut driver,
+            ComponentRepository::CALENDAR_UUID
+                .eq(calendars[0].uuid)
+                .into(),
+        );
+        assert_eq!(components.len(), 8);
+        assert!(components
+            .iter()
+            .any(|component| component.time_zone.as_deref() == Some("Europe/Berlin")));
+        let custom = PropertyRepository::retrieve(
+            &mut driver,
+            Query::from(PropertyRepository::KEY.eq(String::from("X-CUSTOM"))),
+        );
+        assert_eq!(custom[0].get_params(), "X-PARAM=\"a:b\"");
+        assert_eq!(custom[0].get_owner_type(), OwnerType::COMPONENT);
+        let alarms = AlarmRepository::get_alarms_of_user(&mut driver, &user).unwrap();
+        assert_eq!(alarms.len(), 2);
+
+        drop(driver);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod export;
+pub mod import;
+pub mod parser;
+pub mod recurrence;
+pub mod zone;
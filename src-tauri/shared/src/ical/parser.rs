@@ -0,0 +1,585 @@
+use std::fmt::{self, Display};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use serde::Serialize;
+
+/// A line of an iCalendar file that could not be read.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseError {
+    /// The line the error was found in, starting at 1. Folded lines count as the line they
+    /// start in.
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A parameter of a content line, like `TZID=Europe/Berlin`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// The name of the parameter, in upper case.
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// A property of an iCalendar component, like `DTSTART;TZID=Europe/Berlin:20240101T100000`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentLine {
+    /// The name of the property, in upper case.
+    pub name: String,
+    pub params: Vec<Param>,
+    /// The value as written in the file, still escaped.
+    pub value: String,
+    pub line: usize,
+}
+
+impl ContentLine {
+    /// The first value of the parameter with the given name.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|param| param.name.eq_ignore_ascii_case(name))
+            .and_then(|param| param.values.first())
+            .map(String::as_str)
+    }
+}
+
+/// A component of an iCalendar file, like a VCALENDAR or a VEVENT, along with the components
+/// nested in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcalComponent {
+    /// The name of the component, in upper case.
+    pub name: String,
+    /// The line of its BEGIN.
+    pub line: usize,
+    pub properties: Vec<ContentLine>,
+    pub components: Vec<IcalComponent>,
+    /// The lines of this component that could not be read. They don't affect the other
+    /// components of the file.
+    pub errors: Vec<ParseError>,
+}
+
+impl IcalComponent {
+    fn new(name: String, line: usize) -> Self {
+        Self {
+            name,
+            line,
+            properties: vec![],
+            components: vec![],
+            errors: vec![],
+        }
+    }
+
+    /// The first property with the given name.
+    pub fn property(&self, name: &str) -> Option<&ContentLine> {
+        self.properties
+            .iter()
+            .find(|property| property.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Parses the components of an iCalendar file, usually a single VCALENDAR.
+///
+/// Errors within a component are collected in its `errors`, so the other components can still
+/// be read.
+///
+/// # Errors
+/// If there is content outside of any component, which means the file is no iCalendar file.
+///
+/// # Examples
+///
+/// ```
+/// let components = ical::parser::parse("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nEND:VCALENDAR\r\n")?;
+/// assert_eq!(components[0].name, "VCALENDAR");
+/// ```
+pub fn parse(text: &str) -> Result<Vec<IcalComponent>, ParseError> {
+    let mut roots = vec![];
+    let mut stack: Vec<IcalComponent> = vec![];
+
+    for (line_no, line) in unfold(text) {
+        let content = match parse_line(&line, line_no) {
+            Ok(content) => content,
+            Err(e) => match stack.last_mut() {
+                Some(component) => {
+                    component.errors.push(e);
+                    continue;
+                }
+                None => return Err(e),
+            },
+        };
+
+        match content.name.as_str() {
+            "BEGIN" => stack.push(IcalComponent::new(content.value.to_uppercase(), line_no)),
+            "END" => {
+                let name = content.value.to_uppercase();
+                // Components left open in between are closed as well, they lack their END
+                match stack.iter().rposition(|component| component.name == name) {
+                    Some(idx) => {
+                        while stack.len() > idx {
+                            let mut component = stack.pop().unwrap();
+                            if stack.len() > idx {
+                                let message = format!("Missing END:{}", component.name);
+                                component.errors.push(ParseError::new(line_no, message));
+                            }
+                            close(component, &mut stack, &mut roots);
+                        }
+                    }
+                    None => match stack.last_mut() {
+                        Some(component) => component.errors.push(ParseError::new(
+                            line_no,
+                            format!("END:{} without a BEGIN", name),
+                        )),
+                        None => return Err(ParseError::new(line_no, "END outside of a component")),
+                    },
+                }
+            }
+            _ => match stack.last_mut() {
+                Some(component) => component.properties.push(content),
+                None => {
+                    return Err(ParseError::new(
+                        line_no,
+                        format!("{} outside of a component", content.name),
+                    ))
+                }
+            },
+        }
+    }
+
+    while let Some(mut component) = stack.pop() {
+        let message = format!("Missing END:{}", component.name);
+        component
+            .errors
+            .push(ParseError::new(component.line, message));
+        close(component, &mut stack, &mut roots);
+    }
+
+    Ok(roots)
+}
+
+/// Adds a finished component to its parent, or to the roots if it has none.
+fn close(component: IcalComponent, stack: &mut [IcalComponent], roots: &mut Vec<IcalComponent>) {
+    match stack.last_mut() {
+        Some(parent) => parent.components.push(component),
+        None => roots.push(component),
+    }
+}
+
+/// Joins the lines folded by a line break followed by a space or tab, skipping empty lines.
+///
+/// # Returns
+/// The unfolded lines along with the line number each of them starts in.
+pub fn unfold(text: &str) -> Vec<(usize, String)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut lines: Vec<(usize, String)> = vec![];
+
+    for (idx, line) in text.lines().enumerate() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(continued) if !lines.is_empty() => lines.last_mut().unwrap().1.push_str(continued),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push((idx + 1, line.to_string())),
+        }
+    }
+
+    lines
+}
+
+/// Parses an unfolded content line, `NAME;PARAM=VALUE:VALUE`.
+///
+/// # Errors
+/// If the line has no name or no value, or a parameter is malformed.
+pub fn parse_line(line: &str, line_no: usize) -> Result<ContentLine, ParseError> {
+    let name_end = line.find([';', ':']).unwrap_or(line.len());
+    let name = &line[..name_end];
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(ParseError::new(
+            line_no,
+            format!("Invalid property name '{}'", name),
+        ));
+    }
+
+    let (params, rest) = parse_param_list(&line[name_end..], line_no)?;
+    let value = rest
+        .strip_prefix(':')
+        .ok_or_else(|| ParseError::new(line_no, format!("Missing ':' after property {}", name)))?;
+
+    Ok(ContentLine {
+        name: name.to_uppercase(),
+        params,
+        value: value.to_string(),
+        line: line_no,
+    })
+}
+
+/// Parses parameters formatted like in a content line, without the leading `;`, e.g. the
+/// parameters stored with a `Property`.
+///
+/// # Errors
+/// If a parameter is malformed.
+pub fn parse_params(params: &str) -> Result<Vec<Param>, ParseError> {
+    if params.is_empty() {
+        return Ok(vec![]);
+    }
+    let line = format!(";{}", params);
+    let (params, rest) = parse_param_list(&line, 0)?;
+    match rest.is_empty() {
+        true => Ok(params),
+        false => Err(ParseError::new(0, format!("Unexpected '{}'", rest))),
+    }
+}
+
+/// Formats parameters like in a content line, without the leading `;`. Values containing
+/// separators are quoted.
+pub fn format_params(params: &[Param]) -> String {
+    params
+        .iter()
+        .map(|param| {
+            let values = param
+                .values
+                .iter()
+                .map(|value| {
+                    let value = value
+                        .replace('^', "^^")
+                        .replace('\n', "^n")
+                        .replace('"', "^'");
+                    match value.contains([':', ';', ',']) {
+                        true => format!("\"{}\"", value),
+                        false => value,
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("{}={}", param.name, values)
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Parses the `;NAME=VALUE,VALUE` parameters at the start of `s`.
+///
+/// # Returns
+/// The parameters and what follows them.
+fn parse_param_list(mut s: &str, line_no: usize) -> Result<(Vec<Param>, &str), ParseError> {
+    let mut params = vec![];
+
+    while let Some(rest) = s.strip_prefix(';') {
+        let (name, rest) = rest
+            .split_once('=')
+            .ok_or_else(|| ParseError::new(line_no, "Parameter without a value"))?;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(ParseError::new(
+                line_no,
+                format!("Invalid parameter name '{}'", name),
+            ));
+        }
+
+        let mut values = vec![];
+        s = rest;
+        loop {
+            let value;
+            if let Some(quoted) = s.strip_prefix('"') {
+                let end = quoted.find('"').ok_or_else(|| {
+                    ParseError::new(line_no, format!("Unterminated quote in parameter {}", name))
+                })?;
+                value = &quoted[..end];
+                s = &quoted[end + 1..];
+            } else {
+                let end = s.find([',', ';', ':']).unwrap_or(s.len());
+                value = &s[..end];
+                s = &s[end..];
+            }
+            values.push(decode_param_value(value));
+
+            match s.strip_prefix(',') {
+                Some(rest) => s = rest,
+                None => break,
+            }
+        }
+
+        params.push(Param {
+            name: name.to_uppercase(),
+            values,
+        });
+    }
+
+    Ok((params, s))
+}
+
+/// Decodes the `^n`, `^^` and `^'` escapes of RFC 6868.
+fn decode_param_value(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '^' {
+            decoded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => decoded.push('\n'),
+            Some('^') => decoded.push('^'),
+            Some('\'') => decoded.push('"'),
+            Some(other) => {
+                decoded.push('^');
+                decoded.push(other);
+            }
+            None => decoded.push('^'),
+        }
+    }
+    decoded
+}
+
+/// Removes the escaping of a TEXT value, `\n`, `\,`, `\;` and `\\`.
+pub fn unescape_text(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped @ (',' | ';' | '\\')) => unescaped.push(escaped),
+            // Not a valid escape, keep it as it is
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Escapes a TEXT value, the counterpart of `unescape_text`.
+pub fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Parses a DATE or DATE-TIME value. Times without a `Z` are read as UTC.
+///
+/// # Returns
+/// The point in time, and whether it is a DATE without a time.
+///
+/// # Errors
+/// If the value is neither a DATE nor a DATE-TIME.
+pub fn parse_date_time(value: &str) -> anyhow::Result<(DateTime<Utc>, bool)> {
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map_err(|e| anyhow::anyhow!("Invalid date '{}': {}", value, e))?;
+        return Ok((date.and_hms_opt(0, 0, 0).unwrap().and_utc(), true));
+    }
+
+    let local = value.strip_suffix(['Z', 'z']).unwrap_or(value);
+    let time = NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S")
+        .map_err(|e| anyhow::anyhow!("Invalid date-time '{}': {}", value, e))?;
+    Ok((time.and_utc(), false))
+}
+
+/// Formats a time as a UTC DATE-TIME value, like `20240501T090000Z`.
+pub fn format_date_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Parses a DURATION value, like `PT1H30M` or `-P1W`.
+///
+/// # Errors
+/// If the value is no valid duration.
+pub fn parse_duration(value: &str) -> anyhow::Result<TimeDelta> {
+    let invalid = || anyhow::anyhow!("Invalid duration '{}'", value);
+
+    let (negative, rest) = match value.as_bytes().first() {
+        Some(b'-') => (true, &value[1..]),
+        Some(b'+') => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+
+    let mut duration = TimeDelta::zero();
+    let mut in_time = false;
+    let mut number = String::new();
+    let mut parts = 0;
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' if !in_time && number.is_empty() => in_time = true,
+            unit => {
+                let n: i64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                parts += 1;
+                duration += match (unit, in_time) {
+                    ('W', false) => TimeDelta::weeks(n),
+                    ('D', false) => TimeDelta::days(n),
+                    ('H', true) => TimeDelta::hours(n),
+                    ('M', true) => TimeDelta::minutes(n),
+                    ('S', true) => TimeDelta::seconds(n),
+                    _ => return Err(invalid()),
+                };
+            }
+        }
+    }
+    if !number.is_empty() || parts == 0 {
+        return Err(invalid());
+    }
+
+    Ok(if negative { -duration } else { duration })
+}
+
+/// Formats a DURATION value, like `PT1H30M`. Whole weeks are written as weeks.
+pub fn format_duration(duration: TimeDelta) -> String {
+    let sign = match duration < TimeDelta::zero() {
+        true => "-",
+        false => "",
+    };
+    let seconds = duration.num_seconds().unsigned_abs();
+    if seconds > 0 && seconds.is_multiple_of(7 * 86400) {
+        return format!("{}P{}W", sign, seconds / (7 * 86400));
+    }
+
+    let (days, hours, minutes, rest) = (
+        seconds / 86400,
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+    );
+    let mut value = format!("{}P", sign);
+    if days > 0 {
+        value.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 || rest > 0 || days == 0 {
+        value.push('T');
+        for (amount, unit) in [(hours, 'H'), (minutes, 'M'), (rest, 'S')] {
+            if amount > 0 {
+                value.push_str(&format!("{}{}", amount, unit));
+            }
+        }
+        if seconds == 0 {
+            value.push_str("0S");
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let line = parse_line(
+            r#"ATTENDEE;ROLE=REQ-PARTICIPANT;DELEGATED-FROM="mailto:a@example.com","mailto:b@example.com";CN=^'Bob^':mailto:c@example.com"#,
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(line.name, "ATTENDEE");
+        assert_eq!(line.value, "mailto:c@example.com");
+        assert_eq!(line.param("role"), Some("REQ-PARTICIPANT"));
+        assert_eq!(
+            line.params[1].values,
+            vec!["mailto:a@example.com", "mailto:b@example.com"]
+        );
+        assert_eq!(line.param("CN"), Some("\"Bob\""));
+
+        assert_eq!(
+            parse_params(&format_params(&line.params)).unwrap(),
+            line.params
+        );
+        assert!(parse_line("NO VALUE", 1).is_err());
+        assert!(parse_line("DTSTART;TZID:20240101", 1).is_err());
+        assert!(parse_line(r#"X;A="unterminated:value"#, 1).is_err());
+    }
+
+    #[test]
+    fn test_unfold() {
+        let text =
+            "BEGIN:VEVENT\r\nDESCRIPTION:A long\r\n  description\r\n\t goes on\r\n\r\nEND:VEVENT";
+        assert_eq!(
+            unfold(text),
+            vec![
+                (1, String::from("BEGIN:VEVENT")),
+                (2, String::from("DESCRIPTION:A long description goes on")),
+                (6, String::from("END:VEVENT")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = "BEGIN:VCALENDAR\n\
+                    VERSION:2.0\n\
+                    BEGIN:VEVENT\n\
+                    SUMMARY:Broken\n\
+                    NOT A PROPERTY\n\
+                    END:VEVENT\n\
+                    BEGIN:VTODO\n\
+                    BEGIN:VALARM\n\
+                    ACTION:DISPLAY\n\
+                    END:VTODO\n\
+                    END:VCALENDAR\n";
+        let roots = parse(text).unwrap();
+
+        assert_eq!(roots.len(), 1);
+        let calendar = &roots[0];
+        assert_eq!(calendar.property("version").unwrap().value, "2.0");
+        assert_eq!(calendar.components[0].errors[0].line, 5);
+        assert_eq!(calendar.components[1].name, "VTODO");
+        assert_eq!(
+            calendar.components[1].components[0].errors[0].message,
+            "Missing END:VALARM"
+        );
+        assert!(calendar.errors.is_empty());
+
+        assert!(parse("VERSION:2.0\n").is_err());
+    }
+
+    #[test]
+    fn test_text_escaping() {
+        let text = "Line one\nsemi; comma, backslash \\";
+        assert_eq!(
+            escape_text(text),
+            "Line one\\nsemi\\; comma\\, backslash \\\\"
+        );
+        assert_eq!(unescape_text(&escape_text(text)), text);
+        assert_eq!(unescape_text("C:\\dir"), "C:\\dir");
+    }
+
+    #[test]
+    fn test_date_times() {
+        let (utc, date) = parse_date_time("20240501T093000Z").unwrap();
+        assert_eq!(utc.to_rfc3339(), "2024-05-01T09:30:00+00:00");
+        assert!(!date);
+        assert_eq!(format_date_time(utc), "20240501T093000Z");
+        assert!(parse_date_time("20240501").unwrap().1);
+        assert!(parse_date_time("2024-05-01").is_err());
+
+        assert_eq!(parse_duration("PT1H30M").unwrap(), TimeDelta::minutes(90));
+        assert_eq!(parse_duration("-P1W2D").unwrap(), -TimeDelta::days(9));
+        assert!(parse_duration("P").is_err());
+        assert!(parse_duration("P1H").is_err());
+
+        assert_eq!(format_duration(TimeDelta::minutes(90)), "PT1H30M");
+        assert_eq!(format_duration(-TimeDelta::days(14)), "-P2W");
+        assert_eq!(format_duration(TimeDelta::hours(25)), "P1DT1H");
+        assert_eq!(format_duration(TimeDelta::zero()), "PT0S");
+    }
+}
@@ -0,0 +1,766 @@
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, TimeDelta, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::db::model::property::Property;
+
+use super::{parser, zone};
+
+/// How many periods in a row may produce no occurrence before a rule is taken as exhausted,
+/// e.g. the 30th of February.
+const MAX_EMPTY_PERIODS: u32 = 1000;
+
+/// The most occurrences a series with an end is expanded to when looking for its last one.
+/// Longer series are handled like series without an end.
+const MAX_SERIES_OCCURRENCES: usize = 100_000;
+
+/// How often a rule repeats. Rules repeating more often than daily are not supported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    DAILY,
+    WEEKLY,
+    MONTHLY,
+    YEARLY,
+}
+
+/// A weekday of a BYDAY rule part, like `MO`, or `-1FR` for the last Friday.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeekdayNum {
+    /// Which of the weekdays of the month or year, counted from its end if negative. None for
+    /// all of them.
+    pub nth: Option<i32>,
+    pub weekday: Weekday,
+}
+
+/// A recurrence rule, the value of an RRULE property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    /// The amount of occurrences, including the first one.
+    pub count: Option<u32>,
+    /// The last time an occurrence may start.
+    pub until: Option<DateTime<Utc>>,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+}
+
+/// The occurrences of a component: the ones of its rules and extra dates, without the
+/// excluded dates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recurrence {
+    pub rules: Vec<RecurrenceRule>,
+    /// The RDATE starts.
+    pub dates: Vec<DateTime<Utc>>,
+    /// The EXDATE starts.
+    pub exceptions: Vec<DateTime<Utc>>,
+    /// The zone of DTSTART, which the rules repeat in. Its occurrences keep their wall-clock
+    /// time when the clocks change. None for UTC and floating times.
+    pub zone: Option<Tz>,
+}
+
+impl RecurrenceRule {
+    /// Parses an RRULE value, like `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE`.
+    ///
+    /// # Errors
+    /// If the rule is malformed or uses parts that are not supported, like BYHOUR.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let mut freq = None;
+        let mut rule = Self {
+            freq: Frequency::DAILY,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: vec![],
+            by_month_day: vec![],
+            by_month: vec![],
+            by_set_pos: vec![],
+        };
+
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid rule part '{}'", part))?;
+            match name.to_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => Frequency::DAILY,
+                        "WEEKLY" => Frequency::WEEKLY,
+                        "MONTHLY" => Frequency::MONTHLY,
+                        "YEARLY" => Frequency::YEARLY,
+                        other => bail!("The frequency {} is not supported", other),
+                    })
+                }
+                "INTERVAL" => rule.interval = parse_number(value, 1, i32::MAX)? as u32,
+                "COUNT" => rule.count = Some(parse_number(value, 1, i32::MAX)? as u32),
+                "UNTIL" => {
+                    let (until, is_date) = parser::parse_date_time(value)?;
+                    // A date includes the whole day
+                    rule.until = Some(match is_date {
+                        true => until + TimeDelta::days(1) - TimeDelta::seconds(1),
+                        false => until,
+                    });
+                }
+                "BYDAY" => rule.by_day = split(value, parse_weekday_num)?,
+                "BYMONTHDAY" => rule.by_month_day = split(value, |day| parse_day_number(day, 31))?,
+                "BYMONTH" => {
+                    rule.by_month = split(value, |month| Ok(parse_number(month, 1, 12)? as u32))?
+                }
+                "BYSETPOS" => rule.by_set_pos = split(value, |pos| parse_day_number(pos, 366))?,
+                // Weeks start on Monday, other week starts only matter for parts not supported
+                "WKST" => {}
+                other => bail!("The rule part {} is not supported", other),
+            }
+        }
+
+        rule.freq = freq.ok_or_else(|| anyhow!("The rule has no FREQ"))?;
+        if rule.count.is_some() && rule.until.is_some() {
+            bail!("The rule has both COUNT and UNTIL");
+        }
+        let numbered_days = rule.by_day.iter().any(|day| day.nth.is_some());
+        if numbered_days && matches!(rule.freq, Frequency::DAILY | Frequency::WEEKLY) {
+            bail!("Numbered BYDAY values need a MONTHLY or YEARLY rule");
+        }
+        Ok(rule)
+    }
+
+    /// The starts of the occurrences of this rule in order, beginning with `dtstart`, which is
+    /// always the first occurrence.
+    pub fn starts(&self, dtstart: DateTime<Utc>) -> RuleStarts<'_> {
+        self.starts_in_zone(dtstart, None)
+    }
+
+    /// Like `starts`, repeating the wall-clock time of `dtstart` in the given zone, so a
+    /// meeting at 09:00 stays at 09:00 when the clocks change.
+    pub fn starts_in_zone(&self, dtstart: DateTime<Utc>, zone: Option<Tz>) -> RuleStarts<'_> {
+        RuleStarts {
+            rule: self,
+            dtstart,
+            zone,
+            period: 0,
+            pending: vec![],
+            emitted: 0,
+            empty_periods: 0,
+            done: false,
+        }
+    }
+
+    /// The dates of the occurrences in the `period`th period after the one of `first`, unsorted.
+    /// None once the dates run out of range.
+    fn period_dates(&self, first: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = period.checked_mul(self.interval)?;
+        let dates = match self.freq {
+            Frequency::DAILY => {
+                let day = first.checked_add_days(Days::new(step as u64))?;
+                match self.by_day.is_empty()
+                    || self.by_day.iter().any(|d| d.weekday == day.weekday())
+                {
+                    true => vec![day],
+                    false => vec![],
+                }
+            }
+            Frequency::WEEKLY => {
+                let monday = first
+                    .checked_sub_days(Days::new(first.weekday().num_days_from_monday() as u64))?
+                    .checked_add_days(Days::new(step as u64 * 7))?;
+                let weekdays = match self.by_day.is_empty() {
+                    true => vec![first.weekday()],
+                    false => self.by_day.iter().map(|day| day.weekday).collect(),
+                };
+                weekdays
+                    .into_iter()
+                    .filter_map(|weekday| {
+                        monday.checked_add_days(Days::new(weekday.num_days_from_monday() as u64))
+                    })
+                    .collect()
+            }
+            Frequency::MONTHLY => {
+                let months = first.year() as i64 * 12 + first.month0() as i64 + step as i64;
+                let (year, month) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+                self.month_dates(i32::try_from(year).ok()?, month, first)
+            }
+            Frequency::YEARLY => {
+                let year = first.year().checked_add(i32::try_from(step).ok()?)?;
+                self.year_dates(year, first)
+            }
+        };
+        if dates.iter().any(|date| date.year() > 9999) {
+            return None;
+        }
+
+        Some(
+            dates
+                .into_iter()
+                .filter(|date| self.by_month.is_empty() || self.by_month.contains(&date.month()))
+                .filter(|date| {
+                    self.by_month_day.is_empty()
+                        || self
+                            .by_month_day
+                            .iter()
+                            .any(|day| month_day(date.year(), date.month(), *day) == Some(*date))
+                })
+                .collect(),
+        )
+    }
+
+    /// The dates of a month matching the BYMONTHDAY and BYDAY parts, or the day of `first`.
+    fn month_dates(&self, year: i32, month: u32, first: NaiveDate) -> Vec<NaiveDate> {
+        match (self.by_month_day.is_empty(), self.by_day.is_empty()) {
+            (true, true) => NaiveDate::from_ymd_opt(year, month, first.day())
+                .into_iter()
+                .collect(),
+            (false, _) => {
+                let (Some(start), Some(end)) = month_bounds(year, month) else {
+                    return vec![];
+                };
+                self.by_month_day
+                    .iter()
+                    .filter_map(|day| month_day(year, month, *day))
+                    .filter(|date| self.by_day.is_empty() || self.matches_day(*date, start, end))
+                    .collect()
+            }
+            (true, false) => match month_bounds(year, month) {
+                (Some(start), Some(end)) => self.days_between(start, end),
+                _ => vec![],
+            },
+        }
+    }
+
+    /// The dates of a year matching the BYMONTH, BYMONTHDAY and BYDAY parts, or the day of
+    /// `first`. Without BYMONTH, the days of BYDAY are counted within the whole year.
+    fn year_dates(&self, year: i32, first: NaiveDate) -> Vec<NaiveDate> {
+        if !self.by_month.is_empty() {
+            return self
+                .by_month
+                .iter()
+                .flat_map(|month| self.month_dates(year, *month, first))
+                .collect();
+        }
+        if !self.by_month_day.is_empty() {
+            return (1..=12)
+                .flat_map(|month| self.month_dates(year, month, first))
+                .collect();
+        }
+        if !self.by_day.is_empty() {
+            return match (
+                NaiveDate::from_ymd_opt(year, 1, 1),
+                NaiveDate::from_ymd_opt(year, 12, 31),
+            ) {
+                (Some(start), Some(end)) => self.days_between(start, end),
+                _ => vec![],
+            };
+        }
+        NaiveDate::from_ymd_opt(year, first.month(), first.day())
+            .into_iter()
+            .collect()
+    }
+
+    /// The days from `start` to `end` matching BYDAY.
+    fn days_between(&self, start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+        start
+            .iter_days()
+            .take_while(|date| *date <= end)
+            .filter(|date| self.matches_day(*date, start, end))
+            .collect()
+    }
+
+    /// Whether a date matches BYDAY, numbered days counted within `start..=end`.
+    fn matches_day(&self, date: NaiveDate, start: NaiveDate, end: NaiveDate) -> bool {
+        self.by_day.iter().any(|day| {
+            day.weekday == date.weekday()
+                && match day.nth {
+                    None => true,
+                    Some(nth) if nth > 0 => (date - start).num_days() / 7 == nth as i64 - 1,
+                    Some(nth) => (end - date).num_days() / 7 == -nth as i64 - 1,
+                }
+        })
+    }
+
+    /// Picks the BYSETPOS positions out of the sorted dates of a period.
+    fn select_positions(&self, dates: Vec<NaiveDate>) -> Vec<NaiveDate> {
+        if self.by_set_pos.is_empty() {
+            return dates;
+        }
+        let len = dates.len() as i32;
+        let mut selected = self
+            .by_set_pos
+            .iter()
+            .filter_map(|pos| match *pos > 0 {
+                true => dates.get(*pos as usize - 1),
+                false => usize::try_from(len + pos)
+                    .ok()
+                    .and_then(|idx| dates.get(idx)),
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        selected.sort();
+        selected.dedup();
+        selected
+    }
+}
+
+/// The starts of the occurrences of a rule, see `RecurrenceRule::starts`.
+pub struct RuleStarts<'a> {
+    rule: &'a RecurrenceRule,
+    dtstart: DateTime<Utc>,
+    zone: Option<Tz>,
+    period: u32,
+    /// The starts of the current period not returned yet, latest first.
+    pending: Vec<DateTime<Utc>>,
+    emitted: u32,
+    empty_periods: u32,
+    done: bool,
+}
+
+impl Iterator for RuleStarts<'_> {
+    type Item = DateTime<Utc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.rule.count.is_some_and(|count| self.emitted >= count) {
+            return None;
+        }
+        if self.emitted == 0 {
+            self.emitted += 1;
+            return Some(self.dtstart);
+        }
+
+        while self.pending.is_empty() {
+            if self.empty_periods >= MAX_EMPTY_PERIODS {
+                self.done = true;
+                return None;
+            }
+            let local = match self.zone {
+                Some(zone) => zone::to_local(self.dtstart, zone),
+                None => self.dtstart.naive_utc(),
+            };
+            let Some(dates) = self.rule.period_dates(local.date(), self.period) else {
+                self.done = true;
+                return None;
+            };
+            self.period += 1;
+
+            let mut dates = dates;
+            dates.sort();
+            dates.dedup();
+            let zone = self.zone;
+            self.pending = self
+                .rule
+                .select_positions(dates)
+                .into_iter()
+                .map(|date| at(date.and_time(local.time()), zone))
+                .filter(|start| *start > self.dtstart)
+                .rev()
+                .collect();
+            match self.pending.is_empty() {
+                true => self.empty_periods += 1,
+                false => self.empty_periods = 0,
+            }
+        }
+
+        let start = self.pending.pop()?;
+        if self.rule.until.is_some_and(|until| start > until) {
+            self.done = true;
+            return None;
+        }
+        self.emitted += 1;
+        Some(start)
+    }
+}
+
+impl Recurrence {
+    /// Reads the recurrence of a component from its RRULE, RDATE and EXDATE properties.
+    ///
+    /// # Returns
+    /// None if the component doesn't recur.
+    ///
+    /// # Errors
+    /// If one of the properties is malformed or not supported.
+    pub fn of(properties: &[Property]) -> anyhow::Result<Option<Self>> {
+        let mut recurrence = Self {
+            rules: vec![],
+            dates: vec![],
+            exceptions: vec![],
+            zone: None,
+        };
+        for property in properties {
+            match property.get_key().to_uppercase().as_str() {
+                "DTSTART" => recurrence.zone = zone_of(property),
+                "RRULE" => recurrence
+                    .rules
+                    .push(RecurrenceRule::parse(property.get_val())?),
+                "RDATE" => recurrence.dates.extend(parse_dates(property)?),
+                "EXDATE" => recurrence.exceptions.extend(parse_dates(property)?),
+                _ => {}
+            }
+        }
+
+        match recurrence.rules.is_empty() && recurrence.dates.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(recurrence)),
+        }
+    }
+
+    /// The starts of the occurrences overlapping `from..to`, in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `dtstart` - The start of the first occurrence.
+    /// * `duration` - How long each occurrence lasts.
+    /// * `from` - The start of the range.
+    /// * `to` - The end of the range, exclusively.
+    pub fn starts_in(
+        &self,
+        dtstart: DateTime<Utc>,
+        duration: TimeDelta,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<DateTime<Utc>> {
+        let overlaps = |start: &DateTime<Utc>| *start + duration > from || *start >= from;
+
+        let mut starts = vec![dtstart];
+        for rule in &self.rules {
+            starts.extend(
+                rule.starts_in_zone(dtstart, self.zone)
+                    .take_while(|start| *start < to),
+            );
+        }
+        starts.extend(self.dates.iter().copied());
+
+        self.finish(
+            starts
+                .into_iter()
+                .filter(|start| *start < to && overlaps(start))
+                .collect(),
+        )
+    }
+
+    /// When the last occurrence ends.
+    ///
+    /// # Returns
+    /// None if the series has no end.
+    pub fn series_end(&self, dtstart: DateTime<Utc>, duration: TimeDelta) -> Option<DateTime<Utc>> {
+        let mut starts = vec![dtstart];
+        for rule in &self.rules {
+            if rule.count.is_none() && rule.until.is_none() {
+                return None;
+            }
+            let rule_starts = rule
+                .starts_in_zone(dtstart, self.zone)
+                .take(MAX_SERIES_OCCURRENCES + 1)
+                .collect::<Vec<_>>();
+            if rule_starts.len() > MAX_SERIES_OCCURRENCES {
+                return None;
+            }
+            starts.extend(rule_starts);
+        }
+        starts.extend(self.dates.iter().copied());
+
+        self.finish(starts)
+            .last()
+            .map(|start| *start + duration)
+            .or(Some(dtstart + duration))
+    }
+
+    /// Sorts the starts and removes duplicates and excluded ones.
+    fn finish(&self, mut starts: Vec<DateTime<Utc>>) -> Vec<DateTime<Utc>> {
+        starts.retain(|start| !self.exceptions.contains(start));
+        starts.sort();
+        starts.dedup();
+        starts
+    }
+}
+
+/// Parses the comma separated times of an RDATE or EXDATE, in the zone of its TZID. Of periods,
+/// only the start is read.
+fn parse_dates(property: &Property) -> anyhow::Result<Vec<DateTime<Utc>>> {
+    let zone = zone_of(property);
+    property
+        .get_val()
+        .split(',')
+        .filter(|date| !date.is_empty())
+        .map(|date| {
+            let start = date.split('/').next().unwrap_or(date);
+            zone::parse_date_time_in(start, zone).map(|(start, _)| start)
+        })
+        .collect()
+}
+
+/// The zone of the TZID of a property, None if it has none or it is unknown.
+fn zone_of(property: &Property) -> Option<Tz> {
+    property
+        .param("TZID")
+        .and_then(|tzid| zone::parse_zone(&tzid))
+}
+
+fn split<T>(value: &str, parse: impl Fn(&str) -> anyhow::Result<T>) -> anyhow::Result<Vec<T>> {
+    value.split(',').map(|item| parse(item.trim())).collect()
+}
+
+fn parse_number(value: &str, min: i32, max: i32) -> anyhow::Result<i32> {
+    match value.parse::<i32>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ => bail!("Invalid number '{}'", value),
+    }
+}
+
+/// Parses a day or position that may count from the end, like `-1`. Zero is invalid.
+fn parse_day_number(value: &str, max: i32) -> anyhow::Result<i32> {
+    match parse_number(value, -max, max)? {
+        0 => bail!("Invalid number '{}'", value),
+        number => Ok(number),
+    }
+}
+
+fn parse_weekday_num(value: &str) -> anyhow::Result<WeekdayNum> {
+    let split = value.len().saturating_sub(2);
+    let (nth, weekday) = value.split_at_checked(split).unwrap_or(("", value));
+    let weekday = match weekday.to_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => bail!("Invalid weekday '{}'", value),
+    };
+    let nth = match nth {
+        "" => None,
+        nth => Some(parse_day_number(nth.trim_start_matches('+'), 53)?),
+    };
+    Ok(WeekdayNum { nth, weekday })
+}
+
+/// The first and last day of a month.
+fn month_bounds(year: i32, month: u32) -> (Option<NaiveDate>, Option<NaiveDate>) {
+    let start = NaiveDate::from_ymd_opt(year, month, 1);
+    let end = start
+        .and_then(|start| start.checked_add_months(Months::new(1)))
+        .and_then(|next| next.pred_opt());
+    (start, end)
+}
+
+/// The given day of a month, counted from its end if negative.
+fn month_day(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    match day > 0 {
+        true => NaiveDate::from_ymd_opt(year, month, day as u32),
+        false => {
+            let (_, end) = month_bounds(year, month);
+            let date = end?.checked_sub_days(Days::new((-day - 1) as u64))?;
+            (date.month() == month).then_some(date)
+        }
+    }
+}
+
+/// The point in time of a wall-clock time in the given zone, or in UTC.
+fn at(local: NaiveDateTime, zone: Option<Tz>) -> DateTime<Utc> {
+    match zone {
+        Some(zone) => zone::from_local(local, zone),
+        None => local.and_utc(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::model::property::OwnerType;
+    use uuid::Uuid;
+
+    fn time(value: &str) -> DateTime<Utc> {
+        parser::parse_date_time(value).unwrap().0
+    }
+
+    fn starts(rule: &str, dtstart: &str, take: usize) -> Vec<String> {
+        RecurrenceRule::parse(rule)
+            .unwrap()
+            .starts(time(dtstart))
+            .take(take)
+            .map(parser::format_date_time)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let rule =
+            RecurrenceRule::parse("FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR,+2MO;COUNT=5").unwrap();
+        assert_eq!(rule.freq, Frequency::MONTHLY);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.count, Some(5));
+        assert_eq!(
+            rule.by_day,
+            vec![
+                WeekdayNum {
+                    nth: Some(-1),
+                    weekday: Weekday::Fri
+                },
+                WeekdayNum {
+                    nth: Some(2),
+                    weekday: Weekday::Mon
+                },
+            ]
+        );
+
+        assert!(RecurrenceRule::parse("INTERVAL=2").is_err());
+        assert!(RecurrenceRule::parse("FREQ=HOURLY").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;BYHOUR=9").is_err());
+        assert!(RecurrenceRule::parse("FREQ=DAILY;COUNT=2;UNTIL=20240101").is_err());
+        assert!(RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=1MO").is_err());
+        assert!(RecurrenceRule::parse("FREQ=MONTHLY;BYMONTHDAY=0").is_err());
+    }
+
+    #[test]
+    fn test_starts() {
+        assert_eq!(
+            starts("FREQ=DAILY;INTERVAL=2;COUNT=3", "20240130T090000Z", 10),
+            vec!["20240130T090000Z", "20240201T090000Z", "20240203T090000Z"]
+        );
+        assert_eq!(
+            starts("FREQ=WEEKLY;BYDAY=MO,WE", "20240501T090000Z", 4),
+            vec![
+                "20240501T090000Z",
+                "20240506T090000Z",
+                "20240508T090000Z",
+                "20240513T090000Z"
+            ]
+        );
+        // Months without a 31st are skipped
+        assert_eq!(
+            starts("FREQ=MONTHLY;UNTIL=20240601", "20240131T120000Z", 10),
+            vec!["20240131T120000Z", "20240331T120000Z", "20240531T120000Z"]
+        );
+        assert_eq!(
+            starts("FREQ=MONTHLY;BYDAY=-1FR", "20240126T100000Z", 3),
+            vec!["20240126T100000Z", "20240223T100000Z", "20240329T100000Z"]
+        );
+        // The last workday of the month
+        assert_eq!(
+            starts(
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "20240131T080000Z",
+                3
+            ),
+            vec!["20240131T080000Z", "20240229T080000Z", "20240329T080000Z"]
+        );
+        assert_eq!(
+            starts(
+                "FREQ=MONTHLY;BYMONTHDAY=1,-1;COUNT=4",
+                "20240101T000000Z",
+                10
+            ),
+            vec![
+                "20240101T000000Z",
+                "20240131T000000Z",
+                "20240201T000000Z",
+                "20240229T000000Z"
+            ]
+        );
+        // The second Sunday of May
+        assert_eq!(
+            starts("FREQ=YEARLY;BYMONTH=5;BYDAY=2SU", "20240512T000000Z", 3),
+            vec!["20240512T000000Z", "20250511T000000Z", "20260510T000000Z"]
+        );
+        assert_eq!(
+            starts("FREQ=YEARLY", "20240229T000000Z", 2),
+            vec!["20240229T000000Z", "20280229T000000Z"]
+        );
+        // Never matches, but must not loop forever
+        assert_eq!(
+            starts("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30", "20240101T000000Z", 3),
+            vec!["20240101T000000Z"]
+        );
+    }
+
+    #[test]
+    fn test_recurrence() {
+        let property = |key: &str, val: &str| {
+            Property::new(
+                Uuid::nil(),
+                OwnerType::COMPONENT,
+                key.to_string(),
+                val.to_string(),
+            )
+        };
+        let properties = vec![
+            property("SUMMARY", "Team meeting"),
+            property("RRULE", "FREQ=WEEKLY;COUNT=4"),
+            property("EXDATE", "20240508T090000Z"),
+            property("RDATE", "20240510T150000Z,20240601T090000Z"),
+        ];
+        let recurrence = Recurrence::of(&properties).unwrap().unwrap();
+        let dtstart = time("20240501T090000Z");
+        let hour = TimeDelta::hours(1);
+
+        let may = recurrence
+            .starts_in(
+                dtstart,
+                hour,
+                time("20240501T093000Z"),
+                time("20240601T000000Z"),
+            )
+            .into_iter()
+            .map(parser::format_date_time)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            may,
+            vec![
+                "20240501T090000Z",
+                "20240510T150000Z",
+                "20240515T090000Z",
+                "20240522T090000Z"
+            ]
+        );
+        assert_eq!(
+            recurrence.series_end(dtstart, hour),
+            Some(time("20240601T100000Z"))
+        );
+
+        let endless = Recurrence::of(&[property("RRULE", "FREQ=DAILY")])
+            .unwrap()
+            .unwrap();
+        assert_eq!(endless.series_end(dtstart, hour), None);
+        assert_eq!(Recurrence::of(&properties[..1]).unwrap(), None);
+        assert!(Recurrence::of(&[property("RRULE", "FREQ=SECONDLY")]).is_err());
+    }
+
+    #[test]
+    fn test_zoned_recurrence() {
+        let property = |key: &str, val: &str| {
+            Property::new(
+                Uuid::nil(),
+                OwnerType::COMPONENT,
+                key.to_string(),
+                val.to_string(),
+            )
+            .with_params(String::from("TZID=Europe/Berlin"))
+        };
+        let properties = vec![
+            property("DTSTART", "20240325T090000"),
+            Property::hold(String::from("RRULE"), String::from("FREQ=WEEKLY;COUNT=4")),
+            property("EXDATE", "20240408T090000"),
+        ];
+        let recurrence = Recurrence::of(&properties).unwrap().unwrap();
+        let dtstart = time("20240325T080000Z");
+
+        // 09:00 in Berlin is 08:00 UTC until the clocks change on March 31, 07:00 UTC after
+        let starts = recurrence
+            .starts_in(
+                dtstart,
+                TimeDelta::hours(1),
+                dtstart,
+                time("20240501T000000Z"),
+            )
+            .into_iter()
+            .map(parser::format_date_time)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            vec!["20240325T080000Z", "20240401T070000Z", "20240415T070000Z"]
+        );
+        assert_eq!(
+            recurrence.series_end(dtstart, TimeDelta::hours(1)),
+            Some(time("20240415T080000Z"))
+        );
+    }
+}
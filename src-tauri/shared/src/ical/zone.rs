@@ -0,0 +1,241 @@
+use chrono::{DateTime, LocalResult, NaiveDateTime, Offset, TimeDelta, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+
+use super::parser;
+
+/// The format of a local DATE-TIME value, one without a `Z`.
+const LOCAL_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+/// Looks up an IANA time zone, like `Europe/Berlin`. A leading `/`, which marks globally
+/// unique TZIDs, is ignored.
+///
+/// # Returns
+/// None if there is no IANA zone with the given name.
+pub fn parse_zone(tzid: &str) -> Option<Tz> {
+    tzid.trim().trim_start_matches('/').parse().ok()
+}
+
+/// The wall-clock time of a point in time in the given zone.
+pub fn to_local(time: DateTime<Utc>, zone: Tz) -> NaiveDateTime {
+    time.with_timezone(&zone).naive_local()
+}
+
+/// The point in time of a wall-clock time in the given zone.
+///
+/// Times occurring twice, when the clocks are turned back, are read as the first of both.
+/// Times skipped when the clocks are turned forward are read with the offset from before, so
+/// 02:30 becomes 03:30 on a day the clocks jump from 02:00 to 03:00.
+pub fn from_local(local: NaiveDateTime, zone: Tz) -> DateTime<Utc> {
+    match zone.from_local_datetime(&local) {
+        LocalResult::Single(time) => time.with_timezone(&Utc),
+        LocalResult::Ambiguous(first, _) => first.with_timezone(&Utc),
+        LocalResult::None => {
+            let before = zone
+                .offset_from_utc_datetime(&(local - TimeDelta::days(1)))
+                .fix();
+            (local - TimeDelta::seconds(before.local_minus_utc() as i64)).and_utc()
+        }
+    }
+}
+
+/// Parses a DATE or DATE-TIME value of a property with the given zone, its TZID. Times with a
+/// `Z` and dates are read like `parser::parse_date_time` does, other times as wall-clock times
+/// in the zone.
+///
+/// # Errors
+/// If the value is neither a DATE nor a DATE-TIME.
+pub fn parse_date_time_in(value: &str, zone: Option<Tz>) -> anyhow::Result<(DateTime<Utc>, bool)> {
+    let (time, is_date) = parser::parse_date_time(value)?;
+    match zone {
+        Some(zone) if !is_date && !value.ends_with(['Z', 'z']) => {
+            Ok((from_local(time.naive_utc(), zone), false))
+        }
+        _ => Ok((time, is_date)),
+    }
+}
+
+/// Formats a time as a DATE-TIME value in the given zone, like `20240501T110000`, to be
+/// written with a TZID parameter.
+pub fn format_local(time: DateTime<Utc>, zone: Tz) -> String {
+    to_local(time, zone).format(LOCAL_FORMAT).to_string()
+}
+
+/// The VTIMEZONE of a zone as content lines, describing its offsets from `from` until `to`.
+///
+/// Each change of the offset within the range is written as a STANDARD or DAYLIGHT
+/// observance of its own, starting with the offset in effect at `from`.
+pub fn vtimezone(zone: Tz, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<String> {
+    let mut lines = vec![
+        String::from("BEGIN:VTIMEZONE"),
+        format!("TZID:{}", zone.name()),
+    ];
+    let start = offset_at(zone, from);
+    lines.extend(observance(to_local(from, zone), start, start));
+
+    let mut before = start;
+    for change in changes(zone, from, to) {
+        let after = offset_at(zone, change);
+        let local = change.naive_utc() + TimeDelta::seconds(before.fix().local_minus_utc() as i64);
+        lines.extend(observance(local, before, after));
+        before = after;
+    }
+
+    lines.push(String::from("END:VTIMEZONE"));
+    lines
+}
+
+/// The offset of a zone at a point in time.
+fn offset_at(zone: Tz, time: DateTime<Utc>) -> <Tz as TimeZone>::Offset {
+    zone.offset_from_utc_datetime(&time.naive_utc())
+}
+
+/// The points in time within `from..to` the offset of a zone changes at, to the second.
+/// Offsets are assumed not to change more than once a day.
+fn changes(zone: Tz, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let mut changes = vec![];
+    let mut day = from;
+    while day < to {
+        let next = day + TimeDelta::days(1);
+        if offset_at(zone, day) != offset_at(zone, next) {
+            let (mut unchanged, mut changed) = (day, next);
+            while changed - unchanged > TimeDelta::seconds(1) {
+                let middle = unchanged + (changed - unchanged) / 2;
+                match offset_at(zone, middle) == offset_at(zone, unchanged) {
+                    true => unchanged = middle,
+                    false => changed = middle,
+                }
+            }
+            if changed < to {
+                changes.push(changed);
+            }
+        }
+        day = next;
+    }
+    changes
+}
+
+/// A STANDARD or DAYLIGHT observance starting at a wall-clock time, given in the offset from
+/// before.
+fn observance(
+    local: NaiveDateTime,
+    before: <Tz as TimeZone>::Offset,
+    after: <Tz as TimeZone>::Offset,
+) -> Vec<String> {
+    let name = match after.dst_offset().is_zero() {
+        true => "STANDARD",
+        false => "DAYLIGHT",
+    };
+    let mut lines = vec![
+        format!("BEGIN:{}", name),
+        format!("DTSTART:{}", local.format(LOCAL_FORMAT)),
+        format!(
+            "TZOFFSETFROM:{}",
+            format_offset(before.fix().local_minus_utc())
+        ),
+        format!(
+            "TZOFFSETTO:{}",
+            format_offset(after.fix().local_minus_utc())
+        ),
+    ];
+    if let Some(abbreviation) = after.abbreviation() {
+        lines.push(format!("TZNAME:{}", parser::escape_text(abbreviation)));
+    }
+    lines.push(format!("END:{}", name));
+    lines
+}
+
+/// Formats an offset from UTC in seconds as a UTC-OFFSET value, like `+0100`.
+fn format_offset(seconds: i32) -> String {
+    let sign = match seconds < 0 {
+        true => '-',
+        false => '+',
+    };
+    let seconds = seconds.unsigned_abs();
+    let (hours, minutes, rest) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match rest {
+        0 => format!("{}{:02}{:02}", sign, hours, minutes),
+        rest => format!("{}{:02}{:02}{:02}", sign, hours, minutes, rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn local(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, LOCAL_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn test_from_local() {
+        let berlin = parse_zone("/Europe/Berlin").unwrap();
+        assert_eq!(parse_zone("Mars/Olympus"), None);
+
+        assert_eq!(
+            parser::format_date_time(from_local(local("20240115T090000"), berlin)),
+            "20240115T080000Z"
+        );
+        // Skipped when the clocks are turned forward
+        assert_eq!(
+            parser::format_date_time(from_local(local("20240331T023000"), berlin)),
+            "20240331T013000Z"
+        );
+        // Occurs twice when the clocks are turned back
+        assert_eq!(
+            parser::format_date_time(from_local(local("20241027T023000"), berlin)),
+            "20241027T003000Z"
+        );
+
+        let (time, is_date) = parse_date_time_in("20240701T090000", Some(berlin)).unwrap();
+        assert_eq!(
+            (parser::format_date_time(time), is_date),
+            (String::from("20240701T070000Z"), false)
+        );
+        let (time, _) = parse_date_time_in("20240701T090000Z", Some(berlin)).unwrap();
+        assert_eq!(format_local(time, berlin), "20240701T110000");
+        assert!(parse_date_time_in("20240701", Some(berlin)).unwrap().1);
+    }
+
+    #[test]
+    fn test_vtimezone() {
+        let berlin = parse_zone("Europe/Berlin").unwrap();
+        let year = |year| {
+            NaiveDate::from_ymd_opt(year, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        assert_eq!(
+            vtimezone(berlin, year(2024), year(2025)),
+            vec![
+                "BEGIN:VTIMEZONE",
+                "TZID:Europe/Berlin",
+                "BEGIN:STANDARD",
+                "DTSTART:20240101T010000",
+                "TZOFFSETFROM:+0100",
+                "TZOFFSETTO:+0100",
+                "TZNAME:CET",
+                "END:STANDARD",
+                "BEGIN:DAYLIGHT",
+                "DTSTART:20240331T020000",
+                "TZOFFSETFROM:+0100",
+                "TZOFFSETTO:+0200",
+                "TZNAME:CEST",
+                "END:DAYLIGHT",
+                "BEGIN:STANDARD",
+                "DTSTART:20241027T030000",
+                "TZOFFSETFROM:+0200",
+                "TZOFFSETTO:+0100",
+                "TZNAME:CET",
+                "END:STANDARD",
+                "END:VTIMEZONE",
+            ]
+        );
+
+        let tokyo = parse_zone("Asia/Tokyo").unwrap();
+        assert_eq!(vtimezone(tokyo, year(2024), year(2025)).len(), 9);
+        assert_eq!(format_offset(-(3 * 3600 + 30 * 60)), "-0330");
+    }
+}
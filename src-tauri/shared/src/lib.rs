@@ -1,6 +1,10 @@
+pub mod alarms;
 pub mod auth_util;
 pub mod bencher;
+pub mod cache;
 pub mod crypto;
 pub mod current;
 pub mod db;
 pub mod errors;
+pub mod ical;
+pub mod notifications;
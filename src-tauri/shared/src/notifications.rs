@@ -0,0 +1,238 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use pg_driver::{DriverConfig, ListenerEvent, NotificationListener, ReconnectPolicy};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use uuid::Uuid;
+
+use crate::cache::CalendarCache;
+use crate::current::{self, get_app_handle};
+
+/// The channel the database announces changes on, see the `change_notifications` migration.
+pub const CHANGES_CHANNEL: &str = "scheduleflow_changes";
+
+/// The name of the Tauri event every `ChangeEvent` is emitted as.
+pub const CHANGE_EVENT: &str = "dataChanged";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A row of a calendar that was changed, by this or any other client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    /// The table of the changed row, `calendars`, `components`, `properties` or `alarms`.
+    pub table: String,
+    pub op: ChangeOp,
+    pub uuid: Uuid,
+    /// The calendar the row belongs to. None if it is unknown, like for the properties of a
+    /// component deleted along with them.
+    pub calendar_uuid: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ChangeEvent {
+    Changed(Change),
+    /// Changes may have been missed while the database was unreachable, so all calendars have
+    /// to be reloaded.
+    Resync,
+    /// The calendars of the given user were reloaded in the background, after outdated ones
+    /// were returned from the cache.
+    Reloaded {
+        user_uuid: Uuid,
+    },
+}
+
+type Listener = Arc<dyn Fn(&ChangeEvent) + Send + Sync>;
+
+static LISTENERS: Mutex<Vec<Listener>> = Mutex::new(Vec::new());
+
+/// Registers a function called for every change of the calendars, after the cached calendars
+/// were invalidated.
+///
+/// # Examples
+///
+/// ```
+/// notifications::on_change(|event| {
+///     if let ChangeEvent::Changed(change) = event {
+///         println!("{} of {} changed", change.table, change.uuid);
+///     }
+/// });
+/// ```
+pub fn on_change(listener: impl Fn(&ChangeEvent) + Send + Sync + 'static) {
+    LISTENERS.lock().unwrap().push(Arc::new(listener));
+}
+
+/// Starts listening for changes in the background, if not done yet. Every change invalidates
+/// the cached calendars it affects, is emitted to the frontend as `CHANGE_EVENT` and passed to the
+/// functions registered with `on_change`.
+///
+/// A local SQLite database has no other clients, nothing is listened for then.
+///
+/// # Errors
+/// If the listener thread could not be started.
+pub fn listen() -> anyhow::Result<()> {
+    static LISTENER: OnceLock<Mutex<NotificationListener>> = OnceLock::new();
+
+    let config = current::pool().driver_config();
+    if LISTENER.get().is_some() || matches!(config, DriverConfig::Sqlite { .. }) {
+        return Ok(());
+    }
+
+    let listener = NotificationListener::spawn(
+        config,
+        &[CHANGES_CHANNEL],
+        ReconnectPolicy::default(),
+        dispatch,
+    )?;
+    // Another thread may have been faster, its listener is kept and this one stopped
+    let _ = LISTENER.set(Mutex::new(listener));
+    Ok(())
+}
+
+fn dispatch(event: ListenerEvent) {
+    if let Some(event) = to_change_event(event) {
+        invalidate(current::calendar_cache(), &event);
+        publish(&event);
+    }
+}
+
+fn to_change_event(event: ListenerEvent) -> Option<ChangeEvent> {
+    match event {
+        ListenerEvent::Notification(notification) => {
+            match serde_json::from_str(&notification.payload) {
+                Ok(change) => Some(ChangeEvent::Changed(change)),
+                Err(e) => {
+                    log::warn!(
+                        "Ignoring malformed change notification '{}': {}",
+                        notification.payload,
+                        e
+                    );
+                    None
+                }
+            }
+        }
+        ListenerEvent::Reconnected => Some(ChangeEvent::Resync),
+    }
+}
+
+/// Marks the cached calendars affected by the given event as outdated.
+fn invalidate(cache: &CalendarCache, event: &ChangeEvent) {
+    match event {
+        ChangeEvent::Changed(change) => match change.calendar_uuid {
+            Some(calendar_uuid) => {
+                cache.invalidate(calendar_uuid);
+                // The users got or lost a calendar
+                if change.table == "calendars" && change.op != ChangeOp::Update {
+                    cache.invalidate_users();
+                }
+            }
+            None => cache.invalidate_all(),
+        },
+        ChangeEvent::Resync => cache.invalidate_all(),
+        ChangeEvent::Reloaded { .. } => {}
+    }
+}
+
+/// Notifies the frontend and the listeners about the given event.
+pub(crate) fn publish(event: &ChangeEvent) {
+    if let Some(app_handle) = get_app_handle() {
+        if let Err(e) = app_handle.emit(CHANGE_EVENT, event) {
+            log::warn!("Could not emit {}: {}", CHANGE_EVENT, e);
+        }
+    }
+
+    // Don't hold the lock while calling out, listeners may register other listeners
+    let listeners = LISTENERS.lock().unwrap().clone();
+    for listener in listeners {
+        listener(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::cache::Lookup;
+    use crate::db::model::simple::simple_calendar::SimpleCalendar;
+
+    fn notification(payload: String) -> ListenerEvent {
+        ListenerEvent::Notification(pg_driver::Notification {
+            channel: String::from(CHANGES_CHANNEL),
+            payload,
+        })
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let calendar = Uuid::new_v4();
+        let component = Uuid::new_v4();
+        // Formatted like the output of json_build_object
+        let payload = format!(
+            r#"{{"table" : "components", "op" : "insert", "uuid" : "{}", "calendar_uuid" : "{}"}}"#,
+            component, calendar
+        );
+        let change = Change {
+            table: String::from("components"),
+            op: ChangeOp::Insert,
+            uuid: component,
+            calendar_uuid: Some(calendar),
+        };
+        assert_eq!(
+            to_change_event(notification(payload)),
+            Some(ChangeEvent::Changed(change.clone()))
+        );
+        assert_eq!(
+            to_change_event(notification(String::from("not json"))),
+            None
+        );
+        assert_eq!(
+            to_change_event(ListenerEvent::Reconnected),
+            Some(ChangeEvent::Resync)
+        );
+
+        let json = serde_json::to_value(ChangeEvent::Changed(change.clone())).unwrap();
+        assert_eq!(json["kind"], "changed");
+        assert_eq!(json["op"], "insert");
+        assert_eq!(json["calendar_uuid"], calendar.to_string());
+
+        let (tx, rx) = mpsc::channel();
+        on_change(move |event| tx.send(event.clone()).unwrap());
+        publish(&ChangeEvent::Resync);
+        assert_eq!(rx.recv().unwrap(), ChangeEvent::Resync);
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let cache = CalendarCache::new(Duration::from_secs(60));
+        let user = Uuid::new_v4();
+        let calendars = ["Work", "Home"].map(|name| {
+            SimpleCalendar::new(Uuid::new_v4(), String::from(name), vec![], vec![], vec![])
+        });
+        cache.store_calendars_of(user, &calendars);
+        let change = |table: &str, op, calendar_uuid| {
+            ChangeEvent::Changed(Change {
+                table: String::from(table),
+                op,
+                uuid: Uuid::new_v4(),
+                calendar_uuid,
+            })
+        };
+
+        let work = calendars[0].get_uuid();
+        let home = calendars[1].get_uuid();
+        invalidate(&cache, &change("properties", ChangeOp::Update, Some(work)));
+        assert!(!cache.calendar(work).is_fresh());
+        assert!(cache.calendar(home).is_fresh());
+
+        invalidate(&cache, &change("components", ChangeOp::Delete, None));
+        assert!(matches!(cache.calendar(home), Lookup::Stale(_)));
+    }
+}
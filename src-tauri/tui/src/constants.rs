@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 pub(crate) const BANNER: &'static str = r#"
 ░▒▓███████▓▒░░▒▓██████▓▒░░▒▓█▓▒░░▒▓█▓▒░▒▓████████▓▒░▒▓███████▓▒░░▒▓█▓▒░░▒▓█▓▒░▒▓█▓▒░      ░▒▓████████▓▒░▒▓████████▓▒░▒▓█▓▒░      ░▒▓██████▓▒░░▒▓█▓▒░░▒▓█▓▒░░▒▓█▓▒░ 
 ░▒▓█▓▒░      ░▒▓█▓▒░░▒▓█▓▒░▒▓█▓▒░░▒▓█▓▒░▒▓█▓▒░      ░▒▓█▓▒░░▒▓█▓▒░▒▓█▓▒░░▒▓█▓▒░▒▓█▓▒░      ░▒▓█▓▒░      ░▒▓█▓▒░      ░▒▓█▓▒░     ░▒▓█▓▒░░▒▓█▓▒░▒▓█▓▒░░▒▓█▓▒░░▒▓█▓▒░ 
@@ -9,3 +11,12 @@ pub(crate) const BANNER: &'static str = r#"
 "#;
 
 pub(crate) const BANNER_HEIGHT: u8 = 8;
+
+/// How long a change notification stays visible.
+pub(crate) const NOTIFICATION_DURATION: Duration = Duration::from_secs(5);
+
+/// How long the tui waits for input before it checks for change notifications again.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a reminder popup is snoozed for with 's'.
+pub(crate) const SNOOZE_MINUTES: i64 = 5;
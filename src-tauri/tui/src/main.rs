@@ -1,12 +1,41 @@
+use std::ops::DerefMut;
+
 use dotenv::dotenv;
+use shared::{current::driver, db::migrations::Migrations, notifications};
 use ui::tui::Tui;
 
 mod constants;
+mod migrate;
 mod ui;
 
 fn main() {
-    // TODO: Driver
     dotenv().ok();
+    if let Err(e) = config::load() {
+        eprintln!("Invalid configuration: {}", e);
+        std::process::exit(1);
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        if let Err(e) = migrate::run(&args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match driver() {
+        Ok(mut driver) => {
+            if let Err(e) = Migrations::run(driver.deref_mut()) {
+                eprintln!("Could not migrate the database: {:#}", e);
+            }
+        }
+        Err(e) => eprintln!("Could not migrate the database: {}", e),
+    }
+    if let Err(e) = notifications::listen() {
+        eprintln!("Could not listen for database changes: {:#}", e);
+    }
+
     if let Err(e) = Tui::start() {
         panic!("Tui could not start: {}", e)
     }
@@ -0,0 +1,54 @@
+use std::ops::DerefMut;
+
+use color_eyre::eyre::{eyre, Result};
+use shared::{current::driver, db::migrations::Migrations};
+
+/// Handles `tui migrate [up | status | rollback [steps]]`, managing the database schema without
+/// starting the user interface.
+pub(crate) fn run(args: &[String]) -> Result<()> {
+    let mut driver = driver().map_err(|e| eyre!(e))?;
+
+    match args.first().map(String::as_str) {
+        None | Some("up") => {
+            let applied = Migrations::run(driver.deref_mut()).map_err(|e| eyre!("{:#}", e))?;
+            if applied.is_empty() {
+                println!("The database is up to date");
+            }
+            for version in applied {
+                println!("Applied migration {}", version);
+            }
+        }
+        Some("status") => {
+            let status = Migrations::status(driver.deref_mut()).map_err(|e| eyre!("{:#}", e))?;
+            for migration in status {
+                let state = if migration.applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!("{:>4}  {:<8} {}", migration.version, state, migration.name);
+            }
+        }
+        Some("rollback") => {
+            let steps = match args.get(1) {
+                Some(steps) => steps
+                    .parse()
+                    .map_err(|_| eyre!("Invalid amount of steps: {}", steps))?,
+                None => 1,
+            };
+            let reverted =
+                Migrations::rollback(driver.deref_mut(), steps).map_err(|e| eyre!("{:#}", e))?;
+            for version in reverted {
+                println!("Reverted migration {}", version);
+            }
+        }
+        Some(cmd) => {
+            return Err(eyre!(
+                "Unknown migrate command '{}', expected one of: up, status, rollback [steps]",
+                cmd
+            ))
+        }
+    }
+
+    Ok(())
+}
@@ -1,20 +1,96 @@
+use std::ops::DerefMut;
+use std::path::Path;
+
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use color_eyre::eyre::Result;
 use crossterm::event::KeyCode;
 use ratatui::{
     layout::Rect,
-    widgets::{Block, Borders},
+    text::{Line, Text},
+    widgets::{Block, Borders, Paragraph},
     Frame,
 };
+use shared::{
+    current::{driver, get_current_user},
+    db::{
+        model::{
+            scheduling::{Slot, SlotRequest},
+            user::User,
+        },
+        repository::user_repository::UserRepository,
+        service::{calendar_service::CalendarService, scheduling_service::SchedulingService},
+    },
+    ical::import::ImportReport,
+};
 
-use crate::ui::tui::Cmd;
+use crate::ui::{tui::Cmd, widgets::input_field::InputWidget};
 
 use super::screen::Screen;
 
 #[derive(Clone)]
-pub(crate) struct HomePageScreen;
+pub(crate) struct HomePageScreen {
+    /// The path of an .ics file to import.
+    import_path: InputWidget,
+    /// A meeting to find slots for, as its length in minutes followed by the emails of the
+    /// attendees. Optional attendees are prefixed with `?`.
+    meeting: InputWidget,
+    /// What the report shows, `Import` or `Slots`.
+    report_title: &'static str,
+    /// The outcome of the last import or slot search, line by line.
+    report: Vec<String>,
+}
+
+/// How many days ahead to search for meeting slots.
+const SLOT_SEARCH_DAYS: i64 = 14;
+
+/// How many meeting slots to propose.
+const SLOT_COUNT: usize = 5;
 
 impl HomePageScreen {
     pub(crate) fn new() -> Self {
-        Self {}
+        Self {
+            import_path: InputWidget::new(String::from("Import .ics file"), 'i'),
+            meeting: InputWidget::new(String::from("Find meeting slots"), 'm'),
+            report_title: "Import",
+            report: vec![],
+        }
+    }
+
+    fn import(&mut self) -> Cmd {
+        let path = Path::new(self.import_path.input().trim());
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("Imported calendar"));
+
+        self.report = match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                let user = get_current_user().lock().unwrap().clone().unwrap();
+                match CalendarService::import_ics(&user, &contents, &name) {
+                    Ok(report) => describe_report(&report),
+                    Err(e) => vec![e.to_string()],
+                }
+            }
+            Err(e) => vec![format!("Could not read {}: {}", path.display(), e)],
+        };
+        self.report_title = "Import";
+
+        self.unfocus_all()
+    }
+
+    fn find_slots(&mut self) -> Cmd {
+        let user = get_current_user().lock().unwrap().clone().unwrap();
+        self.report = match slot_request(self.meeting.input()) {
+            Ok(request) => match SchedulingService::find_slots(&user, &request) {
+                Ok(slots) if slots.is_empty() => vec![String::from("No free slot found")],
+                Ok(slots) => describe_slots(&user, &slots),
+                Err(e) => vec![e.to_string()],
+            },
+            Err(e) => vec![e],
+        };
+        self.report_title = "Slots";
+
+        self.unfocus_all()
     }
 }
 
@@ -23,23 +99,173 @@ impl Screen for HomePageScreen {
         self
     }
 
-    fn render(&self, f: &mut Frame, bounds: Rect) -> color_eyre::eyre::Result<()> {
-        todo!("")
+    fn render(&self, f: &mut Frame, bounds: Rect) -> Result<()> {
+        let input_height = 3;
+        let width = bounds.width * 2 / 3;
+        let start_x = bounds.x + (bounds.width - width) / 2;
+        let start_y = bounds.y + 2;
+
+        let path_bounds = Rect::new(start_x, start_y, width, input_height);
+        self.import_path.render(f, path_bounds);
+
+        let meeting_bounds = Rect::new(start_x, start_y + input_height, width, input_height);
+        self.meeting.render(f, meeting_bounds);
+
+        let report_top = start_y + 2 * input_height + 1;
+        let report_bounds = Rect::new(
+            start_x,
+            report_top,
+            width,
+            bounds.bottom().saturating_sub(report_top + 1),
+        );
+        let lines = self
+            .report
+            .iter()
+            .map(|line| Line::from(line.as_str()))
+            .collect::<Vec<_>>();
+        let report = Paragraph::new(Text::from(lines)).block(
+            Block::default()
+                .title(format!(" {} ", self.report_title))
+                .borders(Borders::ALL),
+        );
+        f.render_widget(report, report_bounds);
+
+        Ok(())
     }
 
     fn unfocus_all(&mut self) -> Cmd {
-        todo!()
+        self.import_path.set_focus(false);
+        self.meeting.set_focus(false);
+        return Cmd::ChangeMode;
     }
 
     fn cycle_input_fields(&mut self) {
-        todo!()
+        // Both input fields are submitted on their own
     }
 
     fn handle_input(&mut self, key: KeyCode) -> Cmd {
-        todo!()
+        match key {
+            KeyCode::Esc => return self.unfocus_all(),
+            KeyCode::Enter if self.meeting.is_focused() => return self.find_slots(),
+            KeyCode::Enter => return self.import(),
+            _ => {
+                self.import_path.handle_input(key);
+                self.meeting.handle_input(key);
+                return Cmd::None;
+            }
+        };
     }
 
     fn handle_cmd(&mut self, key: KeyCode) -> Cmd {
-        todo!()
+        if key == KeyCode::Char(self.import_path.key) {
+            self.import_path.set_focus(true);
+            return Cmd::ChangeMode;
+        } else if key == KeyCode::Char(self.meeting.key) {
+            self.meeting.set_focus(true);
+            return Cmd::ChangeMode;
+        }
+
+        return Cmd::None;
+    }
+}
+
+/// Lists what an import stored and what it skipped.
+fn describe_report(report: &ImportReport) -> Vec<String> {
+    let mut lines = report
+        .calendars
+        .iter()
+        .map(|calendar| {
+            format!(
+                "Imported {} with {} entries",
+                calendar.name, calendar.components
+            )
+        })
+        .collect::<Vec<_>>();
+    let issues = report
+        .errors
+        .iter()
+        .map(|issue| ("Skipped", issue))
+        .chain(report.warnings.iter().map(|issue| ("Warning", issue)));
+    for (kind, issue) in issues {
+        lines.push(format!(
+            "{} {} {}in line {}: {}",
+            kind,
+            issue.component,
+            issue
+                .uid
+                .as_ref()
+                .map(|uid| format!("{} ", uid))
+                .unwrap_or_default(),
+            issue.line,
+            issue.message
+        ));
+    }
+    for conflict in &report.conflicts {
+        lines.push(format!(
+            "Overlaps {} at {}",
+            conflict.summary.as_deref().unwrap_or("an event"),
+            conflict
+                .starts_at
+                .with_timezone(&Local)
+                .format("%a %Y-%m-%d %H:%M")
+        ));
     }
+    lines
+}
+
+/// Reads a meeting like `30 alice@example.com ?bob@example.com`, searched from now on.
+fn slot_request(input: &str) -> Result<SlotRequest, String> {
+    let mut words = input.split_whitespace();
+    let duration_minutes = words
+        .next()
+        .and_then(|minutes| minutes.parse::<i64>().ok())
+        .ok_or_else(|| String::from("Start with the length of the meeting in minutes"))?;
+
+    let mut required = vec![];
+    let mut optional = vec![];
+    for word in words {
+        let (email, attendees) = match word.strip_prefix('?') {
+            Some(email) => (email, &mut optional),
+            None => (word, &mut required),
+        };
+        let mut driver = driver().map_err(String::from)?;
+        let attendee = UserRepository::get_by_email(driver.deref_mut(), email.to_string())
+            .map_err(|_| format!("There is no user with the email {}", email))?;
+        attendees.push(attendee.get_uuid());
+    }
+
+    let from = Utc::now();
+    Ok(SlotRequest {
+        required,
+        optional,
+        duration_minutes,
+        from,
+        to: from + TimeDelta::days(SLOT_SEARCH_DAYS),
+        count: SLOT_COUNT,
+    })
+}
+
+/// Lists the slots in the zone of the user, with how many optional attendees can attend.
+fn describe_slots(user: &User, slots: &[Slot]) -> Vec<String> {
+    let show = |time: DateTime<Utc>| match user.display_zone() {
+        Some(zone) => time
+            .with_timezone(&zone)
+            .format("%a %Y-%m-%d %H:%M")
+            .to_string(),
+        None => time
+            .with_timezone(&Local)
+            .format("%a %Y-%m-%d %H:%M")
+            .to_string(),
+    };
+    slots
+        .iter()
+        .map(|slot| {
+            format!(
+                "{} - {}, {} optional attendees",
+                show(slot.starts_at),
+                show(slot.ends_at),
+                slot.optional_attendees.len()
+            )
+        })
+        .collect()
 }
@@ -1,4 +1,10 @@
-use std::{boxed::Box, io, ops::DerefMut};
+use std::{
+    boxed::Box,
+    io,
+    ops::DerefMut,
+    sync::{mpsc, Arc, Mutex},
+    time::Instant,
+};
 
 use color_eyre::Result;
 use crossterm::{
@@ -11,10 +17,16 @@ use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Rect},
     text::{Line, Text},
-    widgets::Paragraph,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame, Terminal,
 };
-use shared::{auth_util::AuthUtil, current::driver};
+use shared::{
+    alarms::{self, Notifier},
+    auth_util::AuthUtil,
+    current::{driver, get_current_user},
+    db::{model::alarm::DueAlarm, service::alarm_service::AlarmService},
+    notifications::{self, ChangeEvent, ChangeOp},
+};
 
 use crate::constants;
 
@@ -54,12 +66,27 @@ impl PartialEq for Cmd {
     }
 }
 
+/// Hands the due alarms over to the tui, which shows them as popups.
+struct TuiNotifier {
+    alarms_tx: Mutex<mpsc::Sender<DueAlarm>>,
+}
+
+impl Notifier for TuiNotifier {
+    fn notify(&self, alarm: &DueAlarm) {
+        let _ = self.alarms_tx.lock().unwrap().send(alarm.clone());
+    }
+}
+
 pub(crate) struct Tui {
     state: AppState,
     current_mode: Mode,
     login_screen: LoginScreen,
     signup_screen: SignupScreen,
     home_page_screen: HomePageScreen,
+    /// The most recent change of the calendars and when it was received.
+    notification: Option<(String, Instant)>,
+    /// The due alarms not snoozed or dismissed yet, the first one is shown as a popup.
+    alarms: Vec<DueAlarm>,
 }
 
 impl Tui {
@@ -70,6 +97,8 @@ impl Tui {
             login_screen: LoginScreen::new(),
             signup_screen: SignupScreen::new(),
             home_page_screen: HomePageScreen::new(),
+            notification: None,
+            alarms: vec![],
         }
     }
 
@@ -99,14 +128,42 @@ impl Tui {
         //    tui.state = AppState::HomePageScreen;
         //}
 
+        // Show the changes made to the calendars while the tui is open, e.g. by other clients
+        let (changes_tx, changes) = mpsc::channel();
+        notifications::on_change(move |event| {
+            let _ = changes_tx.send(event.clone());
+        });
+
+        // Remind the user of their events and to-dos
+        let (alarms_tx, due_alarms) = mpsc::channel();
+        let notifier = Arc::new(TuiNotifier {
+            alarms_tx: Mutex::new(alarms_tx),
+        });
+        if let Err(e) = alarms::start(notifier) {
+            tui.notification = Some((format!("Reminders are off: {}", e), Instant::now()));
+        }
+
         loop {
+            if let Some(event) = changes.try_iter().last() {
+                tui.notification = Some((describe_change(&event), Instant::now()));
+            }
+            tui.alarms.extend(due_alarms.try_iter());
+
             terminal.draw(|f| {
                 let bounds = Rect::new(2, 1, f.area().width - 4, f.area().height - 2);
                 tui.render(f, bounds);
+                tui.render_notification(f, bounds);
+                tui.render_alarm(f, bounds);
             })?;
 
-            // Handle user input
+            // Handle user input, redrawing now and then to show new notifications
+            if !event::poll(constants::POLL_INTERVAL)? {
+                continue;
+            }
             if let event::Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && tui.handle_alarm_key(key.code) {
+                    continue;
+                }
                 if key.kind == KeyEventKind::Press {
                     match tui.current_mode {
                         Mode::EDIT => match tui.get_active_window().handle_input(key.code) {
@@ -118,13 +175,11 @@ impl Tui {
                         Mode::NORMAL => {
                             match key.code {
                                 KeyCode::Char('q') => break, // Quit the application
-                                _ => {
-                                    if let Cmd::NavigateTo(screen) =
-                                        tui.get_active_window().handle_cmd(key.code)
-                                    {
-                                        tui.navigate_to(&screen);
-                                    }
-                                }
+                                _ => match tui.get_active_window().handle_cmd(key.code) {
+                                    Cmd::ChangeMode => tui.change_mode(),
+                                    Cmd::NavigateTo(screen) => tui.navigate_to(&screen),
+                                    Cmd::None => {}
+                                },
                             }
                         }
                         _ => {}
@@ -213,6 +268,86 @@ impl Tui {
         Box::new(self.login_screen.clone())
     }
 
+    /// Renders the most recent change notification into the last line, until it expires.
+    fn render_notification(&self, frame: &mut Frame, bounds: Rect) {
+        if let Some((text, received)) = &self.notification {
+            if received.elapsed() < constants::NOTIFICATION_DURATION {
+                let line_bounds = Rect::new(bounds.x, bounds.bottom() - 1, bounds.width, 1);
+                let line = Paragraph::new(text.as_str()).alignment(Alignment::Center);
+                frame.render_widget(line, line_bounds);
+            }
+        }
+    }
+
+    /// Renders the first due alarm as a popup in the middle of the screen.
+    fn render_alarm(&self, frame: &mut Frame, bounds: Rect) {
+        let Some(alarm) = self.alarms.first() else {
+            return;
+        };
+
+        let mut lines = vec![Line::from(
+            alarm
+                .summary
+                .clone()
+                .unwrap_or_else(|| String::from("Untitled entry")),
+        )];
+        if let Some(starts_at) = alarm.starts_at {
+            lines.push(Line::from(format!(
+                "Starts at {}",
+                starts_at.format("%Y-%m-%d %H:%M UTC")
+            )));
+        }
+        if let Some(description) = &alarm.description {
+            lines.push(Line::from(description.as_str()));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!(
+            "'s' to snooze for {} minutes, 'd' to dismiss",
+            constants::SNOOZE_MINUTES
+        )));
+
+        let width = bounds.width.min(60);
+        let height = (lines.len() as u16 + 2).min(bounds.height);
+        let popup_bounds = Rect::new(
+            bounds.x + (bounds.width - width) / 2,
+            bounds.y + (bounds.height - height) / 2,
+            width,
+            height,
+        );
+        let popup = Paragraph::new(Text::from(lines))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().title("Reminder").borders(Borders::ALL));
+        frame.render_widget(Clear, popup_bounds);
+        frame.render_widget(popup, popup_bounds);
+    }
+
+    /// Snoozes or dismisses the alarm shown as a popup.
+    ///
+    /// # Returns
+    /// Whether the key was handled by the popup.
+    fn handle_alarm_key(&mut self, key: KeyCode) -> bool {
+        let Some(alarm) = self.alarms.first() else {
+            return false;
+        };
+        let Some(user) = get_current_user().lock().unwrap().clone() else {
+            return false;
+        };
+
+        let result = match key {
+            KeyCode::Char('s') => {
+                AlarmService::snooze(&user, alarm.alarm_uuid, constants::SNOOZE_MINUTES)
+            }
+            KeyCode::Char('d') => AlarmService::dismiss(&user, alarm.alarm_uuid),
+            _ => return false,
+        };
+        if let Err(e) = result {
+            self.notification = Some((String::from(e), Instant::now()));
+        }
+        self.alarms.remove(0);
+        true
+    }
+
     fn change_mode(&mut self) {
         if self.current_mode == Mode::NORMAL {
             self.current_mode = Mode::EDIT;
@@ -237,3 +372,28 @@ impl Tui {
         panic!("WTF");
     }
 }
+
+fn describe_change(event: &ChangeEvent) -> String {
+    match event {
+        ChangeEvent::Changed(change) => {
+            let op = match change.op {
+                ChangeOp::Insert => "added to",
+                ChangeOp::Update => "changed in",
+                ChangeOp::Delete => "removed from",
+            };
+            let what = match change.table.as_str() {
+                "calendars" => "A calendar was",
+                "components" => "An entry was",
+                _ => "A property was",
+            };
+            match change.calendar_uuid {
+                Some(calendar) if change.table != "calendars" => {
+                    format!("{} {} calendar {}", what, op, calendar)
+                }
+                _ => format!("{} {} the database", what, op),
+            }
+        }
+        ChangeEvent::Resync => String::from("Reconnected, the calendars may have changed"),
+        ChangeEvent::Reloaded { .. } => String::from("Calendars reloaded"),
+    }
+}
@@ -67,10 +67,19 @@ async function storeNewCalendar(e) {
 	const usersElements = document.getElementById("calendar-users").children;
 	const users = getEmailsOfCalendarUsers(usersElements);
 
-	await invoke("store_new_calendar", {
+	const stored = await invoke("store_new_calendar", {
 		calendarName: name,
 		usersEmails: users,
-	});
+	})
+		.then(() => true)
+		.catch((e) => {
+			createErrorToast(e);
+			return false;
+		});
+
+	if (!stored) {
+		return;
+	}
 
 	document.getElementById("create-calendar-modal").style.display = "none";
 